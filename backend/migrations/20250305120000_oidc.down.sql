@@ -0,0 +1,8 @@
+-- Add down migration script here
+
+DROP INDEX IF EXISTS users_oidc_subject_idx;
+ALTER TABLE users
+    DROP COLUMN IF EXISTS oidc_subject;
+
+DROP TABLE IF EXISTS oidc_login_states;
+DROP TABLE IF EXISTS oidc_providers;
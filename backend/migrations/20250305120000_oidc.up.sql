@@ -0,0 +1,35 @@
+-- Add up migration script here
+
+CREATE TABLE IF NOT EXISTS oidc_providers
+(
+    company_id             UUID PRIMARY KEY,
+    issuer                 VARCHAR NOT NULL,
+    client_id              VARCHAR NOT NULL,
+    client_secret          VARCHAR NOT NULL,
+    redirect_uri           VARCHAR NOT NULL,
+    scopes                 VARCHAR NOT NULL DEFAULT 'openid email profile',
+    name_claim             VARCHAR NOT NULL DEFAULT 'given_name',
+    surname_claim          VARCHAR NOT NULL DEFAULT 'family_name',
+    email_claim            VARCHAR NOT NULL DEFAULT 'email',
+    role_claim             VARCHAR,
+    role_mapping           JSONB   NOT NULL DEFAULT '{}',
+    default_role           ROLE    NOT NULL DEFAULT 'guest',
+    password_login_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    FOREIGN KEY (company_id) REFERENCES companies (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS oidc_login_states
+(
+    state         VARCHAR PRIMARY KEY,
+    company_id    UUID      NOT NULL,
+    code_verifier VARCHAR   NOT NULL,
+    nonce         VARCHAR   NOT NULL,
+    return_to     VARCHAR,
+    created_at    TIMESTAMP NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (company_id) REFERENCES companies (id) ON DELETE CASCADE
+);
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS oidc_subject VARCHAR;
+
+CREATE UNIQUE INDEX IF NOT EXISTS users_oidc_subject_idx ON users (company_id, oidc_subject);
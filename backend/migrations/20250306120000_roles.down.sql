@@ -0,0 +1,11 @@
+-- Add down migration script here
+
+ALTER TABLE users
+    DROP COLUMN IF EXISTS role_id;
+
+DROP TRIGGER IF EXISTS companies_builtin_roles ON companies;
+DROP FUNCTION IF EXISTS create_builtin_roles();
+
+DROP INDEX IF EXISTS roles_company_id_idx;
+DROP TABLE IF EXISTS roles;
+DROP TYPE IF EXISTS PERMISSION;
@@ -0,0 +1,68 @@
+-- Add up migration script here
+
+DO
+$$
+    BEGIN
+        CREATE TYPE PERMISSION AS ENUM (
+            'book',
+            'manage_bookings',
+            'manage_layout',
+            'manage_users',
+            'verify_guests',
+            'view_analytics',
+            'manage_roles',
+            'manage_company'
+            );
+    EXCEPTION
+        WHEN DUPLICATE_OBJECT THEN NULL;
+    END
+$$;
+
+-- Roles with base_role set are the built-in defaults every company gets, one per ROLE value.
+CREATE TABLE IF NOT EXISTS roles
+(
+    id          UUID DEFAULT uuidv7() PRIMARY KEY,
+    name        VARCHAR(60)  NOT NULL,
+    permissions PERMISSION[] NOT NULL DEFAULT '{}',
+    base_role   ROLE,
+    company_id  UUID         NOT NULL,
+    FOREIGN KEY (company_id) REFERENCES companies (id) ON DELETE CASCADE,
+    UNIQUE (company_id, name),
+    UNIQUE (company_id, base_role)
+);
+
+CREATE INDEX IF NOT EXISTS roles_company_id_idx ON roles (company_id);
+
+CREATE OR REPLACE FUNCTION create_builtin_roles() RETURNS TRIGGER
+AS
+$$
+BEGIN
+    INSERT INTO roles (name, permissions, base_role, company_id)
+    VALUES ('Admin', enum_range(NULL::PERMISSION), 'admin', NEW.id),
+           ('Student', '{book}', 'student', NEW.id),
+           ('Guest', '{}', 'guest', NEW.id),
+           ('Verified guest', '{book}', 'verified_guest', NEW.id)
+    ON CONFLICT DO NOTHING;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS companies_builtin_roles ON companies;
+CREATE TRIGGER companies_builtin_roles
+    AFTER INSERT
+    ON companies
+    FOR EACH ROW
+EXECUTE FUNCTION create_builtin_roles();
+
+INSERT INTO roles (name, permissions, base_role, company_id)
+SELECT r.name, r.permissions::PERMISSION[], r.base_role::ROLE, c.id
+FROM companies c
+         CROSS JOIN (VALUES ('Admin', enum_range(NULL::PERMISSION)::TEXT[], 'admin'),
+                            ('Student', '{book}', 'student'),
+                            ('Guest', '{}', 'guest'),
+                            ('Verified guest', '{book}', 'verified_guest')) AS r (name, permissions, base_role)
+ON CONFLICT DO NOTHING;
+
+-- Custom role assigned to a user, when NULL the built-in role matching users.role applies.
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS role_id UUID REFERENCES roles (id) ON DELETE SET NULL;
@@ -0,0 +1,4 @@
+-- Add down migration script here
+
+DROP TABLE IF EXISTS user_activations;
+DROP TABLE IF EXISTS invites;
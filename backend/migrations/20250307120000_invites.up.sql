@@ -0,0 +1,29 @@
+-- Add up migration script here
+
+CREATE TABLE IF NOT EXISTS invites
+(
+    id         UUID                 DEFAULT uuidv7() PRIMARY KEY,
+    code       VARCHAR(64) NOT NULL UNIQUE,
+    role_id    UUID        NOT NULL,
+    max_uses   INTEGER,
+    uses       INTEGER     NOT NULL DEFAULT 0,
+    expires_at timestamp   NOT NULL,
+    created_at timestamp   NOT NULL DEFAULT NOW(),
+    company_id UUID        NOT NULL,
+    FOREIGN KEY (role_id) REFERENCES roles (id) ON DELETE CASCADE,
+    FOREIGN KEY (company_id) REFERENCES companies (id) ON DELETE CASCADE,
+    CHECK (max_uses IS NULL OR max_uses > 0)
+);
+
+CREATE INDEX IF NOT EXISTS invites_company_id_idx ON invites (company_id);
+
+-- Accounts created by an admin get a one-time token to set their own password, only its hash is stored.
+CREATE TABLE IF NOT EXISTS user_activations
+(
+    token_hash VARCHAR(64) PRIMARY KEY,
+    user_id    UUID        NOT NULL,
+    expires_at timestamp   NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS user_activations_user_id_idx ON user_activations (user_id);
@@ -0,0 +1,7 @@
+-- Add down migration script here
+
+ALTER TABLE users
+    DROP COLUMN IF EXISTS external_id,
+    DROP COLUMN IF EXISTS active;
+
+DROP TABLE IF EXISTS scim_tokens;
@@ -0,0 +1,15 @@
+-- Add up migration script here
+
+-- One SCIM bearer secret per company, only its hash is stored.
+CREATE TABLE IF NOT EXISTS scim_tokens
+(
+    company_id   UUID PRIMARY KEY,
+    token_hash   VARCHAR(64) NOT NULL UNIQUE,
+    default_role ROLE        NOT NULL DEFAULT 'student',
+    created_at   timestamp   NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (company_id) REFERENCES companies (id) ON DELETE CASCADE
+);
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS active      BOOLEAN NOT NULL DEFAULT TRUE,
+    ADD COLUMN IF NOT EXISTS external_id VARCHAR(255);
@@ -0,0 +1,25 @@
+-- Add up migration script here
+
+-- Service accounts are users without a usable password, they authenticate with API keys only.
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS service_account BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Keys are limited to `permissions` on top of whatever their user's role grants, only the hash is stored.
+CREATE TABLE IF NOT EXISTS api_keys
+(
+    id           UUID                  DEFAULT uuidv7() PRIMARY KEY,
+    name         VARCHAR(120) NOT NULL,
+    prefix       VARCHAR(16)  NOT NULL,
+    token_hash   VARCHAR(64)  NOT NULL UNIQUE,
+    permissions  PERMISSION[] NOT NULL DEFAULT '{}',
+    expires_at   timestamp,
+    last_used_at timestamp,
+    created_at   timestamp    NOT NULL DEFAULT NOW(),
+    user_id      UUID         NOT NULL,
+    company_id   UUID         NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+    FOREIGN KEY (company_id) REFERENCES companies (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS api_keys_user_id_idx ON api_keys (user_id);
+CREATE INDEX IF NOT EXISTS api_keys_company_id_idx ON api_keys (company_id);
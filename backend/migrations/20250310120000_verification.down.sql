@@ -0,0 +1,22 @@
+-- Add down migration script here
+
+CREATE TABLE IF NOT EXISTS pending_verifications
+(
+    user_id    UUID PRIMARY KEY,
+    company_id UUID,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+    FOREIGN KEY (company_id) REFERENCES companies (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS pending_verifications_company_id_idx ON pending_verifications (company_id);
+
+INSERT INTO pending_verifications (user_id, company_id)
+SELECT user_id, company_id
+FROM verification_cases
+WHERE status = 'pending'
+ON CONFLICT DO NOTHING;
+
+DROP TABLE IF EXISTS verification_comments;
+DROP TABLE IF EXISTS verification_documents;
+DROP TABLE IF EXISTS verification_cases;
+DROP TYPE IF EXISTS VERIFICATION_STATUS;
@@ -0,0 +1,69 @@
+-- Add up migration script here
+
+DO
+$$
+    BEGIN
+        CREATE TYPE VERIFICATION_STATUS AS ENUM (
+            'pending',
+            'approved',
+            'rejected',
+            'needs_more_info'
+            );
+    EXCEPTION
+        WHEN DUPLICATE_OBJECT THEN NULL;
+    END
+$$;
+
+-- One case per guest, resubmitting documents moves it back to pending.
+CREATE TABLE IF NOT EXISTS verification_cases
+(
+    id         UUID                         DEFAULT uuidv7() PRIMARY KEY,
+    user_id    UUID                NOT NULL UNIQUE,
+    status     VERIFICATION_STATUS NOT NULL DEFAULT 'pending',
+    created_at timestamp           NOT NULL DEFAULT NOW(),
+    updated_at timestamp           NOT NULL DEFAULT NOW(),
+    decided_at timestamp,
+    company_id UUID                NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+    FOREIGN KEY (company_id) REFERENCES companies (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS verification_cases_company_id_status_idx ON verification_cases (company_id, status);
+
+-- Every upload is kept under its own S3 key.
+CREATE TABLE IF NOT EXISTS verification_documents
+(
+    case_id     UUID      NOT NULL,
+    version     INTEGER   NOT NULL,
+    s3_key      VARCHAR   NOT NULL,
+    uploaded_at timestamp NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (case_id, version),
+    FOREIGN KEY (case_id) REFERENCES verification_cases (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS verification_comments
+(
+    id         UUID                    DEFAULT uuidv7() PRIMARY KEY,
+    case_id    UUID           NOT NULL,
+    author_id  UUID,
+    status     VERIFICATION_STATUS,
+    body       VARCHAR(2000)  NOT NULL,
+    created_at timestamp      NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (case_id) REFERENCES verification_cases (id) ON DELETE CASCADE,
+    FOREIGN KEY (author_id) REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS verification_comments_case_id_idx ON verification_comments (case_id);
+
+INSERT INTO verification_cases (user_id, company_id)
+SELECT pv.user_id, u.company_id
+FROM pending_verifications pv
+         JOIN users u ON u.id = pv.user_id
+ON CONFLICT DO NOTHING;
+
+INSERT INTO verification_documents (case_id, version, s3_key)
+SELECT id, 1, 'users/' || user_id || '/document.pdf'
+FROM verification_cases
+ON CONFLICT DO NOTHING;
+
+DROP TABLE IF EXISTS pending_verifications;
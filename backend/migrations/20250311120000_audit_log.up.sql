@@ -0,0 +1,41 @@
+-- Add up migration script here
+
+CREATE TABLE IF NOT EXISTS audit_log
+(
+    id         UUID                 DEFAULT uuidv7() PRIMARY KEY,
+    company_id UUID        NOT NULL,
+    actor_id   UUID,
+    action     VARCHAR(64) NOT NULL,
+    entity     VARCHAR(32) NOT NULL,
+    entity_id  UUID,
+    before     JSONB,
+    after      JSONB,
+    ip         VARCHAR(45),
+    request_id VARCHAR(64),
+    created_at timestamp   NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_company_id_created_at_idx ON audit_log (company_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS audit_log_entity_idx ON audit_log (company_id, entity, entity_id);
+
+CREATE OR REPLACE FUNCTION reject_audit_log_changes() RETURNS TRIGGER
+AS
+$$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS audit_log_append_only ON audit_log;
+CREATE TRIGGER audit_log_append_only
+    BEFORE UPDATE OR DELETE
+    ON audit_log
+    FOR EACH ROW
+EXECUTE FUNCTION reject_audit_log_changes();
+
+DROP TRIGGER IF EXISTS audit_log_no_truncate ON audit_log;
+CREATE TRIGGER audit_log_no_truncate
+    BEFORE TRUNCATE
+    ON audit_log
+    FOR EACH STATEMENT
+EXECUTE FUNCTION reject_audit_log_changes();
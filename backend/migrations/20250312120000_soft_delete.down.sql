@@ -0,0 +1,18 @@
+-- Add down migration script here
+
+DELETE FROM coworking_spaces WHERE deleted_at IS NOT NULL;
+DELETE FROM buildings WHERE deleted_at IS NOT NULL;
+DELETE FROM item_types WHERE deleted_at IS NOT NULL;
+DELETE FROM users WHERE deleted_at IS NOT NULL;
+
+ALTER TABLE item_types
+    DROP COLUMN IF EXISTS deleted_at;
+
+ALTER TABLE coworking_spaces
+    DROP COLUMN IF EXISTS deleted_at;
+
+ALTER TABLE buildings
+    DROP COLUMN IF EXISTS deleted_at;
+
+ALTER TABLE users
+    DROP COLUMN IF EXISTS deleted_at;
@@ -0,0 +1,18 @@
+-- Add up migration script here
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS deleted_at timestamp;
+
+ALTER TABLE buildings
+    ADD COLUMN IF NOT EXISTS deleted_at timestamp;
+
+ALTER TABLE coworking_spaces
+    ADD COLUMN IF NOT EXISTS deleted_at timestamp;
+
+ALTER TABLE item_types
+    ADD COLUMN IF NOT EXISTS deleted_at timestamp;
+
+CREATE INDEX IF NOT EXISTS users_deleted_at_idx ON users (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS buildings_deleted_at_idx ON buildings (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS coworking_spaces_deleted_at_idx ON coworking_spaces (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS item_types_deleted_at_idx ON item_types (deleted_at) WHERE deleted_at IS NOT NULL;
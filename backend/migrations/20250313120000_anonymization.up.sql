@@ -0,0 +1,5 @@
+-- Add up migration script here
+
+-- Anonymized users stay deleted for good, so their bookings are kept for statistics.
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS anonymized_at timestamp;
@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+DROP INDEX IF EXISTS users_identity_id_idx;
+
+ALTER TABLE users
+    DROP COLUMN IF EXISTS identity_id;
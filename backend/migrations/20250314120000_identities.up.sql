@@ -0,0 +1,16 @@
+-- Add up migration script here
+
+-- Rows of one person in different companies share an identity and its password,
+-- each row is a membership with its own role. Existing accounts start out as their own identity.
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS identity_id UUID;
+
+UPDATE users
+SET identity_id = id
+WHERE identity_id IS NULL;
+
+ALTER TABLE users
+    ALTER COLUMN identity_id SET DEFAULT uuidv7(),
+    ALTER COLUMN identity_id SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS users_identity_id_idx ON users (identity_id);
@@ -0,0 +1,9 @@
+-- Add down migration script here
+
+DROP FUNCTION IF EXISTS can_book_item(UUID, UUID);
+DROP FUNCTION IF EXISTS user_team_ids(UUID);
+
+DROP TABLE IF EXISTS coworking_item_teams;
+DROP TABLE IF EXISTS coworking_teams;
+DROP TABLE IF EXISTS team_members;
+DROP TABLE IF EXISTS teams;
@@ -0,0 +1,76 @@
+-- Add up migration script here
+
+-- Teams nest into departments through parent_id. A team with departments can't be deleted.
+CREATE TABLE IF NOT EXISTS teams
+(
+    id         UUID      DEFAULT uuidv7() PRIMARY KEY,
+    name       VARCHAR(120) NOT NULL,
+    parent_id  UUID REFERENCES teams (id) ON DELETE RESTRICT,
+    company_id UUID         NOT NULL REFERENCES companies (id) ON DELETE CASCADE,
+    created_at timestamp DEFAULT NOW() NOT NULL,
+    UNIQUE NULLS NOT DISTINCT (company_id, parent_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS teams_company_id_idx ON teams (company_id);
+CREATE INDEX IF NOT EXISTS teams_parent_id_idx ON teams (parent_id);
+
+-- Managers book on behalf of the members of their team and its departments.
+CREATE TABLE IF NOT EXISTS team_members
+(
+    team_id UUID    NOT NULL REFERENCES teams (id) ON DELETE CASCADE,
+    user_id UUID    NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    manager BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (team_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS team_members_user_id_idx ON team_members (user_id);
+
+-- Coworkings and placed items without teams can be booked by everybody.
+CREATE TABLE IF NOT EXISTS coworking_teams
+(
+    coworking_id UUID NOT NULL REFERENCES coworking_spaces (id) ON DELETE CASCADE,
+    team_id      UUID NOT NULL REFERENCES teams (id) ON DELETE CASCADE,
+    PRIMARY KEY (coworking_id, team_id)
+);
+
+CREATE TABLE IF NOT EXISTS coworking_item_teams
+(
+    coworking_item_id UUID NOT NULL REFERENCES coworking_items (id) ON DELETE CASCADE,
+    team_id           UUID NOT NULL REFERENCES teams (id) ON DELETE CASCADE,
+    PRIMARY KEY (coworking_item_id, team_id)
+);
+
+-- Teams of the user along with every team above them, a team admits the members of its departments.
+CREATE OR REPLACE FUNCTION user_team_ids(target UUID) RETURNS SETOF UUID
+AS
+$$
+WITH RECURSIVE up AS (SELECT t.id, t.parent_id
+                      FROM teams t
+                               JOIN team_members m ON m.team_id = t.id
+                      WHERE m.user_id = target
+                      UNION
+                      SELECT t.id, t.parent_id
+                      FROM teams t
+                               JOIN up ON up.parent_id = t.id)
+SELECT id
+FROM up;
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION can_book_item(target UUID, item UUID) RETURNS BOOLEAN
+AS
+$$
+SELECT (NOT EXISTS (SELECT 1 FROM coworking_item_teams WHERE coworking_item_id = item)
+    OR EXISTS (SELECT 1
+               FROM coworking_item_teams
+               WHERE coworking_item_id = item
+                 AND team_id IN (SELECT user_team_ids(target))))
+           AND (NOT EXISTS (SELECT 1
+                            FROM coworking_teams ct
+                                     JOIN coworking_items ci ON ci.coworking_id = ct.coworking_id
+                            WHERE ci.id = item)
+    OR EXISTS (SELECT 1
+               FROM coworking_teams ct
+                        JOIN coworking_items ci ON ci.coworking_id = ct.coworking_id
+               WHERE ci.id = item
+                 AND ct.team_id IN (SELECT user_team_ids(target))));
+$$ LANGUAGE sql STABLE;
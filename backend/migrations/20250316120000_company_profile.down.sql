@@ -0,0 +1,14 @@
+-- Add down migration script here
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS company_domain VARCHAR(30);
+
+UPDATE users u
+SET company_domain = c.domain
+FROM companies c
+WHERE c.id = u.company_id;
+
+ALTER TABLE users ALTER COLUMN company_domain SET NOT NULL;
+
+ALTER TABLE companies
+    DROP COLUMN IF EXISTS primary_color,
+    DROP COLUMN IF EXISTS accent_color;
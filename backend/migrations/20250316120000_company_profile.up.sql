@@ -0,0 +1,8 @@
+-- Add up migration script here
+
+ALTER TABLE companies
+    ADD COLUMN IF NOT EXISTS primary_color VARCHAR(7),
+    ADD COLUMN IF NOT EXISTS accent_color  VARCHAR(7);
+
+-- Users are joined with their company instead, so the domain can change.
+ALTER TABLE users DROP COLUMN IF EXISTS company_domain;
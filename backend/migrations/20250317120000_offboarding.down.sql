@@ -0,0 +1,14 @@
+-- Add down migration script here
+
+CREATE OR REPLACE FUNCTION reject_audit_log_changes() RETURNS TRIGGER
+AS
+$$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TABLE IF EXISTS company_offboardings;
+
+ALTER TABLE companies
+    DROP COLUMN IF EXISTS owner_id;
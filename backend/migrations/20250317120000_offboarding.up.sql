@@ -0,0 +1,47 @@
+-- Add up migration script here
+
+ALTER TABLE companies
+    ADD COLUMN IF NOT EXISTS owner_id UUID REFERENCES users (id) ON DELETE SET NULL;
+
+-- The admin created along with the company owns it.
+UPDATE companies c
+SET owner_id = (
+    SELECT u.id
+    FROM users u
+    WHERE u.company_id = c.id AND u.role = 'admin' AND NOT u.service_account
+    ORDER BY u.id
+    LIMIT 1
+)
+WHERE c.owner_id IS NULL;
+
+-- Not tied to the company, so the record and the report outlive it.
+CREATE TABLE IF NOT EXISTS company_offboardings
+(
+    id             UUID                  DEFAULT uuidv7() PRIMARY KEY,
+    company_id     UUID         NOT NULL,
+    company_name   VARCHAR(120) NOT NULL,
+    company_domain VARCHAR(30)  NOT NULL,
+    requested_by   UUID         NOT NULL,
+    token_hash     VARCHAR(64)  NOT NULL,
+    created_at     timestamp    NOT NULL DEFAULT NOW(),
+    purge_after    timestamp    NOT NULL,
+    cancelled_at   timestamp,
+    completed_at   timestamp,
+    report         JSONB
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS company_offboardings_pending_idx
+    ON company_offboardings (company_id)
+    WHERE cancelled_at IS NULL AND completed_at IS NULL;
+
+-- The log of a company goes away with the company.
+CREATE OR REPLACE FUNCTION reject_audit_log_changes() RETURNS TRIGGER
+AS
+$$
+BEGIN
+    IF TG_OP = 'DELETE' AND NOT EXISTS (SELECT 1 FROM companies WHERE id = OLD.company_id) THEN
+        RETURN OLD;
+    END IF;
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,25 @@
+-- Add down migration script here
+
+DO $$
+DECLARE
+    t TEXT;
+BEGIN
+    FOREACH t IN ARRAY ARRAY [
+        'users', 'roles', 'teams', 'invites', 'api_keys', 'scim_tokens', 'oidc_providers',
+        'oidc_login_states', 'buildings', 'coworking_spaces', 'item_types', 'bookings',
+        'verification_cases', 'audit_log', 'company_offboardings', 'companies', 'coworking_items',
+        'coworking_teams', 'coworking_item_teams', 'team_members', 'verification_comments',
+        'verification_documents', 'user_activations'
+    ]
+    LOOP
+        EXECUTE format('DROP POLICY IF EXISTS tenant_isolation ON %I', t);
+        EXECUTE format('ALTER TABLE %I DISABLE ROW LEVEL SECURITY', t);
+    END LOOP;
+END $$;
+
+DROP FUNCTION IF EXISTS tenant_id();
+
+-- The role is shared by every database of the cluster, so it's only stripped of its grants here.
+ALTER DEFAULT PRIVILEGES IN SCHEMA public REVOKE SELECT, INSERT, UPDATE, DELETE ON TABLES FROM booq_tenant;
+REVOKE SELECT, INSERT, UPDATE, DELETE ON ALL TABLES IN SCHEMA public FROM booq_tenant;
+REVOKE USAGE ON SCHEMA public FROM booq_tenant;
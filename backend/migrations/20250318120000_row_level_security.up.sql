@@ -0,0 +1,77 @@
+-- Add up migration script here
+
+-- Connections of authenticated requests switch to this role and set `app.company_id`, see `Db::conn`.
+-- The owner of the tables isn't subject to the policies, the role is.
+DO $$
+BEGIN
+    CREATE ROLE booq_tenant NOLOGIN;
+EXCEPTION
+    WHEN DUPLICATE_OBJECT THEN NULL;
+END $$;
+
+GRANT booq_tenant TO CURRENT_USER;
+GRANT USAGE ON SCHEMA public TO booq_tenant;
+GRANT SELECT, INSERT, UPDATE, DELETE ON ALL TABLES IN SCHEMA public TO booq_tenant;
+ALTER DEFAULT PRIVILEGES IN SCHEMA public GRANT SELECT, INSERT, UPDATE, DELETE ON TABLES TO booq_tenant;
+
+CREATE OR REPLACE FUNCTION tenant_id() RETURNS UUID
+AS
+$$
+SELECT NULLIF(current_setting('app.company_id', TRUE), '')::uuid
+$$ LANGUAGE sql STABLE;
+
+DO $$
+DECLARE
+    t TEXT;
+BEGIN
+    FOREACH t IN ARRAY ARRAY [
+        'users', 'roles', 'teams', 'invites', 'api_keys', 'scim_tokens', 'oidc_providers',
+        'oidc_login_states', 'buildings', 'coworking_spaces', 'item_types', 'bookings',
+        'verification_cases', 'audit_log', 'company_offboardings'
+    ]
+    LOOP
+        EXECUTE format('ALTER TABLE %I ENABLE ROW LEVEL SECURITY', t);
+        EXECUTE format('DROP POLICY IF EXISTS tenant_isolation ON %I', t);
+        EXECUTE format('CREATE POLICY tenant_isolation ON %I TO booq_tenant USING (company_id = tenant_id())', t);
+    END LOOP;
+END $$;
+
+ALTER TABLE companies ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS tenant_isolation ON companies;
+CREATE POLICY tenant_isolation ON companies TO booq_tenant USING (id = tenant_id());
+
+-- Tables without a company of their own go through their parent, which is filtered by its own policy.
+ALTER TABLE coworking_items ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS tenant_isolation ON coworking_items;
+CREATE POLICY tenant_isolation ON coworking_items TO booq_tenant
+    USING (coworking_id IN (SELECT id FROM coworking_spaces));
+
+ALTER TABLE coworking_teams ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS tenant_isolation ON coworking_teams;
+CREATE POLICY tenant_isolation ON coworking_teams TO booq_tenant
+    USING (coworking_id IN (SELECT id FROM coworking_spaces));
+
+ALTER TABLE coworking_item_teams ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS tenant_isolation ON coworking_item_teams;
+CREATE POLICY tenant_isolation ON coworking_item_teams TO booq_tenant
+    USING (coworking_item_id IN (SELECT id FROM coworking_items));
+
+ALTER TABLE team_members ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS tenant_isolation ON team_members;
+CREATE POLICY tenant_isolation ON team_members TO booq_tenant
+    USING (team_id IN (SELECT id FROM teams));
+
+ALTER TABLE verification_comments ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS tenant_isolation ON verification_comments;
+CREATE POLICY tenant_isolation ON verification_comments TO booq_tenant
+    USING (case_id IN (SELECT id FROM verification_cases));
+
+ALTER TABLE verification_documents ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS tenant_isolation ON verification_documents;
+CREATE POLICY tenant_isolation ON verification_documents TO booq_tenant
+    USING (case_id IN (SELECT id FROM verification_cases));
+
+ALTER TABLE user_activations ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS tenant_isolation ON user_activations;
+CREATE POLICY tenant_isolation ON user_activations TO booq_tenant
+    USING (user_id IN (SELECT id FROM users));
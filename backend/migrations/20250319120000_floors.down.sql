@@ -0,0 +1,9 @@
+-- Add down migration script here
+
+ALTER TABLE coworking_spaces
+    DROP CONSTRAINT IF EXISTS coworking_spaces_floor_fkey;
+DROP INDEX IF EXISTS coworking_spaces_floor_id_idx;
+ALTER TABLE coworking_spaces
+    DROP COLUMN IF EXISTS floor_id;
+
+DROP TABLE IF EXISTS floors;
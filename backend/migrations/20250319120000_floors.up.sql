@@ -0,0 +1,48 @@
+-- Add up migration script here
+
+-- Coworkings are laid out on the floors of their building, ordered by display_order for navigation.
+CREATE TABLE IF NOT EXISTS floors
+(
+    id            UUID DEFAULT uuidv7() PRIMARY KEY,
+    building_id   UUID         NOT NULL REFERENCES buildings (id) ON DELETE CASCADE,
+    company_id    UUID         NOT NULL REFERENCES companies (id) ON DELETE CASCADE,
+    number        INT          NOT NULL,
+    name          VARCHAR(100) NOT NULL,
+    display_order INT          NOT NULL,
+    UNIQUE (building_id, number),
+    -- Lets coworkings reference the floor together with their building, so both always match.
+    UNIQUE (id, building_id)
+);
+
+CREATE INDEX IF NOT EXISTS floors_company_id_idx ON floors (company_id);
+
+ALTER TABLE floors ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS tenant_isolation ON floors;
+CREATE POLICY tenant_isolation ON floors TO booq_tenant USING (company_id = tenant_id());
+
+-- Every building starts with a ground floor that takes the coworkings it already has.
+INSERT INTO floors (building_id, company_id, number, name, display_order)
+SELECT id, company_id, 0, 'Ground floor', 0
+FROM buildings
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE coworking_spaces
+    ADD COLUMN IF NOT EXISTS floor_id UUID;
+
+UPDATE coworking_spaces c
+SET floor_id = f.id
+FROM floors f
+WHERE f.building_id = c.building_id
+  AND f.number = 0
+  AND c.floor_id IS NULL;
+
+ALTER TABLE coworking_spaces
+    ALTER COLUMN floor_id SET NOT NULL;
+
+ALTER TABLE coworking_spaces
+    DROP CONSTRAINT IF EXISTS coworking_spaces_floor_fkey;
+ALTER TABLE coworking_spaces
+    ADD CONSTRAINT coworking_spaces_floor_fkey
+        FOREIGN KEY (floor_id, building_id) REFERENCES floors (id, building_id) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS coworking_spaces_floor_id_idx ON coworking_spaces (floor_id);
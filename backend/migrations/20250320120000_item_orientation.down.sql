@@ -0,0 +1,7 @@
+-- Add down migration script here
+
+DROP FUNCTION IF EXISTS item_footprint(POINT, POINT[], INT, BOOLEAN);
+
+ALTER TABLE coworking_items
+    DROP COLUMN IF EXISTS rotation,
+    DROP COLUMN IF EXISTS mirrored;
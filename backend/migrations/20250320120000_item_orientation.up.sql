@@ -0,0 +1,26 @@
+-- Add up migration script here
+
+-- Items are turned clockwise around their base point, after mirroring across the vertical axis.
+ALTER TABLE coworking_items
+    ADD COLUMN IF NOT EXISTS rotation INT     NOT NULL DEFAULT 0 CHECK (rotation IN (0, 90, 180, 270)),
+    ADD COLUMN IF NOT EXISTS mirrored BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Cells covered by an item placed at base with the offsets of its type.
+CREATE OR REPLACE FUNCTION item_footprint(base POINT, offsets POINT[], rotation INT, mirrored BOOLEAN)
+    RETURNS POINT[]
+AS
+$$
+SELECT COALESCE(array_agg(point(base[0] + r.x, base[1] + r.y) ORDER BY o.n), '{}')
+FROM unnest(offsets) WITH ORDINALITY AS o(p, n)
+         CROSS JOIN LATERAL (SELECT CASE WHEN mirrored THEN -o.p[0] ELSE o.p[0] END AS x, o.p[1] AS y) m
+         CROSS JOIN LATERAL (SELECT CASE rotation
+                                        WHEN 90 THEN -m.y
+                                        WHEN 180 THEN -m.x
+                                        WHEN 270 THEN m.y
+                                        ELSE m.x END AS x,
+                                    CASE rotation
+                                        WHEN 90 THEN m.x
+                                        WHEN 180 THEN -m.y
+                                        WHEN 270 THEN -m.x
+                                        ELSE m.y END AS y) r;
+$$ LANGUAGE sql IMMUTABLE;
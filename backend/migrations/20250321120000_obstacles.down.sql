@@ -0,0 +1,4 @@
+-- Add down migration script here
+
+DROP TABLE IF EXISTS coworking_obstacles;
+DROP TYPE IF EXISTS OBSTACLE_KIND;
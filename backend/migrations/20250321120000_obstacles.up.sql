@@ -0,0 +1,31 @@
+-- Add up migration script here
+
+DO
+$$
+    BEGIN
+        CREATE TYPE OBSTACLE_KIND AS ENUM (
+            'wall',
+            'pillar',
+            'door',
+            'window'
+            );
+    EXCEPTION
+        WHEN DUPLICATE_OBJECT THEN NULL;
+    END
+$$;
+
+-- Cells of a coworking no item can be placed on, shaping rooms that aren't plain rectangles.
+CREATE TABLE IF NOT EXISTS coworking_obstacles
+(
+    id           UUID DEFAULT uuidv7() PRIMARY KEY,
+    coworking_id UUID          NOT NULL REFERENCES coworking_spaces (id) ON DELETE CASCADE,
+    kind         OBSTACLE_KIND NOT NULL,
+    cells        POINT[]       NOT NULL CHECK (cardinality(cells) > 0)
+);
+
+CREATE INDEX IF NOT EXISTS coworking_obstacles_coworking_id_idx ON coworking_obstacles (coworking_id);
+
+ALTER TABLE coworking_obstacles ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS tenant_isolation ON coworking_obstacles;
+CREATE POLICY tenant_isolation ON coworking_obstacles TO booq_tenant
+    USING (coworking_id IN (SELECT id FROM coworking_spaces));
@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+DROP TABLE IF EXISTS layout_version_items;
+DROP TABLE IF EXISTS layout_versions;
+DROP TYPE IF EXISTS LAYOUT_VERSION_STATUS;
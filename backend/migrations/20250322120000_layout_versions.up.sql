@@ -0,0 +1,70 @@
+-- Add up migration script here
+
+DO
+$$
+    BEGIN
+        CREATE TYPE LAYOUT_VERSION_STATUS AS ENUM (
+            'draft',
+            'scheduled',
+            'published',
+            'archived'
+            );
+    EXCEPTION
+        WHEN DUPLICATE_OBJECT THEN NULL;
+    END
+$$;
+
+-- Layouts of a coworking are prepared as drafts and copied to coworking_items when published.
+CREATE TABLE IF NOT EXISTS layout_versions
+(
+    id           UUID                           DEFAULT uuidv7() PRIMARY KEY,
+    coworking_id UUID                  NOT NULL REFERENCES coworking_spaces (id) ON DELETE CASCADE,
+    company_id   UUID                  NOT NULL REFERENCES companies (id) ON DELETE CASCADE,
+    status       LAYOUT_VERSION_STATUS NOT NULL DEFAULT 'draft',
+    created_by   UUID REFERENCES users (id) ON DELETE SET NULL,
+    created_at   timestamp             NOT NULL DEFAULT NOW(),
+    publish_at   timestamp,
+    published_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    published_at timestamp,
+    CHECK ((status = 'scheduled') = (publish_at IS NOT NULL))
+);
+
+CREATE INDEX IF NOT EXISTS layout_versions_company_id_idx ON layout_versions (company_id);
+CREATE INDEX IF NOT EXISTS layout_versions_coworking_id_idx ON layout_versions (coworking_id);
+CREATE INDEX IF NOT EXISTS layout_versions_publish_at_idx ON layout_versions (publish_at) WHERE status = 'scheduled';
+CREATE UNIQUE INDEX IF NOT EXISTS layout_versions_published_idx ON layout_versions (coworking_id) WHERE status = 'published';
+
+-- item_key is the id of the coworking item the entry becomes, bookings follow it between versions.
+CREATE TABLE IF NOT EXISTS layout_version_items
+(
+    version_id  UUID    NOT NULL REFERENCES layout_versions (id) ON DELETE CASCADE,
+    item_key    UUID    NOT NULL,
+    item_id     UUID    NOT NULL REFERENCES item_types (id) ON DELETE CASCADE,
+    name        VARCHAR NOT NULL,
+    description VARCHAR,
+    base_point  point   NOT NULL,
+    rotation    INT     NOT NULL DEFAULT 0 CHECK (rotation IN (0, 90, 180, 270)),
+    mirrored    BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (version_id, item_key)
+);
+
+-- Current layouts become the first published version, so there's something to roll back to.
+INSERT INTO layout_versions (coworking_id, company_id, status, published_at)
+SELECT c.id, c.company_id, 'published', NOW()
+FROM coworking_spaces c
+WHERE NOT EXISTS (SELECT 1 FROM layout_versions v WHERE v.coworking_id = c.id);
+
+INSERT INTO layout_version_items (version_id, item_key, item_id, name, description, base_point, rotation, mirrored)
+SELECT v.id, ci.id, ci.item_id, ci.name, ci.description, ci.base_point, ci.rotation, ci.mirrored
+FROM coworking_items ci
+JOIN layout_versions v ON v.coworking_id = ci.coworking_id AND v.status = 'published'
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE layout_versions ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS tenant_isolation ON layout_versions;
+CREATE POLICY tenant_isolation ON layout_versions TO booq_tenant USING (company_id = tenant_id());
+
+ALTER TABLE layout_version_items ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS tenant_isolation ON layout_version_items;
+CREATE POLICY tenant_isolation ON layout_version_items TO booq_tenant
+    USING (version_id IN (SELECT id FROM layout_versions));
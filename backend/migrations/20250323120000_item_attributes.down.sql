@@ -0,0 +1,8 @@
+-- Add down migration script here
+
+DROP FUNCTION IF EXISTS placed_item_attribute_values(UUID, UUID);
+DROP FUNCTION IF EXISTS type_attribute_values(UUID);
+DROP TABLE IF EXISTS coworking_item_attribute_values;
+DROP TABLE IF EXISTS item_type_attribute_values;
+DROP TABLE IF EXISTS item_attributes;
+DROP TYPE IF EXISTS ITEM_ATTRIBUTE_KIND;
@@ -0,0 +1,82 @@
+-- Add up migration script here
+
+DO
+$$
+    BEGIN
+        CREATE TYPE ITEM_ATTRIBUTE_KIND AS ENUM (
+            'boolean',
+            'enum',
+            'number'
+            );
+    EXCEPTION
+        WHEN DUPLICATE_OBJECT THEN NULL;
+    END
+$$;
+
+-- Company-defined amenities like monitors or a standing desk, `key` is what search filters use.
+CREATE TABLE IF NOT EXISTS item_attributes
+(
+    id         UUID                         DEFAULT uuidv7() PRIMARY KEY,
+    company_id UUID                NOT NULL REFERENCES companies (id) ON DELETE CASCADE,
+    key        VARCHAR             NOT NULL,
+    name       VARCHAR             NOT NULL,
+    kind       ITEM_ATTRIBUTE_KIND NOT NULL,
+    options    VARCHAR[]           NOT NULL DEFAULT '{}',
+    UNIQUE (company_id, key),
+    CHECK ((kind = 'enum') = (cardinality(options) > 0))
+);
+
+-- Values every item of the type has.
+CREATE TABLE IF NOT EXISTS item_type_attribute_values
+(
+    item_type_id UUID  NOT NULL REFERENCES item_types (id) ON DELETE CASCADE,
+    attribute_id UUID  NOT NULL REFERENCES item_attributes (id) ON DELETE CASCADE,
+    value        JSONB NOT NULL,
+    PRIMARY KEY (item_type_id, attribute_id)
+);
+
+-- Values of a single placed item that differ from its type.
+CREATE TABLE IF NOT EXISTS coworking_item_attribute_values
+(
+    coworking_item_id UUID  NOT NULL REFERENCES coworking_items (id) ON DELETE CASCADE,
+    attribute_id      UUID  NOT NULL REFERENCES item_attributes (id) ON DELETE CASCADE,
+    value             JSONB NOT NULL,
+    PRIMARY KEY (coworking_item_id, attribute_id)
+);
+
+CREATE INDEX IF NOT EXISTS item_type_attribute_values_attribute_id_idx ON item_type_attribute_values (attribute_id);
+CREATE INDEX IF NOT EXISTS coworking_item_attribute_values_attribute_id_idx ON coworking_item_attribute_values (attribute_id);
+
+CREATE OR REPLACE FUNCTION type_attribute_values(item_type UUID) RETURNS JSONB
+AS
+$$
+SELECT COALESCE(jsonb_object_agg(a.key, v.value), '{}')
+FROM item_type_attribute_values v
+         JOIN item_attributes a ON a.id = v.attribute_id
+WHERE v.item_type_id = item_type;
+$$ LANGUAGE sql STABLE;
+
+-- Values of the placed item of that type by attribute key, its own ones over those of the type.
+CREATE OR REPLACE FUNCTION placed_item_attribute_values(item UUID, item_type UUID) RETURNS JSONB
+AS
+$$
+SELECT type_attribute_values(item_type)
+           || COALESCE((SELECT jsonb_object_agg(a.key, v.value)
+                        FROM coworking_item_attribute_values v
+                                 JOIN item_attributes a ON a.id = v.attribute_id
+                        WHERE v.coworking_item_id = item), '{}');
+$$ LANGUAGE sql STABLE;
+
+ALTER TABLE item_attributes ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS tenant_isolation ON item_attributes;
+CREATE POLICY tenant_isolation ON item_attributes TO booq_tenant USING (company_id = tenant_id());
+
+ALTER TABLE item_type_attribute_values ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS tenant_isolation ON item_type_attribute_values;
+CREATE POLICY tenant_isolation ON item_type_attribute_values TO booq_tenant
+    USING (attribute_id IN (SELECT id FROM item_attributes));
+
+ALTER TABLE coworking_item_attribute_values ENABLE ROW LEVEL SECURITY;
+DROP POLICY IF EXISTS tenant_isolation ON coworking_item_attribute_values;
+CREATE POLICY tenant_isolation ON coworking_item_attribute_values TO booq_tenant
+    USING (attribute_id IN (SELECT id FROM item_attributes));
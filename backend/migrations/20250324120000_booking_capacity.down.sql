@@ -0,0 +1,22 @@
+-- Add down migration script here
+
+DROP TRIGGER IF EXISTS bookings_capacity ON bookings;
+DROP FUNCTION IF EXISTS check_booking_capacity();
+DROP FUNCTION IF EXISTS booked_attendees(UUID, TIMESTAMP, TIMESTAMP, UUID);
+
+DELETE FROM bookings WHERE shared;
+ALTER TABLE bookings
+    DROP CONSTRAINT IF EXISTS bookings_coworking_item_id_tsrange_excl;
+ALTER TABLE bookings
+    DROP COLUMN IF EXISTS shared,
+    DROP COLUMN IF EXISTS attendees;
+ALTER TABLE bookings
+    ADD CONSTRAINT bookings_coworking_item_id_tsrange_excl EXCLUDE USING gist (
+        coworking_item_id WITH =,
+        tsrange(time_start, time_end) WITH &&
+        );
+
+ALTER TABLE item_types
+    DROP COLUMN IF EXISTS capacity,
+    DROP COLUMN IF EXISTS booking_mode;
+DROP TYPE IF EXISTS BOOKING_MODE;
@@ -0,0 +1,108 @@
+-- Add up migration script here
+
+DO
+$$
+    BEGIN
+        CREATE TYPE BOOKING_MODE AS ENUM (
+            'exclusive',
+            'shared'
+            );
+    EXCEPTION
+        WHEN DUPLICATE_OBJECT THEN NULL;
+    END
+$$;
+
+-- Exclusive items take one booking at a time, shared ones take overlapping bookings until the
+-- attendees fill the capacity.
+ALTER TABLE item_types
+    ADD COLUMN IF NOT EXISTS booking_mode BOOKING_MODE NOT NULL DEFAULT 'exclusive',
+    ADD COLUMN IF NOT EXISTS capacity     INTEGER      NOT NULL DEFAULT 1 CHECK (capacity > 0);
+
+-- `shared` is copied from the item type on write, so the exclusion below only covers exclusive items.
+ALTER TABLE bookings
+    ADD COLUMN IF NOT EXISTS attendees INTEGER NOT NULL DEFAULT 1 CHECK (attendees > 0),
+    ADD COLUMN IF NOT EXISTS shared    BOOLEAN NOT NULL DEFAULT false;
+
+ALTER TABLE bookings
+    DROP CONSTRAINT IF EXISTS bookings_coworking_item_id_tsrange_excl;
+ALTER TABLE bookings
+    ADD CONSTRAINT bookings_coworking_item_id_tsrange_excl EXCLUDE USING gist (
+        coworking_item_id WITH =,
+        tsrange(time_start, time_end) WITH &&
+        ) WHERE (NOT shared);
+
+-- Most attendees the item has at once during the interval, not counting the `skip` booking.
+CREATE OR REPLACE FUNCTION booked_attendees(item UUID, range_start TIMESTAMP, range_end TIMESTAMP,
+                                            skip UUID) RETURNS INTEGER
+AS
+$$
+SELECT COALESCE(MAX((SELECT SUM(b.attendees)
+                     FROM bookings b
+                     WHERE b.coworking_item_id = item
+                       AND b.id IS DISTINCT FROM skip
+                       AND b.time_start <= p.at
+                       AND b.time_end > p.at)), 0)::INTEGER
+FROM (SELECT range_start AS at
+      UNION
+      SELECT time_start
+      FROM bookings
+      WHERE coworking_item_id = item
+        AND id IS DISTINCT FROM skip
+        AND time_start > range_start
+        AND time_start < range_end) p;
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION check_booking_capacity() RETURNS TRIGGER
+AS
+$$
+DECLARE
+    mode  BOOKING_MODE;
+    seats INTEGER;
+BEGIN
+    -- Bookings of the same item queue up here, so the count below sees every committed one.
+    PERFORM 1 FROM coworking_items WHERE id = NEW.coworking_item_id FOR UPDATE;
+
+    SELECT t.booking_mode, t.capacity
+    INTO mode, seats
+    FROM coworking_items ci
+             JOIN item_types t ON t.id = ci.item_id
+    WHERE ci.id = NEW.coworking_item_id;
+
+    IF NOT FOUND THEN
+        RETURN NEW;
+    END IF;
+
+    NEW.shared := mode = 'shared';
+
+    IF NEW.attendees > seats THEN
+        RAISE EXCEPTION 'Item fits % attendees', seats
+            USING ERRCODE = 'check_violation', CONSTRAINT = 'bookings_attendees_capacity';
+    END IF;
+
+    IF NEW.shared THEN
+        IF booked_attendees(NEW.coworking_item_id, NEW.time_start, NEW.time_end, NEW.id)
+               + NEW.attendees > seats THEN
+            RAISE EXCEPTION 'Item is full at that time'
+                USING ERRCODE = 'exclusion_violation', CONSTRAINT = 'bookings_capacity_excl';
+        END IF;
+    -- Shared bookings made before the item became exclusive are out of the exclusion's reach.
+    ELSIF EXISTS (SELECT 1
+                  FROM bookings
+                  WHERE coworking_item_id = NEW.coworking_item_id
+                    AND shared
+                    AND id IS DISTINCT FROM NEW.id
+                    AND tsrange(time_start, time_end) && tsrange(NEW.time_start, NEW.time_end)) THEN
+        RAISE EXCEPTION 'Item is booked at that time'
+            USING ERRCODE = 'exclusion_violation', CONSTRAINT = 'bookings_coworking_item_id_tsrange_excl';
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS bookings_capacity ON bookings;
+CREATE TRIGGER bookings_capacity
+    BEFORE INSERT OR UPDATE OF coworking_item_id, time_start, time_end, attendees
+    ON bookings
+    FOR EACH ROW
+EXECUTE FUNCTION check_booking_capacity();
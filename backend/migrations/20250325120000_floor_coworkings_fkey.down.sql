@@ -0,0 +1,7 @@
+-- Add down migration script here
+
+ALTER TABLE coworking_spaces
+    DROP CONSTRAINT IF EXISTS coworking_spaces_floor_fkey;
+ALTER TABLE coworking_spaces
+    ADD CONSTRAINT coworking_spaces_floor_fkey
+        FOREIGN KEY (floor_id, building_id) REFERENCES floors (id, building_id) ON DELETE CASCADE;
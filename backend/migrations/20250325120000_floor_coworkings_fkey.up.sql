@@ -0,0 +1,10 @@
+-- Add up migration script here
+
+-- Deleting a floor no longer takes its coworkings along, trashed ones included, so they can't vanish
+-- with their bookings behind the trash's back. NO ACTION rather than RESTRICT: it's checked at the end of
+-- the statement, so purging a whole building still removes floors and coworkings together.
+ALTER TABLE coworking_spaces
+    DROP CONSTRAINT IF EXISTS coworking_spaces_floor_fkey;
+ALTER TABLE coworking_spaces
+    ADD CONSTRAINT coworking_spaces_floor_fkey
+        FOREIGN KEY (floor_id, building_id) REFERENCES floors (id, building_id) ON DELETE NO ACTION;
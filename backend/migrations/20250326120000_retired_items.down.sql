@@ -0,0 +1,9 @@
+-- Add down migration script here
+
+DROP TRIGGER IF EXISTS bookings_item_live ON bookings;
+DROP FUNCTION IF EXISTS check_booking_item_live();
+
+DELETE FROM coworking_items WHERE retired_at IS NOT NULL;
+DROP INDEX IF EXISTS coworking_items_live_idx;
+ALTER TABLE coworking_items
+    DROP COLUMN IF EXISTS retired_at;
@@ -0,0 +1,27 @@
+-- Add up migration script here
+
+-- Items taken off the layout stay for the history of their past bookings, only their upcoming ones go.
+ALTER TABLE coworking_items
+    ADD COLUMN IF NOT EXISTS retired_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS coworking_items_live_idx ON coworking_items (coworking_id) WHERE retired_at IS NULL;
+
+CREATE OR REPLACE FUNCTION check_booking_item_live() RETURNS TRIGGER
+AS
+$$
+BEGIN
+    IF EXISTS (SELECT 1 FROM coworking_items WHERE id = NEW.coworking_item_id AND retired_at IS NOT NULL) THEN
+        RAISE EXCEPTION 'Item was taken off the layout'
+            USING ERRCODE = 'check_violation', CONSTRAINT = 'bookings_item_live';
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS bookings_item_live ON bookings;
+CREATE TRIGGER bookings_item_live
+    BEFORE INSERT OR UPDATE OF coworking_item_id, time_start, time_end
+    ON bookings
+    FOR EACH ROW
+EXECUTE FUNCTION check_booking_item_live();
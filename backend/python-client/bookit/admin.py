@@ -20,8 +20,37 @@ class AdminApi(UserApi):
         data = extract_data(r)
         return r.status_code, data
 
-    def list_requests(self):
-        r = self.s.get(BASE_URL + "/admin/list_requests")
+    def list_requests(self, status=None, search=None, limit=None, offset=None):
+        r = self.s.get(BASE_URL + "/admin/list_requests", params={
+            "status": status,
+            "search": search,
+            "limit": limit,
+            "offset": offset
+        })
+        data = extract_json(r)
+        return r.status_code, data
+
+    def get_verification_case(self, case_id):
+        r = self.s.get(BASE_URL + f"/admin/verifications/{case_id}")
+        data = extract_json(r)
+        return r.status_code, data
+
+    def get_verification_document(self, case_id, version):
+        r = self.s.get(BASE_URL + f"/admin/verifications/{case_id}/documents/{version}")
+        return r.status_code, r.content
+
+    def decide_verification(self, case_id, status, comment=None):
+        r = self.s.post(BASE_URL + f"/admin/verifications/{case_id}/decision", json={
+            "status": status,
+            "comment": comment
+        })
+        data = extract_json(r)
+        return r.status_code, data
+
+    def comment_verification(self, case_id, body):
+        r = self.s.post(BASE_URL + f"/admin/verifications/{case_id}/comments", json={
+            "body": body
+        })
         data = extract_json(r)
         return r.status_code, data
 
@@ -57,6 +86,256 @@ class AdminApi(UserApi):
 
         return r.status_code, data
 
+    def get_sso(self):
+        r = self.s.get(BASE_URL + "/admin/sso")
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def put_sso(self, config):
+        r = self.s.put(BASE_URL + "/admin/sso", json=config)
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def delete_sso(self):
+        r = self.s.delete(BASE_URL + "/admin/sso")
+
+        return r.status_code, None
+
+    def list_roles(self):
+        r = self.s.get(BASE_URL + "/admin/roles")
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def create_role(self, name, permissions):
+        r = self.s.post(BASE_URL + "/admin/roles", json={
+            "name": name,
+            "permissions": permissions
+        })
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def patch_role(self, role_id, patch_dict):
+        r = self.s.patch(BASE_URL + f"/admin/roles/{role_id}", json=patch_dict)
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def delete_role(self, role_id):
+        r = self.s.delete(BASE_URL + f"/admin/roles/{role_id}")
+
+        return r.status_code, None
+
+    def assign_role(self, user_id, role_id):
+        r = self.s.put(BASE_URL + f"/admin/user/{user_id}/role", json={
+            "role_id": role_id
+        })
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def create_invite(self, role_id, expires_in_hours=None, max_uses=None):
+        r = self.s.post(BASE_URL + "/admin/invites", json={
+            "role_id": role_id,
+            "expires_in_hours": expires_in_hours,
+            "max_uses": max_uses
+        })
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def list_invites(self):
+        r = self.s.get(BASE_URL + "/admin/invites")
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def delete_invite(self, invite_id):
+        r = self.s.delete(BASE_URL + f"/admin/invites/{invite_id}")
+
+        return r.status_code, None
+
+    def import_users(self, csv: bytes, dry_run=False):
+        r = self.s.post(
+            BASE_URL + "/admin/user/import",
+            params={"dry_run": "true" if dry_run else "false"},
+            files={"file": ("users.csv", csv, "text/csv")}
+        )
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def create_scim_token(self, default_role=None):
+        r = self.s.post(BASE_URL + "/admin/scim/token", json={
+            "default_role": default_role
+        })
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def delete_scim_token(self):
+        r = self.s.delete(BASE_URL + "/admin/scim/token")
+
+        return r.status_code, None
+
+    def create_service_account(self, name, role_id):
+        r = self.s.post(BASE_URL + "/admin/service_accounts", json={
+            "name": name,
+            "role_id": role_id
+        })
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def list_service_accounts(self):
+        r = self.s.get(BASE_URL + "/admin/service_accounts")
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def delete_service_account(self, account_id):
+        r = self.s.delete(BASE_URL + f"/admin/service_accounts/{account_id}")
+
+        return r.status_code, None
+
+    def create_service_account_key(self, account_id, name, permissions, expires_in_days=None):
+        r = self.s.post(BASE_URL + f"/admin/service_accounts/{account_id}/api_keys", json={
+            "name": name,
+            "permissions": permissions,
+            "expires_in_days": expires_in_days
+        })
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def list_company_api_keys(self):
+        r = self.s.get(BASE_URL + "/admin/api_keys")
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def revoke_api_key(self, key_id):
+        r = self.s.delete(BASE_URL + f"/admin/api_keys/{key_id}")
+
+        return r.status_code, None
+
+    def list_audit(self, actor_id=None, action=None, entity=None, entity_id=None, since=None, until=None,
+                   limit=None, offset=None):
+        r = self.s.get(BASE_URL + "/admin/audit", params={
+            "actor_id": actor_id,
+            "action": action,
+            "entity": entity,
+            "entity_id": entity_id,
+            "from": since,
+            "to": until,
+            "limit": limit,
+            "offset": offset
+        })
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def export_audit(self, format=None, action=None, entity=None, entity_id=None):
+        r = self.s.get(BASE_URL + "/admin/audit/export", params={
+            "format": format,
+            "action": action,
+            "entity": entity,
+            "entity_id": entity_id
+        })
+
+        data = extract_json(r) if format == "json" else extract_data(r)
+
+        return r.status_code, data
+
+    def list_trash(self, entity=None):
+        r = self.s.get(BASE_URL + "/admin/trash", params={"entity": entity})
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def restore(self, entity, entity_id):
+        r = self.s.post(BASE_URL + f"/admin/trash/{entity}/{entity_id}/restore")
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def create_team(self, name, parent_id=None):
+        r = self.s.post(BASE_URL + "/teams", json={
+            "name": name,
+            "parent_id": parent_id
+        })
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def patch_team(self, team_id, patch_dict):
+        r = self.s.patch(BASE_URL + f"/teams/{team_id}", json=patch_dict)
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def delete_team(self, team_id):
+        r = self.s.delete(BASE_URL + f"/teams/{team_id}")
+
+        return r.status_code, None
+
+    def get_company(self):
+        r = self.s.get(BASE_URL + "/company")
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def patch_company(self, patch_dict=None, logo=None):
+        files = {}
+        if patch_dict:
+            files["json"] = dumps(patch_dict).encode()
+        if logo:
+            files["logo"] = ("logo", logo, "image/png")
+
+        r = self.s.patch(BASE_URL + "/company", files=files)
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def get_offboarding(self):
+        r = self.s.get(BASE_URL + "/company/offboarding")
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def offboard_company(self, domain):
+        r = self.s.post(BASE_URL + "/company/offboarding", json={
+            "domain": domain
+        })
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def cancel_offboarding(self):
+        r = self.s.delete(BASE_URL + "/company/offboarding")
+
+        return r.status_code, None
+
     def verify_guest(self, user_id):
         r = self.s.post(BASE_URL + f"/admin/user/{user_id}/verify")
 
@@ -73,12 +352,13 @@ class AdminApi(UserApi):
 
         return r.status_code, data
 
-    def new_coworking(self, building_id, address, height, width):
+    def new_coworking(self, building_id, address, height, width, floor_id=None):
         r = self.s.post(BASE_URL + f"/place/{building_id}/coworking/new",
             json={
                 "address": address,
                 "height": height,
-                "width": width
+                "width": width,
+                "floor_id": floor_id
             }
         )
 
@@ -86,6 +366,60 @@ class AdminApi(UserApi):
 
         return r.status_code, data
 
+    def patch_coworking(self, building_id, coworking_id, patch_dict):
+        r = self.s.patch(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}", json=patch_dict)
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def new_floor(self, building_id, number, name, display_order=None):
+        r = self.s.post(BASE_URL + f"/place/{building_id}/floor/new", json={
+            "number": number,
+            "name": name,
+            "display_order": display_order
+        })
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def patch_floor(self, building_id, floor_id, patch_dict):
+        r = self.s.patch(BASE_URL + f"/place/{building_id}/floor/{floor_id}", json=patch_dict)
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def delete_floor(self, building_id, floor_id):
+        r = self.s.delete(BASE_URL + f"/place/{building_id}/floor/{floor_id}")
+
+        return r.status_code, None
+
+    def delete_place(self, building_id):
+        r = self.s.delete(BASE_URL + f"/place/{building_id}")
+
+        return r.status_code, None
+
+    def list_coworkings(self, building_id):
+        r = self.s.get(BASE_URL + f"/place/{building_id}/coworking/list")
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def coworking_bookings(self, building_id, coworking_id):
+        r = self.s.get(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/bookings")
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def delete_coworking(self, building_id, coworking_id):
+        r = self.s.delete(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}")
+
+        return r.status_code, None
+
     def new_item(self, icon, item):
 
         item = deepcopy(item)
@@ -97,12 +431,52 @@ class AdminApi(UserApi):
         data = extract_json(r)
         return r.status_code, data
 
+    def patch_item_type(self, item_id, patch_dict=None, icon=None):
+        files = {}
+        if patch_dict:
+            files["json"] = dumps(patch_dict).encode()
+        if icon:
+            files["icon"] = ("icon.svg", icon, "image/svg+xml")
+
+        r = self.s.patch(BASE_URL + f"/items/{item_id}", files=files)
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def item_type_impact(self, item_id, patch_dict):
+        r = self.s.post(BASE_URL + f"/items/{item_id}/impact", json=patch_dict)
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def create_attribute(self, key, name, kind, options=None):
+        r = self.s.post(BASE_URL + "/items/attributes", json={
+            "key": key,
+            "name": name,
+            "kind": kind,
+            "options": options or []
+        })
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def delete_attribute(self, attribute_id):
+        r = self.s.delete(BASE_URL + f"/items/attributes/{attribute_id}")
+
+        return r.status_code, None
+
+    def put_item_type_attributes(self, item_id, values):
+        r = self.s.put(BASE_URL + f"/items/{item_id}/attributes", json=values)
+
+        data = extract_json(r)
+        return r.status_code, data
+
     def delete_item(self, item_id):
         r = self.s.delete(BASE_URL + f"/items/{item_id}")
 
         return r.status_code, None
 
-    def place_item(self, building_id, coworking_id, item_id, basepoint):
+    def place_item(self, building_id, coworking_id, item_id, basepoint, rotation=0, mirrored=False):
         r = self.s.post(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/items/new", json={
             "base_point": {
                 "x": basepoint.x,
@@ -110,5 +484,148 @@ class AdminApi(UserApi):
             },
             "description": "lol",
             "item_id": item_id,
-            "name": "Lol item"
+            "name": "Lol item",
+            "rotation": rotation,
+            "mirrored": mirrored
+        })
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def patch_placed_item(self, building_id, coworking_id, item_id, patch_dict):
+        r = self.s.patch(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/items/{item_id}", json=patch_dict)
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def delete_placed_item(self, building_id, coworking_id, item_id):
+        r = self.s.delete(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/items/{item_id}")
+
+        return r.status_code, None
+
+    def put_items(self, building_id, coworking_id, items):
+        r = self.s.put(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/items/put", json=items)
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def put_obstacles(self, building_id, coworking_id, obstacles):
+        r = self.s.put(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/obstacles", json=[
+            {"kind": kind, "cells": [{"x": p.x, "y": p.y} for p in cells]} for kind, cells in obstacles
+        ])
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def export_layout(self, building_id, coworking_id):
+        r = self.s.get(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/layout/export")
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def import_layout(self, building_id, document, address=None, floor_id=None, dry_run=False):
+        r = self.s.post(BASE_URL + f"/place/{building_id}/coworking/import",
+                        params={"dry_run": "true" if dry_run else "false"},
+                        json={
+                            "document": document,
+                            "address": address,
+                            "floor_id": floor_id
+                        })
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def clone_coworking(self, building_id, coworking_id, address=None, to_building_id=None, floor_id=None,
+                        dry_run=False):
+        r = self.s.post(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/clone",
+                        params={"dry_run": "true" if dry_run else "false"},
+                        json={
+                            "address": address,
+                            "building_id": to_building_id,
+                            "floor_id": floor_id
+                        })
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def list_layout_versions(self, building_id, coworking_id):
+        r = self.s.get(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/layout/versions")
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def new_layout_draft(self, building_id, coworking_id):
+        r = self.s.post(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/layout/versions")
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def put_draft_items(self, building_id, coworking_id, version_id, items):
+        r = self.s.put(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/layout/versions/{version_id}/items",
+                       json=items)
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def layout_diff(self, building_id, coworking_id, version_id):
+        r = self.s.get(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/layout/versions/{version_id}/diff")
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def publish_layout(self, building_id, coworking_id, version_id, publish_at=None):
+        r = self.s.post(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/layout/versions/{version_id}/publish",
+                        json={"publish_at": publish_at})
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def rollback_layout(self, building_id, coworking_id, version_id):
+        r = self.s.post(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/layout/versions/{version_id}/rollback")
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def delete_layout_version(self, building_id, coworking_id, version_id):
+        r = self.s.delete(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/layout/versions/{version_id}")
+
+        return r.status_code, None
+
+    def reserve_coworking(self, building_id, coworking_id, team_ids):
+        r = self.s.put(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/teams", json={
+            "team_ids": team_ids
+        })
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def reserve_item(self, building_id, coworking_id, item_id, team_ids):
+        r = self.s.put(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/items/{item_id}/teams", json={
+            "team_ids": team_ids
         })
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def put_item_attributes(self, building_id, coworking_id, item_id, values):
+        r = self.s.put(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/items/{item_id}/attributes",
+                       json=values)
+
+        data = extract_json(r)
+
+        return r.status_code, data
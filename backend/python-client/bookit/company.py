@@ -15,3 +15,21 @@ class CompanyApi:
         r = self.s.post(BASE_URL + "/company/register", json=company.__dict__)
         data = extract_json(r)
         return r.status_code, data
+
+    def get_branding(self, domain):
+        r = self.s.get(BASE_URL + f"/company/branding/{domain}")
+        data = extract_json(r)
+        return r.status_code, data
+
+    def get_logo(self, company_id):
+        r = self.s.get(BASE_URL + f"/company/{company_id}/logo")
+        return r.status_code, r.content
+
+    def get_offboarding_report(self, offboarding_id, token):
+        r = self.s.get(BASE_URL + f"/company/offboarding/{offboarding_id}", params={"token": token})
+        data = extract_json(r)
+        return r.status_code, data
+
+    def get_offboarding_export(self, offboarding_id, token):
+        r = self.s.get(BASE_URL + f"/company/offboarding/{offboarding_id}/export", params={"token": token})
+        return r.status_code, r.content
@@ -5,14 +5,19 @@ class Point:
 
 
 class Item:
-    def __init__(self, bookable, name, description, offsets, color):
+    def __init__(self, bookable, name, description, offsets, color, booking_mode="exclusive", capacity=1):
         self.bookable = bookable
         self.name = name
         self.description = description
         self.offsets = offsets
         self.color = color
+        self.booking_mode = booking_mode
+        self.capacity = capacity
 
 TABLE_SMALL = Item(True, "Small table", "Small table for work and stuff", [Point(0, 0)], "134d23")
 TABLE_HOR = Item(True, "Medium table", "Medium table for meetings", [Point(0, 0), Point(1, 0)], "134d23")
 TABLE_VERT = Item(True, "Medium table", "Medium table for meetings", [Point(0, 0), Point(0, 1)], "134d23")
+MEETING_ROOM = Item(True, "Meeting", "Meeting room for 8", [Point(0, 0), Point(1, 0), Point(0, 1), Point(1, 1)], "134d23",
+                    capacity=8)
+LOUNGE = Item(True, "Lounge", "Lounge with 3 seats", [Point(0, 0), Point(1, 0)], "134d23", "shared", 3)
 TOILET = Item(False, "WC", "Water closet", [Point(x, y) for x, y in  __import__("itertools").product(range(3), range(2))], "134d23")
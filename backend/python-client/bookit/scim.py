@@ -0,0 +1,68 @@
+from requests.sessions import Session
+from .config import BASE_URL
+from .util import extract_json
+
+USER_SCHEMA = "urn:ietf:params:scim:schemas:core:2.0:User"
+PATCH_SCHEMA = "urn:ietf:params:scim:api:messages:2.0:PatchOp"
+
+
+class ScimApi:
+    """Talks to /scim/v2 the way an identity provider does."""
+
+    def __init__(self, token):
+        self.s = Session()
+        self.s.headers["authorization"] = f"Bearer {token}"
+        self.s.headers["content-type"] = "application/scim+json"
+
+    def list_users(self, filter=None, start_index=None, count=None):
+        r = self.s.get(BASE_URL + "/scim/v2/Users", params={
+            "filter": filter,
+            "startIndex": start_index,
+            "count": count
+        })
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def get_user(self, user_id):
+        r = self.s.get(BASE_URL + f"/scim/v2/Users/{user_id}")
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def create_user(self, user_name, given_name, family_name, external_id=None, active=True):
+        r = self.s.post(BASE_URL + "/scim/v2/Users", json={
+            "schemas": [USER_SCHEMA],
+            "externalId": external_id,
+            "userName": user_name,
+            "name": {"givenName": given_name, "familyName": family_name},
+            "active": active
+        })
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def replace_user(self, user_id, user_dict):
+        r = self.s.put(BASE_URL + f"/scim/v2/Users/{user_id}", json=user_dict)
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def patch_user(self, user_id, operations):
+        r = self.s.patch(BASE_URL + f"/scim/v2/Users/{user_id}", json={
+            "schemas": [PATCH_SCHEMA],
+            "Operations": operations
+        })
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def delete_user(self, user_id):
+        r = self.s.delete(BASE_URL + f"/scim/v2/Users/{user_id}")
+
+        return r.status_code, None
@@ -27,11 +27,50 @@ class UserApi:
             ...
         return r.status_code, None
 
-    def login(self):
+    def login(self, any_company=False):
+        json = {
+            "email": self.user.email,
+            "password": self.user.password,
+        }
+        if not any_company:
+            json["domain"] = self.user.company_domain
+
+        r = self.s.post(BASE_URL + "/user/login", json=json)
+        try:
+            self.token = r.json()["jwt"]
+            self.s.headers["authorization"] = f"Bearer {self.token}"
+        except:
+            ...
+        return r.status_code, extract_json(r)
+
+    def sso_login(self, return_to=None):
+        r = self.s.get(BASE_URL + f"/user/sso/{self.user.company_domain}/authorize", allow_redirects=False,
+                       params={"return_to": return_to})
+        if r.status_code != 303:
+            return r.status_code, None
+
+        # The identity provider answers with a redirect back to the callback.
+        r = self.s.get(r.headers["location"], allow_redirects=False)
+        r = self.s.get(r.headers["location"], allow_redirects=False)
+        try:
+            self.token = r.json()["jwt"]
+            self.s.headers["authorization"] = f"Bearer {self.token}"
+        except:
+            ...
+        return r.status_code, None
+
+    def get_invite(self, code):
+        r = self.s.get(BASE_URL + f"/user/invite/{code}")
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def register_with_invite(self, code):
         r = self.s.post(
-            BASE_URL + "/user/login",
+            BASE_URL + f"/user/invite/{code}/register",
             json={
-                "domain": self.user.company_domain,
+                "name": self.user.name,
+                "surname": self.user.surname,
                 "email": self.user.email,
                 "password": self.user.password,
             },
@@ -43,6 +82,18 @@ class UserApi:
             ...
         return r.status_code, None
 
+    def activate(self, token):
+        r = self.s.post(BASE_URL + "/user/activate", json={
+            "token": token,
+            "password": self.user.password,
+        })
+        try:
+            self.token = r.json()["jwt"]
+            self.s.headers["authorization"] = f"Bearer {self.token}"
+        except:
+            ...
+        return r.status_code, None
+
     def get_profile(self):
         r = self.s.get(BASE_URL + "/user/profile")
         data = extract_json(r)
@@ -55,6 +106,12 @@ class UserApi:
         )
         return r.status_code, r.text
 
+    def get_verification(self):
+        r = self.s.get(BASE_URL + "/user/verification")
+        data = extract_json(r)
+
+        return r.status_code, data
+
     def patch_profile(self, patch_dict=None, avatar=None):
         files = {}
         if patch_dict:
@@ -68,6 +125,29 @@ class UserApi:
 
         return r.status_code, data
 
+    def list_api_keys(self):
+        r = self.s.get(BASE_URL + "/user/api_keys")
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def create_api_key(self, name, permissions, expires_in_days=None):
+        r = self.s.post(BASE_URL + "/user/api_keys", json={
+            "name": name,
+            "permissions": permissions,
+            "expires_in_days": expires_in_days
+        })
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def delete_api_key(self, key_id):
+        r = self.s.delete(BASE_URL + f"/user/api_keys/{key_id}")
+
+        return r.status_code, None
+
     def get_avatar(self, user_id):
         r = self.s.get(BASE_URL + f"/user/{user_id}/avatar")
         return r.status_code, r.content
@@ -78,7 +158,132 @@ class UserApi:
         data = extract_json(r)
         return r.status_code, data
 
-    def delete(self):
-        r = self.s.delete(BASE_URL + "/user")
+    def list_attributes(self):
+        r = self.s.get(BASE_URL + "/items/attributes")
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def list_floors(self, building_id):
+        r = self.s.get(BASE_URL + f"/place/{building_id}/floor/list")
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def list_floor_coworkings(self, building_id, floor_id):
+        r = self.s.get(BASE_URL + f"/place/{building_id}/floor/{floor_id}/coworking/list")
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def get_coworking_items(self, building_id, coworking_id):
+        r = self.s.get(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/items")
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def get_coworking_layout(self, building_id, coworking_id):
+        r = self.s.get(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/layout")
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def render_layout(self, building_id, coworking_id, format=None, at=None, cell_size=None):
+        r = self.s.get(BASE_URL + f"/place/{building_id}/coworking/{coworking_id}/layout/render", params={
+            "format": format,
+            "at": at,
+            "cell_size": cell_size
+        })
+        return r.status_code, r.headers.get("content-type"), r.content
+
+    def create_booking(self, coworking_id, coworking_item_id, time_start, time_end, user_id=None, attendees=None):
+        r = self.s.post(BASE_URL + "/booking/create", json={
+            "coworking_id": coworking_id,
+            "coworking_item_id": coworking_item_id,
+            "time_start": time_start,
+            "time_end": time_end,
+            "user_id": user_id,
+            "attendees": attendees
+        })
+
+        data = extract_json(r)
+
+        return r.status_code, data
+
+    def available_items(self, time_start, time_end, *filters, building_id=None, coworking_id=None, attendees=None):
+        # Filters like `monitors>=2` go into the query as they are, requests would escape `=`.
+        r = self.s.get(BASE_URL + "/booking/available?" + "&".join(filters), params={
+            "time_start": time_start,
+            "time_end": time_end,
+            "building_id": building_id,
+            "coworking_id": coworking_id,
+            "attendees": attendees
+        })
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def patch_booking(self, booking_id, patch_dict):
+        r = self.s.patch(BASE_URL + f"/booking/{booking_id}", json=patch_dict)
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def delete_booking(self, booking_id):
+        r = self.s.delete(BASE_URL + f"/booking/{booking_id}")
+        return r.status_code, None
+
+    def list_companies(self):
+        r = self.s.get(BASE_URL + "/user/companies")
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def switch_company(self, company_id):
+        r = self.s.post(BASE_URL + f"/user/companies/{company_id}/switch")
+        try:
+            self.token = r.json()["jwt"]
+            self.s.headers["authorization"] = f"Bearer {self.token}"
+        except:
+            ...
+        return r.status_code, None
+
+    def link_company(self, domain, password):
+        r = self.s.post(BASE_URL + "/user/companies/link", json={
+            "domain": domain,
+            "password": password,
+        })
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def list_teams(self):
+        r = self.s.get(BASE_URL + "/teams")
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def team_members(self, team_id):
+        r = self.s.get(BASE_URL + f"/teams/{team_id}/members")
+
+        data = extract_json(r)
+        return r.status_code, data
+
+    def put_team_member(self, team_id, user_id, manager=False):
+        r = self.s.put(BASE_URL + f"/teams/{team_id}/members/{user_id}", json={
+            "manager": manager
+        })
+        return r.status_code, None
+
+    def delete_team_member(self, team_id, user_id):
+        r = self.s.delete(BASE_URL + f"/teams/{team_id}/members/{user_id}")
+        return r.status_code, None
+
+    def export_data(self):
+        r = self.s.get(BASE_URL + "/user/export")
+        return r.status_code, r.content
+
+    def delete(self, mode=None):
+        r = self.s.delete(BASE_URL + "/user", params={"mode": mode})
         return r.status_code, r.content
     #def create
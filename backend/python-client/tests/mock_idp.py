@@ -0,0 +1,92 @@
+import base64
+import hashlib
+import secrets
+import threading
+import time
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+from json import dumps
+from urllib.parse import parse_qs, urlencode, urlparse
+
+import jwt
+
+
+class MockIdp:
+    """Minimal OpenID Connect provider for SSO tests.
+
+    Signs ID tokens with HS256 using the client secret and checks PKCE on the token endpoint.
+    """
+
+    def __init__(self, client_id, client_secret, host="0.0.0.0", port=0, public_url=None):
+        self.client_id = client_id
+        self.client_secret = client_secret
+        self.claims = {}
+        self.codes = {}
+
+        self.server = ThreadingHTTPServer((host, port), self._handler())
+        self.url = public_url or f"http://localhost:{self.server.server_port}"
+
+    def __enter__(self):
+        threading.Thread(target=self.server.serve_forever, daemon=True).start()
+        return self
+
+    def __exit__(self, *args):
+        self.server.shutdown()
+
+    def _handler(idp):
+        class Handler(BaseHTTPRequestHandler):
+            def _json(self, status, data):
+                body = dumps(data).encode()
+                self.send_response(status)
+                self.send_header("Content-Type", "application/json")
+                self.send_header("Content-Length", str(len(body)))
+                self.end_headers()
+                self.wfile.write(body)
+
+            def do_GET(self):
+                url = urlparse(self.path)
+                if url.path == "/.well-known/openid-configuration":
+                    return self._json(200, {
+                        "issuer": idp.url,
+                        "authorization_endpoint": idp.url + "/authorize",
+                        "token_endpoint": idp.url + "/token",
+                        "jwks_uri": idp.url + "/jwks",
+                    })
+                if url.path == "/jwks":
+                    return self._json(200, {"keys": []})
+                if url.path == "/authorize":
+                    q = {k: v[0] for k, v in parse_qs(url.query).items()}
+                    code = secrets.token_urlsafe(16)
+                    idp.codes[code] = q
+                    self.send_response(302)
+                    self.send_header("Location", q["redirect_uri"] + "?" + urlencode({"code": code, "state": q["state"]}))
+                    self.end_headers()
+                    return
+                self._json(404, {})
+
+            def do_POST(self):
+                length = int(self.headers.get("Content-Length", 0))
+                form = {k: v[0] for k, v in parse_qs(self.rfile.read(length).decode()).items()}
+                request = idp.codes.pop(form.get("code"), None)
+                if request is None or form.get("client_secret") != idp.client_secret:
+                    return self._json(400, {"error": "invalid_grant"})
+
+                digest = hashlib.sha256(form["code_verifier"].encode()).digest()
+                challenge = base64.urlsafe_b64encode(digest).rstrip(b"=").decode()
+                if challenge != request["code_challenge"]:
+                    return self._json(400, {"error": "invalid_grant"})
+
+                now = int(time.time())
+                id_token = jwt.encode({
+                    "iss": idp.url,
+                    "aud": idp.client_id,
+                    "iat": now,
+                    "exp": now + 300,
+                    "nonce": request["nonce"],
+                    **idp.claims,
+                }, idp.client_secret, algorithm="HS256")
+                self._json(200, {"access_token": "unused", "token_type": "Bearer", "id_token": id_token})
+
+            def log_message(self, *args):
+                ...
+
+        return Handler
@@ -0,0 +1,110 @@
+import jwt
+
+from bookit.admin import AdminApi
+from .util import builtin_role, create_random_company, create_random_user_api, rand_n_str
+
+
+def test_personal_api_key_scopes():
+    tok, _ = create_random_company()
+    admin = AdminApi(token=tok)
+
+    s, d = admin.create_api_key("reports", ["manage_users"], expires_in_days=30)
+    assert s == 201
+    assert d["key"].startswith("bqk_")
+    assert d["key"].startswith(d["prefix"])
+
+    api = AdminApi(token=d["key"])
+    s, _ = api.list_users()
+    assert s == 200
+
+    # Outside of the key scopes, even though the owner is an admin.
+    s, _ = api.list_roles()
+    assert s == 403
+
+    # Keys can't mint new keys.
+    s, _ = api.create_api_key("more", [])
+    assert s == 403
+
+    # Nor take over the account they act for.
+    s, _ = api.patch_profile({"name": "Mallory"})
+    assert s == 403
+    s, _ = api.export_data()
+    assert s == 403
+
+    s, keys = admin.list_api_keys()
+    assert s == 200
+    assert keys[0]["last_used_at"] is not None
+
+    s, _ = admin.delete_api_key(d["id"])
+    assert s == 204
+
+    s, _ = api.list_users()
+    assert s == 403
+
+
+def test_api_key_limited_to_owner_permissions():
+    tok, domain = create_random_company()
+
+    api = create_random_user_api(domain)
+    s, _ = api.register()
+    assert s == 201
+
+    s, _ = api.create_api_key("sneaky", ["manage_users"])
+    assert s == 403
+
+    s, d = api.create_api_key("kiosk", [])
+    assert s == 201
+
+    s, _ = AdminApi(token=d["key"]).get_profile()
+    assert s == 200
+
+
+def test_service_account():
+    tok, _ = create_random_company()
+    admin = AdminApi(token=tok)
+
+    s, account = admin.create_service_account("Kiosk", builtin_role(admin, "ADMIN"))
+    assert s == 201
+
+    s, d = admin.create_service_account_key(account["id"], "kiosk", ["manage_users"])
+    assert s == 201
+
+    kiosk = AdminApi(token=d["key"])
+    s, users = kiosk.list_users()
+    assert s == 200
+    assert account["id"] not in [u["id"] for u in users]
+
+    s, keys = admin.list_company_api_keys()
+    assert s == 200
+    assert d["id"] in [k["id"] for k in keys]
+
+    s, _ = admin.delete_service_account(account["id"])
+    assert s == 204
+
+    s, _ = kiosk.list_users()
+    assert s == 403
+
+
+def test_service_accounts_limited_to_actor_permissions():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+
+    api = create_random_user_api(domain)
+    s, _ = api.register()
+    assert s == 201
+    s, role = admin.create_role(rand_n_str(10), ["book", "manage_users"])
+    assert s == 201
+    user_id = jwt.decode(api.token, options={"verify_signature": False})["user_id"]
+    s, _ = admin.assign_role(user_id, role["id"])
+    assert s == 200
+    manager = AdminApi(token=api.token)
+
+    s, _ = manager.create_service_account("Kiosk", builtin_role(admin, "ADMIN"))
+    assert s == 403
+
+    s, account = admin.create_service_account("Kiosk", builtin_role(admin, "ADMIN"))
+    assert s == 201
+    s, _ = manager.create_service_account_key(account["id"], "kiosk", ["manage_company"])
+    assert s == 403
+    s, _ = manager.create_service_account_key(account["id"], "kiosk", ["manage_users"])
+    assert s == 201
@@ -0,0 +1,80 @@
+from bookit.admin import AdminApi
+from bookit.items_collection import Point, TABLE_HOR
+from .util import bookers, create_random_company, open_space, tomorrow
+
+
+def test_attributes_and_search():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    (booker,), _ = bookers(admin, domain, 1)
+    building, coworking, item = open_space(admin)
+    place = (building["id"], coworking["id"])
+    s, wide = admin.new_item(None, TABLE_HOR)
+    assert s == 201
+    s, wide_item = admin.place_item(*place, wide["id"], Point(4, 4))
+    assert s == 201
+
+    s, _ = admin.create_attribute("monitors", "Monitors", "number")
+    assert s == 201
+    s, _ = admin.create_attribute("standing", "Standing desk", "boolean")
+    assert s == 201
+    s, _ = admin.create_attribute("zone", "Zone", "enum", ["quiet", "loud"])
+    assert s == 201
+    s, _ = admin.create_attribute("monitors", "Screens", "number")
+    assert s == 409
+    s, _ = admin.create_attribute("zone2", "Zone", "enum")
+    assert s == 400
+    s, _ = admin.create_attribute("Bad key", "Bad", "boolean")
+    assert s == 400
+
+    s, attributes = booker.list_attributes()
+    assert s == 200
+    assert [a["key"] for a in attributes] == ["monitors", "standing", "zone"]
+
+    # Types give the values, placed items override them.
+    s, values = admin.put_item_type_attributes(item["item_id"], {"monitors": 1, "zone": "quiet"})
+    assert s == 200 and values == {"monitors": 1, "zone": "quiet"}
+    s, _ = admin.put_item_type_attributes(wide["id"], {"monitors": 2, "standing": True})
+    assert s == 200
+    s, values = admin.put_item_attributes(*place, item["id"], {"monitors": 3})
+    assert s == 200 and values == {"monitors": 3, "zone": "quiet"}
+    s, _ = admin.put_item_type_attributes(wide["id"], {"zone": "noisy"})
+    assert s == 400
+    s, _ = admin.put_item_type_attributes(wide["id"], {"sofa": True})
+    assert s == 400
+
+    s, items = booker.get_coworking_items(*place)
+    assert s == 200
+    assert {i["id"]: i["attributes"]["monitors"] for i in items} == {item["id"]: 3, wide_item["id"]: 2}
+
+    start, end = tomorrow()
+    s, found = booker.available_items(start, end, "monitors>=2")
+    assert s == 200
+    assert {i["id"] for i in found} == {item["id"], wide_item["id"]}
+    s, found = booker.available_items(start, end, "monitors>=2", "standing=true")
+    assert s == 200
+    assert [i["id"] for i in found] == [wide_item["id"]]
+    assert found[0]["coworking_id"] == coworking["id"] and found[0]["building_id"] == building["id"]
+    s, found = booker.available_items(start, end, "standing=false", "zone!=loud")
+    assert s == 200
+    assert [i["id"] for i in found] == [item["id"]]
+
+    s, _ = booker.available_items(start, end, "standing>=1")
+    assert s == 400
+    s, _ = booker.available_items(start, end, "sofa=true")
+    assert s == 400
+    s, _ = booker.available_items(end, start)
+    assert s == 400
+
+    # Booked items are not available.
+    s, _ = booker.create_booking(coworking["id"], wide_item["id"], start, end)
+    assert s == 201
+    s, found = booker.available_items(start, end, "monitors>1", coworking_id=coworking["id"])
+    assert s == 200
+    assert [i["id"] for i in found] == [item["id"]]
+
+    s, _ = admin.delete_attribute(attributes[0]["id"])
+    assert s == 204
+    s, items = booker.get_coworking_items(*place)
+    assert s == 200
+    assert all("monitors" not in i["attributes"] for i in items)
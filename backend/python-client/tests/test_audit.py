@@ -0,0 +1,83 @@
+import csv
+import io
+
+from bookit.admin import AdminApi
+from .util import create_random_company, create_random_user_api
+
+
+def test_audit_log_records_changes():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+
+    s, building = admin.new_place("Main st. 1")
+    assert s == 201
+
+    s, role = admin.create_role("Auditors", ["book"])
+    assert s == 201
+    s, _ = admin.patch_role(role["id"], {"permissions": ["book", "view_analytics"]})
+    assert s == 200
+
+    s, _ = admin.put_sso({
+        "issuer": "https://idp.example.com",
+        "client_id": "booq",
+        "client_secret": "very-secret"
+    })
+    assert s == 200
+
+    s, entries = admin.list_audit()
+    assert s == 200
+    actions = [e["action"] for e in entries]
+    assert actions[:4] == ["sso_provider.put", "role.update", "role.create", "building.create"]
+    assert actions[-1] == "company.create"
+
+    update = entries[1]
+    assert update["entity"] == "role"
+    assert update["entity_id"] == role["id"]
+    # Only the changed fields are kept.
+    assert update["before"] == {"permissions": ["book"]}
+    assert update["after"] == {"permissions": ["book", "view_analytics"]}
+    assert update["request_id"]
+
+    # Secrets never make it into the log.
+    assert "client_secret" not in entries[0]["after"]
+
+    s, entries = admin.list_audit(entity="building", entity_id=building["id"])
+    assert s == 200
+    assert len(entries) == 1
+    assert entries[0]["after"]["address"] == "Main st. 1"
+
+    s, entries = admin.list_audit(limit=1, offset=1)
+    assert s == 200
+    assert [e["action"] for e in entries] == ["role.update"]
+
+    s, _ = admin.list_audit(limit=0)
+    assert s == 400
+
+
+def test_audit_log_export():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+
+    s, role = admin.create_role("Exported", [])
+    assert s == 201
+    s, _ = admin.delete_role(role["id"])
+    assert s == 204
+
+    s, data = admin.export_audit(entity="role")
+    assert s == 200
+    rows = list(csv.DictReader(io.StringIO(data)))
+    assert [r["action"] for r in rows] == ["role.delete", "role.create"]
+    assert rows[0]["entity_id"] == role["id"]
+    assert '"Exported"' in rows[0]["before"]
+
+    s, data = admin.export_audit(format="json", action="role.delete")
+    assert s == 200
+    assert len(data) == 1
+    assert data[0]["after"] is None
+
+    # Only admins can read the log.
+    api = create_random_user_api(domain)
+    s, _ = api.register()
+    assert s == 201
+    s, _ = AdminApi(token=api.token).list_audit()
+    assert s == 403
@@ -0,0 +1,58 @@
+from json import dumps
+
+from bookit.admin import AdminApi
+from .util import bookers, create_random_company, open_space, tomorrow
+
+
+def test_foreign_booking_is_untouched():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    (owner, intruder), _ = bookers(admin, domain, 2)
+    building, coworking, item = open_space(admin)
+
+    s, booking = owner.create_booking(coworking["id"], item["id"], *tomorrow())
+    assert s == 201
+    s, _ = intruder.create_booking(coworking["id"], item["id"], *tomorrow(2))
+    assert s == 201
+
+    # Moving onto the intruder's own booking would conflict, but ownership comes first.
+    s, _ = intruder.delete_booking(booking["id"])
+    assert s == 403
+    start, end = tomorrow(2)
+    s, _ = intruder.patch_booking(booking["id"], {"time_start": start, "time_end": end})
+    assert s == 403
+
+    tok, _ = create_random_company()
+    other = AdminApi(token=tok)
+    s, _ = other.delete_booking(booking["id"])
+    assert s == 404
+    s, _ = other.patch_booking(booking["id"], {"time_start": start, "time_end": end})
+    assert s == 404
+
+    s, bookings = admin.coworking_bookings(building["id"], coworking["id"])
+    assert s == 200
+    unchanged = next(b for b in bookings if b["id"] == booking["id"])
+    assert unchanged["time_start"] == booking["time_start"]
+
+    # Admins manage every booking of the company.
+    s, _ = admin.delete_booking(booking["id"])
+    assert s == 204
+    s, _ = owner.delete_booking(booking["id"])
+    assert s == 404
+
+
+def test_foreign_user_is_untouched():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    _, (user_id,) = bookers(admin, domain, 1)
+
+    tok, _ = create_random_company()
+    other = AdminApi(token=tok)
+    s, _ = other.patch_user(user_id, dumps({"name": "Mallory"}))
+    assert s == 404
+    s, _ = other.delete_user(user_id)
+    assert s == 404
+
+    s, user = admin.get_user(user_id)
+    assert s == 200
+    assert user["name"] != "Mallory"
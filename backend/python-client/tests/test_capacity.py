@@ -0,0 +1,101 @@
+from concurrent.futures import ThreadPoolExecutor
+
+from bookit.admin import AdminApi
+from bookit.items_collection import Point, LOUNGE, MEETING_ROOM, TOILET
+from .util import bookers, create_random_company, open_space, tomorrow
+
+
+def test_meeting_room_and_lounge():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    apis, _ = bookers(admin, domain, 4)
+    building, coworking, _ = open_space(admin)
+    place = (building["id"], coworking["id"])
+
+    s, room_type = admin.new_item(None, MEETING_ROOM)
+    assert s == 201
+    assert (room_type["booking_mode"], room_type["capacity"]) == ("exclusive", 8)
+    s, lounge_type = admin.new_item(None, LOUNGE)
+    assert s == 201
+    assert (lounge_type["booking_mode"], lounge_type["capacity"]) == ("shared", 3)
+    s, room = admin.place_item(*place, room_type["id"], Point(3, 3))
+    assert s == 201
+    s, lounge = admin.place_item(*place, lounge_type["id"], Point(6, 6))
+    assert s == 201
+
+    # The room takes one booking at a time, as many people as fit.
+    start, end = tomorrow()
+    s, booking = apis[0].create_booking(coworking["id"], room["id"], start, end, attendees=6)
+    assert s == 201 and booking["attendees"] == 6
+    s, _ = apis[1].create_booking(coworking["id"], room["id"], start, end, attendees=1)
+    assert s == 409
+    s, _ = apis[1].create_booking(coworking["id"], room["id"], *tomorrow(2), attendees=9)
+    assert s == 400
+    s, _ = apis[0].patch_booking(booking["id"], {"attendees": 9})
+    assert s == 400
+
+    # The lounge takes overlapping bookings until its seats run out.
+    s, _ = apis[0].create_booking(coworking["id"], lounge["id"], start, end, attendees=2)
+    assert s == 201
+    s, found = apis[1].available_items(start, end, coworking_id=coworking["id"], attendees=2)
+    assert s == 200
+    assert lounge["id"] not in {i["id"] for i in found}
+    s, found = apis[1].available_items(start, end, coworking_id=coworking["id"])
+    assert s == 200
+    assert lounge["id"] in {i["id"] for i in found} and room["id"] not in {i["id"] for i in found}
+    s, _ = apis[1].create_booking(coworking["id"], lounge["id"], start, end, attendees=2)
+    assert s == 409
+    s, _ = apis[1].create_booking(coworking["id"], lounge["id"], start, end)
+    assert s == 201
+
+    # Racing bookings for the last seat, only one gets it.
+    later_start, later_end = tomorrow(3)
+    s, _ = apis[0].create_booking(coworking["id"], lounge["id"], later_start, later_end, attendees=2)
+    assert s == 201
+    # Shared items only show up busy once the seats are taken.
+    s, _, svg = apis[0].render_layout(*place, at=later_start)
+    assert s == 200 and b"#c62828" not in svg
+    with ThreadPoolExecutor(3) as pool:
+        results = list(pool.map(
+            lambda api: api.create_booking(coworking["id"], lounge["id"], later_start, later_end)[0],
+            apis[1:],
+        ))
+    assert sorted(results) == [201, 409, 409]
+    s, _, svg = apis[0].render_layout(*place, at=later_start)
+    assert s == 200 and b"#c62828" in svg
+
+    s, bookings = admin.coworking_bookings(*place)
+    assert s == 200
+    assert sum(b["attendees"] for b in bookings if b["coworking_item_id"] == lounge["id"]) == 6
+
+    # The type can't change under bookings that wouldn't fit anymore.
+    s, impact = admin.item_type_impact(lounge_type["id"], {"booking_mode": "exclusive"})
+    assert s == 200
+    assert impact["overbooked"] == [lounge["id"]]
+    s, _ = admin.patch_item_type(lounge_type["id"], {"booking_mode": "exclusive"})
+    assert s == 409
+    s, _ = admin.patch_item_type(lounge_type["id"], {"capacity": 2})
+    assert s == 409
+    s, impact = admin.item_type_impact(lounge_type["id"], {"capacity": 4})
+    assert s == 200 and impact["overbooked"] == []
+    s, _ = admin.patch_item_type(lounge_type["id"], {"capacity": 4})
+    assert s == 200
+    s, _ = admin.create_booking(coworking["id"], lounge["id"], start, end)
+    assert s == 201
+
+    # Nor can the lounge itself get a type they don't fit, moved on its own or published with a draft.
+    s, toilet_type = admin.new_item(None, TOILET)
+    assert s == 201
+    for type_id in (room_type["id"], toilet_type["id"]):
+        s, _ = admin.patch_placed_item(*place, lounge["id"], {"item_id": type_id})
+        assert s == 409
+    s, draft = admin.new_layout_draft(*place)
+    assert s == 201
+    items = [
+        {k: v for k, v in i.items() if k != "footprint"} | ({"item_id": room_type["id"]} if i["key"] == lounge["id"] else {})
+        for i in draft["items"]
+    ]
+    s, _ = admin.put_draft_items(*place, draft["version"]["id"], items)
+    assert s == 200
+    s, _ = admin.publish_layout(*place, draft["version"]["id"])
+    assert s == 409
@@ -1,5 +1,8 @@
-from .util import create_company, rand_n_str
+from bookit.admin import AdminApi
+from bookit.company import CompanyApi
+from .util import create_company, create_random_company, create_random_user_api, rand_n_str
 
+LOGO = b"\x89PNG\r\n\x1a\nnot really a png"
 
 
 # Covers usage
@@ -13,3 +16,68 @@ def test_create_company():
 
     status_code, data = create_company(domain, name)
     assert status_code == 409
+
+
+def test_company_profile():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    api = create_random_user_api(domain)
+    s, _ = api.register()
+    assert s == 201
+
+    s, company = admin.get_company()
+    assert s == 200
+    assert company["domain"] == domain
+    assert company["avatar"] is None
+
+    s, _ = AdminApi(token=api.token).get_company()
+    assert s == 403
+    s, _ = admin.patch_company({"primary_color": "blue"})
+    assert s == 400
+
+    s, company = admin.patch_company({"name": "Renamed", "primary_color": "#112233"}, logo=LOGO)
+    assert s == 200
+    assert company["name"] == "Renamed"
+    assert company["primary_color"] == "#112233"
+    assert company["accent_color"] is None
+
+    s, branding = CompanyApi().get_branding(domain)
+    assert s == 200
+    assert branding == {
+        "name": "Renamed",
+        "logo": company["avatar"],
+        "primary_color": "#112233",
+        "accent_color": None,
+    }
+    s, logo = CompanyApi().get_logo(company["id"])
+    assert s == 200
+    assert logo == LOGO
+
+
+def test_company_domain_change():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    api = create_random_user_api(domain)
+    s, _ = api.register()
+    assert s == 201
+
+    _, taken = create_random_company()
+    s, _ = admin.patch_company({"domain": taken})
+    assert s == 409
+
+    new_domain = rand_n_str(10)
+    s, company = admin.patch_company({"domain": new_domain})
+    assert s == 200
+    assert company["domain"] == new_domain
+
+    s, _ = api.login()
+    assert s == 404
+    api.user.company_domain = new_domain
+    s, _ = api.login()
+    assert s == 200
+    s, profile = api.patch_profile({"name": "Lol"})
+    assert s == 200
+    assert profile["company_domain"] == new_domain
+
+    s, _ = CompanyApi().get_branding(domain)
+    assert s == 404
@@ -0,0 +1,70 @@
+from bookit.admin import AdminApi
+from .util import bookers, create_random_company
+
+
+def test_building_floors():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    (member,), _ = bookers(admin, domain, 1)
+
+    s, building = admin.new_place("Floors st. 6")
+    assert s == 201
+
+    # Buildings start with a ground floor that takes coworkings by default.
+    s, floors = member.list_floors(building["id"])
+    assert s == 200
+    assert [(f["number"], f["name"]) for f in floors] == [(0, "Ground floor")]
+    ground = floors[0]
+
+    s, lobby = admin.new_coworking(building["id"], "Lobby", 5, 5)
+    assert s == 201
+    assert lobby["floor_id"] == ground["id"]
+
+    s, second = admin.new_floor(building["id"], 2, "Second floor")
+    assert s == 201
+    s, basement = admin.new_floor(building["id"], -1, "Parking", display_order=10)
+    assert s == 201
+    s, _ = admin.new_floor(building["id"], 2, "Duplicate")
+    assert s == 409
+
+    s, floors = member.list_floors(building["id"])
+    assert s == 200
+    assert [f["id"] for f in floors] == [ground["id"], second["id"], basement["id"]]
+
+    s, zone = admin.new_coworking(building["id"], "Zone A", 10, 10, floor_id=second["id"])
+    assert s == 201
+    s, coworkings = member.list_floor_coworkings(building["id"], second["id"])
+    assert s == 200
+    assert [c["id"] for c in coworkings] == [zone["id"]]
+
+    # Coworkings move between floors of their own building only.
+    s, other = admin.new_place("Floors st. 7")
+    assert s == 201
+    s, floors = member.list_floors(other["id"])
+    assert s == 200
+    s, _ = admin.patch_coworking(building["id"], zone["id"], {"floor_id": floors[0]["id"]})
+    assert s == 404
+    s, moved = admin.patch_coworking(building["id"], zone["id"], {"floor_id": basement["id"]})
+    assert s == 200
+    assert moved["floor_id"] == basement["id"]
+
+    s, _ = admin.delete_floor(building["id"], basement["id"])
+    assert s == 409
+
+    # Coworkings in the trash still hold the floor.
+    s, _ = admin.delete_coworking(building["id"], zone["id"])
+    assert s == 204
+    s, _ = admin.delete_floor(building["id"], basement["id"])
+    assert s == 409
+    s, _ = admin.restore("coworking", zone["id"])
+    assert s == 204
+    s, _ = admin.delete_floor(building["id"], second["id"])
+    assert s == 204
+    s, renamed = admin.patch_floor(building["id"], basement["id"], {"name": "Garage", "display_order": -1})
+    assert s == 200
+    s, floors = member.list_floors(building["id"])
+    assert s == 200
+    assert [f["name"] for f in floors] == ["Garage", "Ground floor"]
+
+    s, _ = AdminApi(token=member.token).new_floor(building["id"], 3, "Third floor")
+    assert s == 403
@@ -0,0 +1,33 @@
+from bookit.admin import AdminApi
+from .util import bookers, create_random_company, open_space, tomorrow
+
+
+def test_other_company_is_invisible():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    (member,), (member_id,) = bookers(admin, domain, 1)
+    building, coworking, item = open_space(admin)
+    s, booking = member.create_booking(coworking["id"], item["id"], *tomorrow())
+    assert s == 201
+
+    tok, _ = create_random_company()
+    other = AdminApi(token=tok)
+
+    s, _ = other.get_user(member_id)
+    assert s == 404
+    s, _ = other.delete_user(member_id)
+    assert s == 404
+    s, _ = other.delete_booking(booking["id"])
+    assert s == 404
+    s, _ = other.delete_place(building["id"])
+    assert s == 404
+    s, users = other.list_users()
+    assert s == 200
+    assert member_id not in [u["id"] for u in users]
+
+    # Nothing was touched.
+    s, user = admin.get_user(member_id)
+    assert s == 200
+    s, bookings = admin.coworking_bookings(building["id"], coworking["id"])
+    assert s == 200
+    assert [b["id"] for b in bookings] == [booking["id"]]
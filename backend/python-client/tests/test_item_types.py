@@ -0,0 +1,58 @@
+from bookit.admin import AdminApi
+from bookit.items_collection import Point, TABLE_SMALL
+from .util import bookers, create_random_company, open_space, tomorrow
+
+
+def test_edit_placed_type():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    (user,), _ = bookers(admin, domain, 1)
+    building, coworking, item = open_space(admin)
+    place = (building["id"], coworking["id"])
+    item_type_id = item["item_id"]
+
+    s, booking = user.create_booking(coworking["id"], item["id"], *tomorrow())
+    assert s == 201
+    s, neighbour = admin.place_item(*place, item_type_id, Point(3, 1))
+    assert s == 201
+
+    s, item_type = admin.patch_item_type(item_type_id, {"name": "Desk", "color": "ff0000"})
+    assert s == 200
+    assert item_type["name"] == "Desk" and item_type["color"] == "ff0000"
+    assert item_type["description"] == TABLE_SMALL.description
+
+    # Three cells wide, the desk at (1, 1) would run into the one at (3, 1).
+    wide = {"offsets": [{"x": 0, "y": 0}, {"x": 1, "y": 0}, {"x": 2, "y": 0}]}
+    s, impact = admin.item_type_impact(item_type_id, wide)
+    assert s == 200
+    assert impact["placed_items"] == 2 and impact["coworkings"] == 1
+    assert impact["future_bookings"] == 1
+    assert [(c["coworking_item_id"], c["reason"], c["other_item_id"]) for c in impact["collisions"]] == [
+        (item["id"], "item", neighbour["id"])
+    ]
+    s, _ = admin.patch_item_type(item_type_id, wide)
+    assert s == 409
+
+    s, _ = admin.delete_placed_item(*place, neighbour["id"])
+    assert s == 204
+    s, impact = admin.item_type_impact(item_type_id, wide)
+    assert s == 200
+    assert impact["collisions"] == []
+    s, item_type = admin.patch_item_type(item_type_id, wide)
+    assert s == 200
+    assert len(item_type["offsets"]) == 3
+
+    s, items = admin.get_coworking_items(*place)
+    assert s == 200
+    assert [sorted((p["x"], p["y"]) for p in i["footprint"]) for i in items] == [[(1, 1), (2, 1), (3, 1)]]
+    s, bookings = admin.coworking_bookings(*place)
+    assert s == 200
+    assert [b["id"] for b in bookings] == [booking["id"]]
+
+    s, _ = admin.patch_item_type(item_type_id, {"offsets": []})
+    assert s == 400
+
+    tok, _ = create_random_company()
+    other = AdminApi(token=tok)
+    s, _ = other.patch_item_type(item_type_id, {"name": "Mine"})
+    assert s == 404
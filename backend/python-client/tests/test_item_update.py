@@ -0,0 +1,45 @@
+from bookit.admin import AdminApi
+from bookit.items_collection import Point, TABLE_HOR
+from .util import bookers, create_random_company, open_space, tomorrow
+
+
+def test_move_keeps_bookings():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    (user,), _ = bookers(admin, domain, 1)
+    building, coworking, item = open_space(admin)
+    place = (building["id"], coworking["id"])
+
+    s, booking = user.create_booking(coworking["id"], item["id"], *tomorrow())
+    assert s == 201
+    s, wide = admin.new_item(None, TABLE_HOR)
+    assert s == 201
+    s, neighbour = admin.place_item(*place, wide["id"], Point(4, 4))
+    assert s == 201
+
+    s, moved = admin.patch_placed_item(*place, item["id"], {"base_point": {"x": 0, "y": 1}, "name": "Window"})
+    assert s == 200
+    assert moved["id"] == item["id"]
+    assert (moved["base_point"]["x"], moved["base_point"]["y"]) == (0, 1)
+    assert moved["name"] == "Window" and moved["description"] == item["description"]
+
+    # The item itself doesn't count as taken, the neighbour and the borders do.
+    s, _ = admin.patch_placed_item(*place, item["id"], {"base_point": {"x": 0, "y": 2}})
+    assert s == 200
+    s, _ = admin.patch_placed_item(*place, item["id"], {"base_point": {"x": 4, "y": 4}})
+    assert s == 409
+    s, _ = admin.patch_placed_item(*place, neighbour["id"], {"base_point": {"x": 9, "y": 0}})
+    assert s == 409
+    s, _ = admin.patch_placed_item(*place, item["id"], {"rotation": 45})
+    assert s == 400
+
+    s, swapped = admin.patch_placed_item(*place, item["id"], {"item_id": wide["id"], "rotation": 90})
+    assert s == 200
+    assert swapped["item_id"] == wide["id"] and swapped["rotation"] == 90
+
+    s, bookings = admin.coworking_bookings(*place)
+    assert s == 200
+    assert [b["id"] for b in bookings] == [booking["id"]]
+
+    s, _ = admin.patch_placed_item(*place, booking["id"], {"name": "Ghost"})
+    assert s == 404
@@ -0,0 +1,69 @@
+from bookit.admin import AdminApi
+from bookit.items_collection import Point, TABLE_HOR
+from .util import create_random_company, open_space
+
+
+def test_export_import_clone():
+    tok, _ = create_random_company()
+    admin = AdminApi(token=tok)
+    building, coworking, item = open_space(admin)
+    place = (building["id"], coworking["id"])
+    s, wide = admin.new_item(None, TABLE_HOR)
+    assert s == 201
+    s, _ = admin.place_item(*place, wide["id"], Point(4, 4))
+    assert s == 201
+    s, _ = admin.put_obstacles(*place, [("pillar", [Point(8, 8)])])
+    assert s == 200
+    # Imports take the names the item type endpoints do.
+    s, _ = admin.patch_item_type(item["item_id"], {"name": "Desk"})
+    assert s == 200
+    s, _ = admin.patch_item_type(wide["id"], {"name": "Wide desk"})
+    assert s == 200
+
+    s, document = admin.export_layout(*place)
+    assert s == 200
+    assert document["schema_version"] == 1
+    assert (document["coworking"]["width"], document["coworking"]["height"]) == (10, 10)
+    assert {t["name"] for t in document["item_types"]} == {"Desk", "Wide desk"}
+    assert len(document["items"]) == 2 and len(document["obstacles"]) == 1
+
+    # Another company has none of the item types, they're created on import.
+    other_tok, _ = create_random_company()
+    other = AdminApi(token=other_tok)
+    s, other_building = other.new_place("Import st. 1")
+    assert s == 201
+
+    s, report = other.import_layout(other_building["id"], document, dry_run=True)
+    assert s == 200
+    assert report["dry_run"] and report["coworking"] is None
+    assert all(t["created"] and t["item_type_id"] is None for t in report["item_types"])
+    s, coworkings = other.list_coworkings(other_building["id"])
+    assert s == 200 and coworkings == []
+
+    s, report = other.import_layout(other_building["id"], document, address="Copy")
+    assert s == 201
+    assert report["coworking"]["address"] == "Copy" and report["items"] == 2
+    s, layout = other.get_coworking_layout(other_building["id"], report["coworking"]["id"])
+    assert s == 200
+    assert len(layout["items"]) == 2 and len(layout["obstacles"]) == 1
+
+    # Cloning maps the item types onto themselves.
+    s, report = admin.clone_coworking(*place)
+    assert s == 201
+    assert not any(t["created"] or t["offsets_differ"] for t in report["item_types"])
+    assert {t["item_type_id"] for t in report["item_types"]} == {item["item_id"], wide["id"]}
+    assert report["coworking"]["id"] != coworking["id"]
+
+    # Everything is checked before anything is saved.
+    broken = dict(document, items=document["items"] + [dict(document["items"][0], item_type="Sofa")])
+    s, _ = other.import_layout(other_building["id"], broken, dry_run=True)
+    assert s == 400
+    overlapping = dict(document, items=document["items"] + [document["items"][0]])
+    s, _ = other.import_layout(other_building["id"], overlapping, dry_run=True)
+    assert s == 409
+    s, _ = other.import_layout(other_building["id"], dict(document, schema_version=2), dry_run=True)
+    assert s == 400
+    for bad in ({"name": "Extra wide desk"}, {"color": "teal"}, {"offsets": []}):
+        types = [dict(document["item_types"][0], **bad)] + document["item_types"][1:]
+        s, _ = other.import_layout(other_building["id"], dict(document, item_types=types), dry_run=True)
+        assert s == 400
@@ -0,0 +1,173 @@
+from datetime import datetime, timedelta
+
+from bookit.admin import AdminApi
+from .util import bookers, create_random_company, open_space, tomorrow
+
+
+def draft_item(item, x, y, key=True):
+    draft = {
+        "item_id": item["item_id"],
+        "name": item["name"],
+        "description": item["description"],
+        "base_point": {"x": x, "y": y},
+        "rotation": item["rotation"],
+        "mirrored": item["mirrored"],
+    }
+    if key:
+        draft["key"] = item["id"]
+    return draft
+
+
+def test_bookings_follow_items():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    (user,), _ = bookers(admin, domain, 1)
+    building, coworking, item = open_space(admin)
+    place = (building["id"], coworking["id"])
+
+    s, booking = user.create_booking(coworking["id"], item["id"], *tomorrow())
+    assert s == 201
+
+    s, draft = admin.new_layout_draft(*place)
+    assert s == 201
+    assert draft["version"]["status"] == "draft"
+    assert [i["key"] for i in draft["items"]] == [item["id"]]
+    version_id = draft["version"]["id"]
+
+    # Items can't overlap in a draft either.
+    s, _ = admin.put_draft_items(*place, version_id, [draft_item(item, 5, 5), draft_item(item, 5, 5, key=False)])
+    assert s == 409
+    s, draft = admin.put_draft_items(*place, version_id, [draft_item(item, 5, 5), draft_item(item, 1, 1, key=False)])
+    assert s == 200
+    assert len(draft["items"]) == 2
+
+    s, diff = admin.layout_diff(*place, version_id)
+    assert s == 200
+    assert [i["key"] for i in diff["changed"]] == [item["id"]]
+    assert len(diff["added"]) == 1
+    assert diff["removed"] == [] and diff["affected_bookings"] == []
+
+    # Nothing goes live until the draft is published.
+    s, items = admin.get_coworking_items(*place)
+    assert s == 200
+    assert [(i["base_point"]["x"], i["base_point"]["y"]) for i in items] == [(1, 1)]
+
+    s, version = admin.publish_layout(*place, version_id)
+    assert s == 200
+    assert version["status"] == "published"
+    s, _ = admin.publish_layout(*place, version_id)
+    assert s == 409
+
+    s, items = admin.get_coworking_items(*place)
+    assert s == 200
+    moved = next(i for i in items if i["id"] == item["id"])
+    assert (moved["base_point"]["x"], moved["base_point"]["y"]) == (5, 5)
+    s, bookings = admin.coworking_bookings(*place)
+    assert s == 200
+    assert [b["id"] for b in bookings] == [booking["id"]]
+
+    # Dropping the booked desk shows up before it's published.
+    s, draft = admin.new_layout_draft(*place)
+    assert s == 201
+    version_id = draft["version"]["id"]
+    kept = [i for i in items if i["id"] != item["id"]]
+    s, _ = admin.put_draft_items(*place, version_id, [draft_item(i, 1, 1) for i in kept])
+    assert s == 200
+    s, diff = admin.layout_diff(*place, version_id)
+    assert s == 200
+    assert [i["key"] for i in diff["removed"]] == [item["id"]]
+    assert [b["id"] for b in diff["affected_bookings"]] == [booking["id"]]
+
+    publish_at = (datetime.now() + timedelta(days=7)).replace(microsecond=0).isoformat()
+    s, version = admin.publish_layout(*place, version_id, publish_at)
+    assert s == 200
+    assert version["status"] == "scheduled"
+    s, _ = admin.put_draft_items(*place, version_id, [])
+    assert s == 409
+    s, _ = admin.delete_layout_version(*place, version_id)
+    assert s == 204
+
+    s, versions = admin.list_layout_versions(*place)
+    assert s == 200
+    assert [v["status"] for v in versions] == ["published", "archived"]
+
+    # Rolling back brings the desk back to where it was, with its booking.
+    s, _ = admin.rollback_layout(*place, versions[0]["id"])
+    assert s == 409
+    s, version = admin.rollback_layout(*place, versions[1]["id"])
+    assert s == 200
+    assert version["status"] == "published"
+    s, items = admin.get_coworking_items(*place)
+    assert s == 200
+    assert [(i["base_point"]["x"], i["base_point"]["y"]) for i in items] == [(1, 1)]
+    s, bookings = admin.coworking_bookings(*place)
+    assert s == 200
+    assert [b["id"] for b in bookings] == [booking["id"]]
+
+
+def test_removed_items_are_retired():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    (user,), _ = bookers(admin, domain, 1)
+    building, coworking, item = open_space(admin)
+    place = (building["id"], coworking["id"])
+
+    s, team = admin.create_team("Desk owners")
+    assert s == 201
+    s, _ = admin.reserve_item(*place, item["id"], [team["id"]])
+    assert s == 200
+    s, versions = admin.list_layout_versions(*place)
+    assert s == 200
+    with_desk = versions[0]["id"]
+
+    s, draft = admin.new_layout_draft(*place)
+    assert s == 201
+    s, _ = admin.put_draft_items(*place, draft["version"]["id"], [])
+    assert s == 200
+    s, _ = admin.publish_layout(*place, draft["version"]["id"])
+    assert s == 200
+
+    s, items = admin.get_coworking_items(*place)
+    assert s == 200
+    assert items == []
+    s, _ = user.create_booking(coworking["id"], item["id"], *tomorrow())
+    assert s == 404
+    s, _ = admin.patch_placed_item(*place, item["id"], {"name": "Gone"})
+    assert s == 404
+
+    # The desk comes back as it was, not as a new item.
+    s, _ = admin.rollback_layout(*place, with_desk)
+    assert s == 200
+    s, items = admin.get_coworking_items(*place)
+    assert s == 200
+    assert [(i["id"], i["teams"]) for i in items] == [(item["id"], [team["id"]])]
+
+
+def test_put_items_keeps_placed_items():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    (user,), _ = bookers(admin, domain, 1)
+    building, coworking, item = open_space(admin)
+    place = (building["id"], coworking["id"])
+
+    s, booking = user.create_booking(coworking["id"], item["id"], *tomorrow())
+    assert s == 201
+
+    moved = {**draft_item(item, 3, 3, key=False), "id": item["id"]}
+    s, items = admin.put_items(*place, [moved, draft_item(item, 6, 6, key=False)])
+    assert s == 201
+    assert len(items) == 2 and item["id"] in [i["id"] for i in items]
+    s, bookings = admin.coworking_bookings(*place)
+    assert s == 200
+    assert [b["id"] for b in bookings] == [booking["id"]]
+
+    # Items left out go with their upcoming bookings, every put is a version to roll back to.
+    s, items = admin.put_items(*place, [])
+    assert s == 201
+    assert items == []
+    s, bookings = admin.coworking_bookings(*place)
+    assert s == 200
+    assert bookings == []
+    s, versions = admin.list_layout_versions(*place)
+    assert s == 200
+    assert [v["status"] for v in versions] == ["published", "archived", "archived"]
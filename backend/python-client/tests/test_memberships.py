@@ -0,0 +1,93 @@
+from json import dumps
+from uuid import uuid4
+
+from bookit.admin import AdminApi
+from bookit.user import User, UserApi
+from .util import create_random_company, rand_n_str
+
+
+def contractor_in_two_companies():
+    _, domain_a = create_random_company()
+    tok_b, domain_b = create_random_company()
+    email = rand_n_str(10) + "@179.ru"
+
+    api = UserApi(User(domain_a, email, "Con", "Tractor", "sup3rSecret!"))
+    s, _ = api.register()
+    assert s == 201
+    other = UserApi(User(domain_b, email, "Con", "Tractor", "an0therSecret!"))
+    s, _ = other.register()
+    assert s == 201
+
+    return api, other, AdminApi(token=tok_b)
+
+
+def test_link_and_switch_companies():
+    api, other, _ = contractor_in_two_companies()
+    domain_a, domain_b = api.user.company_domain, other.user.company_domain
+
+    # Separate accounts only see their own company.
+    s, data = api.login(any_company=True)
+    assert s == 200
+    assert [c["company_domain"] for c in data["companies"]] == [domain_a]
+
+    s, _ = api.link_company(domain_b, "wr0ngPassword!")
+    assert s == 403
+    s, companies = api.link_company(domain_b, other.user.password)
+    assert s == 200
+    assert [c["company_domain"] for c in companies] == [domain_a, domain_b]
+    assert [c["role_name"] for c in companies] == ["Guest", "Guest"]
+    s, _ = api.link_company(domain_b, other.user.password)
+    assert s == 409
+
+    # The linked account takes over the password.
+    s, _ = other.login()
+    assert s == 403
+    s, data = api.login(any_company=True)
+    assert s == 200
+    assert [c["company_domain"] for c in data["companies"]] == [domain_a, domain_b]
+    company_a, company_b = [c["company_id"] for c in data["companies"]]
+
+    s, profile = api.get_profile()
+    assert s == 200
+    assert profile["company_id"] == company_a
+
+    s, _ = api.switch_company(company_b)
+    assert s == 200
+    s, profile = api.get_profile()
+    assert s == 200
+    assert profile["company_id"] == company_b
+
+    s, companies = api.list_companies()
+    assert s == 200
+    assert [c["company_id"] for c in companies] == [company_a, company_b]
+
+    s, _ = api.switch_company(str(uuid4()))
+    assert s == 404
+
+
+def test_password_change_and_admin_reset():
+    api, other, admin_b = contractor_in_two_companies()
+    domain_b = other.user.company_domain
+
+    s, _ = api.link_company(domain_b, other.user.password)
+    assert s == 200
+
+    # Changing the own password changes it everywhere.
+    api.user.password = "n3wSecret!"
+    s, _ = api.patch_profile({"password": api.user.password})
+    assert s == 200
+    other.user.password = api.user.password
+    s, _ = other.login()
+    assert s == 200
+
+    # An admin setting the password unlinks the account from the other company.
+    s, users = admin_b.list_users()
+    user_id = next(u["id"] for u in users if u["email"] == api.user.email)
+    s, _ = admin_b.patch_user(user_id, dumps({"password": "adm1nSecret!"}))
+    assert s == 200
+
+    s, data = api.login(any_company=True)
+    assert s == 200
+    assert [c["company_domain"] for c in data["companies"]] == [api.user.company_domain]
+    s, _ = other.login()
+    assert s == 403
@@ -0,0 +1,47 @@
+from bookit.admin import AdminApi
+from bookit.items_collection import Point, TABLE_HOR
+from .util import create_random_company
+
+
+def test_l_shaped_room():
+    tok, _ = create_random_company()
+    admin = AdminApi(token=tok)
+    s, building = admin.new_place("Obstacles st. 1")
+    assert s == 201
+    s, coworking = admin.new_coworking(building["id"], "L room", 6, 6)
+    assert s == 201
+    s, item_type = admin.new_item(None, TABLE_HOR)
+    assert s == 201
+    s, desk = admin.place_item(building["id"], coworking["id"], item_type["id"], Point(0, 0))
+    assert s == 201
+
+    # The top right corner of the room is cut off by a wall, with a pillar in the middle.
+    corner = [Point(x, y) for x in range(3, 6) for y in range(3)]
+    s, _ = admin.put_obstacles(building["id"], coworking["id"], [("wall", corner + [Point(1, 0)])])
+    assert s == 409
+    s, _ = admin.put_obstacles(building["id"], coworking["id"], [("wall", [Point(6, 0)])])
+    assert s == 409
+    s, obstacles = admin.put_obstacles(building["id"], coworking["id"], [("wall", corner), ("pillar", [Point(2, 4)])])
+    assert s == 200
+    assert [o["kind"] for o in obstacles] == ["wall", "pillar"]
+
+    s, _ = admin.place_item(building["id"], coworking["id"], item_type["id"], Point(2, 1))
+    assert s == 409
+    s, _ = admin.place_item(building["id"], coworking["id"], item_type["id"], Point(1, 4))
+    assert s == 409
+    s, _ = admin.place_item(building["id"], coworking["id"], item_type["id"], Point(0, 5))
+    assert s == 201
+
+    s, layout = admin.get_coworking_layout(building["id"], coworking["id"])
+    assert s == 200
+    assert len(layout["items"]) == 2
+    wall = next(o for o in layout["obstacles"] if o["kind"] == "wall")
+    assert [(p["x"], p["y"]) for p in wall["cells"]] == [(p.x, p.y) for p in corner]
+
+    # Shrinking the room would leave the wall outside of it.
+    s, _ = admin.patch_coworking(building["id"], coworking["id"], {"width": 4})
+    assert s == 409
+    s, _ = admin.put_obstacles(building["id"], coworking["id"], [])
+    assert s == 200
+    s, _ = admin.patch_coworking(building["id"], coworking["id"], {"width": 4})
+    assert s == 200
@@ -0,0 +1,79 @@
+import io
+import json
+import zipfile
+
+from bookit.admin import AdminApi
+from bookit.company import CompanyApi
+from .util import create_random_company, create_random_user_api
+
+LOGO = b"\x89PNG\r\n\x1a\nnot really a png"
+
+
+def test_offboarding_requires_owner_and_confirmation():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    api = create_random_user_api(domain)
+    s, _ = api.register()
+    assert s == 201
+
+    s, _ = AdminApi(token=api.token).offboard_company(domain)
+    assert s == 403
+    s, _ = admin.offboard_company("wrongdomain")
+    assert s == 400
+    s, _ = admin.get_offboarding()
+    assert s == 404
+
+    s, scheduled = admin.offboard_company(domain)
+    assert s == 201
+    assert scheduled["company_domain"] == domain
+    assert scheduled["completed_at"] is None
+    assert scheduled["report"] is None
+    s, _ = admin.offboard_company(domain)
+    assert s == 409
+
+    s, offboarding = admin.get_offboarding()
+    assert s == 200
+    assert offboarding["id"] == scheduled["id"]
+    assert offboarding["purge_after"] == scheduled["purge_after"]
+
+    s, _ = admin.cancel_offboarding()
+    assert s == 204
+    s, _ = admin.get_offboarding()
+    assert s == 404
+    s, _ = CompanyApi().get_offboarding_export(scheduled["id"], scheduled["token"])
+    assert s == 404
+    s, report = CompanyApi().get_offboarding_report(scheduled["id"], scheduled["token"])
+    assert s == 200
+    assert report["cancelled_at"] is not None
+
+    s, _ = admin.offboard_company(domain)
+    assert s == 201
+
+
+def test_offboarding_export():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    api = create_random_user_api(domain)
+    s, _ = api.register()
+    assert s == 201
+    s, company = admin.patch_company(logo=LOGO)
+    assert s == 200
+
+    s, scheduled = admin.offboard_company(domain)
+    assert s == 201
+
+    s, _ = CompanyApi().get_offboarding_export(scheduled["id"], "wrong token")
+    assert s == 404
+    s, data = CompanyApi().get_offboarding_export(scheduled["id"], scheduled["token"])
+    assert s == 200
+
+    archive = zipfile.ZipFile(io.BytesIO(data))
+    names = archive.namelist()
+    assert "company.json" in names
+    assert "audit_log.json" in names
+    assert archive.read(f"files/companies/{company['id']}/logo") == LOGO
+
+    users = json.loads(archive.read("users.json"))
+    assert sorted(u["email"] for u in users) == sorted([api.user.email, domain + "@nonexistentemail.com"])
+    assert all("password" not in u for u in users)
+    assert [c["domain"] for c in json.loads(archive.read("company.json"))] == [domain]
@@ -0,0 +1,132 @@
+import jwt
+
+from bookit.admin import AdminApi
+from .util import builtin_role, create_random_company, create_random_user_api, rand_n_str
+
+
+def test_invite_registration():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+
+    s, invite = admin.create_invite(builtin_role(admin, "STUDENT"), max_uses=1)
+    assert s == 201
+    assert invite["code"] in invite["link"]
+
+    api = create_random_user_api(domain)
+    s, d = api.get_invite(invite["code"])
+    assert s == 200
+    assert d["company_domain"] == domain
+    assert d["role_name"] == "Student"
+
+    s, _ = api.register_with_invite(invite["code"])
+    assert s == 201
+
+    s, d = api.get_profile()
+    assert s == 200
+    assert d["role"] == "STUDENT"
+
+    # The only use is taken.
+    other = create_random_user_api(domain)
+    s, _ = other.register_with_invite(invite["code"])
+    assert s == 404
+
+    s, d = admin.list_invites()
+    assert s == 200
+    assert invite["id"] not in [i["id"] for i in d]
+
+
+def test_invite_revoke():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+
+    s, invite = admin.create_invite(builtin_role(admin, "GUEST"), expires_in_hours=1)
+    assert s == 201
+
+    s, _ = admin.delete_invite(invite["id"])
+    assert s == 204
+
+    api = create_random_user_api(domain)
+    s, _ = api.register_with_invite(invite["code"])
+    assert s == 404
+
+
+def test_invite_requires_manage_users():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+
+    api = create_random_user_api(domain)
+    s, _ = api.register()
+    assert s == 201
+
+    guest = AdminApi(token=api.token)
+    s, _ = guest.create_invite(builtin_role(admin, "ADMIN"))
+    assert s == 403
+
+
+def test_invites_and_imports_cant_grant_more_than_actor_has():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+
+    api = create_random_user_api(domain)
+    s, _ = api.register()
+    assert s == 201
+    s, role = admin.create_role(rand_n_str(10), ["book", "manage_users"])
+    assert s == 201
+    user_id = jwt.decode(api.token, options={"verify_signature": False})["user_id"]
+    s, _ = admin.assign_role(user_id, role["id"])
+    assert s == 200
+    manager = AdminApi(token=api.token)
+
+    s, _ = manager.create_invite(builtin_role(admin, "ADMIN"))
+    assert s == 403
+    s, _ = manager.create_invite(builtin_role(admin, "GUEST"))
+    assert s == 201
+
+    csv = (
+        "name,surname,email,role\n"
+        f"Ivan,Ivanov,{rand_n_str(10)}@179.ru,Admin\n"
+        f"Olga,Ivanova,{rand_n_str(10)}@179.ru,\n"
+    ).encode()
+    s, report = manager.import_users(csv)
+    assert s == 200
+    assert [c["row"] for c in report["created"]] == [3]
+    assert [i["row"] for i in report["invalid"]] == [2]
+
+
+def test_import_users():
+    tok, _ = create_random_company()
+    admin = AdminApi(token=tok)
+
+    email = rand_n_str(10) + "@179.ru"
+    csv = (
+        "name,surname,email,role\n"
+        f"Ivan,Ivanov,{email},Student\n"
+        f"Ivan,Ivanov,{email.upper()},\n"
+        "Petr,Petrov,not-an-email,\n"
+        f"Anna,Petrova,{rand_n_str(10)}@179.ru,Wizard\n"
+        f"Olga,Ivanova,{rand_n_str(10)}@179.ru,\n"
+    ).encode()
+
+    s, report = admin.import_users(csv, dry_run=True)
+    assert s == 200
+    assert report["dry_run"]
+    assert [c["row"] for c in report["created"]] == [2, 6]
+    assert [d["row"] for d in report["duplicates"]] == [3]
+    assert sorted(i["row"] for i in report["invalid"]) == [4, 5]
+
+    _, users = admin.list_users()
+    assert email not in [u["email"] for u in users]
+
+    s, report = admin.import_users(csv)
+    assert s == 200
+    assert all(c["user_id"] for c in report["created"])
+
+    _, users = admin.list_users()
+    imported = next(u for u in users if u["email"] == email)
+    assert imported["role"] == "STUDENT"
+
+    # Importing the same file again only finds duplicates.
+    s, report = admin.import_users(csv)
+    assert s == 200
+    assert report["created"] == []
+    assert [d["row"] for d in report["duplicates"]] == [2, 3, 6]
@@ -0,0 +1,45 @@
+from bookit.admin import AdminApi
+from bookit.items_collection import Item, Point
+from .util import create_random_company
+
+L_SHAPE = Item(True, "L desk", "Corner desk", [Point(0, 0), Point(1, 0), Point(1, 1)], "134d23")
+
+
+def cells(item):
+    return sorted((p["x"], p["y"]) for p in item["footprint"])
+
+
+def test_turned_items():
+    tok, _ = create_random_company()
+    admin = AdminApi(token=tok)
+    s, building = admin.new_place("Orientation st. 1")
+    assert s == 201
+    s, coworking = admin.new_coworking(building["id"], "Corner office", 6, 6)
+    assert s == 201
+    s, item_type = admin.new_item(None, L_SHAPE)
+    assert s == 201
+
+    def place(base_point, **orientation):
+        return admin.place_item(building["id"], coworking["id"], item_type["id"], base_point, **orientation)
+
+    s, desk = place(Point(1, 1), rotation=90)
+    assert s == 201
+    assert desk["rotation"] == 90 and not desk["mirrored"]
+    assert cells(desk) == [(0, 2), (1, 1), (1, 2)]
+
+    s, desk = place(Point(4, 1), rotation=180, mirrored=True)
+    assert s == 201
+    assert cells(desk) == [(4, 1), (5, 0), (5, 1)]
+
+    # Turned around the base point, the desk would stick out of the coworking on the left.
+    s, _ = place(Point(0, 4), rotation=180)
+    assert s == 409
+    # Mirrored, the same desk at (2, 1) covers (1, 1) taken by the first one.
+    s, _ = place(Point(2, 1), mirrored=True)
+    assert s == 409
+    s, _ = place(Point(3, 3), rotation=45)
+    assert s == 400
+
+    s, items = admin.get_coworking_items(building["id"], coworking["id"])
+    assert s == 200
+    assert sorted(cells(i) for i in items) == [[(0, 2), (1, 1), (1, 2)], [(4, 1), (5, 0), (5, 1)]]
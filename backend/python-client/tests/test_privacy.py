@@ -0,0 +1,110 @@
+import io
+import json
+import zipfile
+from datetime import datetime, timedelta
+
+from bookit.admin import AdminApi
+from bookit.items_collection import Point, TABLE_SMALL
+from .util import create_random_company, create_random_user_api
+
+AVATAR = b"\x89PNG\r\n\x1a\nnot really a png"
+DOCUMENT = b"%PDF-1.4 passport scan"
+
+
+def book_a_table(admin, api):
+    s, role = admin.create_role("Bookers", ["book"])
+    assert s == 201
+    s, users = admin.list_users()
+    user_id = next(u["id"] for u in users if u["email"] == api.user.email)
+    s, _ = admin.assign_role(user_id, role["id"])
+    assert s == 200
+
+    s, building = admin.new_place("Privacy st. 1")
+    assert s == 201
+    s, coworking = admin.new_coworking(building["id"], "Open space", 10, 10)
+    assert s == 201
+    s, item_type = admin.new_item(None, TABLE_SMALL)
+    assert s == 201
+    s, item = admin.place_item(building["id"], coworking["id"], item_type["id"], Point(1, 1))
+    assert s == 201
+
+    start = (datetime.now() + timedelta(days=1)).replace(minute=0, second=0, microsecond=0)
+    s, booking = api.create_booking(coworking["id"], item["id"], start.isoformat(),
+                                    (start + timedelta(hours=1)).isoformat())
+    assert s == 201
+
+    return building, coworking, booking
+
+
+def test_export_personal_data():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+
+    api = create_random_user_api(domain)
+    s, _ = api.register()
+    assert s == 201
+    s, _ = api.patch_profile(avatar=AVATAR)
+    assert s == 200
+    s, _ = api.upload_document(DOCUMENT)
+    assert s == 200
+    _, _, booking = book_a_table(admin, api)
+
+    s, data = api.export_data()
+    assert s == 200
+    archive = zipfile.ZipFile(io.BytesIO(data))
+    assert sorted(archive.namelist()) == [
+        "avatar", "bookings.json", "documents/1.pdf", "profile.json", "verification.json"
+    ]
+    assert json.loads(archive.read("profile.json"))["email"] == api.user.email
+    assert [b["id"] for b in json.loads(archive.read("bookings.json"))] == [booking["id"]]
+    assert json.loads(archive.read("verification.json"))["status"] == "pending"
+    assert archive.read("documents/1.pdf") == DOCUMENT
+    assert archive.read("avatar") == AVATAR
+
+
+def test_anonymize_keeps_bookings():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+
+    api = create_random_user_api(domain)
+    s, _ = api.register()
+    assert s == 201
+    s, _ = api.upload_document(DOCUMENT)
+    assert s == 200
+    building, coworking, booking = book_a_table(admin, api)
+
+    s, _ = api.delete(mode="anonymize")
+    assert s == 204
+
+    s, _ = api.login()
+    assert s == 404
+    s, users = admin.list_users()
+    assert api.user.email not in [u["email"] for u in users]
+    s, trash = admin.list_trash(entity="user")
+    assert trash == []
+
+    # The booking stays for statistics, without anything pointing back to the person.
+    s, bookings = admin.coworking_bookings(building["id"], coworking["id"])
+    assert s == 200
+    assert [b["id"] for b in bookings] == [booking["id"]]
+
+    # The e-mail is free again.
+    s, _ = api.register()
+    assert s == 201
+
+
+def test_delete_removes_bookings():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+
+    api = create_random_user_api(domain)
+    s, _ = api.register()
+    assert s == 201
+    building, coworking, _ = book_a_table(admin, api)
+
+    s, _ = api.delete()
+    assert s == 204
+
+    s, bookings = admin.coworking_bookings(building["id"], coworking["id"])
+    assert s == 200
+    assert bookings == []
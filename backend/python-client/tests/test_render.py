@@ -0,0 +1,39 @@
+from bookit.admin import AdminApi
+from .util import bookers, create_random_company, open_space, tomorrow
+
+
+def test_render_layout():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    (user,), _ = bookers(admin, domain, 1)
+    building, coworking, item = open_space(admin)
+    place = (building["id"], coworking["id"])
+
+    s, content_type, svg = user.render_layout(*place, cell_size=20)
+    assert s == 200
+    assert content_type == "image/svg+xml"
+    assert svg.startswith(b"<svg") and b'width="200"' in svg
+    assert b"#2e7d32" not in svg
+
+    # The booked desk shows up red at that moment.
+    start, end = tomorrow()
+    s, _ = user.create_booking(coworking["id"], item["id"], start, end)
+    assert s == 201
+    s, _, svg = user.render_layout(*place, at=start)
+    assert s == 200
+    assert b"#c62828" in svg and b"#2e7d32" not in svg
+
+    s, content_type, png = user.render_layout(*place, format="png", at=end)
+    assert s == 200
+    assert content_type == "image/png"
+    assert png.startswith(b"\x89PNG")
+
+    s, _, _ = user.render_layout(*place, cell_size=1000)
+    assert s == 400
+
+    # Big coworkings get smaller cells rather than huge pictures.
+    s, hall = admin.new_coworking(building["id"], "Hall", 100, 100)
+    assert s == 201
+    s, _, svg = user.render_layout(building["id"], hall["id"], cell_size=200)
+    assert s == 200
+    assert b'width="4000"' in svg
@@ -0,0 +1,120 @@
+import jwt
+
+from bookit.admin import AdminApi
+from .util import create_random_company, create_random_user_api, rand_n_str
+
+
+def user_id(api):
+    return jwt.decode(api.token, options={"verify_signature": False})["user_id"]
+
+
+def test_builtin_roles():
+    tok, _ = create_random_company()
+    admin = AdminApi(token=tok)
+
+    s, d = admin.list_roles()
+    assert s == 200
+    assert {r["base_role"] for r in d} == {"ADMIN", "STUDENT", "GUEST", "VERIFIEDGUEST"}
+
+    admin_role = next(r for r in d if r["base_role"] == "ADMIN")
+    s, _ = admin.patch_role(admin_role["id"], {"permissions": []})
+    assert s == 403
+
+    s, _ = admin.delete_role(admin_role["id"])
+    assert s == 403
+
+    s, _ = admin.assign_role(user_id(admin), admin_role["id"])
+    assert s == 403
+
+
+def test_custom_role_grants_permissions():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+
+    api = create_random_user_api(domain)
+    s, _ = api.register()
+    assert s == 201
+
+    s, d = api.get_profile()
+    assert d["permissions"] == []
+
+    s, role = admin.create_role("Office manager", ["book", "manage_layout"])
+    assert s == 201
+
+    s, _ = admin.create_role("Office manager", [])
+    assert s == 409
+
+    s, d = admin.assign_role(user_id(api), role["id"])
+    assert s == 200
+
+    s, d = api.get_profile()
+    assert s == 200
+    assert d["role"] == "GUEST"
+    assert sorted(d["permissions"]) == ["book", "manage_layout"]
+
+    manager = AdminApi(token=api.token)
+    s, _ = manager.new_place(rand_n_str(10))
+    assert s == 201
+
+    s, _ = manager.list_users()
+    assert s == 403
+
+    # Users of a removed role fall back to their built-in role.
+    s, _ = admin.delete_role(role["id"])
+    assert s == 204
+
+    s, _ = manager.new_place(rand_n_str(10))
+    assert s == 403
+
+
+def test_roles_are_company_scoped():
+    tok, _ = create_random_company()
+    admin = AdminApi(token=tok)
+    other_tok, _ = create_random_company()
+    other = AdminApi(token=other_tok)
+
+    s, role = admin.create_role(rand_n_str(10), ["book"])
+    assert s == 201
+
+    s, _ = other.patch_role(role["id"], {"name": rand_n_str(10)})
+    assert s == 404
+
+    s, _ = other.delete_role(role["id"])
+    assert s == 404
+
+
+def test_roles_cant_grant_more_than_actor_has():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+
+    api = create_random_user_api(domain)
+    s, _ = api.register()
+    assert s == 201
+    s, role_manager = admin.create_role(rand_n_str(10), ["book", "manage_roles"])
+    assert s == 201
+    s, _ = admin.assign_role(user_id(api), role_manager["id"])
+    assert s == 200
+    manager = AdminApi(token=api.token)
+
+    s, _ = manager.create_role(rand_n_str(10), ["manage_users"])
+    assert s == 403
+    s, booker = manager.create_role(rand_n_str(10), ["book"])
+    assert s == 201
+    s, _ = manager.patch_role(booker["id"], {"permissions": ["book", "manage_company"]})
+    assert s == 403
+
+    s, roles = admin.list_roles()
+    admin_role = next(r for r in roles if r["base_role"] == "ADMIN")
+    s, _ = manager.patch_role(admin_role["id"], {"name": rand_n_str(10)})
+    assert s == 403
+
+    # Nobody gets promoted above the manager or demoted by them when they're above.
+    other = create_random_user_api(domain)
+    s, _ = other.register()
+    assert s == 201
+    s, _ = manager.assign_role(user_id(other), admin_role["id"])
+    assert s == 403
+    s, _ = manager.assign_role(user_id(other), booker["id"])
+    assert s == 200
+    s, _ = manager.assign_role(user_id(admin), booker["id"])
+    assert s == 403
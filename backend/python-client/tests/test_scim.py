@@ -0,0 +1,97 @@
+from bookit.admin import AdminApi
+from bookit.scim import ScimApi
+from .util import create_random_company, create_random_user_api, rand_n_str
+
+
+def scim_api(admin):
+    s, d = admin.create_scim_token()
+    assert s == 201
+    assert d["default_role"] == "STUDENT"
+    return ScimApi(d["token"])
+
+
+def test_scim_provisioning():
+    tok, _ = create_random_company()
+    admin = AdminApi(token=tok)
+    scim = scim_api(admin)
+
+    email = rand_n_str(10) + "@179.ru"
+    s, user = scim.create_user(email, "Lol", "Loll", external_id="ext-1")
+    assert s == 201
+    assert user["userName"] == email
+    assert user["active"]
+
+    s, _ = scim.create_user(email, "Lol", "Loll")
+    assert s == 409
+
+    s, d = scim.list_users(filter=f'userName eq "{email}"')
+    assert s == 200
+    assert d["totalResults"] == 1
+    assert d["Resources"][0]["id"] == user["id"]
+
+    s, d = scim.patch_user(user["id"], [
+        {"op": "replace", "path": "name.familyName", "value": "Kek"},
+        {"op": "Replace", "value": {"externalId": "ext-2"}}
+    ])
+    assert s == 200
+    assert d["name"]["familyName"] == "Kek"
+    assert d["externalId"] == "ext-2"
+
+    s, _ = scim.delete_user(user["id"])
+    assert s == 204
+
+    s, d = scim.get_user(user["id"])
+    assert s == 404
+    assert d["status"] == "404"
+
+    # Deleted users go to the trash like ones removed by an admin.
+    s, trash = admin.list_trash("user")
+    assert s == 200
+    assert [t["id"] for t in trash] == [user["id"]]
+
+
+def test_scim_deactivation_blocks_login():
+    tok, domain = create_random_company()
+    scim = scim_api(AdminApi(token=tok))
+
+    api = create_random_user_api(domain)
+    s, _ = api.register()
+    assert s == 201
+
+    s, d = scim.list_users(filter=f'userName eq "{api.user.email}"')
+    user_id = d["Resources"][0]["id"]
+
+    s, d = scim.patch_user(user_id, [{"op": "replace", "path": "active", "value": "False"}])
+    assert s == 200
+    assert not d["active"]
+
+    s, _ = api.login()
+    assert s == 403
+
+    s, _ = scim.patch_user(user_id, [{"op": "replace", "value": {"active": True}}])
+    assert s == 200
+
+    s, _ = api.login()
+    assert s == 200
+
+
+def test_scim_token():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    scim = scim_api(admin)
+
+    other_tok, _ = create_random_company()
+    other = scim_api(AdminApi(token=other_tok))
+
+    s, user = scim.create_user(rand_n_str(10) + "@179.ru", "Lol", "Loll")
+    assert s == 201
+
+    # Tokens are bound to their company.
+    s, _ = other.get_user(user["id"])
+    assert s == 404
+
+    s, _ = admin.delete_scim_token()
+    assert s == 204
+
+    s, _ = scim.list_users()
+    assert s == 403
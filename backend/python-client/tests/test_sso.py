@@ -0,0 +1,114 @@
+import os
+
+from bookit.admin import AdminApi
+from bookit.config import BASE_URL
+from .mock_idp import MockIdp
+from .util import create_random_company, create_random_user_api, rand_n_str
+
+# The backend has to reach the mock provider, override when the backend runs elsewhere.
+IDP_URL = os.environ.get("BOOKIT_MOCK_IDP_URL")
+IDP_PORT = int(os.environ.get("BOOKIT_MOCK_IDP_PORT", 0))
+
+
+def configure(admin, idp, **extra):
+    return admin.put_sso({
+        "issuer": idp.url,
+        "client_id": idp.client_id,
+        "client_secret": idp.client_secret,
+        "redirect_uri": BASE_URL + "/user/sso/callback",
+        "role_claim": "groups",
+        "role_mapping": {"booq-students": "STUDENT"},
+        **extra,
+    })
+
+
+def test_sso_provisioning():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+
+    with MockIdp("booq", rand_n_str(20), port=IDP_PORT, public_url=IDP_URL) as idp:
+        s, d = configure(admin, idp)
+        assert s == 200
+        assert "client_secret" not in d
+
+        api = create_random_user_api(domain)
+        idp.claims = {
+            "sub": rand_n_str(10),
+            "email": api.user.email,
+            "given_name": "Sso",
+            "family_name": "User",
+            "groups": ["booq-students"],
+        }
+
+        s, _ = api.sso_login()
+        assert s == 200
+
+        s, d = api.get_profile()
+        assert s == 200
+        assert d["email"] == api.user.email
+        assert d["name"] == "Sso"
+        assert d["role"] == "STUDENT"
+
+        # Second login resolves the same user by subject.
+        s, _ = api.sso_login()
+        assert s == 200
+        s, d = admin.list_users()
+        assert len([u for u in d if u["email"] == api.user.email]) == 1
+
+
+def test_sso_links_existing_user_and_disables_password_login():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+
+    api = create_random_user_api(domain)
+    s, _ = api.register()
+    assert s == 201
+
+    with MockIdp("booq", rand_n_str(20), port=IDP_PORT, public_url=IDP_URL) as idp:
+        s, _ = configure(admin, idp, password_login_enabled=False)
+        assert s == 200
+
+        s, _ = api.login()
+        assert s == 403
+
+        # Only e-mails the provider has verified are linked.
+        idp.claims = {"sub": rand_n_str(10), "email": api.user.email}
+        s, _ = api.sso_login()
+        assert s == 409
+
+        idp.claims = {"sub": rand_n_str(10), "email": api.user.email, "email_verified": True}
+        s, _ = api.sso_login()
+        assert s == 200
+
+        s, d = api.get_profile()
+        assert s == 200
+        assert d["role"] == "GUEST"
+
+    s, _ = admin.delete_sso()
+    assert s == 204
+
+    s, _ = api.login()
+    assert s == 200
+
+
+def test_sso_not_configured():
+    _, domain = create_random_company()
+    api = create_random_user_api(domain)
+
+    s, _ = api.sso_login()
+    assert s == 404
+
+
+def test_sso_return_to_stays_in_app():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+
+    with MockIdp("booq", rand_n_str(20), port=IDP_PORT, public_url=IDP_URL) as idp:
+        s, _ = configure(admin, idp)
+        assert s == 200
+
+        api = create_random_user_api(domain)
+        s, _ = api.sso_login(return_to="https://attacker.example/steal")
+        assert s == 400
+        s, _ = api.sso_login(return_to="not a url")
+        assert s == 400
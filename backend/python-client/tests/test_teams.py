@@ -0,0 +1,90 @@
+from bookit.admin import AdminApi
+from .util import bookers, create_random_company, open_space, tomorrow
+
+
+def test_reserved_desk():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    (member, outsider), (member_id, _) = bookers(admin, domain, 2)
+
+    s, engineering = admin.create_team("Engineering")
+    assert s == 201
+    s, backend = admin.create_team("Backend", engineering["id"])
+    assert s == 201
+    s, _ = admin.put_team_member(backend["id"], member_id)
+    assert s == 204
+
+    building, coworking, item = open_space(admin)
+    s, teams = admin.reserve_item(building["id"], coworking["id"], item["id"], [engineering["id"]])
+    assert s == 200
+    assert [t["id"] for t in teams] == [engineering["id"]]
+
+    # Departments count as members of the team above them.
+    s, items = member.get_coworking_items(building["id"], coworking["id"])
+    assert s == 200
+    assert items[0]["teams"] == [engineering["id"]]
+    assert items[0]["can_book"]
+    s, _ = member.create_booking(coworking["id"], item["id"], *tomorrow())
+    assert s == 201
+
+    s, items = outsider.get_coworking_items(building["id"], coworking["id"])
+    assert s == 200
+    assert not items[0]["can_book"]
+    s, _ = outsider.create_booking(coworking["id"], item["id"], *tomorrow(2))
+    assert s == 403
+
+    # Reserving the whole coworking works the same way.
+    s, _ = admin.reserve_item(building["id"], coworking["id"], item["id"], [])
+    assert s == 200
+    s, _ = admin.reserve_coworking(building["id"], coworking["id"], [backend["id"]])
+    assert s == 200
+    s, _ = outsider.create_booking(coworking["id"], item["id"], *tomorrow(2))
+    assert s == 403
+    s, _ = admin.reserve_coworking(building["id"], coworking["id"], [])
+    assert s == 200
+    s, _ = outsider.create_booking(coworking["id"], item["id"], *tomorrow(2))
+    assert s == 201
+
+    # Teams with departments can't be deleted.
+    s, _ = admin.delete_team(engineering["id"])
+    assert s == 409
+    s, _ = admin.patch_team(engineering["id"], {"parent_id": backend["id"]})
+    assert s == 409
+
+
+def test_manager_books_on_behalf():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    (manager, member, outsider), (manager_id, member_id, outsider_id) = bookers(admin, domain, 3)
+
+    s, team = admin.create_team("Sales")
+    assert s == 201
+    s, _ = admin.put_team_member(team["id"], manager_id, manager=True)
+    assert s == 204
+
+    # Managers add members, but only admins make managers.
+    s, _ = manager.put_team_member(team["id"], member_id)
+    assert s == 204
+    s, _ = manager.put_team_member(team["id"], outsider_id, manager=True)
+    assert s == 403
+    s, _ = member.put_team_member(team["id"], outsider_id)
+    assert s == 403
+
+    s, members = manager.team_members(team["id"])
+    assert s == 200
+    assert sorted(m["user_id"] for m in members) == sorted([manager_id, member_id])
+
+    _, coworking, item = open_space(admin)
+    s, booking = manager.create_booking(coworking["id"], item["id"], *tomorrow(), user_id=member_id)
+    assert s == 201
+    assert booking["user_id"] == member_id
+
+    s, _ = manager.create_booking(coworking["id"], item["id"], *tomorrow(2), user_id=outsider_id)
+    assert s == 403
+    s, _ = member.create_booking(coworking["id"], item["id"], *tomorrow(2), user_id=manager_id)
+    assert s == 403
+
+    s, _ = manager.delete_team_member(team["id"], member_id)
+    assert s == 204
+    s, _ = manager.create_booking(coworking["id"], item["id"], *tomorrow(2), user_id=member_id)
+    assert s == 403
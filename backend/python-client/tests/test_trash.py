@@ -0,0 +1,97 @@
+from bookit.admin import AdminApi
+from bookit.items_collection import TABLE_SMALL
+from .util import create_random_company, create_random_user_api
+
+
+def test_restore_building_with_coworkings():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+
+    s, building = admin.new_place("Trash st. 1")
+    assert s == 201
+    s, kept = admin.new_coworking(building["id"], "Kept", 10, 10)
+    assert s == 201
+    s, deleted = admin.new_coworking(building["id"], "Deleted before", 10, 10)
+    assert s == 201
+
+    s, _ = admin.delete_coworking(building["id"], deleted["id"])
+    assert s == 204
+    s, _ = admin.delete_place(building["id"])
+    assert s == 204
+
+    s, coworkings = admin.list_coworkings(building["id"])
+    assert s == 200
+    assert coworkings == []
+
+    # Coworkings deleted along with the building aren't listed on their own.
+    s, trash = admin.list_trash()
+    assert s == 200
+    assert [(t["entity"], t["id"]) for t in trash] == [
+        ("building", building["id"]),
+        ("coworking", deleted["id"]),
+    ]
+
+    s, _ = admin.restore("coworking", deleted["id"])
+    assert s == 409
+
+    s, _ = admin.restore("building", building["id"])
+    assert s == 204
+    s, coworkings = admin.list_coworkings(building["id"])
+    assert s == 200
+    assert [c["id"] for c in coworkings] == [kept["id"]]
+
+    s, _ = admin.restore("coworking", deleted["id"])
+    assert s == 204
+    s, _ = admin.restore("coworking", deleted["id"])
+    assert s == 404
+
+    s, entries = admin.list_audit(entity="building")
+    assert s == 200
+    assert [e["action"] for e in entries[:2]] == ["building.restore", "building.delete"]
+
+
+def test_restore_user_and_item_type():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+
+    api = create_random_user_api(domain)
+    s, _ = api.register()
+    assert s == 201
+
+    # Regular users can't see the trash.
+    s, _ = AdminApi(token=api.token).list_trash()
+    assert s == 403
+
+    s, users = admin.list_users()
+    assert s == 200
+    user_id = next(u["id"] for u in users if u["email"] == api.user.email)
+
+    s, _ = admin.delete_user(user_id)
+    assert s == 200
+    s, _ = api.login()
+    assert s == 404
+    s, users = admin.list_users()
+    assert user_id not in [u["id"] for u in users]
+
+    s, item = admin.new_item(None, TABLE_SMALL)
+    assert s == 201
+    s, _ = admin.delete_item(item["id"])
+    assert s == 204
+    s, items = admin.get_items()
+    assert s == 200
+    assert items == []
+
+    s, trash = admin.list_trash(entity="item_type")
+    assert s == 200
+    assert [t["id"] for t in trash] == [item["id"]]
+    assert trash[0]["purge_at"] > trash[0]["deleted_at"]
+
+    s, _ = admin.restore("user", user_id)
+    assert s == 204
+    s, _ = api.login()
+    assert s == 200
+
+    s, _ = admin.restore("item_type", item["id"])
+    assert s == 204
+    s, items = admin.get_items()
+    assert [i["id"] for i in items] == [item["id"]]
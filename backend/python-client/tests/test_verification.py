@@ -0,0 +1,95 @@
+from bookit.admin import AdminApi
+from .util import create_random_company, create_random_user_api
+
+
+def create_guest(domain):
+    api = create_random_user_api(domain)
+    s, _ = api.register()
+    assert s == 201
+    return api
+
+
+def test_verification_resubmission():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    guest = create_guest(domain)
+
+    s, _ = guest.upload_document(b"%PDF-1.4 first")
+    assert s == 200
+
+    s, d = guest.get_profile()
+    assert d["verification_status"] == "pending"
+
+    s, queue = admin.list_requests(status="pending", search=guest.user.email)
+    assert s == 200
+    assert len(queue) == 1
+    case_id = queue[0]["id"]
+
+    # Asking for more information needs a reason.
+    s, _ = admin.decide_verification(case_id, "needs_more_info")
+    assert s == 400
+
+    s, d = admin.decide_verification(case_id, "needs_more_info", "The scan is blurry")
+    assert s == 200
+    assert d["status"] == "needs_more_info"
+
+    s, d = guest.get_verification()
+    assert s == 200
+    assert d["comments"][0]["body"] == "The scan is blurry"
+
+    s, _ = guest.upload_document(b"%PDF-1.4 second")
+    assert s == 200
+
+    s, d = admin.get_verification_case(case_id)
+    assert d["status"] == "pending"
+    assert [doc["version"] for doc in d["documents"]] == [1, 2]
+
+    # Earlier versions are kept.
+    s, content = admin.get_verification_document(case_id, 1)
+    assert s == 200
+    assert content == b"%PDF-1.4 first"
+
+    s, d = admin.decide_verification(case_id, "approved")
+    assert s == 200
+
+    # The old token still says guest, but the approved case can't be reopened.
+    s, _ = guest.upload_document(b"%PDF-1.4 third")
+    assert s == 409
+
+    s, d = guest.get_profile()
+    assert d["role"] == "VERIFIEDGUEST"
+    assert d["verification_status"] == "approved"
+
+    s, _ = admin.decide_verification(case_id, "rejected", "Too late")
+    assert s == 409
+
+
+def test_verification_rejection():
+    tok, domain = create_random_company()
+    admin = AdminApi(token=tok)
+    guest = create_guest(domain)
+
+    s, _ = guest.upload_document(b"%PDF-1.4")
+    assert s == 200
+
+    s, d = guest.get_verification()
+    case_id = d["id"]
+
+    s, _ = admin.comment_verification(case_id, "Checking with the front desk")
+    assert s == 201
+
+    s, d = admin.decide_verification(case_id, "rejected", "Not a student ID")
+    assert s == 200
+    assert len(d["comments"]) == 2
+
+    s, queue = admin.list_requests(status="rejected")
+    assert s == 200
+    assert [c["id"] for c in queue] == [case_id]
+
+    s, queue = admin.list_requests(status="pending")
+    assert s == 200
+    assert queue == []
+
+    s, d = guest.get_profile()
+    assert d["role"] == "GUEST"
+    assert d["verification_status"] == "rejected"
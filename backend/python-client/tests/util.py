@@ -1,8 +1,10 @@
 import random
 import string
+from datetime import datetime, timedelta
 
 from bookit.company import Company, CompanyApi
 from bookit.admin import AdminApi
+from bookit.items_collection import Point, TABLE_SMALL
 from bookit.user import User, UserApi
 
 def rand_n_str(n):
@@ -33,3 +35,46 @@ def create_random_user_api(domain):
 def create_admin_api():
     tok, _ = create_random_company()
     return AdminApi(token=tok)
+
+def builtin_role(admin, base_role):
+    _, roles = admin.list_roles()
+    return next(r["id"] for r in roles if r["base_role"] == base_role)
+
+def bookers(admin, domain, count):
+    s, role = admin.create_role("Bookers", ["book"])
+    assert s == 201
+
+    apis = []
+    for _ in range(count):
+        api = create_random_user_api(domain)
+        s, _ = api.register()
+        assert s == 201
+        apis.append(api)
+
+    s, users = admin.list_users()
+    ids = []
+    for api in apis:
+        user_id = next(u["id"] for u in users if u["email"] == api.user.email)
+        s, _ = admin.assign_role(user_id, role["id"])
+        assert s == 200
+        ids.append(user_id)
+
+    return apis, ids
+
+
+def open_space(admin):
+    s, building = admin.new_place("Teams st. 1")
+    assert s == 201
+    s, coworking = admin.new_coworking(building["id"], "Open space", 10, 10)
+    assert s == 201
+    s, item_type = admin.new_item(None, TABLE_SMALL)
+    assert s == 201
+    s, item = admin.place_item(building["id"], coworking["id"], item_type["id"], Point(1, 1))
+    assert s == 201
+
+    return building, coworking, item
+
+
+def tomorrow(hours=0):
+    start = (datetime.now() + timedelta(days=1, hours=hours)).replace(minute=0, second=0, microsecond=0)
+    return start.isoformat(), (start + timedelta(hours=1)).isoformat()
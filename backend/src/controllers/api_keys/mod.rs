@@ -0,0 +1,100 @@
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    controllers::roles::user_permissions,
+    errors::ProdError,
+    forms::api_keys::{CreateApiKeyForm, CreatedApiKey},
+    jwt::{
+        hashing::hash_token,
+        models::{Claims, API_KEY_PREFIX},
+    },
+    models::{ApiKeyModel, Permission, RoleModel},
+    oidc::random_token,
+};
+
+/// Characters of the key kept in plain text to tell keys apart, the prefix and a bit of the secret.
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+/// Keys can't manage keys, service accounts or the account they act for, a leaked one mustn't be
+/// able to outlive its revocation or take the account over.
+pub fn reject_api_key(claims: &Claims) -> Result<(), ProdError> {
+    if claims.scopes.is_some() {
+        return Err(ProdError::Forbidden(
+            "API keys can't be used for this".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub async fn issue_api_key(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    company_id: Uuid,
+    form: CreateApiKeyForm,
+) -> Result<CreatedApiKey, ProdError> {
+    let granted = user_permissions(conn, user_id).await?;
+    if let Some(missing) = form.permissions.iter().find(|p| !granted.contains(p)) {
+        return Err(ProdError::Forbidden(format!(
+            "Key owner doesn't have permission `{}`",
+            missing.as_str()
+        )));
+    }
+
+    let key = format!("{API_KEY_PREFIX}{}", random_token());
+
+    let api_key = sqlx::query_as!(
+        ApiKeyModel,
+        r#"
+        INSERT INTO api_keys (name, prefix, token_hash, permissions, expires_at, user_id, company_id)
+        VALUES ($1, $2, $3, $4, NOW() + make_interval(days => $5), $6, $7)
+        RETURNING id, name, prefix, permissions as "permissions: Vec<Permission>",
+                  expires_at, last_used_at, created_at, user_id, company_id
+        "#,
+        form.name,
+        key.chars().take(DISPLAY_PREFIX_LEN).collect::<String>(),
+        hash_token(&key),
+        form.permissions as Vec<Permission>,
+        form.expires_in_days,
+        user_id,
+        company_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(CreatedApiKey { key, api_key })
+}
+
+/// Looks up a key presented by a client and records its use.
+/// Expired keys and keys of deactivated users are rejected.
+pub async fn resolve_api_key(conn: &mut PgConnection, key: &str) -> Result<Claims, ProdError> {
+    let record = sqlx::query!(
+        r#"
+        UPDATE api_keys k
+        SET last_used_at = NOW()
+        FROM users u
+        WHERE u.id = k.user_id
+            AND k.token_hash = $1
+            AND (k.expires_at IS NULL OR k.expires_at > NOW())
+            AND u.active
+            AND u.deleted_at IS NULL
+        RETURNING u.id, u.company_id, u.role as "role: RoleModel",
+                  k.permissions as "permissions: Vec<Permission>"
+        "#,
+        hash_token(key)
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::Forbidden("Invalid or expired API key".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    Ok(Claims::for_api_key(
+        &record.id,
+        &record.company_id,
+        &record.role,
+        record.permissions,
+    ))
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::errors::ProdError;
+use crate::models::{AttributeKind, ItemAttributeModel};
+
+/// Attributes of the company, by key.
+pub async fn company_attributes(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+) -> Result<Vec<ItemAttributeModel>, ProdError> {
+    Ok(sqlx::query_as!(
+        ItemAttributeModel,
+        r#"
+        SELECT id, key, name, kind as "kind: AttributeKind", options, company_id
+        FROM item_attributes
+        WHERE company_id = $1
+        ORDER BY key
+        "#,
+        company_id
+    )
+    .fetch_all(conn)
+    .await?)
+}
+
+fn valid_value(attribute: &ItemAttributeModel, value: &Value) -> bool {
+    match attribute.kind {
+        AttributeKind::Boolean => value.is_boolean(),
+        AttributeKind::Number => value.is_number(),
+        AttributeKind::Enum => value
+            .as_str()
+            .is_some_and(|value| attribute.options.iter().any(|option| option == value)),
+    }
+}
+
+/// Attribute ids and values of the form, ready to be stored.
+///
+/// Fails on keys the company doesn't have and on values that don't fit the attribute.
+pub async fn attribute_values(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+    values: HashMap<String, Value>,
+) -> Result<(Vec<Uuid>, Vec<Value>), ProdError> {
+    let attributes = company_attributes(conn, company_id).await?;
+
+    let mut ids = Vec::new();
+    let mut checked = Vec::new();
+    for (key, value) in values {
+        let Some(attribute) = attributes.iter().find(|attribute| attribute.key == key) else {
+            return Err(ProdError::ShitHappened(format!("No such attribute {key}")));
+        };
+        if !valid_value(attribute, &value) {
+            return Err(ProdError::ShitHappened(format!(
+                "Wrong value for attribute {key}"
+            )));
+        }
+        ids.push(attribute.id);
+        checked.push(value);
+    }
+
+    Ok((ids, checked))
+}
+
+/// Replaces the attribute values of the item type.
+pub async fn set_type_attribute_values(
+    conn: &mut PgConnection,
+    item_type_id: Uuid,
+    ids: &[Uuid],
+    values: &[Value],
+) -> Result<(), ProdError> {
+    sqlx::query!(
+        r#"DELETE FROM item_type_attribute_values WHERE item_type_id = $1"#,
+        item_type_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO item_type_attribute_values (item_type_id, attribute_id, value)
+        SELECT $1, a, v
+        FROM unnest($2::uuid[], $3::jsonb[]) AS t(a, v)
+        "#,
+        item_type_id,
+        ids,
+        values
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Replaces the attribute values a placed item has over those of its type.
+pub async fn set_item_attribute_values(
+    conn: &mut PgConnection,
+    coworking_item_id: Uuid,
+    ids: &[Uuid],
+    values: &[Value],
+) -> Result<(), ProdError> {
+    sqlx::query!(
+        r#"DELETE FROM coworking_item_attribute_values WHERE coworking_item_id = $1"#,
+        coworking_item_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO coworking_item_attribute_values (coworking_item_id, attribute_id, value)
+        SELECT $1, a, v
+        FROM unnest($2::uuid[], $3::jsonb[]) AS t(a, v)
+        "#,
+        coworking_item_id,
+        ids,
+        values
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum FilterOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Operators by the text they're written with, longer ones first so `>=` isn't read as `>`.
+const OPERATORS: [(&str, FilterOp); 6] = [
+    (">=", FilterOp::Ge),
+    ("<=", FilterOp::Le),
+    ("!=", FilterOp::Ne),
+    ("=", FilterOp::Eq),
+    (">", FilterOp::Gt),
+    ("<", FilterOp::Lt),
+];
+
+#[derive(Debug, PartialEq)]
+enum Expected {
+    Boolean(bool),
+    Option(String),
+    Number(f64),
+}
+
+/// Condition on an attribute of the items, like `monitors>=2`.
+#[derive(Debug, PartialEq)]
+pub struct AttributeFilter {
+    key: String,
+    op: FilterOp,
+    expected: Expected,
+}
+
+/// Splits `monitors>=2` into the key, the operator and the value.
+fn split_filter(filter: &str) -> Option<(&str, FilterOp, &str)> {
+    let at = filter.find(['<', '>', '!', '='])?;
+    let (key, rest) = filter.split_at(at);
+    OPERATORS.iter().find_map(|(text, op)| {
+        rest.strip_prefix(text)
+            .map(|value| (key.trim(), *op, value.trim()))
+    })
+}
+
+impl AttributeFilter {
+    /// Reads a filter like `monitors>=2` against the attributes of the company.
+    ///
+    /// Booleans and enums can only be compared with `=` and `!=`.
+    pub fn parse(filter: &str, attributes: &[ItemAttributeModel]) -> Result<Self, ProdError> {
+        let wrong = || ProdError::ShitHappened(format!("Wrong attribute filter {filter}"));
+
+        let (key, op, value) = split_filter(filter).ok_or_else(wrong)?;
+        let attribute = attributes
+            .iter()
+            .find(|attribute| attribute.key == key)
+            .ok_or_else(|| ProdError::ShitHappened(format!("No such attribute {key}")))?;
+
+        let expected = match attribute.kind {
+            AttributeKind::Boolean => Expected::Boolean(value.parse().map_err(|_| wrong())?),
+            AttributeKind::Enum if attribute.options.iter().any(|option| option == value) => {
+                Expected::Option(value.to_string())
+            }
+            AttributeKind::Enum => return Err(wrong()),
+            AttributeKind::Number => Expected::Number(value.parse().map_err(|_| wrong())?),
+        };
+        if !matches!(expected, Expected::Number(_)) && !matches!(op, FilterOp::Eq | FilterOp::Ne) {
+            return Err(wrong());
+        }
+
+        Ok(Self {
+            key: key.to_string(),
+            op,
+            expected,
+        })
+    }
+
+    /// Whether the attribute values of an item pass the filter.
+    ///
+    /// An item without the attribute counts as `false` for booleans and fails number comparisons.
+    pub fn matches(&self, values: &Value) -> bool {
+        let actual = values.get(&self.key);
+        let equal = match &self.expected {
+            Expected::Boolean(expected) => {
+                actual.and_then(Value::as_bool).unwrap_or(false) == *expected
+            }
+            Expected::Option(expected) => actual.and_then(Value::as_str) == Some(expected.as_str()),
+            Expected::Number(expected) => {
+                let Some(actual) = actual.and_then(Value::as_f64) else {
+                    return false;
+                };
+                return match self.op {
+                    FilterOp::Eq => (actual - expected).abs() < f64::EPSILON,
+                    FilterOp::Ne => (actual - expected).abs() >= f64::EPSILON,
+                    FilterOp::Lt => actual < *expected,
+                    FilterOp::Le => actual <= *expected,
+                    FilterOp::Gt => actual > *expected,
+                    FilterOp::Ge => actual >= *expected,
+                };
+            }
+        };
+
+        (self.op == FilterOp::Eq) == equal
+    }
+}
+
+/// Filters from query parameters, which come apart at the first `=`: `monitors>=2` arrives as
+/// `("monitors>", "2")` and `monitors>2` as `("monitors>2", "")`.
+pub fn query_filters(
+    params: &[(String, String)],
+    skip: &[&str],
+    attributes: &[ItemAttributeModel],
+) -> Result<Vec<AttributeFilter>, ProdError> {
+    params
+        .iter()
+        .filter(|(key, _)| !skip.contains(&key.as_str()))
+        .map(|(key, value)| {
+            let filter = if value.is_empty() && key.contains(['<', '>']) {
+                key.clone()
+            } else {
+                format!("{key}={value}")
+            };
+            AttributeFilter::parse(&filter, attributes)
+        })
+        .collect()
+}
@@ -0,0 +1,279 @@
+use chrono::NaiveDateTime;
+use serde_json::Value;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    errors::ProdError, jwt::models::Claims, middlewares::RequestMeta, models::AuditLogModel,
+};
+
+/// Entries returned when the log is queried without a limit.
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+
+/// Columns that never make it into the log.
+const REDACTED_FIELDS: &[&str] = &["password", "token_hash", "client_secret", "code"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditEntity {
+    Company,
+    User,
+    ServiceAccount,
+    Role,
+    Team,
+    Invite,
+    ApiKey,
+    ScimToken,
+    SsoProvider,
+    VerificationCase,
+    Building,
+    Floor,
+    Coworking,
+    /// All items placed in a coworking, keyed by the coworking id.
+    Layout,
+    /// All obstacles of a coworking, keyed by the coworking id.
+    Obstacles,
+    LayoutVersion,
+    CoworkingItem,
+    ItemType,
+    ItemAttribute,
+    Booking,
+}
+
+impl AuditEntity {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Company => "company",
+            Self::User => "user",
+            Self::ServiceAccount => "service_account",
+            Self::Role => "role",
+            Self::Team => "team",
+            Self::Invite => "invite",
+            Self::ApiKey => "api_key",
+            Self::ScimToken => "scim_token",
+            Self::SsoProvider => "sso_provider",
+            Self::VerificationCase => "verification_case",
+            Self::Building => "building",
+            Self::Floor => "floor",
+            Self::Coworking => "coworking",
+            Self::Layout => "layout",
+            Self::Obstacles => "obstacles",
+            Self::LayoutVersion => "layout_version",
+            Self::CoworkingItem => "coworking_item",
+            Self::ItemType => "item_type",
+            Self::ItemAttribute => "item_attribute",
+            Self::Booking => "booking",
+        }
+    }
+
+    const fn snapshot_query(self) -> &'static str {
+        match self {
+            Self::Company => "SELECT to_jsonb(t) FROM companies t WHERE id = $1",
+            Self::User | Self::ServiceAccount => "SELECT to_jsonb(t) FROM users t WHERE id = $1",
+            Self::Role => "SELECT to_jsonb(t) FROM roles t WHERE id = $1",
+            Self::Team => {
+                "SELECT to_jsonb(t) || jsonb_build_object('members', (SELECT COALESCE(jsonb_agg(to_jsonb(m) ORDER BY m.user_id), '[]') FROM team_members m WHERE m.team_id = t.id)) FROM teams t WHERE id = $1"
+            }
+            Self::Invite => "SELECT to_jsonb(t) FROM invites t WHERE id = $1",
+            Self::ApiKey => "SELECT to_jsonb(t) FROM api_keys t WHERE id = $1",
+            Self::ScimToken => "SELECT to_jsonb(t) FROM scim_tokens t WHERE company_id = $1",
+            Self::SsoProvider => "SELECT to_jsonb(t) FROM oidc_providers t WHERE company_id = $1",
+            Self::VerificationCase => "SELECT to_jsonb(t) FROM verification_cases t WHERE id = $1",
+            Self::Building => "SELECT to_jsonb(t) FROM buildings t WHERE id = $1",
+            Self::Floor => "SELECT to_jsonb(t) FROM floors t WHERE id = $1",
+            Self::Coworking => {
+                "SELECT to_jsonb(t) || jsonb_build_object('teams', ARRAY(SELECT team_id FROM coworking_teams WHERE coworking_id = t.id ORDER BY team_id)) FROM coworking_spaces t WHERE id = $1"
+            }
+            Self::Layout => {
+                "SELECT COALESCE(jsonb_agg(to_jsonb(t) ORDER BY t.id), '[]') FROM coworking_items t WHERE coworking_id = $1 AND retired_at IS NULL"
+            }
+            Self::Obstacles => {
+                "SELECT COALESCE(jsonb_agg(to_jsonb(t) ORDER BY t.id), '[]') FROM coworking_obstacles t WHERE coworking_id = $1"
+            }
+            Self::LayoutVersion => {
+                "SELECT to_jsonb(t) || jsonb_build_object('items', (SELECT COALESCE(jsonb_agg(to_jsonb(i) - 'version_id' ORDER BY i.item_key), '[]') FROM layout_version_items i WHERE i.version_id = t.id)) FROM layout_versions t WHERE id = $1"
+            }
+            Self::CoworkingItem => {
+                "SELECT to_jsonb(t) || jsonb_build_object('teams', ARRAY(SELECT team_id FROM coworking_item_teams WHERE coworking_item_id = t.id ORDER BY team_id), 'attributes', placed_item_attribute_values(t.id, t.item_id)) FROM coworking_items t WHERE id = $1"
+            }
+            Self::ItemType => {
+                "SELECT to_jsonb(t) || jsonb_build_object('attributes', type_attribute_values(t.id)) FROM item_types t WHERE id = $1"
+            }
+            Self::ItemAttribute => "SELECT to_jsonb(t) FROM item_attributes t WHERE id = $1",
+            Self::Booking => "SELECT to_jsonb(t) FROM bookings t WHERE id = $1",
+        }
+    }
+}
+
+/// A single change to be written to the log.
+pub struct AuditEntry {
+    action: &'static str,
+    entity: AuditEntity,
+    entity_id: Option<Uuid>,
+    before: Option<Value>,
+    after: Option<Value>,
+}
+
+impl AuditEntry {
+    /// `action` reads as `<entity>.<verb>`, e.g. `role.update`.
+    pub fn new(
+        action: &'static str,
+        entity: AuditEntity,
+        entity_id: impl Into<Option<Uuid>>,
+    ) -> Self {
+        Self {
+            action,
+            entity,
+            entity_id: entity_id.into(),
+            before: None,
+            after: None,
+        }
+    }
+
+    #[must_use]
+    pub fn before(mut self, before: Option<Value>) -> Self {
+        self.before = before;
+        self
+    }
+
+    #[must_use]
+    pub fn after(mut self, after: Option<Value>) -> Self {
+        self.after = after;
+        self
+    }
+}
+
+/// Current state of the entity as JSON, `None` if it doesn't exist.
+pub async fn snapshot(
+    conn: &mut PgConnection,
+    entity: AuditEntity,
+    id: Uuid,
+) -> Result<Option<Value>, ProdError> {
+    Ok(
+        sqlx::query_scalar::<_, Option<Value>>(entity.snapshot_query())
+            .bind(id)
+            .fetch_optional(conn)
+            .await?
+            .flatten(),
+    )
+}
+
+/// Writes the entry on behalf of the authenticated user.
+///
+/// Call it inside the transaction of the change, so the change and its entry land together.
+pub async fn record(
+    conn: &mut PgConnection,
+    meta: &RequestMeta,
+    claims: &Claims,
+    entry: AuditEntry,
+) -> Result<(), ProdError> {
+    record_as(conn, meta, claims.company_id, Some(claims.user_id), entry).await
+}
+
+pub async fn record_as(
+    conn: &mut PgConnection,
+    meta: &RequestMeta,
+    company_id: Uuid,
+    actor_id: Option<Uuid>,
+    entry: AuditEntry,
+) -> Result<(), ProdError> {
+    let (before, after) = diff(entry.before.map(redact), entry.after.map(redact));
+
+    sqlx::query!(
+        r#"
+        INSERT INTO audit_log (company_id, actor_id, action, entity, entity_id, before, after, ip, request_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+        company_id,
+        actor_id,
+        entry.action,
+        entry.entity.as_str(),
+        entry.entity_id,
+        before,
+        after,
+        meta.ip,
+        meta.request_id,
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+fn redact(value: Value) -> Value {
+    match value {
+        Value::Object(mut fields) => {
+            fields.retain(|key, _| !REDACTED_FIELDS.contains(&key.as_str()));
+            Value::Object(fields.into_iter().map(|(k, v)| (k, redact(v))).collect())
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(redact).collect()),
+        value => value,
+    }
+}
+
+/// Drops the fields both sides agree on, so an update only keeps what it changed.
+fn diff(before: Option<Value>, after: Option<Value>) -> (Option<Value>, Option<Value>) {
+    match (before, after) {
+        (Some(Value::Object(mut before)), Some(Value::Object(mut after))) => {
+            let unchanged: Vec<String> = before
+                .iter()
+                .filter(|(key, value)| after.get(key.as_str()) == Some(value))
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in &unchanged {
+                before.remove(key);
+                after.remove(key);
+            }
+            (Some(Value::Object(before)), Some(Value::Object(after)))
+        }
+        (before, after) => (before, after),
+    }
+}
+
+#[derive(Default)]
+pub struct AuditFilter {
+    pub actor_id: Option<Uuid>,
+    pub action: Option<String>,
+    pub entity: Option<String>,
+    pub entity_id: Option<Uuid>,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+}
+
+/// Company log entries matching the filter, newest first.
+///
+/// `limit` of `None` returns a page of the default size, pass `Some(i64::MAX)` to get everything.
+pub async fn audit_log(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+    filter: AuditFilter,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<AuditLogModel>, ProdError> {
+    Ok(sqlx::query_as!(
+        AuditLogModel,
+        r#"
+        SELECT id, actor_id, action, entity, entity_id, before, after, ip, request_id, created_at
+        FROM audit_log
+        WHERE company_id = $1
+            AND ($2::UUID IS NULL OR actor_id = $2)
+            AND ($3::TEXT IS NULL OR action = $3)
+            AND ($4::TEXT IS NULL OR entity = $4)
+            AND ($5::UUID IS NULL OR entity_id = $5)
+            AND ($6::TIMESTAMP IS NULL OR created_at >= $6)
+            AND ($7::TIMESTAMP IS NULL OR created_at < $7)
+        ORDER BY created_at DESC, id DESC
+        LIMIT $8 OFFSET $9
+        "#,
+        company_id,
+        filter.actor_id,
+        filter.action,
+        filter.entity,
+        filter.entity_id,
+        filter.from,
+        filter.to,
+        limit.unwrap_or(DEFAULT_PAGE_LIMIT),
+        offset.unwrap_or(0),
+    )
+    .fetch_all(conn)
+    .await?)
+}
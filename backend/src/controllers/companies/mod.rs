@@ -0,0 +1,112 @@
+pub mod offboarding;
+
+use crate::errors::ProdError;
+use crate::forms::companies::PatchCompanyForm;
+use crate::models::CompaniesModel;
+use crate::s3::utils::upload_file;
+use crate::{AppState, BASE_URL};
+use axum::body::Bytes;
+use axum::extract::Multipart;
+use sqlx::PgConnection;
+use tracing::log::warn;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Updates the company from the `json` and `logo` multipart fields.
+///
+/// Users are joined with their company by id, so a new domain applies to all of them at once.
+pub async fn update_company(
+    state: &AppState,
+    conn: &mut PgConnection,
+    company_id: Uuid,
+    mut multipart: Multipart,
+) -> Result<CompaniesModel, ProdError> {
+    let mut form: Option<PatchCompanyForm> = None;
+    let mut logo: Option<(Bytes, String)> = None;
+
+    while let Ok(Some(field)) = multipart.next_field().await {
+        if let Some(field_name) = field.name() {
+            match field_name {
+                "json" => {
+                    if let Ok(text) = field.text().await {
+                        match serde_json::from_str::<PatchCompanyForm>(&text) {
+                            Ok(data) => form = Some(data),
+                            Err(err) => {
+                                warn!("Failed to parse JSON: {}", err);
+                            }
+                        }
+                    }
+                }
+                "logo" => {
+                    if let Some(content_type) = field.content_type() {
+                        let valid = [
+                            "image/png",
+                            "image/jpeg",
+                            "image/bmp",
+                            "image/svg",
+                            "image/svg+xml",
+                        ]
+                        .contains(&content_type);
+
+                        if !valid {
+                            return Err(ProdError::ShitHappened("Wrong image format".to_string()));
+                        }
+
+                        let content_type = content_type.to_string();
+                        let image = field
+                            .bytes()
+                            .await
+                            .map_err(|err| ProdError::ShitHappened(err.to_string()))?;
+                        logo = Some((image, content_type));
+                    }
+                }
+                _ => {
+                    warn!("Unknown field: {}", field_name);
+                }
+            }
+        }
+    }
+
+    if let Some(form_data) = form.as_ref() {
+        form_data.validate()?;
+    }
+
+    let logo_url = match logo {
+        Some((image, content_type)) => {
+            let name = format!("companies/{company_id}/logo");
+            upload_file(state, &name, content_type, image).await?;
+            Some(format!("{BASE_URL}/company/{company_id}/logo"))
+        }
+        None => None,
+    };
+
+    sqlx::query_as!(
+        CompaniesModel,
+        r#"
+        UPDATE companies
+        SET
+            name = COALESCE($2, name),
+            domain = COALESCE($3, domain),
+            primary_color = COALESCE($4, primary_color),
+            accent_color = COALESCE($5, accent_color),
+            avatar = COALESCE($6, avatar)
+        WHERE id = $1
+        RETURNING id, name, domain, avatar, primary_color, accent_color, owner_id
+        "#,
+        company_id,
+        form.as_ref().and_then(|data| data.name.as_ref()),
+        form.as_ref().and_then(|data| data.domain.as_ref()),
+        form.as_ref().and_then(|data| data.primary_color.as_ref()),
+        form.as_ref().and_then(|data| data.accent_color.as_ref()),
+        logo_url,
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such company".to_string()),
+        sqlx::Error::Database(e) if e.is_unique_violation() => {
+            ProdError::Conflict("This domain is already taken".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    })
+}
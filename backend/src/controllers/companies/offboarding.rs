@@ -0,0 +1,299 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use axum::body::Bytes;
+use serde_json::Value;
+use sqlx::types::Json;
+use sqlx::{Acquire, PgConnection};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::controllers::users::privacy::{to_json, zip};
+use crate::db::Db;
+use crate::errors::ProdError;
+use crate::jwt::models::Claims;
+use crate::models::{CompanyOffboardingModel, OffboardingReport};
+use crate::s3::utils::{delete_files, download_file, list_files, upload_file};
+use crate::AppState;
+
+/// Days between confirming the offboarding and the teardown, it can be cancelled until then.
+pub const GRACE_DAYS: i32 = 30;
+
+const OFFBOARDING_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Everything stored about the company, as `<name>.json` in the export. Secrets are left out.
+const EXPORTED: &[(&str, &str)] = &[
+    ("company", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM companies t WHERE id = $1"),
+    ("users", "SELECT COALESCE(jsonb_agg(to_jsonb(t) - 'password'), '[]') FROM users t WHERE company_id = $1"),
+    ("roles", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM roles t WHERE company_id = $1"),
+    ("teams", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM teams t WHERE company_id = $1"),
+    ("team_members", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM team_members t JOIN teams p ON p.id = t.team_id WHERE p.company_id = $1"),
+    ("invites", "SELECT COALESCE(jsonb_agg(to_jsonb(t) - 'code'), '[]') FROM invites t WHERE company_id = $1"),
+    ("api_keys", "SELECT COALESCE(jsonb_agg(to_jsonb(t) - 'token_hash'), '[]') FROM api_keys t WHERE company_id = $1"),
+    ("sso_provider", "SELECT COALESCE(jsonb_agg(to_jsonb(t) - 'client_secret'), '[]') FROM oidc_providers t WHERE company_id = $1"),
+    ("buildings", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM buildings t WHERE company_id = $1"),
+    ("floors", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM floors t WHERE company_id = $1"),
+    ("coworkings", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM coworking_spaces t WHERE company_id = $1"),
+    ("coworking_teams", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM coworking_teams t JOIN coworking_spaces s ON s.id = t.coworking_id WHERE s.company_id = $1"),
+    ("item_types", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM item_types t WHERE company_id = $1"),
+    ("coworking_items", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM coworking_items t JOIN coworking_spaces s ON s.id = t.coworking_id WHERE s.company_id = $1"),
+    ("coworking_obstacles", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM coworking_obstacles t JOIN coworking_spaces s ON s.id = t.coworking_id WHERE s.company_id = $1"),
+    ("layout_versions", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM layout_versions t WHERE company_id = $1"),
+    ("layout_version_items", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM layout_version_items t JOIN layout_versions v ON v.id = t.version_id WHERE v.company_id = $1"),
+    ("item_attributes", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM item_attributes t WHERE company_id = $1"),
+    ("item_type_attribute_values", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM item_type_attribute_values t JOIN item_attributes a ON a.id = t.attribute_id WHERE a.company_id = $1"),
+    ("coworking_item_attribute_values", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM coworking_item_attribute_values t JOIN item_attributes a ON a.id = t.attribute_id WHERE a.company_id = $1"),
+    ("coworking_item_teams", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM coworking_item_teams t JOIN coworking_items i ON i.id = t.coworking_item_id JOIN coworking_spaces s ON s.id = i.coworking_id WHERE s.company_id = $1"),
+    ("bookings", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM bookings t WHERE company_id = $1"),
+    ("verification_cases", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM verification_cases t WHERE company_id = $1"),
+    ("verification_comments", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM verification_comments t JOIN verification_cases c ON c.id = t.case_id WHERE c.company_id = $1"),
+    ("verification_documents", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM verification_documents t JOIN verification_cases c ON c.id = t.case_id WHERE c.company_id = $1"),
+    ("audit_log", "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]') FROM audit_log t WHERE company_id = $1"),
+];
+
+/// Fails unless the authenticated user owns the company, API keys never do.
+pub async fn require_owner(conn: &mut PgConnection, claims: &Claims) -> Result<(), ProdError> {
+    let owner_id = sqlx::query_scalar!(
+        r#"SELECT owner_id FROM companies WHERE id = $1"#,
+        claims.company_id
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NoCompany,
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    if claims.scopes.is_some() || owner_id != Some(claims.user_id) {
+        return Err(ProdError::Forbidden(
+            "Only the owner of the company can do this".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Offboarding of the company that is neither cancelled nor done yet.
+pub async fn pending_offboarding(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+) -> Result<CompanyOffboardingModel, ProdError> {
+    sqlx::query_as!(
+        CompanyOffboardingModel,
+        r#"
+        SELECT id, company_id, company_name, company_domain, requested_by, created_at,
+               purge_after, cancelled_at, completed_at,
+               report as "report: Json<OffboardingReport>"
+        FROM company_offboardings
+        WHERE company_id = $1 AND cancelled_at IS NULL AND completed_at IS NULL
+        "#,
+        company_id
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => {
+            ProdError::NotFound("The company isn't being offboarded".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    })
+}
+
+/// Offboarding the token was issued for, it outlives the company.
+pub async fn offboarding_by_token(
+    conn: &mut PgConnection,
+    offboarding_id: Uuid,
+    token_hash: &str,
+) -> Result<CompanyOffboardingModel, ProdError> {
+    sqlx::query_as!(
+        CompanyOffboardingModel,
+        r#"
+        SELECT id, company_id, company_name, company_domain, requested_by, created_at,
+               purge_after, cancelled_at, completed_at,
+               report as "report: Json<OffboardingReport>"
+        FROM company_offboardings
+        WHERE id = $1 AND token_hash = $2
+        "#,
+        offboarding_id,
+        token_hash
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such offboarding".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })
+}
+
+/// Where the export made right before the teardown is kept.
+pub fn archive_name(offboarding_id: Uuid) -> String {
+    format!("offboardings/{offboarding_id}/export.zip")
+}
+
+/// Prefixes of every file stored for the company.
+async fn file_prefixes(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+) -> Result<Vec<String>, ProdError> {
+    let users = sqlx::query_scalar!(r#"SELECT id FROM users WHERE company_id = $1"#, company_id)
+        .fetch_all(&mut *conn)
+        .await?;
+    let items = sqlx::query_scalar!(
+        r#"SELECT id FROM item_types WHERE company_id = $1"#,
+        company_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(users
+        .into_iter()
+        .map(|id| format!("users/{id}/"))
+        .chain(items.into_iter().map(|id| format!("items/{id}/")))
+        .chain([format!("companies/{company_id}/")])
+        .collect())
+}
+
+/// Every row and file of the company as a ZIP: a JSON file per table of [`EXPORTED`] and
+/// the files under `files/`, named as they are stored.
+///
+/// Returns the archive along with the number of rows by table and the names of the files.
+pub async fn export_company(
+    state: &AppState,
+    conn: &mut PgConnection,
+    company_id: Uuid,
+) -> Result<(Vec<u8>, BTreeMap<String, usize>, Vec<String>), ProdError> {
+    let mut files = Vec::new();
+    let mut rows = BTreeMap::new();
+
+    for (name, query) in EXPORTED {
+        let table: Value = sqlx::query_scalar(query)
+            .bind(company_id)
+            .fetch_one(&mut *conn)
+            .await?;
+        rows.insert((*name).to_string(), table.as_array().map_or(0, Vec::len));
+        files.push((format!("{name}.json"), to_json(&table)?));
+    }
+
+    let mut names = Vec::new();
+    for prefix in file_prefixes(conn, company_id).await? {
+        names.extend(list_files(state, &prefix).await?);
+    }
+    for name in &names {
+        let content = download_file(state, name).await?;
+        files.push((format!("files/{name}"), content.to_vec()));
+    }
+
+    Ok((zip(files)?, rows, names))
+}
+
+/// Exports the company, removes it with all its rows and files and records the report.
+///
+/// The rows go in one transaction that is only committed once the files are gone,
+/// so a failed teardown is retried from the start.
+pub async fn teardown(
+    state: &AppState,
+    offboarding: &CompanyOffboardingModel,
+) -> Result<OffboardingReport, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let mut tx = conn.begin().await?;
+
+    let (archive, rows, names) = export_company(state, tx.as_mut(), offboarding.company_id).await?;
+    let report = OffboardingReport {
+        rows,
+        files: names.len(),
+        archive_bytes: archive.len(),
+    };
+    upload_file(
+        state,
+        &archive_name(offboarding.id),
+        "application/zip".to_string(),
+        Bytes::from(archive),
+    )
+    .await?;
+
+    let prefixes = file_prefixes(tx.as_mut(), offboarding.company_id).await?;
+
+    // Everything else cascades from the company, the audit log only goes once it's gone.
+    sqlx::query!(
+        r#"DELETE FROM companies WHERE id = $1"#,
+        offboarding.company_id
+    )
+    .execute(tx.as_mut())
+    .await?;
+    sqlx::query!(
+        r#"DELETE FROM audit_log WHERE company_id = $1"#,
+        offboarding.company_id
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    for prefix in prefixes {
+        delete_files(state, &prefix).await?;
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE company_offboardings
+        SET completed_at = NOW(), report = $2
+        WHERE id = $1
+        "#,
+        offboarding.id,
+        Json(&report) as _
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(report)
+}
+
+/// Offboardings whose grace period is over.
+async fn due_offboardings(state: &AppState) -> Result<Vec<CompanyOffboardingModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+
+    Ok(sqlx::query_as!(
+        CompanyOffboardingModel,
+        r#"
+        SELECT id, company_id, company_name, company_domain, requested_by, created_at,
+               purge_after, cancelled_at, completed_at,
+               report as "report: Json<OffboardingReport>"
+        FROM company_offboardings
+        WHERE cancelled_at IS NULL AND completed_at IS NULL AND purge_after <= NOW()
+        ORDER BY purge_after
+        "#
+    )
+    .fetch_all(conn.as_mut())
+    .await?)
+}
+
+/// Tears down the companies whose grace period is over every hour, for as long as the server is up.
+pub async fn offboarding_job(state: AppState) {
+    let mut interval = tokio::time::interval(OFFBOARDING_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let offboardings = match due_offboardings(&state).await {
+            Ok(offboardings) => offboardings,
+            Err(err) => {
+                warn!("Failed to list due offboardings: {err:?}");
+                continue;
+            }
+        };
+        for offboarding in offboardings {
+            match teardown(&state, &offboarding).await {
+                Ok(report) => info!(
+                    "Offboarded company {}: {} files, {} rows",
+                    offboarding.company_domain,
+                    report.files,
+                    report.rows.values().sum::<usize>()
+                ),
+                Err(err) => warn!(
+                    "Failed to offboard company {}: {err:?}",
+                    offboarding.company_domain
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,536 @@
+use std::collections::{HashMap, HashSet};
+
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::controllers::layout::versions::create_initial_version;
+use crate::errors::ProdError;
+use crate::forms::items::{CreateItemForm, CreateObstacleForm, UpdateItemTypeForm};
+use crate::forms::places::coworking::CreateCoworkingForm;
+use crate::jwt::models::Claims;
+use crate::models::{
+    BookingMode, CollisionReason, CoworkingItemsModel, CoworkingSpacesModel, ItemCollisionModel,
+    ItemTypeImpactModel, ObstacleKind, ObstacleModel, Point,
+};
+
+pub mod portable;
+pub mod render;
+pub mod versions;
+
+const ROTATIONS: [i32; 4] = [0, 90, 180, 270];
+
+/// Coworking of the company that isn't in the trash.
+pub async fn coworking_space(
+    conn: &mut PgConnection,
+    building_id: Uuid,
+    coworking_id: Uuid,
+    company_id: Uuid,
+) -> Result<CoworkingSpacesModel, ProdError> {
+    sqlx::query_as!(
+        CoworkingSpacesModel,
+        r#"
+        SELECT id, address, height, width, building_id, floor_id, company_id
+        FROM coworking_spaces
+        WHERE building_id = $1 AND id = $2 AND company_id = $3 AND deleted_at IS NULL
+        "#,
+        building_id,
+        coworking_id,
+        company_id
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => {
+            ProdError::NotFound("No such coworking or building exists".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    })
+}
+
+/// Creates a coworking in the building of the company together with its first layout version.
+pub async fn insert_coworking(
+    conn: &mut PgConnection,
+    building_id: Uuid,
+    claims: &Claims,
+    form: &CreateCoworkingForm,
+) -> Result<CoworkingSpacesModel, ProdError> {
+    let coworking = sqlx::query_as!(
+        CoworkingSpacesModel,
+        r#"
+        INSERT INTO coworking_spaces (address, height, width, building_id, floor_id, company_id)
+        SELECT $1, $2, $3, b.id, f.id, b.company_id
+        FROM buildings b
+        JOIN floors f ON f.building_id = b.id
+        WHERE b.id = $4 AND b.company_id = $5 AND b.deleted_at IS NULL
+            AND ($6::uuid IS NULL OR f.id = $6)
+        ORDER BY f.display_order, f.number
+        LIMIT 1
+        RETURNING id, address, height, width, building_id, floor_id, company_id
+        "#,
+        form.address,
+        form.height,
+        form.width,
+        building_id,
+        claims.company_id,
+        form.floor_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such building or floor".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    create_initial_version(conn, coworking.id, claims.company_id, claims.user_id).await?;
+
+    Ok(coworking)
+}
+
+/// Cells the item of the form would cover, turned and mirrored like the form asks.
+///
+/// Fails when the item type is missing or in the trash.
+pub async fn placed_footprint(
+    conn: &mut PgConnection,
+    item: &CreateItemForm,
+) -> Result<Vec<Point>, ProdError> {
+    if !ROTATIONS.contains(&item.rotation) {
+        return Err(ProdError::ShitHappened(
+            "Rotation should be one of 0, 90, 180 and 270".to_string(),
+        ));
+    }
+
+    sqlx::query_scalar!(
+        r#"
+        SELECT item_footprint(point($2::bigint, $3::bigint), i.offsets, $4, $5) as "footprint!: Vec<Point>"
+        FROM item_types i
+        WHERE i.id = $1 AND i.deleted_at IS NULL
+        "#,
+        item.item_id,
+        item.base_point.x,
+        item.base_point.y,
+        item.rotation,
+        item.mirrored
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such item type".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })
+}
+
+/// Cells covered by the items already placed in the coworking.
+pub async fn coworking_footprint(
+    conn: &mut PgConnection,
+    coworking_id: Uuid,
+) -> Result<Vec<Point>, ProdError> {
+    let footprints = sqlx::query_scalar!(
+        r#"
+        SELECT item_footprint(c.base_point, i.offsets, c.rotation, c.mirrored) as "footprint!: Vec<Point>"
+        FROM coworking_items c
+        JOIN item_types i ON i.id = c.item_id
+        WHERE c.coworking_id = $1 AND c.retired_at IS NULL
+        "#,
+        coworking_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(footprints.into_iter().flatten().collect())
+}
+
+/// Cells covered by the items of the coworking, except for the one being moved.
+pub async fn other_items_footprint(
+    conn: &mut PgConnection,
+    coworking_id: Uuid,
+    item_id: Uuid,
+) -> Result<Vec<Point>, ProdError> {
+    let footprints = sqlx::query_scalar!(
+        r#"
+        SELECT item_footprint(c.base_point, i.offsets, c.rotation, c.mirrored) as "footprint!: Vec<Point>"
+        FROM coworking_items c
+        JOIN item_types i ON i.id = c.item_id
+        WHERE c.coworking_id = $1 AND c.id <> $2 AND c.retired_at IS NULL
+        "#,
+        coworking_id,
+        item_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(footprints.into_iter().flatten().collect())
+}
+
+/// Cells blocked by the obstacles of the coworking.
+pub async fn obstacle_cells(
+    conn: &mut PgConnection,
+    coworking_id: Uuid,
+) -> Result<Vec<Point>, ProdError> {
+    Ok(sqlx::query_scalar!(
+        r#"
+        SELECT unnest(cells) as "cell!: Point"
+        FROM coworking_obstacles
+        WHERE coworking_id = $1
+        "#,
+        coworking_id
+    )
+    .fetch_all(conn)
+    .await?)
+}
+
+/// Items of the coworking as the user sees them, with `can_book` set for that user.
+///
+/// Comes back empty when the coworking is missing or in the trash.
+pub async fn placed_items(
+    conn: &mut PgConnection,
+    building_id: Uuid,
+    coworking_id: Uuid,
+    claims: &Claims,
+) -> Result<Vec<CoworkingItemsModel>, ProdError> {
+    Ok(sqlx::query_as!(
+        CoworkingItemsModel,
+        r#"
+        SELECT ci.id, ci.item_id, ci.name, ci.description,
+               ci.base_point as "base_point: Point", ci.rotation, ci.mirrored,
+               item_footprint(ci.base_point, i.offsets, ci.rotation, ci.mirrored) as "footprint!: Vec<Point>",
+               ARRAY(
+                   SELECT team_id FROM coworking_item_teams
+                   WHERE coworking_item_id = ci.id
+                   ORDER BY team_id
+               ) as "teams!",
+               can_book_item($4, ci.id) as "can_book!",
+               placed_item_attribute_values(ci.id, ci.item_id) as "attributes!"
+        FROM coworking_items ci
+        JOIN buildings b ON b.id = $1
+        JOIN coworking_spaces c ON c.id = $2 AND c.building_id = b.id
+        JOIN item_types i ON i.id = ci.item_id
+        WHERE c.company_id = $3 AND ci.coworking_id = $2 AND ci.retired_at IS NULL
+            AND c.deleted_at IS NULL AND i.deleted_at IS NULL
+        "#,
+        building_id,
+        coworking_id,
+        claims.company_id,
+        claims.user_id
+    )
+    .fetch_all(conn)
+    .await?)
+}
+
+/// Takes the items off their layout and cancels the bookings that haven't ended yet.
+/// The items stay for the history of their past bookings.
+pub async fn retire_items(conn: &mut PgConnection, item_ids: &[Uuid]) -> Result<(), ProdError> {
+    sqlx::query!(
+        r#"
+        DELETE FROM bookings
+        WHERE coworking_item_id = ANY($1) AND time_end > NOW()
+        "#,
+        item_ids
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE coworking_items SET retired_at = NOW()
+        WHERE id = ANY($1) AND retired_at IS NULL
+        "#,
+        item_ids
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Obstacles of the coworking.
+pub async fn coworking_obstacles(
+    conn: &mut PgConnection,
+    coworking_id: Uuid,
+) -> Result<Vec<ObstacleModel>, ProdError> {
+    Ok(sqlx::query_as!(
+        ObstacleModel,
+        r#"
+        SELECT id, kind as "kind: ObstacleKind", cells as "cells: Vec<Point>"
+        FROM coworking_obstacles
+        WHERE coworking_id = $1
+        ORDER BY id
+        "#,
+        coworking_id
+    )
+    .fetch_all(conn)
+    .await?)
+}
+
+/// Adds an obstacle to the coworking, its cells are not checked.
+pub async fn insert_obstacle(
+    conn: &mut PgConnection,
+    coworking_id: Uuid,
+    obstacle: &CreateObstacleForm,
+) -> Result<(), ProdError> {
+    let (xs, ys): (Vec<i64>, Vec<i64>) = obstacle.cells.iter().map(|p| (p.x, p.y)).unzip();
+    sqlx::query!(
+        r#"
+        INSERT INTO coworking_obstacles (coworking_id, kind, cells)
+        SELECT $1, $2, array_agg(point(x, y) ORDER BY n)
+        FROM unnest($3::bigint[], $4::bigint[]) WITH ORDINALITY AS c(x, y, n)
+        "#,
+        coworking_id,
+        obstacle.kind as ObstacleKind,
+        &xs,
+        &ys
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Whether any of the cells lies outside a coworking of that size.
+pub fn out_of_bounds(cells: &[Point], width: i64, height: i64) -> bool {
+    cells
+        .iter()
+        .any(|p| p.x < 0 || p.y < 0 || p.x >= width || p.y >= height)
+}
+
+/// Fails unless the footprints fit in the coworking, miss the blocked cells and don't overlap.
+pub fn check_placement(
+    footprints: &[Vec<Point>],
+    space: &CoworkingSpacesModel,
+    blocked: &[Point],
+) -> Result<(), ProdError> {
+    let mut taken: HashSet<&Point> = blocked.iter().collect();
+
+    for footprint in footprints {
+        if out_of_bounds(footprint, space.width, space.height) {
+            return Err(ProdError::Conflict(
+                "Item overlaps with borders".to_string(),
+            ));
+        }
+        if footprint.iter().any(|p| blocked.contains(p)) {
+            return Err(ProdError::Conflict(
+                "Item overlaps with obstacle".to_string(),
+            ));
+        }
+        for cell in footprint {
+            if !taken.insert(cell) {
+                return Err(ProdError::Conflict(
+                    "Item overlaps with other item".to_string(),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Placed items whose upcoming bookings wouldn't fit the paired item types, with the booking mode
+/// and capacity of the types replaced by the given ones if set.
+///
+/// Exclusive items can't have overlapping bookings, shared ones can as long as the seats suffice.
+pub async fn overbooked_items(
+    conn: &mut PgConnection,
+    item_ids: &[Uuid],
+    item_type_ids: &[Uuid],
+    booking_mode: Option<BookingMode>,
+    capacity: Option<i32>,
+) -> Result<Vec<Uuid>, ProdError> {
+    Ok(sqlx::query_scalar!(
+        r#"
+        SELECT DISTINCT c.id as "id!"
+        FROM unnest($1::uuid[], $2::uuid[]) AS c(id, item_id)
+        JOIN item_types t ON t.id = c.item_id
+        JOIN bookings b ON b.coworking_item_id = c.id
+        WHERE b.time_end > NOW()
+            AND CASE WHEN COALESCE($3, t.booking_mode) = 'shared'
+                THEN booked_attendees(c.id, b.time_start, b.time_end, NULL) > COALESCE($4, t.capacity)
+                ELSE b.attendees > COALESCE($4, t.capacity) OR EXISTS (
+                    SELECT 1 FROM bookings o
+                    WHERE o.coworking_item_id = c.id AND o.id <> b.id
+                        AND tsrange(o.time_start, o.time_end) && tsrange(b.time_start, b.time_end)
+                )
+            END
+        ORDER BY c.id
+        "#,
+        item_ids,
+        item_type_ids,
+        booking_mode as Option<BookingMode>,
+        capacity
+    )
+    .fetch_all(conn)
+    .await?)
+}
+
+/// Fails when the upcoming bookings of the placed item wouldn't fit the item type it's about to
+/// get, be it because the type isn't bookable or because of its booking mode and capacity.
+pub async fn require_bookings_fit(
+    conn: &mut PgConnection,
+    coworking_item_id: Uuid,
+    item_type_id: Uuid,
+) -> Result<(), ProdError> {
+    let bookable = sqlx::query_scalar!(
+        r#"
+        SELECT t.bookable OR NOT EXISTS (
+            SELECT 1 FROM bookings WHERE coworking_item_id = $1 AND time_end > NOW()
+        ) as "bookable!"
+        FROM item_types t
+        WHERE t.id = $2
+        "#,
+        coworking_item_id,
+        item_type_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such item type exists".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+    if !bookable {
+        return Err(ProdError::Conflict(format!(
+            "Item {coworking_item_id} has upcoming bookings and the new item type isn't bookable"
+        )));
+    }
+
+    if !overbooked_items(conn, &[coworking_item_id], &[item_type_id], None, None)
+        .await?
+        .is_empty()
+    {
+        return Err(ProdError::Conflict(format!(
+            "Item {coworking_item_id} has upcoming bookings that don't fit the new item type"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Items of the type placed in coworkings, what new offsets would make them collide with and
+/// which of them have more bookings than a new booking mode or capacity takes.
+///
+/// Items of other types stay where they are, so only the items of this type are reported.
+pub async fn item_type_impact(
+    conn: &mut PgConnection,
+    item_type_id: Uuid,
+    form: &UpdateItemTypeForm,
+) -> Result<ItemTypeImpactModel, ProdError> {
+    let offsets = form.offsets.as_deref();
+    let (xs, ys): (Vec<i64>, Vec<i64>) = offsets
+        .unwrap_or_default()
+        .iter()
+        .map(|p| (p.x, p.y))
+        .unzip();
+
+    let placed = sqlx::query!(
+        r#"
+        SELECT c.id, c.coworking_id, c.item_id = $1 as "retyped!", s.width, s.height,
+               item_footprint(
+                   c.base_point,
+                   CASE WHEN c.item_id = $1 AND $2 THEN (
+                       SELECT array_agg(point(x, y) ORDER BY n)
+                       FROM unnest($3::bigint[], $4::bigint[]) WITH ORDINALITY AS o(x, y, n)
+                   ) ELSE i.offsets END,
+                   c.rotation,
+                   c.mirrored
+               ) as "footprint!: Vec<Point>"
+        FROM coworking_items c
+        JOIN item_types i ON i.id = c.item_id
+        JOIN coworking_spaces s ON s.id = c.coworking_id
+        WHERE s.deleted_at IS NULL AND c.retired_at IS NULL
+            AND c.coworking_id IN (
+                SELECT coworking_id FROM coworking_items WHERE item_id = $1 AND retired_at IS NULL
+            )
+        ORDER BY c.coworking_id, c.id
+        "#,
+        item_type_id,
+        offsets.is_some(),
+        &xs,
+        &ys
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let coworking_ids: Vec<Uuid> = placed.iter().map(|item| item.coworking_id).collect();
+    let mut blocked: HashMap<Uuid, HashSet<Point>> = HashMap::new();
+    for obstacle in sqlx::query!(
+        r#"
+        SELECT coworking_id, unnest(cells) as "cell!: Point"
+        FROM coworking_obstacles
+        WHERE coworking_id = ANY($1)
+        "#,
+        &coworking_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?
+    {
+        blocked
+            .entry(obstacle.coworking_id)
+            .or_default()
+            .insert(obstacle.cell);
+    }
+
+    let future_bookings = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) as "count!"
+        FROM bookings b
+        JOIN coworking_items c ON c.id = b.coworking_item_id
+        WHERE c.item_id = $1 AND b.time_end > NOW()
+        "#,
+        item_type_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let item_ids = sqlx::query_scalar!(
+        r#"SELECT id FROM coworking_items WHERE item_id = $1"#,
+        item_type_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    let item_type_ids = vec![item_type_id; item_ids.len()];
+    let overbooked = overbooked_items(
+        conn,
+        &item_ids,
+        &item_type_ids,
+        form.booking_mode,
+        form.capacity,
+    )
+    .await?;
+
+    let mut collisions = Vec::new();
+    let mut coworkings = HashSet::new();
+    let mut placed_items = 0;
+    for item in placed.iter().filter(|item| item.retyped) {
+        placed_items += 1;
+        coworkings.insert(item.coworking_id);
+
+        let reason = if out_of_bounds(&item.footprint, item.width.into(), item.height.into()) {
+            Some((CollisionReason::Borders, None))
+        } else if blocked
+            .get(&item.coworking_id)
+            .is_some_and(|cells| item.footprint.iter().any(|p| cells.contains(p)))
+        {
+            Some((CollisionReason::Obstacle, None))
+        } else {
+            placed
+                .iter()
+                .filter(|other| other.coworking_id == item.coworking_id && other.id != item.id)
+                .find(|other| other.footprint.iter().any(|p| item.footprint.contains(p)))
+                .map(|other| (CollisionReason::Item, Some(other.id)))
+        };
+
+        if let Some((reason, other_item_id)) = reason {
+            collisions.push(ItemCollisionModel {
+                coworking_id: item.coworking_id,
+                coworking_item_id: item.id,
+                reason,
+                other_item_id,
+            });
+        }
+    }
+
+    Ok(ItemTypeImpactModel {
+        placed_items,
+        coworkings: coworkings.len() as i64,
+        future_bookings,
+        collisions,
+        overbooked,
+    })
+}
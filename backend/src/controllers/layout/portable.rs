@@ -0,0 +1,356 @@
+use std::collections::{HashMap, HashSet};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::Bytes;
+use sqlx::PgConnection;
+use tracing::warn;
+use uuid::{NoContext, Timestamp, Uuid};
+
+use crate::controllers::audit::{record, snapshot, AuditEntity, AuditEntry};
+use crate::controllers::layout::versions::live_items;
+use crate::controllers::layout::{
+    check_placement, coworking_obstacles, insert_coworking, insert_obstacle, obstacle_cells,
+    out_of_bounds, ROTATIONS,
+};
+use crate::errors::ProdError;
+use crate::forms::items::CreateObstacleForm;
+use crate::forms::places::coworking::{
+    CreateCoworkingForm, ImportLayoutForm, ImportedItemType, LayoutDocument, LayoutDocumentItem,
+    LayoutDocumentItemType, LayoutDocumentSpace, LayoutImportReport, LAYOUT_SCHEMA_VERSION,
+};
+use crate::jwt::models::Claims;
+use crate::middlewares::RequestMeta;
+use crate::models::{BookingMode, CoworkingSpacesModel, Point};
+use crate::s3::utils::{download_file, upload_file};
+use crate::AppState;
+
+/// Layout of the coworking as a portable document, with the icons of its item types inlined.
+///
+/// An icon that can't be fetched is left out rather than failing the export.
+pub async fn export_layout(
+    state: &AppState,
+    conn: &mut PgConnection,
+    space: &CoworkingSpacesModel,
+) -> Result<LayoutDocument, ProdError> {
+    let placed = sqlx::query!(
+        r#"
+        SELECT c.name, c.description, c.base_point as "base_point: Point", c.rotation, c.mirrored,
+               i.name as item_type
+        FROM coworking_items c
+        JOIN item_types i ON i.id = c.item_id
+        WHERE c.coworking_id = $1 AND c.retired_at IS NULL AND i.deleted_at IS NULL
+        ORDER BY c.id
+        "#,
+        space.id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let types = sqlx::query!(
+        r#"
+        SELECT name, description, color, icon, offsets as "offsets: Vec<Point>", bookable,
+               booking_mode as "booking_mode: BookingMode", capacity
+        FROM item_types
+        WHERE deleted_at IS NULL
+            AND id IN (
+                SELECT item_id FROM coworking_items WHERE coworking_id = $1 AND retired_at IS NULL
+            )
+        ORDER BY id
+        "#,
+        space.id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    // Names are all items have to go by, so only the first type of a name makes it.
+    let mut seen = HashSet::new();
+    let mut item_types = Vec::new();
+    for item_type in types {
+        if !seen.insert(item_type.name.clone()) {
+            continue;
+        }
+
+        let icon = match item_type.icon {
+            Some(name) => match download_file(state, &name).await {
+                Ok(svg) => Some(STANDARD.encode(svg)),
+                Err(err) => {
+                    warn!("Failed to fetch icon {name}: {err}");
+                    None
+                }
+            },
+            None => None,
+        };
+
+        item_types.push(LayoutDocumentItemType {
+            name: item_type.name,
+            description: item_type.description,
+            color: item_type.color,
+            offsets: item_type.offsets,
+            bookable: item_type.bookable,
+            booking_mode: item_type.booking_mode,
+            capacity: Some(item_type.capacity),
+            icon,
+        });
+    }
+
+    let obstacles = coworking_obstacles(conn, space.id)
+        .await?
+        .into_iter()
+        .map(|obstacle| CreateObstacleForm {
+            kind: obstacle.kind,
+            cells: obstacle.cells,
+        })
+        .collect();
+
+    Ok(LayoutDocument {
+        schema_version: LAYOUT_SCHEMA_VERSION,
+        coworking: LayoutDocumentSpace {
+            address: space.address.clone(),
+            height: i32::try_from(space.height).map_err(|err| ProdError::Unknown(err.into()))?,
+            width: i32::try_from(space.width).map_err(|err| ProdError::Unknown(err.into()))?,
+        },
+        item_types,
+        items: placed
+            .into_iter()
+            .map(|item| LayoutDocumentItem {
+                item_type: item.item_type,
+                name: item.name,
+                description: item.description,
+                base_point: item.base_point,
+                rotation: item.rotation,
+                mirrored: item.mirrored,
+            })
+            .collect(),
+        obstacles,
+    })
+}
+
+/// Fails unless every item refers to exactly one item type of the document.
+fn check_document(document: &LayoutDocument) -> Result<(), ProdError> {
+    let mut names = HashSet::new();
+    for item_type in &document.item_types {
+        if !names.insert(item_type.name.as_str()) {
+            return Err(ProdError::ShitHappened(format!(
+                "Item type {} is listed twice",
+                item_type.name
+            )));
+        }
+    }
+
+    for item in &document.items {
+        if !names.contains(item.item_type.as_str()) {
+            return Err(ProdError::ShitHappened(format!(
+                "Item {} has unknown type {}",
+                item.name, item.item_type
+            )));
+        }
+        if !ROTATIONS.contains(&item.rotation) {
+            return Err(ProdError::ShitHappened(
+                "Rotation should be one of 0, 90, 180 and 270".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Item type of the company for the one in the document, created when there's none of that name.
+///
+/// Among types of the same name the one of the same shape wins. Icons of new types are added to
+/// `icons` for the caller to upload once the whole layout checks out.
+async fn map_item_type(
+    conn: &mut PgConnection,
+    meta: &RequestMeta,
+    claims: &Claims,
+    item_type: &LayoutDocumentItemType,
+    dry_run: bool,
+    icons: &mut Vec<(String, Bytes)>,
+) -> Result<(Uuid, ImportedItemType), ProdError> {
+    let candidates = sqlx::query!(
+        r#"
+        SELECT id, offsets as "offsets: Vec<Point>"
+        FROM item_types
+        WHERE company_id = $1 AND name = $2 AND deleted_at IS NULL
+        ORDER BY id
+        "#,
+        claims.company_id,
+        item_type.name
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    if let Some(existing) = candidates
+        .iter()
+        .find(|candidate| candidate.offsets == item_type.offsets)
+        .or_else(|| candidates.first())
+    {
+        return Ok((
+            existing.id,
+            ImportedItemType {
+                name: item_type.name.clone(),
+                item_type_id: Some(existing.id),
+                created: false,
+                offsets_differ: existing.offsets != item_type.offsets,
+            },
+        ));
+    }
+
+    let item_type_id = Uuid::new_v7(Timestamp::now(NoContext));
+    let icon = match &item_type.icon {
+        Some(icon) => {
+            let svg = STANDARD.decode(icon).map_err(|_| {
+                ProdError::ShitHappened(format!(
+                    "Icon of item type {} is not base64",
+                    item_type.name
+                ))
+            })?;
+            let name = format!("items/{item_type_id}/icon.svg");
+            icons.push((name.clone(), Bytes::from(svg)));
+            Some(name)
+        }
+        None => None,
+    };
+
+    let (xs, ys): (Vec<i64>, Vec<i64>) = item_type.offsets.iter().map(|p| (p.x, p.y)).unzip();
+    sqlx::query!(
+        r#"
+        INSERT INTO item_types
+            (id, name, description, color, icon, offsets, bookable, company_id, booking_mode, capacity)
+        SELECT $1, $2, $3, $4, $5, array_agg(point(x, y) ORDER BY n), $6, $7, $10, $11
+        FROM unnest($8::bigint[], $9::bigint[]) WITH ORDINALITY AS o(x, y, n)
+        "#,
+        item_type_id,
+        item_type.name,
+        item_type.description,
+        item_type.color,
+        icon,
+        item_type.bookable,
+        claims.company_id,
+        &xs,
+        &ys,
+        item_type.booking_mode as BookingMode,
+        item_type.capacity.unwrap_or(1)
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let after = snapshot(&mut *conn, AuditEntity::ItemType, item_type_id).await?;
+    record(
+        conn,
+        meta,
+        claims,
+        AuditEntry::new("item_type.create", AuditEntity::ItemType, item_type_id).after(after),
+    )
+    .await?;
+
+    Ok((
+        item_type_id,
+        ImportedItemType {
+            name: item_type.name.clone(),
+            item_type_id: (!dry_run).then_some(item_type_id),
+            created: true,
+            offsets_differ: false,
+        },
+    ))
+}
+
+/// Builds a new coworking in the building from the document.
+///
+/// Everything is written the same way on dry run, so the caller only has to roll back the
+/// transaction. Icons of new item types are uploaded last, and only for real.
+pub async fn import_layout(
+    state: &AppState,
+    conn: &mut PgConnection,
+    meta: &RequestMeta,
+    claims: &Claims,
+    building_id: Uuid,
+    form: ImportLayoutForm,
+    dry_run: bool,
+) -> Result<LayoutImportReport, ProdError> {
+    let document = form.document;
+    check_document(&document)?;
+
+    let space = insert_coworking(
+        &mut *conn,
+        building_id,
+        claims,
+        &CreateCoworkingForm {
+            address: form.address.unwrap_or(document.coworking.address),
+            height: document.coworking.height,
+            width: document.coworking.width,
+            floor_id: form.floor_id,
+        },
+    )
+    .await?;
+
+    let mut type_ids = HashMap::new();
+    let mut item_types = Vec::new();
+    let mut icons = Vec::new();
+    for item_type in &document.item_types {
+        let (item_type_id, imported) =
+            map_item_type(&mut *conn, meta, claims, item_type, dry_run, &mut icons).await?;
+        type_ids.insert(item_type.name.as_str(), item_type_id);
+        item_types.push(imported);
+    }
+
+    for obstacle in &document.obstacles {
+        if out_of_bounds(&obstacle.cells, space.width, space.height) {
+            return Err(ProdError::Conflict(
+                "Obstacle overlaps with borders".to_string(),
+            ));
+        }
+        insert_obstacle(&mut *conn, space.id, obstacle).await?;
+    }
+
+    for item in &document.items {
+        sqlx::query!(
+            r#"
+            INSERT INTO coworking_items
+                (name, description, item_id, base_point, coworking_id, rotation, mirrored)
+            VALUES ($1, $2, $3, point($4::bigint, $5::bigint), $6, $7, $8)
+            "#,
+            item.name,
+            item.description,
+            type_ids[item.item_type.as_str()],
+            item.base_point.x,
+            item.base_point.y,
+            space.id,
+            item.rotation,
+            item.mirrored
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    let footprints: Vec<Vec<Point>> = live_items(&mut *conn, space.id)
+        .await?
+        .into_iter()
+        .map(|item| item.footprint)
+        .collect();
+    let blocked = obstacle_cells(&mut *conn, space.id).await?;
+    check_placement(&footprints, &space, &blocked)?;
+
+    let after = snapshot(&mut *conn, AuditEntity::Coworking, space.id).await?;
+    record(
+        conn,
+        meta,
+        claims,
+        AuditEntry::new("coworking.import", AuditEntity::Coworking, space.id).after(after),
+    )
+    .await?;
+
+    // Uploaded last, so a layout that fails the checks leaves no icons behind.
+    if !dry_run {
+        for (name, svg) in icons {
+            upload_file(state, &name, "image/svg".to_string(), svg).await?;
+        }
+    }
+
+    Ok(LayoutImportReport {
+        dry_run,
+        coworking: (!dry_run).then_some(space),
+        item_types,
+        items: document.items.len(),
+        obstacles: document.obstacles.len(),
+    })
+}
@@ -0,0 +1,266 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::sync::{Arc, LazyLock};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::NaiveDateTime;
+use resvg::{tiny_skia, usvg};
+use sqlx::PgConnection;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::errors::ProdError;
+use crate::models::{
+    CoworkingItemsModel, CoworkingSpacesModel, ObstacleKind, ObstacleModel, Point,
+};
+use crate::s3::utils::download_file;
+use crate::AppState;
+
+/// Fonts for the item names, loaded once since rasterizing happens on every PNG request.
+static FONTS: LazyLock<Arc<usvg::fontdb::Database>> = LazyLock::new(|| {
+    let mut fonts = usvg::fontdb::Database::new();
+    fonts.load_system_fonts();
+    Arc::new(fonts)
+});
+
+const GRID_COLOR: &str = "#e0e0e0";
+const FREE_COLOR: &str = "#2e7d32";
+const BUSY_COLOR: &str = "#c62828";
+
+/// Longest side of a picture in pixels, so a big coworking can't ask for a gigapixel PNG.
+const MAX_SIDE: i64 = 4096;
+
+/// Cell size that fits the coworking into [`MAX_SIDE`], no bigger than the one asked for.
+pub fn fit_cell_size(space: &CoworkingSpacesModel, cell: i64) -> i64 {
+    let longest = space.width.max(space.height).max(1);
+    cell.min(MAX_SIDE / longest).max(1)
+}
+
+/// How items of a type are drawn.
+pub struct ItemLook {
+    pub color: String,
+    /// Icon as a data URI, ready to embed.
+    pub icon: Option<String>,
+    pub bookable: bool,
+}
+
+/// Looks of the item types used by the items, with their icons fetched from S3.
+///
+/// An icon that can't be fetched is left out rather than failing the whole picture.
+pub async fn item_looks(
+    state: &AppState,
+    conn: &mut PgConnection,
+    items: &[CoworkingItemsModel],
+) -> Result<HashMap<Uuid, ItemLook>, ProdError> {
+    let type_ids: Vec<Uuid> = items.iter().map(|item| item.item_id).collect();
+    let types = sqlx::query!(
+        r#"
+        SELECT id, color, icon, bookable
+        FROM item_types
+        WHERE id = ANY($1)
+        "#,
+        &type_ids
+    )
+    .fetch_all(conn)
+    .await?;
+
+    let mut looks = HashMap::new();
+    for item_type in types {
+        let icon = match item_type.icon {
+            Some(name) => match download_file(state, &name).await {
+                Ok(svg) => Some(format!(
+                    "data:image/svg+xml;base64,{}",
+                    STANDARD.encode(svg)
+                )),
+                Err(err) => {
+                    warn!("Failed to fetch icon {name}: {err}");
+                    None
+                }
+            },
+            None => None,
+        };
+
+        looks.insert(
+            item_type.id,
+            ItemLook {
+                color: item_type.color,
+                icon,
+                bookable: item_type.bookable,
+            },
+        );
+    }
+
+    Ok(looks)
+}
+
+/// Items of the coworking that are booked at that moment, shared ones once their seats are taken.
+pub async fn busy_items(
+    conn: &mut PgConnection,
+    coworking_id: Uuid,
+    at: NaiveDateTime,
+) -> Result<HashSet<Uuid>, ProdError> {
+    Ok(sqlx::query_scalar!(
+        r#"
+        SELECT b.coworking_item_id
+        FROM bookings b
+        JOIN coworking_items c ON c.id = b.coworking_item_id
+        JOIN item_types t ON t.id = c.item_id
+        WHERE b.coworking_space_id = $1 AND b.time_start <= $2 AND b.time_end > $2
+        GROUP BY b.coworking_item_id, t.booking_mode, t.capacity
+        HAVING t.booking_mode = 'exclusive' OR SUM(b.attendees) >= t.capacity
+        "#,
+        coworking_id,
+        at
+    )
+    .fetch_all(conn)
+    .await?
+    .into_iter()
+    .collect())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Item type colors are stored as typed in, with or without `#`. Anything else is drawn grey.
+fn fill(color: &str) -> String {
+    let hex = color.trim_start_matches('#');
+    if matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        format!("#{hex}")
+    } else {
+        "#9e9e9e".to_string()
+    }
+}
+
+const fn obstacle_fill(kind: ObstacleKind) -> &'static str {
+    match kind {
+        ObstacleKind::Wall => "#424242",
+        ObstacleKind::Pillar => "#757575",
+        ObstacleKind::Door => "#a1887f",
+        ObstacleKind::Window => "#90caf9",
+    }
+}
+
+fn cell_rect(svg: &mut String, p: &Point, cell: i64, fill: &str, extra: &str) {
+    let _ = write!(
+        svg,
+        r#"<rect x="{}" y="{}" width="{cell}" height="{cell}" fill="{fill}"{extra}/>"#,
+        p.x * cell,
+        p.y * cell,
+    );
+}
+
+/// Draws the coworking as SVG, `cell` pixels per grid cell.
+///
+/// With `busy` set, bookable items are tinted green when free and red when booked.
+pub fn layout_svg(
+    space: &CoworkingSpacesModel,
+    items: &[CoworkingItemsModel],
+    looks: &HashMap<Uuid, ItemLook>,
+    obstacles: &[ObstacleModel],
+    busy: Option<&HashSet<Uuid>>,
+    cell: i64,
+) -> String {
+    let (width, height) = (space.width * cell, space.height * cell);
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    let _ = write!(
+        svg,
+        r##"<title>{}</title><rect width="{width}" height="{height}" fill="#ffffff"/>"##,
+        escape(&space.address)
+    );
+
+    let _ = write!(svg, r#"<g stroke="{GRID_COLOR}" stroke-width="1">"#);
+    for x in 0..=space.width {
+        let _ = write!(
+            svg,
+            r#"<line x1="{0}" y1="0" x2="{0}" y2="{height}"/>"#,
+            x * cell
+        );
+    }
+    for y in 0..=space.height {
+        let _ = write!(
+            svg,
+            r#"<line x1="0" y1="{0}" x2="{width}" y2="{0}"/>"#,
+            y * cell
+        );
+    }
+    svg.push_str("</g>");
+
+    for obstacle in obstacles {
+        let fill = obstacle_fill(obstacle.kind);
+        for p in &obstacle.cells {
+            cell_rect(&mut svg, p, cell, fill, "");
+        }
+    }
+
+    let font_size = (cell / 4).max(6);
+    for item in items {
+        let look = looks.get(&item.item_id);
+        let color = look.map_or_else(|| fill(""), |look| fill(&look.color));
+
+        let _ = write!(svg, "<g><title>{}</title>", escape(&item.name));
+        for p in &item.footprint {
+            cell_rect(
+                &mut svg,
+                p,
+                cell,
+                &color,
+                r##" stroke="#ffffff" stroke-width="1""##,
+            );
+        }
+
+        if let Some(icon) = look.and_then(|look| look.icon.as_ref()) {
+            let _ = write!(
+                svg,
+                r#"<image x="{}" y="{}" width="{cell}" height="{cell}" href="{icon}"/>"#,
+                item.base_point.x * cell,
+                item.base_point.y * cell,
+            );
+        }
+
+        if let (Some(busy), Some(true)) = (busy, look.map(|look| look.bookable)) {
+            let tint = if busy.contains(&item.id) {
+                BUSY_COLOR
+            } else {
+                FREE_COLOR
+            };
+            for p in &item.footprint {
+                cell_rect(&mut svg, p, cell, tint, r#" fill-opacity="0.45""#);
+            }
+        }
+
+        let _ = write!(
+            svg,
+            r##"<text x="{}" y="{}" font-family="sans-serif" font-size="{font_size}" fill="#000000">{}</text></g>"##,
+            item.base_point.x * cell + 2,
+            (item.base_point.y + 1) * cell - 2,
+            escape(&item.name)
+        );
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Rasterizes an SVG made by [`layout_svg`] to PNG.
+pub fn svg_to_png(svg: &str) -> Result<Vec<u8>, ProdError> {
+    let options = usvg::Options {
+        fontdb: FONTS.clone(),
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_str(svg, &options).map_err(|err| ProdError::Unknown(err.into()))?;
+
+    let size = tree.size().to_int_size();
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| ProdError::ShitHappened("Picture is too big".to_string()))?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+
+    pixmap
+        .encode_png()
+        .map_err(|err| ProdError::Unknown(err.into()))
+}
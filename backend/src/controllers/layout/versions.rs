@@ -0,0 +1,609 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use sqlx::{Acquire, PgConnection};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::controllers::audit::{record_as, snapshot, AuditEntity, AuditEntry};
+use crate::controllers::layout::{
+    check_placement, obstacle_cells, placed_footprint, require_bookings_fit, retire_items,
+};
+use crate::db::Db;
+use crate::errors::ProdError;
+use crate::forms::items::DraftItemForm;
+use crate::middlewares::RequestMeta;
+use crate::models::{
+    BookingModel, CoworkingSpacesModel, LayoutDiffModel, LayoutItemModel, LayoutVersionModel,
+    LayoutVersionStatus, Point,
+};
+use crate::AppState;
+
+const PUBLISH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Version of a coworking of the company.
+pub async fn layout_version(
+    conn: &mut PgConnection,
+    coworking_id: Uuid,
+    version_id: Uuid,
+    company_id: Uuid,
+) -> Result<LayoutVersionModel, ProdError> {
+    sqlx::query_as!(
+        LayoutVersionModel,
+        r#"
+        SELECT id, coworking_id, status as "status: LayoutVersionStatus", created_by, created_at,
+               publish_at, published_by, published_at, company_id
+        FROM layout_versions
+        WHERE id = $1 AND coworking_id = $2 AND company_id = $3
+        "#,
+        version_id,
+        coworking_id,
+        company_id
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such layout version".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })
+}
+
+/// Items of the version, ordered by key.
+pub async fn version_items(
+    conn: &mut PgConnection,
+    version_id: Uuid,
+) -> Result<Vec<LayoutItemModel>, ProdError> {
+    Ok(sqlx::query_as!(
+        LayoutItemModel,
+        r#"
+        SELECT v.item_key as key, v.item_id, v.name, v.description,
+               v.base_point as "base_point: Point", v.rotation, v.mirrored,
+               item_footprint(v.base_point, i.offsets, v.rotation, v.mirrored) as "footprint!: Vec<Point>"
+        FROM layout_version_items v
+        JOIN item_types i ON i.id = v.item_id
+        WHERE v.version_id = $1
+        ORDER BY v.item_key
+        "#,
+        version_id
+    )
+    .fetch_all(conn)
+    .await?)
+}
+
+/// Items placed in the coworking right now, keyed by their id.
+pub async fn live_items(
+    conn: &mut PgConnection,
+    coworking_id: Uuid,
+) -> Result<Vec<LayoutItemModel>, ProdError> {
+    Ok(sqlx::query_as!(
+        LayoutItemModel,
+        r#"
+        SELECT c.id as key, c.item_id, c.name, c.description,
+               c.base_point as "base_point: Point", c.rotation, c.mirrored,
+               item_footprint(c.base_point, i.offsets, c.rotation, c.mirrored) as "footprint!: Vec<Point>"
+        FROM coworking_items c
+        JOIN item_types i ON i.id = c.item_id
+        WHERE c.coworking_id = $1 AND c.retired_at IS NULL
+        ORDER BY c.id
+        "#,
+        coworking_id
+    )
+    .fetch_all(conn)
+    .await?)
+}
+
+/// Empty published version of a new coworking, the first layout to roll back to.
+pub async fn create_initial_version(
+    conn: &mut PgConnection,
+    coworking_id: Uuid,
+    company_id: Uuid,
+    created_by: Uuid,
+) -> Result<(), ProdError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO layout_versions
+            (coworking_id, company_id, status, created_by, published_by, published_at)
+        VALUES ($1, $2, 'published', $3, $3, NOW())
+        "#,
+        coworking_id,
+        company_id,
+        created_by
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Copies the live layout of the coworking into a new draft.
+pub async fn create_draft(
+    conn: &mut PgConnection,
+    coworking_id: Uuid,
+    company_id: Uuid,
+    created_by: Uuid,
+) -> Result<Uuid, ProdError> {
+    let version_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO layout_versions (coworking_id, company_id, created_by)
+        VALUES ($1, $2, $3)
+        RETURNING id
+        "#,
+        coworking_id,
+        company_id,
+        created_by
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO layout_version_items
+            (version_id, item_key, item_id, name, description, base_point, rotation, mirrored)
+        SELECT $1, id, item_id, name, description, base_point, rotation, mirrored
+        FROM coworking_items
+        WHERE coworking_id = $2 AND retired_at IS NULL
+        "#,
+        version_id,
+        coworking_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(version_id)
+}
+
+/// Copies the items of the version into a new draft of its coworking.
+pub async fn copy_version(
+    conn: &mut PgConnection,
+    version: &LayoutVersionModel,
+    created_by: Uuid,
+) -> Result<Uuid, ProdError> {
+    let copy_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO layout_versions (coworking_id, company_id, created_by)
+        VALUES ($1, $2, $3)
+        RETURNING id
+        "#,
+        version.coworking_id,
+        version.company_id,
+        created_by
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO layout_version_items
+            (version_id, item_key, item_id, name, description, base_point, rotation, mirrored)
+        SELECT $1, item_key, item_id, name, description, base_point, rotation, mirrored
+        FROM layout_version_items
+        WHERE version_id = $2
+        "#,
+        copy_id,
+        version.id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(copy_id)
+}
+
+/// Same placement and description, the parts of an item a version can change.
+fn unchanged(a: &LayoutItemModel, b: &LayoutItemModel) -> bool {
+    a.item_id == b.item_id
+        && a.name == b.name
+        && a.description == b.description
+        && a.base_point == b.base_point
+        && a.rotation == b.rotation
+        && a.mirrored == b.mirrored
+}
+
+/// Fails unless all keys belong to items the coworking has or had in one of its versions.
+async fn check_item_keys(
+    conn: &mut PgConnection,
+    coworking_id: Uuid,
+    keys: &[Uuid],
+) -> Result<(), ProdError> {
+    let unknown = sqlx::query_scalar!(
+        r#"
+        SELECT k as "key!"
+        FROM unnest($2::uuid[]) k
+        WHERE k NOT IN (SELECT id FROM coworking_items WHERE coworking_id = $1)
+            AND k NOT IN (
+                SELECT i.item_key
+                FROM layout_version_items i
+                JOIN layout_versions v ON v.id = i.version_id
+                WHERE v.coworking_id = $1
+            )
+        LIMIT 1
+        "#,
+        coworking_id,
+        keys
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    unknown.map_or_else(
+        || Ok(()),
+        |key| {
+            Err(ProdError::NotFound(format!(
+                "No item {key} in that coworking"
+            )))
+        },
+    )
+}
+
+/// Replaces the items of the version, checked against the coworking like placed items.
+/// Items without a key get a new one.
+pub async fn put_version_items(
+    conn: &mut PgConnection,
+    space: &CoworkingSpacesModel,
+    version_id: Uuid,
+    items: Vec<DraftItemForm>,
+) -> Result<(), ProdError> {
+    let mut keys: Vec<Uuid> = items.iter().filter_map(|item| item.key).collect();
+    check_item_keys(conn, space.id, &keys).await?;
+    keys.sort_unstable();
+    keys.dedup();
+    if keys.len() != items.iter().filter(|item| item.key.is_some()).count() {
+        return Err(ProdError::ShitHappened(
+            "Item keys should be unique".to_string(),
+        ));
+    }
+
+    let mut footprints = Vec::new();
+    for draft in &items {
+        footprints.push(placed_footprint(conn, &draft.item).await?);
+    }
+    let blocked = obstacle_cells(conn, space.id).await?;
+    check_placement(&footprints, space, &blocked)?;
+
+    sqlx::query!(
+        r#"DELETE FROM layout_version_items WHERE version_id = $1"#,
+        version_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    for draft in items {
+        let item = draft.item;
+        sqlx::query!(
+            r#"
+            INSERT INTO layout_version_items
+                (version_id, item_key, item_id, name, description, base_point, rotation, mirrored)
+            VALUES ($1, $2, $3, $4, $5, point($6::bigint, $7::bigint), $8, $9)
+            "#,
+            version_id,
+            draft.key.unwrap_or_else(Uuid::now_v7),
+            item.item_id,
+            item.name,
+            item.description,
+            item.base_point.x,
+            item.base_point.y,
+            item.rotation,
+            item.mirrored
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Compares the version with the live layout of its coworking.
+pub async fn diff(
+    conn: &mut PgConnection,
+    version: &LayoutVersionModel,
+) -> Result<LayoutDiffModel, ProdError> {
+    let mut live: HashMap<Uuid, LayoutItemModel> = live_items(conn, version.coworking_id)
+        .await?
+        .into_iter()
+        .map(|item| (item.key, item))
+        .collect();
+
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+    for item in version_items(conn, version.id).await? {
+        match live.remove(&item.key) {
+            None => added.push(item),
+            Some(current) if !unchanged(&current, &item) => changed.push(item),
+            Some(_) => {}
+        }
+    }
+    let mut removed: Vec<LayoutItemModel> = live.into_values().collect();
+    removed.sort_by_key(|item| item.key);
+
+    let removed_keys: Vec<Uuid> = removed.iter().map(|item| item.key).collect();
+    let affected_bookings = sqlx::query_as!(
+        BookingModel,
+        r#"
+        SELECT id, user_id, coworking_space_id, coworking_item_id,
+               company_id, time_start, time_end, attendees
+        FROM bookings
+        WHERE coworking_item_id = ANY($1) AND time_end > NOW()
+        ORDER BY time_start
+        "#,
+        &removed_keys
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(LayoutDiffModel {
+        added,
+        removed,
+        changed,
+        affected_bookings,
+    })
+}
+
+/// Makes the version the live layout of its coworking and archives the one it replaces.
+///
+/// Items keep their ids across versions, so only bookings of removed items are cancelled.
+/// Removed items are retired rather than deleted and come back when a version that has them is
+/// published again.
+pub async fn publish(
+    conn: &mut PgConnection,
+    version: &LayoutVersionModel,
+    published_by: Option<Uuid>,
+) -> Result<LayoutVersionModel, ProdError> {
+    let space = sqlx::query_as!(
+        CoworkingSpacesModel,
+        r#"
+        SELECT id, address, height, width, building_id, floor_id, company_id
+        FROM coworking_spaces
+        WHERE id = $1 AND deleted_at IS NULL
+        FOR UPDATE
+        "#,
+        version.coworking_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such coworking exists".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    let placements = sqlx::query!(
+        r#"
+        SELECT item_footprint(v.base_point, i.offsets, v.rotation, v.mirrored) as "footprint!: Vec<Point>",
+               i.deleted_at IS NOT NULL as "trashed!"
+        FROM layout_version_items v
+        JOIN item_types i ON i.id = v.item_id
+        WHERE v.version_id = $1
+        "#,
+        version.id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    if placements.iter().any(|placement| placement.trashed) {
+        return Err(ProdError::Conflict(
+            "Some item types of the layout are in the trash".to_string(),
+        ));
+    }
+    let footprints: Vec<Vec<Point>> = placements.into_iter().map(|p| p.footprint).collect();
+    let blocked = obstacle_cells(conn, space.id).await?;
+    check_placement(&footprints, &space, &blocked)?;
+
+    // New bookings of the items wait for this lock in their trigger, so none slip past the check.
+    let retyped = sqlx::query!(
+        r#"
+        SELECT c.id, v.item_id
+        FROM layout_version_items v
+        JOIN coworking_items c ON c.id = v.item_key AND c.coworking_id = $2
+        WHERE v.version_id = $1 AND c.item_id <> v.item_id
+        FOR UPDATE OF c
+        "#,
+        version.id,
+        space.id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    for item in retyped {
+        require_bookings_fit(conn, item.id, item.item_id).await?;
+    }
+
+    // Items placed one by one since the last publish belong to the version being replaced.
+    sqlx::query!(
+        r#"
+        DELETE FROM layout_version_items
+        WHERE version_id IN (
+            SELECT id FROM layout_versions WHERE coworking_id = $1 AND status = 'published'
+        )
+        "#,
+        space.id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO layout_version_items
+            (version_id, item_key, item_id, name, description, base_point, rotation, mirrored)
+        SELECT v.id, c.id, c.item_id, c.name, c.description, c.base_point, c.rotation, c.mirrored
+        FROM coworking_items c
+        JOIN layout_versions v ON v.coworking_id = c.coworking_id AND v.status = 'published'
+        WHERE c.coworking_id = $1 AND c.retired_at IS NULL
+        "#,
+        space.id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let removed = sqlx::query_scalar!(
+        r#"
+        SELECT id FROM coworking_items
+        WHERE coworking_id = $1 AND retired_at IS NULL
+            AND id NOT IN (SELECT item_key FROM layout_version_items WHERE version_id = $2)
+        "#,
+        space.id,
+        version.id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    retire_items(conn, &removed).await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO coworking_items
+            (id, name, description, item_id, base_point, coworking_id, rotation, mirrored)
+        SELECT item_key, name, description, item_id, base_point, $2, rotation, mirrored
+        FROM layout_version_items
+        WHERE version_id = $1
+        ON CONFLICT (id) DO UPDATE SET
+            name = EXCLUDED.name,
+            description = EXCLUDED.description,
+            item_id = EXCLUDED.item_id,
+            base_point = EXCLUDED.base_point,
+            rotation = EXCLUDED.rotation,
+            mirrored = EXCLUDED.mirrored,
+            retired_at = NULL
+        WHERE coworking_items.coworking_id = EXCLUDED.coworking_id
+        "#,
+        version.id,
+        space.id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE layout_versions SET status = 'archived'
+        WHERE coworking_id = $1 AND status = 'published'
+        "#,
+        space.id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(sqlx::query_as!(
+        LayoutVersionModel,
+        r#"
+        UPDATE layout_versions
+        SET status = 'published', publish_at = NULL, published_by = $2, published_at = NOW()
+        WHERE id = $1
+        RETURNING id, coworking_id, status as "status: LayoutVersionStatus", created_by,
+                  created_at, publish_at, published_by, published_at, company_id
+        "#,
+        version.id,
+        published_by
+    )
+    .fetch_one(conn)
+    .await?)
+}
+
+/// Scheduled versions whose time has come.
+async fn due_versions(state: &AppState) -> Result<Vec<LayoutVersionModel>, ProdError> {
+    let mut conn = state.pool.unscoped_conn().await?;
+
+    Ok(sqlx::query_as!(
+        LayoutVersionModel,
+        r#"
+        SELECT id, coworking_id, status as "status: LayoutVersionStatus", created_by, created_at,
+               publish_at, published_by, published_at, company_id
+        FROM layout_versions
+        WHERE status = 'scheduled' AND publish_at <= NOW()
+        ORDER BY publish_at
+        "#
+    )
+    .fetch_all(conn.as_mut())
+    .await?)
+}
+
+/// Publishes a scheduled version on behalf of whoever scheduled it.
+///
+/// Gives `false` when the version was unscheduled or published since it was listed.
+async fn publish_scheduled(state: &AppState, version_id: Uuid) -> Result<bool, ProdError> {
+    let mut conn = state.pool.unscoped_conn().await?;
+    let mut tx = conn.begin().await?;
+
+    let Some(version) = sqlx::query_as!(
+        LayoutVersionModel,
+        r#"
+        SELECT id, coworking_id, status as "status: LayoutVersionStatus", created_by, created_at,
+               publish_at, published_by, published_at, company_id
+        FROM layout_versions
+        WHERE id = $1 AND status = 'scheduled' AND publish_at <= NOW()
+        FOR UPDATE
+        "#,
+        version_id
+    )
+    .fetch_optional(tx.as_mut())
+    .await?
+    else {
+        return Ok(false);
+    };
+
+    let before = snapshot(tx.as_mut(), AuditEntity::Layout, version.coworking_id).await?;
+    publish(tx.as_mut(), &version, version.published_by).await?;
+    let after = snapshot(tx.as_mut(), AuditEntity::Layout, version.coworking_id).await?;
+
+    let meta = RequestMeta {
+        request_id: version.id.to_string(),
+        ip: None,
+    };
+    record_as(
+        tx.as_mut(),
+        &meta,
+        version.company_id,
+        version.published_by,
+        AuditEntry::new("layout.publish", AuditEntity::Layout, version.coworking_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+/// Publishes scheduled versions every minute, for as long as the server is up.
+///
+/// A version that no longer fits its coworking goes back to being a draft.
+pub async fn layout_publish_job(state: AppState) {
+    let mut interval = tokio::time::interval(PUBLISH_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let versions = match due_versions(&state).await {
+            Ok(versions) => versions,
+            Err(err) => {
+                warn!("Failed to list due layout versions: {err:?}");
+                continue;
+            }
+        };
+        for version in versions {
+            match publish_scheduled(&state, version.id).await {
+                Ok(true) => info!("Published layout version {}", version.id),
+                Ok(false) => {}
+                Err(err) => {
+                    warn!("Failed to publish layout version {}: {err:?}", version.id);
+                    if let Err(err) = unschedule(&state, version.id).await {
+                        warn!(
+                            "Failed to unschedule layout version {}: {err:?}",
+                            version.id
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn unschedule(state: &AppState, version_id: Uuid) -> Result<(), ProdError> {
+    let mut conn = state.pool.unscoped_conn().await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE layout_versions SET status = 'draft', publish_at = NULL
+        WHERE id = $1 AND status = 'scheduled'
+        "#,
+        version_id
+    )
+    .execute(conn.as_mut())
+    .await?;
+
+    Ok(())
+}
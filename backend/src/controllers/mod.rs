@@ -1 +1,11 @@
+pub mod api_keys;
+pub mod attributes;
+pub mod audit;
+pub mod companies;
+pub mod layout;
+pub mod roles;
+pub mod scim;
+pub mod teams;
+pub mod trash;
 pub mod users;
+pub mod verification;
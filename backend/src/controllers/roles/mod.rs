@@ -0,0 +1,134 @@
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::errors::ProdError;
+use crate::jwt::models::Claims;
+use crate::models::{Permission, RoleModel};
+
+/// Permissions granted by the user's custom role, or by the built-in role matching `users.role`.
+/// Deactivated users have none.
+pub async fn user_permissions(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+) -> Result<Vec<Permission>, ProdError> {
+    sqlx::query!(
+        r#"
+        SELECT r.permissions as "permissions: Vec<Permission>"
+        FROM users u
+        JOIN roles r ON r.company_id = u.company_id
+            AND (r.id = u.role_id OR (u.role_id IS NULL AND r.base_role = u.role))
+        WHERE u.id = $1 AND u.active AND u.deleted_at IS NULL
+        "#,
+        user_id
+    )
+    .fetch_one(conn)
+    .await
+    .map(|record| record.permissions)
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::Forbidden("No such user".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })
+}
+
+/// Permissions of the authenticated user, narrowed down to the API key scopes if one was used.
+pub async fn claims_permissions(
+    conn: &mut PgConnection,
+    claims: &Claims,
+) -> Result<Vec<Permission>, ProdError> {
+    let mut granted = user_permissions(conn, claims.user_id).await?;
+
+    if let Some(scopes) = &claims.scopes {
+        granted.retain(|permission| scopes.contains(permission));
+    }
+
+    Ok(granted)
+}
+
+pub async fn require_permission(
+    conn: &mut PgConnection,
+    claims: &Claims,
+    permission: Permission,
+) -> Result<(), ProdError> {
+    if claims_permissions(conn, claims)
+        .await?
+        .contains(&permission)
+    {
+        Ok(())
+    } else {
+        Err(ProdError::Forbidden(format!(
+            "Missing permission `{}`",
+            permission.as_str()
+        )))
+    }
+}
+
+/// Nobody can hand out permissions they don't have themselves, be it through roles, invites or keys.
+pub async fn require_grantable(
+    conn: &mut PgConnection,
+    claims: &Claims,
+    permissions: &[Permission],
+) -> Result<(), ProdError> {
+    let granted = claims_permissions(conn, claims).await?;
+
+    if let Some(missing) = permissions.iter().find(|p| !granted.contains(p)) {
+        return Err(ProdError::Forbidden(format!(
+            "You can't grant permission `{}` you don't have",
+            missing.as_str()
+        )));
+    }
+
+    Ok(())
+}
+
+/// [`require_grantable`] for everything a company role grants.
+pub async fn require_grantable_role(
+    conn: &mut PgConnection,
+    claims: &Claims,
+    role_id: Uuid,
+) -> Result<(), ProdError> {
+    let permissions = sqlx::query!(
+        r#"
+        SELECT permissions as "permissions: Vec<Permission>"
+        FROM roles
+        WHERE id = $1 AND company_id = $2
+        "#,
+        role_id,
+        claims.company_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map(|record| record.permissions)
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such role".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    require_grantable(conn, claims, &permissions).await
+}
+
+/// Splits a company role into the `users.role` / `users.role_id` pair it's stored as.
+/// Built-in roles are stored as the base role itself, custom ones as an override on top of it.
+pub async fn resolve_role(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+    role_id: Uuid,
+) -> Result<(Option<RoleModel>, Option<Uuid>), ProdError> {
+    let base_role = sqlx::query!(
+        r#"
+        SELECT base_role as "base_role: RoleModel"
+        FROM roles
+        WHERE id = $1 AND company_id = $2
+        "#,
+        role_id,
+        company_id
+    )
+    .fetch_one(conn)
+    .await
+    .map(|record| record.base_role)
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such role".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    Ok(base_role.map_or((None, Some(role_id)), |base_role| (Some(base_role), None)))
+}
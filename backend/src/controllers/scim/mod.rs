@@ -0,0 +1,185 @@
+use axum::http::HeaderMap;
+use serde_json::Value;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    errors::ProdError, forms::scim::ScimPatchOperation, jwt::hashing::hash_token,
+    models::RoleModel, models::UserModel,
+};
+
+pub struct ScimCompany {
+    pub company_id: Uuid,
+    pub default_role: RoleModel,
+}
+
+/// Resolves the company from the SCIM bearer secret.
+pub async fn scim_company(
+    conn: &mut PgConnection,
+    headers: &HeaderMap,
+) -> Result<ScimCompany, ProdError> {
+    let token = headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| ProdError::Forbidden("No authorization token was found".to_string()))?;
+
+    sqlx::query_as!(
+        ScimCompany,
+        r#"
+        SELECT company_id, default_role as "default_role: RoleModel"
+        FROM scim_tokens
+        WHERE token_hash = $1
+        "#,
+        hash_token(token)
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::Forbidden("Invalid SCIM token".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })
+}
+
+pub enum ScimFilter {
+    UserName(String),
+    ExternalId(String),
+}
+
+/// Parses the `attribute eq "value"` filters identity providers use to look up users.
+pub fn parse_filter(filter: &str) -> Result<ScimFilter, ProdError> {
+    let invalid = || ProdError::ShitHappened(format!("Unsupported filter `{filter}`"));
+
+    let mut parts = filter.trim().splitn(3, ' ');
+    let (Some(attribute), Some(operator), Some(value)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid());
+    };
+
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(invalid)?
+        .to_string();
+
+    if !operator.eq_ignore_ascii_case("eq") {
+        return Err(invalid());
+    }
+
+    if attribute.eq_ignore_ascii_case("userName") {
+        Ok(ScimFilter::UserName(value))
+    } else if attribute.eq_ignore_ascii_case("externalId") {
+        Ok(ScimFilter::ExternalId(value))
+    } else {
+        Err(invalid())
+    }
+}
+
+fn string_value(path: &str, value: &Value) -> Result<String, ProdError> {
+    match value {
+        Value::String(value) => Ok(value.clone()),
+        // Multi-valued `emails`, the primary or the first one wins.
+        Value::Array(values) => values
+            .iter()
+            .find(|email| email["primary"].as_bool() == Some(true))
+            .or_else(|| values.first())
+            .and_then(|email| email["value"].as_str())
+            .map(ToString::to_string)
+            .ok_or_else(|| ProdError::ShitHappened(format!("`{path}` has no value"))),
+        _ => Err(ProdError::ShitHappened(format!(
+            "`{path}` must be a string"
+        ))),
+    }
+}
+
+fn bool_value(path: &str, value: &Value) -> Result<bool, ProdError> {
+    match value {
+        Value::Bool(value) => Ok(*value),
+        // Some providers send booleans as "True" / "False".
+        Value::String(value) if value.eq_ignore_ascii_case("true") => Ok(true),
+        Value::String(value) if value.eq_ignore_ascii_case("false") => Ok(false),
+        _ => Err(ProdError::ShitHappened(format!(
+            "`{path}` must be a boolean"
+        ))),
+    }
+}
+
+fn apply_value(user: &mut UserModel, path: &str, value: &Value) -> Result<(), ProdError> {
+    // `emails[type eq "work"].value` and alike address the only e-mail a user has.
+    let attribute = path.split(['[', '.']).next().unwrap_or(path);
+
+    match path.to_lowercase().as_str() {
+        "username" => user.email = string_value(path, value)?,
+        "name.givenname" => user.name = string_value(path, value)?,
+        "name.familyname" => user.surname = string_value(path, value)?,
+        "active" => user.active = bool_value(path, value)?,
+        "externalid" => user.external_id = Some(string_value(path, value)?),
+        _ if attribute.eq_ignore_ascii_case("emails") => user.email = string_value(path, value)?,
+        "name" => {
+            if let Some(given_name) = value.get("givenName") {
+                apply_value(user, "name.givenName", given_name)?;
+            }
+            if let Some(family_name) = value.get("familyName") {
+                apply_value(user, "name.familyName", family_name)?;
+            }
+        }
+        // Attributes BooQ doesn't store, like `title` or `displayName`, are ignored.
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Applies a SCIM `PatchOp` to the user, supporting both path and path-less operations.
+pub fn apply_patch(
+    user: &mut UserModel,
+    operations: &[ScimPatchOperation],
+) -> Result<(), ProdError> {
+    for operation in operations {
+        let value = operation.value.as_ref();
+
+        match (operation.op.to_lowercase().as_str(), &operation.path, value) {
+            ("remove", Some(path), _) if path.eq_ignore_ascii_case("externalId") => {
+                user.external_id = None;
+            }
+            ("remove", path, _) => {
+                return Err(ProdError::ShitHappened(format!(
+                    "`{}` can't be removed",
+                    path.as_deref().unwrap_or_default()
+                )));
+            }
+            ("add" | "replace", Some(path), Some(value)) => apply_value(user, path, value)?,
+            ("add" | "replace", None, Some(Value::Object(values))) => {
+                for (path, value) in values {
+                    apply_value(user, path, value)?;
+                }
+            }
+            (op, _, _) => {
+                return Err(ProdError::ShitHappened(format!(
+                    "Unsupported patch operation `{op}`"
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Deletes bookings of the user that haven't started yet.
+pub async fn cancel_future_bookings(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+) -> Result<(), ProdError> {
+    let _ = sqlx::query!(
+        r#"
+        DELETE FROM bookings
+        WHERE user_id = $1 AND time_start > NOW()
+        "#,
+        user_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
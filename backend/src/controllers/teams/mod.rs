@@ -0,0 +1,213 @@
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::controllers::roles::{claims_permissions, user_permissions};
+use crate::errors::ProdError;
+use crate::jwt::models::Claims;
+use crate::models::{Permission, TeamModel};
+
+/// Fails unless the user can change the members of the team: admins with `manage_users`
+/// and managers of the team or of a team above it.
+///
+/// Returns whether the user is such an admin, only they can make managers.
+pub async fn require_team_manager(
+    conn: &mut PgConnection,
+    claims: &Claims,
+    team_id: Uuid,
+) -> Result<bool, ProdError> {
+    let managed = sqlx::query!(
+        r#"
+        WITH RECURSIVE up AS (
+            SELECT id, parent_id FROM teams WHERE id = $1 AND company_id = $2
+            UNION
+            SELECT t.id, t.parent_id FROM teams t JOIN up ON up.parent_id = t.id
+        )
+        SELECT EXISTS (
+            SELECT 1 FROM team_members m
+            JOIN up ON up.id = m.team_id
+            WHERE m.user_id = $3 AND m.manager
+        ) as "managed!"
+        FROM teams
+        WHERE id = $1 AND company_id = $2
+        "#,
+        team_id,
+        claims.company_id,
+        claims.user_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map(|record| record.managed)
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such team".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    if claims_permissions(conn, claims)
+        .await?
+        .contains(&Permission::ManageUsers)
+    {
+        return Ok(true);
+    }
+    if managed {
+        return Ok(false);
+    }
+
+    Err(ProdError::Forbidden(
+        "You don't manage this team".to_string(),
+    ))
+}
+
+/// Fails unless the authenticated user can book on behalf of the user: users with `manage_bookings`
+/// and managers of one of the user's teams. The user has to be able to book themselves.
+pub async fn require_booking_on_behalf(
+    conn: &mut PgConnection,
+    claims: &Claims,
+    user_id: Uuid,
+) -> Result<(), ProdError> {
+    let manages = sqlx::query!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM team_members
+            WHERE user_id = $3 AND manager AND team_id IN (SELECT user_team_ids(u.id))
+        ) as "manages!"
+        FROM users u
+        WHERE u.id = $1 AND u.company_id = $2 AND u.deleted_at IS NULL
+        "#,
+        user_id,
+        claims.company_id,
+        claims.user_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map(|record| record.manages)
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such user".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    if !manages
+        && !claims_permissions(conn, claims)
+            .await?
+            .contains(&Permission::ManageBookings)
+    {
+        return Err(ProdError::Forbidden(
+            "You can only book for members of the teams you manage".to_string(),
+        ));
+    }
+
+    if !user_permissions(conn, user_id)
+        .await?
+        .contains(&Permission::Book)
+    {
+        return Err(ProdError::Forbidden(format!(
+            "The user is missing permission `{}`",
+            Permission::Book.as_str()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Fails if the item or its coworking is reserved for teams the user isn't in.
+pub async fn require_can_book(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    coworking_item_id: Uuid,
+) -> Result<(), ProdError> {
+    let can_book = sqlx::query_scalar!(
+        r#"SELECT can_book_item($1, $2) as "can_book!""#,
+        user_id,
+        coworking_item_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    if can_book {
+        Ok(())
+    } else {
+        Err(ProdError::Forbidden(
+            "This place is reserved for other teams".to_string(),
+        ))
+    }
+}
+
+/// Teams of the company with the given ids, fails if any of them is missing.
+pub async fn company_teams(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+    team_ids: &[Uuid],
+) -> Result<Vec<TeamModel>, ProdError> {
+    let teams = sqlx::query_as!(
+        TeamModel,
+        r#"
+        SELECT id, name, parent_id, company_id
+        FROM teams
+        WHERE company_id = $1 AND id = ANY($2)
+        ORDER BY name, id
+        "#,
+        company_id,
+        team_ids
+    )
+    .fetch_all(conn)
+    .await?;
+
+    if teams.len() != team_ids.len() {
+        return Err(ProdError::NotFound("No such team".to_string()));
+    }
+
+    Ok(teams)
+}
+
+/// Reserves the coworking for the teams, or opens it to everybody when there are none.
+pub async fn set_coworking_teams(
+    conn: &mut PgConnection,
+    coworking_id: Uuid,
+    team_ids: &[Uuid],
+) -> Result<(), ProdError> {
+    sqlx::query!(
+        r#"DELETE FROM coworking_teams WHERE coworking_id = $1"#,
+        coworking_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO coworking_teams (coworking_id, team_id)
+        SELECT $1, UNNEST($2::uuid[])
+        "#,
+        coworking_id,
+        team_ids
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Reserves the placed item for the teams, or opens it to everybody when there are none.
+pub async fn set_item_teams(
+    conn: &mut PgConnection,
+    coworking_item_id: Uuid,
+    team_ids: &[Uuid],
+) -> Result<(), ProdError> {
+    sqlx::query!(
+        r#"DELETE FROM coworking_item_teams WHERE coworking_item_id = $1"#,
+        coworking_item_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO coworking_item_teams (coworking_item_id, team_id)
+        SELECT $1, UNNEST($2::uuid[])
+        "#,
+        coworking_item_id,
+        team_ids
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,244 @@
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use sqlx::{PgConnection, PgPool};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    db::Db,
+    errors::ProdError,
+    models::{TrashEntity, TrashItemModel},
+};
+
+/// Days a soft deleted entity can be restored before the purge job removes it.
+pub static RETENTION_DAYS: LazyLock<i32> = LazyLock::new(|| {
+    dotenvy::var("TRASH_RETENTION_DAYS")
+        .ok()
+        .and_then(|days| days.parse().ok())
+        .unwrap_or(30)
+});
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Restorable entities of the company of the given kinds, most recently deleted first.
+///
+/// Coworkings deleted together with their building are left out, they come back with it.
+pub async fn trash(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+    kinds: &[TrashEntity],
+) -> Result<Vec<TrashItemModel>, ProdError> {
+    let kinds: Vec<&str> = kinds.iter().map(|kind| kind.as_str()).collect();
+
+    Ok(sqlx::query_as!(
+        TrashItemModel,
+        r#"
+        SELECT entity as "entity!", id as "id!", name as "name!", deleted_at as "deleted_at!",
+               deleted_at + make_interval(days => $3) as "purge_at!"
+        FROM (
+            SELECT 'user' as entity, id, name || ' ' || surname as name, deleted_at
+            FROM users
+            WHERE company_id = $1 AND deleted_at IS NOT NULL AND anonymized_at IS NULL
+            UNION ALL
+            SELECT 'building', id, address, deleted_at
+            FROM buildings
+            WHERE company_id = $1 AND deleted_at IS NOT NULL
+            UNION ALL
+            SELECT 'coworking', c.id, c.address, c.deleted_at
+            FROM coworking_spaces c
+            JOIN buildings b ON b.id = c.building_id
+            WHERE c.company_id = $1 AND c.deleted_at IS NOT NULL
+                AND b.deleted_at IS DISTINCT FROM c.deleted_at
+            UNION ALL
+            SELECT 'item_type', id, name, deleted_at
+            FROM item_types
+            WHERE company_id = $1 AND deleted_at IS NOT NULL
+        ) t
+        WHERE entity = ANY($2) AND deleted_at > NOW() - make_interval(days => $3)
+        ORDER BY deleted_at DESC, id
+        "#,
+        company_id,
+        &kinds as &[&str],
+        *RETENTION_DAYS,
+    )
+    .fetch_all(conn)
+    .await?)
+}
+
+/// Brings a soft deleted entity back, a building comes back with the coworkings deleted along with it.
+pub async fn restore(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+    entity: TrashEntity,
+    id: Uuid,
+) -> Result<(), ProdError> {
+    match entity {
+        TrashEntity::User => {
+            sqlx::query!(
+                r#"
+                UPDATE users SET deleted_at = NULL
+                WHERE id = $1 AND company_id = $2 AND anonymized_at IS NULL
+                    AND deleted_at > NOW() - make_interval(days => $3)
+                RETURNING id
+                "#,
+                id,
+                company_id,
+                *RETENTION_DAYS,
+            )
+            .fetch_one(conn)
+            .await
+            .map_err(|err| not_in_trash(err, entity))?;
+        }
+        TrashEntity::Building => restore_building(conn, company_id, id).await?,
+        TrashEntity::Coworking => restore_coworking(conn, company_id, id).await?,
+        TrashEntity::ItemType => {
+            sqlx::query!(
+                r#"
+                UPDATE item_types SET deleted_at = NULL
+                WHERE id = $1 AND company_id = $2
+                    AND deleted_at > NOW() - make_interval(days => $3)
+                RETURNING id
+                "#,
+                id,
+                company_id,
+                *RETENTION_DAYS,
+            )
+            .fetch_one(conn)
+            .await
+            .map_err(|err| not_in_trash(err, entity))?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn restore_building(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+    id: Uuid,
+) -> Result<(), ProdError> {
+    let deleted_at = sqlx::query_scalar!(
+        r#"
+        SELECT deleted_at as "deleted_at!"
+        FROM buildings
+        WHERE id = $1 AND company_id = $2
+            AND deleted_at > NOW() - make_interval(days => $3)
+        FOR UPDATE
+        "#,
+        id,
+        company_id,
+        *RETENTION_DAYS,
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|err| not_in_trash(err, TrashEntity::Building))?;
+
+    sqlx::query!(
+        r#"
+        UPDATE coworking_spaces SET deleted_at = NULL
+        WHERE building_id = $1 AND deleted_at = $2
+        "#,
+        id,
+        deleted_at
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"UPDATE buildings SET deleted_at = NULL WHERE id = $1"#,
+        id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn restore_coworking(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+    id: Uuid,
+) -> Result<(), ProdError> {
+    let building_deleted = sqlx::query_scalar!(
+        r#"
+        SELECT b.deleted_at IS NOT NULL as "building_deleted!"
+        FROM coworking_spaces c
+        JOIN buildings b ON b.id = c.building_id
+        WHERE c.id = $1 AND c.company_id = $2
+            AND c.deleted_at > NOW() - make_interval(days => $3)
+        FOR UPDATE OF c
+        "#,
+        id,
+        company_id,
+        *RETENTION_DAYS,
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|err| not_in_trash(err, TrashEntity::Coworking))?;
+
+    if building_deleted {
+        return Err(ProdError::Conflict(
+            "The building of this coworking is deleted. Restore the building first.".to_string(),
+        ));
+    }
+
+    sqlx::query!(
+        r#"UPDATE coworking_spaces SET deleted_at = NULL WHERE id = $1"#,
+        id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+fn not_in_trash(err: sqlx::Error, entity: TrashEntity) -> ProdError {
+    match err {
+        sqlx::Error::RowNotFound => {
+            ProdError::NotFound(format!("No such {} in the trash", entity.as_str()))
+        }
+        _ => ProdError::DatabaseError(err),
+    }
+}
+
+/// Deletes everything that has been in the trash longer than the retention period,
+/// along with its items and bookings. Anonymized users are kept for their bookings.
+/// Returns the number of removed entities.
+pub async fn purge_expired(conn: &mut PgConnection) -> Result<u64, ProdError> {
+    let mut purged = 0;
+
+    for query in [
+        "DELETE FROM coworking_spaces WHERE deleted_at <= NOW() - make_interval(days => $1)",
+        "DELETE FROM buildings WHERE deleted_at <= NOW() - make_interval(days => $1)",
+        "DELETE FROM item_types WHERE deleted_at <= NOW() - make_interval(days => $1)",
+        "DELETE FROM users WHERE deleted_at <= NOW() - make_interval(days => $1) AND anonymized_at IS NULL",
+    ] {
+        purged += sqlx::query(query)
+            .bind(*RETENTION_DAYS)
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+    }
+
+    Ok(purged)
+}
+
+/// Runs [`purge_expired`] every hour, for as long as the server is up.
+pub async fn purge_job(pool: PgPool) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let purged = match pool.conn().await {
+            Ok(mut conn) => purge_expired(conn.as_mut()).await,
+            Err(err) => Err(err),
+        };
+        match purged {
+            Ok(0) => {}
+            Ok(purged) => info!("Purged {purged} entities from the trash"),
+            Err(err) => warn!("Failed to purge the trash: {err:?}"),
+        }
+    }
+}
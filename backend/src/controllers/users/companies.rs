@@ -0,0 +1,208 @@
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::errors::ProdError;
+use crate::forms::users::{CompanyMembership, LinkCompanyForm};
+use crate::jwt::hashing::Argon;
+use crate::models::{RoleModel, TokenData};
+
+/// Companies the identity has an active account in, oldest membership first.
+pub async fn memberships(
+    conn: &mut PgConnection,
+    identity_id: Uuid,
+) -> Result<Vec<CompanyMembership>, ProdError> {
+    Ok(sqlx::query_as!(
+        CompanyMembership,
+        r#"
+        SELECT c.id as company_id,
+               c.name as company_name,
+               c.domain as company_domain,
+               u.role as "role: RoleModel",
+               r.name as role_name
+        FROM users u
+        JOIN companies c ON c.id = u.company_id
+        JOIN roles r ON r.company_id = u.company_id
+            AND (r.id = u.role_id OR (u.role_id IS NULL AND r.base_role = u.role))
+        WHERE u.identity_id = $1 AND u.active AND u.deleted_at IS NULL
+        ORDER BY u.id
+        "#,
+        identity_id
+    )
+    .fetch_all(conn)
+    .await?)
+}
+
+/// Admins keep password login as a break-glass path in case the identity provider is down.
+pub async fn check_password_login(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+    role: &RoleModel,
+) -> Result<(), ProdError> {
+    let password_login_enabled = sqlx::query!(
+        r#"
+        SELECT password_login_enabled
+        FROM oidc_providers
+        WHERE company_id = $1
+        "#,
+        company_id
+    )
+    .fetch_optional(conn)
+    .await?
+    .is_none_or(|record| record.password_login_enabled);
+
+    if !password_login_enabled && *role != RoleModel::Admin {
+        return Err(ProdError::Forbidden(
+            "Password login is disabled for this company, use SSO".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Account of the user's identity in the company, to issue a token for it.
+pub async fn membership(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    company_id: Uuid,
+) -> Result<TokenData, ProdError> {
+    let member = sqlx::query!(
+        r#"
+        SELECT m.id, m.company_id, m.role as "role: RoleModel", m.active
+        FROM users u
+        JOIN users m ON m.identity_id = u.identity_id
+        WHERE u.id = $1 AND u.deleted_at IS NULL
+            AND m.company_id = $2 AND m.deleted_at IS NULL
+        "#,
+        user_id,
+        company_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => {
+            ProdError::NotFound("You aren't a member of this company".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    if !member.active {
+        return Err(ProdError::Forbidden("User is deactivated".to_string()));
+    }
+    check_password_login(conn, member.company_id, &member.role).await?;
+
+    Ok(TokenData {
+        id: member.id,
+        company_id: member.company_id,
+        role: member.role,
+    })
+}
+
+/// Joins the account with the user's e-mail in another company to the user's identity,
+/// along with everything already linked to it. Linked accounts take over the user's password.
+///
+/// Returns the identity of the user.
+pub async fn link_company(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    form: &LinkCompanyForm,
+) -> Result<Uuid, ProdError> {
+    let user = sqlx::query!(
+        r#"
+        SELECT identity_id, email, password
+        FROM users
+        WHERE id = $1 AND deleted_at IS NULL
+        "#,
+        user_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such user".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    let other = sqlx::query!(
+        r#"
+        SELECT identity_id, password
+        FROM users
+        WHERE email = $1 AND deleted_at IS NULL
+            AND company_id = (SELECT id FROM companies WHERE domain = $2)
+        FOR UPDATE
+        "#,
+        user.email,
+        form.domain
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => {
+            ProdError::NotFound("No account with your e-mail in that company".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    if other.identity_id == user.identity_id {
+        return Err(ProdError::Conflict(
+            "This company is already linked".to_string(),
+        ));
+    }
+
+    if !Argon::verify(form.password.as_bytes(), &other.password)? {
+        return Err(ProdError::Forbidden("wrong password".to_string()));
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET identity_id = $1, password = $2
+        WHERE identity_id = $3
+        "#,
+        user.identity_id,
+        user.password,
+        other.identity_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(user.identity_id)
+}
+
+/// Sets the password hash of every account linked to this one.
+pub async fn share_password(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    password: &str,
+) -> Result<(), ProdError> {
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET password = $2
+        WHERE identity_id = (SELECT identity_id FROM users WHERE id = $1) AND id <> $1
+        "#,
+        user_id,
+        password
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Identity the account belongs to.
+pub async fn identity_of(conn: &mut PgConnection, user_id: Uuid) -> Result<Uuid, ProdError> {
+    sqlx::query!(
+        r#"
+        SELECT identity_id
+        FROM users
+        WHERE id = $1 AND deleted_at IS NULL
+        "#,
+        user_id
+    )
+    .fetch_one(conn)
+    .await
+    .map(|record| record.identity_id)
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such user".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })
+}
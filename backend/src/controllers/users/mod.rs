@@ -1,26 +1,50 @@
+pub mod companies;
+pub mod privacy;
+
+use crate::controllers::roles::resolve_role;
+use crate::controllers::users::companies::share_password;
 use crate::db::Db;
 use crate::errors::ProdError;
 use crate::forms::users::{PatchProfileForm, RegisterForm};
-use crate::jwt::hashing::Argon;
+use crate::jwt::hashing::{hash_token, Argon};
+use crate::mail::{send_mail, APP_URL};
 use crate::models::UserModel;
 use crate::models::{RoleModel, TokenData};
+use crate::oidc::{random_token, OidcIdentity};
 use crate::s3::utils::upload_file;
 use crate::{AppState, BASE_URL};
 use axum::body::Bytes;
 use axum::extract::Multipart;
-use sqlx::Acquire;
+use sqlx::{Acquire, PgConnection};
 use tracing::log::warn;
 use uuid::Uuid;
 use validator::Validate;
 
+/// Days an activation link from an admin-created account stays valid.
+const ACTIVATION_TTL_DAYS: i32 = 7;
+
 pub async fn register_user(
     state: AppState,
     form: RegisterForm,
     role: RoleModel,
 ) -> Result<TokenData, ProdError> {
-    let mut conn = state.pool.conn().await?;
+    let mut conn = state.pool.unscoped_conn().await?;
 
     let mut tx = conn.begin().await?;
+    let user = insert_user(tx.as_mut(), form, role, None).await?;
+    tx.commit().await?;
+
+    Ok(user)
+}
+
+/// Creates the user in the company of the form's domain, `role_id` being a custom role on top of
+/// the base `role`.
+pub async fn insert_user(
+    conn: &mut PgConnection,
+    form: RegisterForm,
+    role: RoleModel,
+    role_id: Option<Uuid>,
+) -> Result<TokenData, ProdError> {
     let company_id = sqlx::query!(
         r#"
         SELECT id FROM companies
@@ -28,7 +52,7 @@ pub async fn register_user(
         "#,
         form.company_domain
     )
-    .fetch_one(tx.as_mut())
+    .fetch_one(&mut *conn)
     .await
     .map(|record| record.id)
     .map_err(|err| match err {
@@ -36,12 +60,12 @@ pub async fn register_user(
         _ => ProdError::DatabaseError(err),
     })?;
 
-    let user = sqlx::query_as!(
+    sqlx::query_as!(
         TokenData,
         r#"
         INSERT INTO users (
             name, surname, email, role,
-            password, company_id, company_domain
+            password, company_id, role_id
         )
         VALUES ( $1, $2, $3, $4, $5, $6, $7)
         RETURNING id, company_id, role as "role: RoleModel"
@@ -52,27 +76,35 @@ pub async fn register_user(
         role as RoleModel,
         Argon::hash_password(form.password.as_bytes())?,
         company_id,
-        form.company_domain
+        role_id
     )
-    .fetch_one(tx.as_mut())
+    .fetch_one(conn)
     .await
     .map_err(|err| match err {
         sqlx::Error::Database(e) if e.is_unique_violation() => {
             ProdError::Conflict("Пользователь с таким e-mail уже существует.".to_string())
         }
         _ => ProdError::DatabaseError(err),
-    })?;
-
-    tx.commit().await?;
-    Ok(user)
+    })
 }
 
+/// Updates the profile from the `json` and `avatar` multipart fields.
+///
+/// A new password is shared with the accounts linked to this one in other companies. When an admin
+/// sets it (`own_account` is false) the account is unlinked instead, so admins of one company can't
+/// take over the others.
 pub async fn update_user(
     user_id: Uuid,
     mut multipart: Multipart,
     state: AppState,
+    own_account: bool,
 ) -> Result<UserModel, ProdError> {
-    let mut conn = state.pool.conn().await?;
+    // The password is shared with the accounts linked in other companies.
+    let mut conn = if own_account {
+        state.pool.unscoped_conn().await?
+    } else {
+        state.pool.conn().await?
+    };
 
     let mut form: Option<PatchProfileForm> = None;
     let mut image: Option<Bytes> = None;
@@ -139,6 +171,7 @@ pub async fn update_user(
         None => None,
     };
 
+    let mut tx = conn.begin().await?;
     let user = sqlx::query_as!(
         UserModel,
         r#"
@@ -147,24 +180,160 @@ pub async fn update_user(
             name = COALESCE($2, name),
             surname = COALESCE($3, surname),
             password = COALESCE($4, password),
-            avatar = COALESCE($5, avatar)
-        WHERE id = $1
+            avatar = COALESCE($5, avatar),
+            identity_id = CASE WHEN $4::varchar IS NULL OR $6 THEN identity_id ELSE uuidv7() END
+        WHERE id = $1 AND deleted_at IS NULL
         RETURNING id, name, surname,
                   email, password, avatar,
-                  company_id, company_domain, role as "role: RoleModel"
+                  company_id,
+                  (SELECT domain FROM companies WHERE companies.id = users.company_id) as "company_domain!",
+                  role as "role: RoleModel", active, external_id
         "#,
         user_id,
         form.as_ref().and_then(|data| data.name.as_ref()),
         form.as_ref().and_then(|data| data.surname.as_ref()),
         hashed_password,
         avatar_url,
+        own_account,
     )
-    .fetch_one(conn.as_mut())
+    .fetch_one(tx.as_mut())
     .await
     .map_err(|err| match err {
         sqlx::Error::RowNotFound => ProdError::NotFound(err.to_string()),
         _ => ProdError::DatabaseError(err),
     })?;
 
+    if let Some(password) = hashed_password.filter(|_| own_account) {
+        share_password(tx.as_mut(), user_id, &password).await?;
+    }
+
+    tx.commit().await?;
+
     Ok(user)
 }
+
+/// Finds the user behind an SSO identity, linking by e-mail or creating them on first login.
+///
+/// Only e-mails the identity provider has verified are linked to existing accounts, anybody could
+/// put an admin's e-mail on an account of their own otherwise.
+pub async fn provision_oidc_user(
+    state: AppState,
+    company_id: Uuid,
+    company_domain: String,
+    identity: OidcIdentity,
+) -> Result<TokenData, ProdError> {
+    let mut conn = state.pool.unscoped_conn().await?;
+    let mut tx = conn.begin().await?;
+
+    let by_subject = sqlx::query_as!(
+        TokenData,
+        r#"
+        SELECT id, company_id, role as "role: RoleModel"
+        FROM users
+        WHERE company_id = $1 AND oidc_subject = $2
+        "#,
+        company_id,
+        identity.subject,
+    )
+    .fetch_optional(tx.as_mut())
+    .await?;
+
+    if let Some(user) = by_subject {
+        return Ok(user);
+    }
+
+    if identity.email_verified {
+        let existing = sqlx::query_as!(
+            TokenData,
+            r#"
+            UPDATE users
+            SET oidc_subject = $3
+            WHERE company_id = $1 AND email = $2 AND oidc_subject IS NULL
+            RETURNING id, company_id, role as "role: RoleModel"
+            "#,
+            company_id,
+            identity.email,
+            identity.subject,
+        )
+        .fetch_optional(tx.as_mut())
+        .await?;
+
+        if let Some(user) = existing {
+            tx.commit().await?;
+            return Ok(user);
+        }
+    }
+
+    let user = insert_user(
+        tx.as_mut(),
+        RegisterForm {
+            name: identity.name,
+            surname: identity.surname,
+            email: identity.email,
+            password: random_token(),
+            company_domain,
+        },
+        identity.role,
+        None,
+    )
+    .await?;
+
+    let _ = sqlx::query!(
+        r#"
+        UPDATE users
+        SET oidc_subject = $2
+        WHERE id = $1
+        "#,
+        user.id,
+        identity.subject,
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(user)
+}
+
+/// Registers user with a company role, either built-in or custom.
+pub async fn register_user_with_role(
+    conn: &mut PgConnection,
+    form: RegisterForm,
+    company_id: Uuid,
+    role_id: Uuid,
+) -> Result<TokenData, ProdError> {
+    let (role, custom_role_id) = resolve_role(&mut *conn, company_id, role_id).await?;
+
+    insert_user(conn, form, role.unwrap_or(RoleModel::Guest), custom_role_id).await
+}
+
+/// Mails a one-time link to set the password of an account created by an admin.
+pub async fn send_activation(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    email: &str,
+    company_name: &str,
+) -> Result<(), ProdError> {
+    let token = random_token();
+
+    let _ = sqlx::query!(
+        r#"
+        INSERT INTO user_activations (token_hash, user_id, expires_at)
+        VALUES ($1, $2, NOW() + make_interval(days => $3))
+        "#,
+        hash_token(&token),
+        user_id,
+        ACTIVATION_TTL_DAYS,
+    )
+    .execute(conn)
+    .await?;
+
+    let body = format!(
+        "An account at {company_name} was created for you.\n\
+         Set your password to activate it: {}/activate#token={token}\n\n\
+         The link is valid for {ACTIVATION_TTL_DAYS} days.",
+        *APP_URL
+    );
+
+    send_mail(email, "Activate your BooQ account", body).await
+}
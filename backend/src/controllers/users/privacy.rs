@@ -0,0 +1,182 @@
+use std::io::{Cursor, Write};
+
+use serde::Serialize;
+use sqlx::PgConnection;
+use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::controllers::verification::{verification_cases, CaseFilter};
+use crate::db::Db;
+use crate::errors::ProdError;
+use crate::models::{PublicBookingModel, RoleModel, UserModel};
+use crate::s3::utils::{delete_files, download_file};
+use crate::AppState;
+
+/// Everything stored about the user as a ZIP: `profile.json`, `bookings.json`, `verification.json`,
+/// the avatar and every uploaded verification document.
+pub async fn export_user_data(state: &AppState, user_id: Uuid) -> Result<Vec<u8>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+
+    let profile = sqlx::query_as!(
+        UserModel,
+        r#"
+        SELECT id, name, surname, email, password, avatar,
+               company_id,
+               (SELECT domain FROM companies WHERE companies.id = users.company_id) as "company_domain!",
+               role as "role: RoleModel", active, external_id
+        FROM users
+        WHERE id = $1 AND deleted_at IS NULL
+        "#,
+        user_id
+    )
+    .fetch_one(conn.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such user".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    let bookings = sqlx::query_as!(
+        PublicBookingModel,
+        r#"
+        SELECT
+            b.id,
+            b.user_id,
+            b.coworking_space_id,
+            b.coworking_item_id,
+            b.time_start,
+            b.time_end,
+            b.attendees,
+            bu.address as building_address,
+            i.name as coworking_item_name,
+            i.description as coworking_item_description,
+            s.address as coworking_space_name
+        FROM bookings b
+        JOIN coworking_spaces s ON s.id = b.coworking_space_id
+        JOIN buildings bu ON bu.id = s.building_id
+        JOIN coworking_items i ON i.id = b.coworking_item_id
+        WHERE b.user_id = $1
+        ORDER BY b.time_start
+        "#,
+        user_id
+    )
+    .fetch_all(conn.as_mut())
+    .await?;
+
+    let verification = verification_cases(
+        conn.as_mut(),
+        profile.company_id,
+        CaseFilter {
+            user_id: Some(user_id),
+            ..CaseFilter::default()
+        },
+    )
+    .await?
+    .pop();
+
+    let documents = sqlx::query!(
+        r#"
+        SELECT d.version, d.s3_key
+        FROM verification_documents d
+        JOIN verification_cases c ON c.id = d.case_id
+        WHERE c.user_id = $1
+        ORDER BY d.version
+        "#,
+        user_id
+    )
+    .fetch_all(conn.as_mut())
+    .await?;
+
+    let mut files = vec![
+        ("profile.json".to_string(), to_json(&profile)?),
+        ("bookings.json".to_string(), to_json(&bookings)?),
+        ("verification.json".to_string(), to_json(&verification)?),
+    ];
+    if profile.avatar.is_some() {
+        let avatar = download_file(state, &format!("users/{user_id}/avatar")).await?;
+        files.push(("avatar".to_string(), avatar.to_vec()));
+    }
+    for document in documents {
+        let content = download_file(state, &document.s3_key).await?;
+        files.push((
+            format!("documents/{}.pdf", document.version),
+            content.to_vec(),
+        ));
+    }
+
+    zip(files)
+}
+
+/// Scrubs personal data from the user and keeps the row, so their bookings stay for statistics.
+///
+/// The account ends up deleted for good: it can't log in, isn't listed anywhere and is never purged.
+pub async fn anonymize_user(conn: &mut PgConnection, user_id: Uuid) -> Result<(), ProdError> {
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET
+            name = 'Deleted',
+            surname = 'User',
+            email = id || '@anonymized.invalid',
+            password = '',
+            avatar = NULL,
+            active = FALSE,
+            external_id = NULL,
+            oidc_subject = NULL,
+            identity_id = uuidv7(),
+            deleted_at = NOW(),
+            anonymized_at = NOW()
+        WHERE id = $1 AND anonymized_at IS NULL
+        "#,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"DELETE FROM verification_cases WHERE user_id = $1"#,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(r#"DELETE FROM api_keys WHERE user_id = $1"#, user_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query!(
+        r#"DELETE FROM user_activations WHERE user_id = $1"#,
+        user_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Avatar and verification documents of the user.
+pub async fn delete_user_files(state: &AppState, user_id: Uuid) -> Result<(), ProdError> {
+    delete_files(state, &format!("users/{user_id}/")).await
+}
+
+pub fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>, ProdError> {
+    serde_json::to_vec_pretty(value).map_err(|err| ProdError::Unknown(err.into()))
+}
+
+pub fn zip(files: Vec<(String, Vec<u8>)>) -> Result<Vec<u8>, ProdError> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for (name, content) in files {
+        writer
+            .start_file(name, options)
+            .map_err(|err| ProdError::Unknown(err.into()))?;
+        writer
+            .write_all(&content)
+            .map_err(|err| ProdError::Unknown(err.into()))?;
+    }
+
+    writer
+        .finish()
+        .map(Cursor::into_inner)
+        .map_err(|err| ProdError::Unknown(err.into()))
+}
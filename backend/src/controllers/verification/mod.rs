@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use sqlx::PgConnection;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    errors::ProdError,
+    forms::{
+        admin::{VerificationDecisionForm, VerificationDocument, VerificationRequest},
+        users::PublicUserData,
+    },
+    mail::send_mail,
+    models::{RoleModel, VerificationCommentModel, VerificationStatus},
+    BASE_URL,
+};
+
+/// Cases returned when the queue is queried without a limit.
+const DEFAULT_QUEUE_LIMIT: i64 = 50;
+
+#[derive(Default)]
+pub struct CaseFilter {
+    pub case_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub status: Option<VerificationStatus>,
+    pub search: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+struct CaseRow {
+    id: Uuid,
+    status: VerificationStatus,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    decided_at: Option<NaiveDateTime>,
+    user_id: Uuid,
+    name: String,
+    surname: String,
+    email: String,
+    avatar: Option<String>,
+    role: RoleModel,
+}
+
+pub fn document_url(case_id: Uuid, version: i32) -> String {
+    format!("{BASE_URL}/admin/verifications/{case_id}/documents/{version}")
+}
+
+/// Company cases matching the filter with their documents and comments, oldest first.
+pub async fn verification_cases(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+    filter: CaseFilter,
+) -> Result<Vec<VerificationRequest>, ProdError> {
+    let rows = sqlx::query_as!(
+        CaseRow,
+        r#"
+        SELECT c.id, c.status as "status: VerificationStatus", c.created_at, c.updated_at, c.decided_at,
+               u.id as user_id, u.name, u.surname, u.email, u.avatar, u.role as "role: RoleModel"
+        FROM verification_cases c
+        JOIN users u ON u.id = c.user_id AND u.deleted_at IS NULL
+        WHERE c.company_id = $1
+            AND ($2::UUID IS NULL OR c.id = $2)
+            AND ($3::UUID IS NULL OR c.user_id = $3)
+            AND ($4::VERIFICATION_STATUS IS NULL OR c.status = $4)
+            AND ($5::TEXT IS NULL
+                OR u.name ILIKE '%' || $5 || '%'
+                OR u.surname ILIKE '%' || $5 || '%'
+                OR u.email ILIKE '%' || $5 || '%')
+        ORDER BY c.updated_at
+        LIMIT $6 OFFSET $7
+        "#,
+        company_id,
+        filter.case_id,
+        filter.user_id,
+        filter.status as Option<VerificationStatus>,
+        filter.search,
+        filter.limit.unwrap_or(DEFAULT_QUEUE_LIMIT),
+        filter.offset.unwrap_or(0),
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let case_ids: Vec<Uuid> = rows.iter().map(|row| row.id).collect();
+
+    let mut documents: HashMap<Uuid, Vec<VerificationDocument>> = HashMap::new();
+    for record in sqlx::query!(
+        r#"
+        SELECT case_id, version, uploaded_at
+        FROM verification_documents
+        WHERE case_id = ANY($1)
+        ORDER BY version
+        "#,
+        &case_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?
+    {
+        documents
+            .entry(record.case_id)
+            .or_default()
+            .push(VerificationDocument {
+                version: record.version,
+                url: document_url(record.case_id, record.version),
+                uploaded_at: record.uploaded_at,
+            });
+    }
+
+    let mut comments: HashMap<Uuid, Vec<VerificationCommentModel>> = HashMap::new();
+    for comment in sqlx::query_as!(
+        VerificationCommentModel,
+        r#"
+        SELECT id, author_id, status as "status: VerificationStatus", body, created_at, case_id
+        FROM verification_comments
+        WHERE case_id = ANY($1)
+        ORDER BY created_at
+        "#,
+        &case_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?
+    {
+        comments.entry(comment.case_id).or_default().push(comment);
+    }
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let documents = documents.remove(&row.id).unwrap_or_default();
+            let latest = documents.last().map_or(1, |document| document.version);
+
+            VerificationRequest {
+                id: row.id,
+                user: PublicUserData {
+                    id: row.user_id,
+                    name: row.name,
+                    surname: row.surname,
+                    email: row.email,
+                    avatar: row.avatar,
+                    role: row.role,
+                },
+                status: row.status,
+                document: document_url(row.id, latest),
+                documents,
+                comments: comments.remove(&row.id).unwrap_or_default(),
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+                decided_at: row.decided_at,
+            }
+        })
+        .collect())
+}
+
+pub async fn verification_case(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+    filter: CaseFilter,
+) -> Result<VerificationRequest, ProdError> {
+    verification_cases(conn, company_id, filter)
+        .await?
+        .pop()
+        .ok_or_else(|| ProdError::NotFound("No such verification case".to_string()))
+}
+
+/// Records the decision and returns the guest's e-mail, approving a case makes the guest a verified guest.
+/// Approved cases are final.
+pub async fn decide_case(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+    case_id: Uuid,
+    author_id: Uuid,
+    form: &VerificationDecisionForm,
+) -> Result<String, ProdError> {
+    if form.status == VerificationStatus::Pending {
+        return Err(ProdError::ShitHappened(
+            "Decision must be `approved`, `rejected` or `needs_more_info`".to_string(),
+        ));
+    }
+
+    if form.status != VerificationStatus::Approved && form.comment.is_none() {
+        return Err(ProdError::ShitHappened(format!(
+            "A comment is required to mark the case `{}`",
+            form.status.as_str()
+        )));
+    }
+
+    let case = sqlx::query!(
+        r#"
+        SELECT c.user_id, c.status as "status: VerificationStatus", u.email
+        FROM verification_cases c
+        JOIN users u ON u.id = c.user_id AND u.deleted_at IS NULL
+        WHERE c.id = $1 AND c.company_id = $2
+        FOR UPDATE OF c
+        "#,
+        case_id,
+        company_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such verification case".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    if case.status == VerificationStatus::Approved {
+        return Err(ProdError::Conflict("Case is already approved".to_string()));
+    }
+
+    let _ = sqlx::query!(
+        r#"
+        UPDATE verification_cases
+        SET status = $2, decided_at = NOW(), updated_at = NOW()
+        WHERE id = $1
+        "#,
+        case_id,
+        form.status as VerificationStatus
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    if form.status == VerificationStatus::Approved {
+        let _ = sqlx::query!(
+            r#"
+            UPDATE users
+            SET role = 'verified_guest'
+            WHERE id = $1 AND role = 'guest'
+            "#,
+            case.user_id
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    if let Some(comment) = &form.comment {
+        let _ = sqlx::query!(
+            r#"
+            INSERT INTO verification_comments (case_id, author_id, status, body)
+            VALUES ($1, $2, $3, $4)
+            "#,
+            case_id,
+            author_id,
+            form.status as VerificationStatus,
+            comment
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(case.email)
+}
+
+/// Lets the guest know about the decision, failures are only logged.
+pub async fn notify_decision(email: &str, form: &VerificationDecisionForm) {
+    let body = match form.status {
+        VerificationStatus::Approved => "Your documents were approved, you can book now.",
+        VerificationStatus::Rejected => "Your documents were rejected.",
+        _ => "More information is needed to verify you, please upload your documents again.",
+    };
+    let body = form.comment.as_ref().map_or_else(
+        || body.to_string(),
+        |comment| format!("{body}\n\n{comment}"),
+    );
+
+    if let Err(err) = send_mail(email, "BooQ verification", body).await {
+        warn!("Failed to notify {email} about verification: {err}");
+    }
+}
@@ -1,16 +1,49 @@
 use sqlx::{pool::PoolConnection, PgPool, Postgres};
+use uuid::Uuid;
 
 use crate::errors::ProdError;
 
+tokio::task_local! {
+    /// Company of the authenticated request being handled, set by [`crate::middlewares::tenant_scope`].
+    pub static TENANT: Option<Uuid>;
+}
+
 pub trait Db {
     type Conn;
+    /// Connection limited by row-level security to the company of the request, if there is one.
     async fn conn(&self) -> Result<Self::Conn, ProdError>;
+    /// Connection that sees every company, for work that spans them or happens before sign-in.
+    async fn unscoped_conn(&self) -> Result<Self::Conn, ProdError>;
+}
+
+/// Connections are reused across requests, so both settings are set every time one is handed out.
+async fn scoped(
+    pool: &PgPool,
+    company_id: Option<Uuid>,
+) -> Result<PoolConnection<Postgres>, ProdError> {
+    let mut conn = pool.acquire().await.map_err(ProdError::DatabaseError)?;
+
+    sqlx::query!(
+        r#"
+        SELECT set_config('app.company_id', COALESCE($1::uuid::text, ''), false) as company_id,
+               set_config('role', CASE WHEN $1 IS NULL THEN 'none' ELSE 'booq_tenant' END, false) as role
+        "#,
+        company_id
+    )
+    .fetch_one(conn.as_mut())
+    .await?;
+
+    Ok(conn)
 }
 
 impl Db for PgPool {
     type Conn = PoolConnection<Postgres>;
 
     async fn conn(&self) -> Result<Self::Conn, ProdError> {
-        self.acquire().await.map_err(ProdError::DatabaseError)
+        scoped(self, TENANT.try_with(|tenant| *tenant).ok().flatten()).await
+    }
+
+    async fn unscoped_conn(&self) -> Result<Self::Conn, ProdError> {
+        scoped(self, None).await
     }
 }
@@ -1,6 +1,8 @@
 use axum::{http::StatusCode, response::IntoResponse, Json};
 use tracing::info;
 
+use crate::{forms::scim::ERROR_SCHEMA, util::ScimJson};
+
 #[derive(thiserror::Error, Debug)]
 pub enum ProdError {
     #[error("JWT error")]
@@ -29,8 +31,13 @@ pub enum ProdError {
     #[error("{0}")]
     S3Error(String),
 
-    #[error("User already submit verification request")]
-    VerificationError,
+    /// The identity provider could not be reached or returned something unexpected.
+    #[error("{0}")]
+    OidcError(String),
+
+    /// An e-mail could not be built or delivered.
+    #[error("{0}")]
+    MailError(String),
 
     /// Not found error
     #[error("{0}")]
@@ -49,23 +56,66 @@ pub enum ProdError {
     Unknown(#[source] Box<dyn std::error::Error + Send + Sync>),
 }
 
-impl IntoResponse for ProdError {
-    fn into_response(self) -> axum::response::Response {
-        let (status, message) = match &self {
+impl ProdError {
+    pub const fn status_code(&self) -> StatusCode {
+        match self {
             Self::AlreadyExists(_) | Self::InvalidRequest(_) | Self::ShitHappened(_) => {
-                (StatusCode::BAD_REQUEST, self.to_string())
+                StatusCode::BAD_REQUEST
             }
             Self::DatabaseError(_)
             | Self::Unknown(_)
             | Self::HashingError(_)
-            | Self::S3Error(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
-            Self::Forbidden(_) | Self::InvalidToken(_) => (StatusCode::FORBIDDEN, self.to_string()),
-            Self::Conflict(_) | Self::VerificationError => (StatusCode::CONFLICT, self.to_string()),
-            Self::NotFound(_) | Self::NoCompany => (StatusCode::NOT_FOUND, self.to_string()),
-        };
+            | Self::S3Error(_)
+            | Self::MailError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Forbidden(_) | Self::InvalidToken(_) => StatusCode::FORBIDDEN,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::OidcError(_) => StatusCode::BAD_GATEWAY,
+            Self::NotFound(_) | Self::NoCompany => StatusCode::NOT_FOUND,
+        }
+    }
+}
+
+impl IntoResponse for ProdError {
+    fn into_response(self) -> axum::response::Response {
+        let status = self.status_code();
+        let message = self.to_string();
 
         info!("returning error: {}", message);
 
         (status, Json(serde_json::json!({ "error": message }))).into_response()
     }
 }
+
+/// [`ProdError`] in the error format SCIM clients expect, see RFC 7644 section 3.12.
+pub struct ScimError(pub ProdError);
+
+impl<E: Into<ProdError>> From<E> for ScimError {
+    fn from(err: E) -> Self {
+        Self(err.into())
+    }
+}
+
+impl IntoResponse for ScimError {
+    fn into_response(self) -> axum::response::Response {
+        let status = self.0.status_code();
+        let scim_type = match self.0 {
+            ProdError::Conflict(_) => Some("uniqueness"),
+            ProdError::InvalidRequest(_) | ProdError::ShitHappened(_) => Some("invalidValue"),
+            _ => None,
+        };
+        let message = self.0.to_string();
+
+        info!("returning SCIM error: {}", message);
+
+        let mut body = serde_json::json!({
+            "schemas": [ERROR_SCHEMA],
+            "status": status.as_str(),
+            "detail": message,
+        });
+        if let Some(scim_type) = scim_type {
+            body["scimType"] = scim_type.into();
+        }
+
+        (status, ScimJson(body)).into_response()
+    }
+}
@@ -0,0 +1,106 @@
+use axum::{
+    extract::{FromRequestParts, Path},
+    http::request::Parts,
+};
+use uuid::Uuid;
+
+use crate::{
+    controllers::roles::require_permission,
+    db::Db,
+    errors::ProdError,
+    jwt::{generate::claims_from_headers, models::Claims},
+    models::{BookingModel, Permission},
+    AppState,
+};
+
+async fn path_id(parts: &mut Parts, state: &AppState) -> Result<Uuid, ProdError> {
+    let Path(id) = Path::<Uuid>::from_request_parts(parts, state)
+        .await
+        .map_err(|err| ProdError::ShitHappened(err.to_string()))?;
+
+    Ok(id)
+}
+
+/// Booking from the `{booking_id}` path, loaded before the handler runs and only handed out
+/// to its owner or to those who can manage bookings.
+///
+/// Bookings of other companies are reported as missing.
+pub struct OwnedBooking {
+    pub booking: BookingModel,
+    pub claims: Claims,
+}
+
+impl FromRequestParts<AppState> for OwnedBooking {
+    type Rejection = ProdError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let booking_id = path_id(parts, state).await?;
+        let claims = claims_from_headers(&parts.headers)?;
+        let mut conn = state.pool.conn().await?;
+
+        let booking = sqlx::query_as!(
+            BookingModel,
+            r#"
+            SELECT id, user_id, coworking_space_id, coworking_item_id,
+                   company_id, time_start, time_end, attendees
+            FROM bookings
+            WHERE id = $1 AND company_id = $2
+            "#,
+            booking_id,
+            claims.company_id
+        )
+        .fetch_one(conn.as_mut())
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::RowNotFound => ProdError::NotFound("No booking was found".to_string()),
+            _ => ProdError::DatabaseError(err),
+        })?;
+
+        if booking.user_id != claims.user_id {
+            require_permission(conn.as_mut(), &claims, Permission::ManageBookings).await?;
+        }
+
+        Ok(Self { booking, claims })
+    }
+}
+
+/// User of the company from the `{user_id}` path, loaded before the handler runs.
+///
+/// Deleted users and users of other companies are reported as missing.
+pub struct CompanyUser {
+    pub user_id: Uuid,
+    pub claims: Claims,
+}
+
+impl FromRequestParts<AppState> for CompanyUser {
+    type Rejection = ProdError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let user_id = path_id(parts, state).await?;
+        let claims = claims_from_headers(&parts.headers)?;
+        let mut conn = state.pool.conn().await?;
+
+        let user_id = sqlx::query_scalar!(
+            r#"
+            SELECT id FROM users
+            WHERE id = $1 AND company_id = $2 AND deleted_at IS NULL
+            "#,
+            user_id,
+            claims.company_id
+        )
+        .fetch_one(conn.as_mut())
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::RowNotFound => ProdError::NotFound("No such user".to_string()),
+            _ => ProdError::DatabaseError(err),
+        })?;
+
+        Ok(Self { user_id, claims })
+    }
+}
@@ -1,10 +1,265 @@
+use std::collections::HashMap;
+
 use crate::forms::users::PublicUserData;
+use crate::models::{
+    Permission, RoleModel, TrashEntity, VerificationCommentModel, VerificationStatus,
+};
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
+use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Serialize, Deserialize, Validate, ToSchema)]
+/// Verification case of a guest with its documents and comments, oldest first.
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct VerificationRequest {
+    pub id: Uuid,
     pub user: PublicUserData,
+    pub status: VerificationStatus,
+
+    /// Link to the latest document.
     pub document: String,
+    pub documents: Vec<VerificationDocument>,
+    pub comments: Vec<VerificationCommentModel>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub decided_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct VerificationDocument {
+    pub version: i32,
+    pub url: String,
+    pub uploaded_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+pub struct VerificationQueueQuery {
+    /// All statuses when not set.
+    pub status: Option<VerificationStatus>,
+
+    /// Part of the guest's name, surname or e-mail.
+    pub search: Option<String>,
+
+    /// Defaults to 50.
+    #[validate(range(min = 1, max = 100, message = "Limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+
+    #[validate(range(min = 0, message = "Offset can't be negative"))]
+    pub offset: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+pub struct VerificationDecisionForm {
+    /// `approved`, `rejected` or `needs_more_info`.
+    pub status: VerificationStatus,
+
+    /// Shown to the guest, required unless approving.
+    #[validate(length(
+        min = 1,
+        max = 2000,
+        message = "Comment length must be between 1 and 2000"
+    ))]
+    pub comment: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+pub struct VerificationCommentForm {
+    #[validate(length(
+        min = 1,
+        max = 2000,
+        message = "Comment length must be between 1 and 2000"
+    ))]
+    pub body: String,
+}
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+pub struct OidcProviderForm {
+    #[validate(url(message = "Issuer must be a valid URL"))]
+    pub issuer: String,
+
+    #[validate(length(min = 1, message = "Client id must not be empty"))]
+    pub client_id: String,
+
+    #[validate(length(min = 1, message = "Client secret must not be empty"))]
+    pub client_secret: String,
+
+    /// Callback URL registered at the identity provider.
+    /// Defaults to `{BASE_URL}/user/sso/callback`.
+    #[validate(url(message = "Redirect URI must be a valid URL"))]
+    pub redirect_uri: Option<String>,
+
+    pub scopes: Option<String>,
+    pub name_claim: Option<String>,
+    pub surname_claim: Option<String>,
+    pub email_claim: Option<String>,
+
+    /// Claim holding the user's group or role, either a string or an array of strings.
+    pub role_claim: Option<String>,
+
+    /// Maps values of `role_claim` to user roles.
+    #[serde(default)]
+    pub role_mapping: HashMap<String, RoleModel>,
+
+    /// Role given to users whose claims match nothing in `role_mapping`.
+    pub default_role: Option<RoleModel>,
+
+    pub password_login_enabled: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreateRoleForm {
+    #[validate(length(
+        min = 1,
+        max = 60,
+        message = "Role name length must be between 1 and 60"
+    ))]
+    pub name: String,
+    pub permissions: Vec<Permission>,
+}
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+pub struct PatchRoleForm {
+    #[validate(length(
+        min = 1,
+        max = 60,
+        message = "Role name length must be between 1 and 60"
+    ))]
+    pub name: Option<String>,
+    pub permissions: Option<Vec<Permission>>,
+}
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+pub struct AssignRoleForm {
+    /// Assigning a built-in role also changes the user's base role.
+    pub role_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreateServiceAccountForm {
+    #[validate(length(
+        min = 1,
+        max = 120,
+        message = "Service account name length must be between 1 and 120"
+    ))]
+    pub name: String,
+
+    /// Role limiting what the account's API keys can do, built-in or custom.
+    pub role_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreateInviteForm {
+    /// Role given to everyone registering with the invite, built-in or custom.
+    pub role_id: Uuid,
+
+    /// Defaults to a week.
+    #[validate(range(min = 1, max = 8760, message = "Invite must expire within a year"))]
+    pub expires_in_hours: Option<i32>,
+
+    /// Unlimited when not set.
+    #[validate(range(min = 1, message = "Invite must allow at least one registration"))]
+    pub max_uses: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ImportUsersQuery {
+    /// Only validate the file and report what would happen.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(ToSchema)]
+pub struct ImportUsersFormData {
+    /// CSV with a `name,surname,email,role` header, `role` is a company role name and may be empty for guests.
+    #[schema(value_type = String, format = "binary")]
+    pub file: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+pub struct ImportUserRow {
+    pub name: String,
+    pub surname: String,
+    pub email: String,
+
+    #[serde(default)]
+    pub role: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ImportedUser {
+    /// Line in the file, the header is line 1.
+    pub row: usize,
+    pub email: String,
+    pub role_id: Uuid,
+
+    /// Not set on dry run.
+    pub user_id: Option<Uuid>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ImportIssue {
+    pub row: usize,
+    pub email: Option<String>,
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub created: Vec<ImportedUser>,
+    pub duplicates: Vec<ImportIssue>,
+    pub invalid: Vec<ImportIssue>,
+}
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+pub struct AuditLogQuery {
+    pub actor_id: Option<Uuid>,
+
+    /// Exact action, e.g. `role.update`.
+    pub action: Option<String>,
+
+    /// Entity type, e.g. `role`.
+    pub entity: Option<String>,
+    pub entity_id: Option<Uuid>,
+
+    /// Entries made at or after this moment.
+    pub from: Option<NaiveDateTime>,
+
+    /// Entries made before this moment.
+    pub to: Option<NaiveDateTime>,
+
+    /// Defaults to 50.
+    #[validate(range(min = 1, max = 500, message = "Limit must be between 1 and 500"))]
+    pub limit: Option<i64>,
+
+    #[validate(range(min = 0, message = "Offset can't be negative"))]
+    pub offset: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct AuditExportQuery {
+    /// Defaults to `csv`.
+    #[serde(default)]
+    pub format: ExportFormat,
+    pub actor_id: Option<Uuid>,
+    pub action: Option<String>,
+    pub entity: Option<String>,
+    pub entity_id: Option<Uuid>,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct TrashQuery {
+    /// Everything the caller may restore when not set.
+    pub entity: Option<TrashEntity>,
 }
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::models::{ApiKeyModel, Permission};
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreateApiKeyForm {
+    #[validate(length(
+        min = 1,
+        max = 120,
+        message = "API key name length must be between 1 and 120"
+    ))]
+    pub name: String,
+
+    /// Subset of the owner's permissions, the key is limited to them.
+    pub permissions: Vec<Permission>,
+
+    /// Never expires when not set.
+    #[validate(range(min = 1, max = 3650, message = "API key must expire within 10 years"))]
+    pub expires_in_days: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CreatedApiKey {
+    /// Secret to send as `Authorization: Bearer <key>`, shown only once.
+    pub key: String,
+
+    #[serde(flatten)]
+    pub api_key: ApiKeyModel,
+}
@@ -10,6 +10,13 @@ pub struct CreateBookingForm {
     pub coworking_item_id: Uuid,
     pub time_start: NaiveDateTime,
     pub time_end: NaiveDateTime,
+
+    /// Books on behalf of a member of a team the user manages, needs `manage_bookings` for anybody else.
+    pub user_id: Option<Uuid>,
+
+    /// People coming, 1 when not set. Can't be more than the capacity of the item.
+    #[validate(range(min = 1))]
+    pub attendees: Option<i32>,
 }
 
 #[derive(Serialize, Deserialize, Validate, ToSchema)]
@@ -18,6 +25,20 @@ pub struct PatchBookingForm {
     pub coworking_item_id: Option<Uuid>,
     pub time_start: Option<NaiveDateTime>,
     pub time_end: Option<NaiveDateTime>,
+    #[validate(range(min = 1))]
+    pub attendees: Option<i32>,
+}
+
+/// Every other query parameter is an attribute filter like `monitors>=2` or `standing=true`.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct AvailabilityQuery {
+    pub time_start: NaiveDateTime,
+    pub time_end: NaiveDateTime,
+    pub building_id: Option<Uuid>,
+    pub coworking_id: Option<Uuid>,
+
+    /// Only items with room for that many more people, 1 when not set.
+    pub attendees: Option<i32>,
 }
 
 #[derive(Serialize, Deserialize, Validate, ToSchema)]
@@ -33,6 +54,7 @@ pub struct BookingDisplayData {
     pub item_name: String,
     pub time_start: NaiveDateTime,
     pub time_end: NaiveDateTime,
+    pub attendees: i32,
 }
 
 #[derive(Serialize, Deserialize, Validate, ToSchema)]
@@ -1,7 +1,15 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use validator::Validate;
 
+use crate::models::CompanyOffboardingModel;
+
+static COLOR_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^#[0-9a-fA-F]{6}$").expect("Invalid regex for color"));
+
 #[derive(ToSchema, Validate, Serialize, Deserialize)]
 pub struct CompanyRegisterData {
     #[validate(length(
@@ -13,3 +21,64 @@ pub struct CompanyRegisterData {
     #[validate(length(min = 3, max = 30, message = "Domaing should be 1 to 30 chars long"))]
     pub domain: String,
 }
+
+#[derive(ToSchema, Validate, Serialize, Deserialize)]
+pub struct PatchCompanyForm {
+    #[validate(length(
+        min = 1,
+        max = 120,
+        message = "Company name length must be between 1 and 120"
+    ))]
+    pub name: Option<String>,
+
+    /// Users log in with the new domain right away, the old one stops working.
+    #[validate(length(min = 3, max = 30, message = "Domain should be 3 to 30 chars long"))]
+    pub domain: Option<String>,
+
+    /// Hex color like `#1a2b3c`.
+    #[validate(regex(path = *COLOR_REGEX, message = "Invalid color"))]
+    pub primary_color: Option<String>,
+
+    #[validate(regex(path = *COLOR_REGEX, message = "Invalid color"))]
+    pub accent_color: Option<String>,
+}
+
+#[derive(ToSchema)]
+pub struct PatchCompanyFormData {
+    #[schema(value_type = PatchCompanyForm, required = false)]
+    pub json: Option<String>,
+
+    #[schema(value_type = String, format = "binary", required = false)]
+    pub logo: Option<Vec<u8>>,
+}
+
+/// What the login page shows before anyone is authenticated.
+#[derive(ToSchema, Serialize, Deserialize)]
+pub struct CompanyBranding {
+    pub name: String,
+    pub logo: Option<String>,
+    pub primary_color: Option<String>,
+    pub accent_color: Option<String>,
+}
+
+#[derive(ToSchema, Validate, Serialize, Deserialize)]
+pub struct OffboardCompanyForm {
+    /// Domain of the company typed again, to confirm that it's the one to delete.
+    #[validate(length(min = 3, max = 30, message = "Domain should be 3 to 30 chars long"))]
+    pub domain: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ScheduledOffboarding {
+    /// Secret to download the export and the report with, shown only once.
+    /// It keeps working after the company and its accounts are gone.
+    pub token: String,
+
+    #[serde(flatten)]
+    pub offboarding: CompanyOffboardingModel,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct OffboardingTokenQuery {
+    pub token: String,
+}
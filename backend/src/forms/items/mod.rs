@@ -1,9 +1,17 @@
+use std::sync::LazyLock;
+
+use chrono::NaiveDateTime;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
-use crate::models::Point;
+use crate::models::{AttributeKind, BookingMode, ObstacleKind, Point};
+
+static ATTRIBUTE_KEY_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[a-z][a-z0-9_]{0,31}$").expect("Invalid regex for attribute key")
+});
 
 #[derive(Serialize, Deserialize, ToSchema, Validate)]
 pub struct CreateItemTypeForm {
@@ -13,6 +21,12 @@ pub struct CreateItemTypeForm {
     pub color: String,
     pub offsets: Vec<Point>,
     pub bookable: bool,
+    #[serde(default)]
+    pub booking_mode: BookingMode,
+
+    /// Most attendees at once, 1 when not set.
+    #[validate(range(min = 1))]
+    pub capacity: Option<i32>,
 }
 
 #[derive(Serialize, Deserialize, ToSchema, Validate)]
@@ -24,6 +38,31 @@ pub struct CreateItemTypeFormData {
     pub icon: Option<Vec<u8>>,
 }
 
+/// Fields that are left out stay as they are. New offsets are checked against every coworking
+/// the type is placed in.
+#[derive(Serialize, Deserialize, ToSchema, Validate, Default)]
+pub struct UpdateItemTypeForm {
+    #[validate(length(min = 1, max = 10))]
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub color: Option<String>,
+    #[validate(length(min = 1, message = "Offsets should have at least one value"))]
+    pub offsets: Option<Vec<Point>>,
+    pub bookable: Option<bool>,
+    pub booking_mode: Option<BookingMode>,
+    #[validate(range(min = 1))]
+    pub capacity: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct UpdateItemTypeFormData {
+    #[schema(value_type = UpdateItemTypeForm, required = false)]
+    pub json: Option<String>,
+
+    #[schema(value_type = String, format = "binary", required = false)]
+    pub icon: Option<Vec<u8>>,
+}
+
 #[derive(Serialize, Deserialize, ToSchema, Validate)]
 pub struct CreateItemForm {
     pub item_id: Uuid,
@@ -31,4 +70,71 @@ pub struct CreateItemForm {
     pub name: String,
     pub description: Option<String>,
     pub base_point: Point,
+
+    /// Clockwise turn in degrees around the base point, one of 0, 90, 180 and 270.
+    #[serde(default)]
+    pub rotation: i32,
+
+    /// Flips the item left to right before it's turned.
+    #[serde(default)]
+    pub mirrored: bool,
+}
+
+/// Changes a placed item in place, fields that are left out stay as they are.
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct UpdateItemForm {
+    pub item_id: Option<Uuid>,
+    #[validate(length(min = 1, max = 10))]
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub base_point: Option<Point>,
+    pub rotation: Option<i32>,
+    pub mirrored: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateObstacleForm {
+    pub kind: ObstacleKind,
+    #[validate(length(min = 1))]
+    pub cells: Vec<Point>,
+}
+
+/// Item of a draft layout. Items without a `key` are new, the others keep the bookings of the
+/// coworking item with that id.
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct DraftItemForm {
+    pub key: Option<Uuid>,
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub item: CreateItemForm,
+}
+
+/// Item put in a coworking. Items with an `id` are the placed items with that id, moved or
+/// changed, the others are new.
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct PutItemForm {
+    pub id: Option<Uuid>,
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub item: CreateItemForm,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct PublishLayoutForm {
+    /// Publishes right away when not set or already passed.
+    pub publish_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateAttributeForm {
+    /// Name used in search filters: lowercase letters, digits and `_`, e.g. `monitors`.
+    #[validate(regex(path = *ATTRIBUTE_KEY_REGEX, message = "Invalid attribute key"))]
+    pub key: String,
+    #[validate(length(min = 1, max = 50))]
+    pub name: String,
+    pub kind: AttributeKind,
+
+    /// Values an `enum` attribute can take, required for `enum` and not allowed for the others.
+    #[serde(default)]
+    pub options: Vec<String>,
 }
@@ -1,6 +1,9 @@
 pub mod admin;
+pub mod api_keys;
 pub mod bookings;
 pub mod companies;
 pub mod items;
 pub mod places;
+pub mod scim;
+pub mod teams;
 pub mod users;
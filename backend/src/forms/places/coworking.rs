@@ -1,7 +1,18 @@
+use std::sync::LazyLock;
+
+use chrono::NaiveDateTime;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
+use uuid::Uuid;
 use validator::Validate;
 
+use crate::forms::items::CreateObstacleForm;
+use crate::models::{BookingMode, CoworkingSpacesModel, Point};
+
+static ITEM_COLOR_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^#?[0-9a-fA-F]{6}$").expect("Invalid regex for item color"));
+
 #[derive(Serialize, Deserialize, ToSchema, Validate)]
 pub struct CreateCoworkingForm {
     pub address: String,
@@ -9,6 +20,8 @@ pub struct CreateCoworkingForm {
     pub height: i32,
     #[validate(range(min = 1, max = 100))]
     pub width: i32,
+    /// Floor of the building, the first one by display order if left out.
+    pub floor_id: Option<Uuid>,
 }
 
 #[derive(Serialize, Deserialize, ToSchema, Validate)]
@@ -18,4 +31,150 @@ pub struct UpdateCoworkingForm {
     pub height: Option<i32>,
     #[validate(range(min = 1, max = 100))]
     pub width: Option<i32>,
+    /// Moves the coworking to another floor of the building.
+    pub floor_id: Option<Uuid>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderFormat {
+    #[default]
+    Svg,
+    Png,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct RenderLayoutQuery {
+    /// Defaults to `svg`.
+    #[serde(default)]
+    pub format: RenderFormat,
+
+    /// Shows which bookable items are free and which are booked at this moment.
+    pub at: Option<NaiveDateTime>,
+
+    /// Pixels per grid cell, 40 if left out. Shrunk for big coworkings to keep the picture within 4096 pixels.
+    #[validate(range(min = 8, max = 200))]
+    pub cell_size: Option<i64>,
+}
+
+/// Version of the layout document that export writes and import reads.
+pub const LAYOUT_SCHEMA_VERSION: i32 = 1;
+
+/// Coworking layout that can be carried over to another coworking or company.
+///
+/// Items refer to their type by name, import maps the names to item types of the company.
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct LayoutDocument {
+    #[validate(range(min = 1, max = 1, message = "Unsupported layout schema version"))]
+    pub schema_version: i32,
+    #[validate(nested)]
+    pub coworking: LayoutDocumentSpace,
+    #[validate(nested)]
+    pub item_types: Vec<LayoutDocumentItemType>,
+    #[validate(nested)]
+    pub items: Vec<LayoutDocumentItem>,
+    #[validate(nested)]
+    pub obstacles: Vec<CreateObstacleForm>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct LayoutDocumentSpace {
+    pub address: String,
+    #[validate(range(min = 1, max = 100))]
+    pub height: i32,
+    #[validate(range(min = 1, max = 100))]
+    pub width: i32,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct LayoutDocumentItemType {
+    #[validate(length(min = 1, max = 10))]
+    pub name: String,
+    #[validate(length(max = 500))]
+    pub description: Option<String>,
+
+    /// Hex color like `134d23`, the `#` is optional.
+    #[validate(regex(path = *ITEM_COLOR_REGEX, message = "Invalid color"))]
+    pub color: String,
+    #[validate(length(min = 1, message = "Offsets should have at least one value"))]
+    pub offsets: Vec<Point>,
+    pub bookable: bool,
+    #[serde(default)]
+    pub booking_mode: BookingMode,
+
+    /// 1 when not set.
+    #[validate(range(min = 1))]
+    pub capacity: Option<i32>,
+
+    /// SVG icon, base64 encoded.
+    pub icon: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct LayoutDocumentItem {
+    /// Name of one of the item types of the document.
+    pub item_type: String,
+    #[validate(length(min = 1, max = 10))]
+    pub name: String,
+    #[validate(length(max = 500))]
+    pub description: Option<String>,
+    pub base_point: Point,
+    #[serde(default)]
+    pub rotation: i32,
+    #[serde(default)]
+    pub mirrored: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ImportLayoutQuery {
+    /// Only validate the document and report what would happen.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct ImportLayoutForm {
+    #[validate(nested)]
+    pub document: LayoutDocument,
+
+    /// Address of the new coworking, the one from the document if left out.
+    pub address: Option<String>,
+
+    /// Floor of the building, the first one by display order if left out.
+    pub floor_id: Option<Uuid>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct CloneCoworkingForm {
+    /// Address of the copy, the same as the original's if left out.
+    pub address: Option<String>,
+
+    /// Building to put the copy in, the original's if left out.
+    pub building_id: Option<Uuid>,
+
+    /// Floor of the building, the first one by display order if left out.
+    pub floor_id: Option<Uuid>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ImportedItemType {
+    pub name: String,
+
+    /// Not set for types that would be created on dry run.
+    pub item_type_id: Option<Uuid>,
+    pub created: bool,
+
+    /// The company's type of that name has another shape than the one in the document.
+    pub offsets_differ: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LayoutImportReport {
+    pub dry_run: bool,
+
+    /// Not set on dry run.
+    pub coworking: Option<CoworkingSpacesModel>,
+    pub item_types: Vec<ImportedItemType>,
+    pub items: usize,
+    pub obstacles: usize,
 }
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateFloorForm {
+    /// Level of the floor, negative for basements.
+    #[validate(range(min = -20, max = 200, message = "Floor number must be between -20 and 200"))]
+    pub number: i32,
+    #[validate(length(min = 1, max = 100, message = "Name should be 1 to 100 chars long"))]
+    pub name: String,
+    /// Position in the list of floors, the floor number by default.
+    pub display_order: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Validate)]
+pub struct UpdateFloorForm {
+    #[validate(range(min = -20, max = 200, message = "Floor number must be between -20 and 200"))]
+    pub number: Option<i32>,
+    #[validate(length(min = 1, max = 100, message = "Name should be 1 to 100 chars long"))]
+    pub name: Option<String>,
+    pub display_order: Option<i32>,
+}
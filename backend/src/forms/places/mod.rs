@@ -1,2 +1,3 @@
 pub mod coworking;
 pub mod building;
+pub mod floor;
@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::{RoleModel, UserModel};
+use crate::BASE_URL;
+
+pub const USER_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+pub const LIST_RESPONSE_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+pub const ERROR_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+
+const fn default_true() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Validate, ToSchema, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimName {
+    #[validate(length(
+        min = 1,
+        max = 120,
+        message = "givenName length must be between 1 and 120"
+    ))]
+    pub given_name: Option<String>,
+
+    #[validate(length(
+        min = 1,
+        max = 120,
+        message = "familyName length must be between 1 and 120"
+    ))]
+    pub family_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ScimEmail {
+    pub value: String,
+
+    #[serde(default)]
+    pub primary: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimMeta {
+    pub resource_type: String,
+    pub location: String,
+}
+
+/// SCIM view of [`UserModel`], `userName` is the e-mail.
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUser {
+    pub schemas: Vec<String>,
+    pub id: Uuid,
+    pub external_id: Option<String>,
+    pub user_name: String,
+    pub name: ScimName,
+    pub emails: Vec<ScimEmail>,
+    pub active: bool,
+    pub meta: ScimMeta,
+}
+
+impl From<UserModel> for ScimUser {
+    fn from(user: UserModel) -> Self {
+        Self {
+            schemas: vec![USER_SCHEMA.to_string()],
+            id: user.id,
+            external_id: user.external_id,
+            user_name: user.email.clone(),
+            name: ScimName {
+                given_name: Some(user.name),
+                family_name: Some(user.surname),
+            },
+            emails: vec![ScimEmail {
+                value: user.email,
+                primary: true,
+            }],
+            active: user.active,
+            meta: ScimMeta {
+                resource_type: "User".to_string(),
+                location: format!("{BASE_URL}/scim/v2/Users/{}", user.id),
+            },
+        }
+    }
+}
+
+impl From<UserModel> for ScimUserForm {
+    fn from(user: UserModel) -> Self {
+        Self {
+            external_id: user.external_id,
+            user_name: user.email,
+            name: ScimName {
+                given_name: Some(user.name),
+                family_name: Some(user.surname),
+            },
+            active: user.active,
+        }
+    }
+}
+
+/// Body of user create and replace requests.
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUserForm {
+    pub external_id: Option<String>,
+
+    #[validate(email(message = "userName must be an e-mail"))]
+    pub user_name: String,
+
+    #[serde(default)]
+    #[validate(nested)]
+    pub name: ScimName,
+
+    #[serde(default = "default_true")]
+    pub active: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ScimPatchOperation {
+    /// `add`, `replace` or `remove`, case-insensitive.
+    pub op: String,
+    pub path: Option<String>,
+    pub value: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "PascalCase")]
+pub struct ScimPatchForm {
+    pub operations: Vec<ScimPatchOperation>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimListQuery {
+    /// Only `userName eq "..."` and `externalId eq "..."` are supported.
+    pub filter: Option<String>,
+
+    /// 1-based index of the first result.
+    pub start_index: Option<i64>,
+    pub count: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimListResponse {
+    pub schemas: Vec<String>,
+    pub total_results: i64,
+    pub start_index: i64,
+    pub items_per_page: i64,
+
+    #[serde(rename = "Resources")]
+    pub resources: Vec<ScimUser>,
+}
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+pub struct ScimTokenForm {
+    /// Role of users created through SCIM, defaults to `STUDENT`.
+    pub default_role: Option<RoleModel>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ScimTokenResponse {
+    /// Bearer secret for the identity provider, shown only once.
+    pub token: String,
+
+    /// SCIM base URL to configure at the identity provider.
+    pub base_url: String,
+    pub default_role: RoleModel,
+}
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreateTeamForm {
+    #[validate(length(
+        min = 1,
+        max = 120,
+        message = "Team name length must be between 1 and 120"
+    ))]
+    pub name: String,
+
+    /// Team the new one is a department of.
+    pub parent_id: Option<Uuid>,
+}
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+pub struct PatchTeamForm {
+    #[validate(length(
+        min = 1,
+        max = 120,
+        message = "Team name length must be between 1 and 120"
+    ))]
+    pub name: Option<String>,
+
+    /// Moves the team under another one.
+    pub parent_id: Option<Uuid>,
+
+    /// Moves the team out of its parent, `parent_id` can't be set along with it.
+    #[serde(default)]
+    pub top_level: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct TeamMemberForm {
+    /// Only admins with `manage_users` can make managers.
+    #[serde(default)]
+    pub manager: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RestrictToTeamsForm {
+    /// Everybody can book when empty.
+    pub team_ids: Vec<Uuid>,
+}
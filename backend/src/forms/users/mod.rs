@@ -1,12 +1,13 @@
 use std::sync::LazyLock;
 
+use chrono::NaiveDateTime;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
-use crate::models::RoleModel;
+use crate::models::{Permission, RoleModel, VerificationStatus};
 
 static PASSWORD_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"[a-zA-Z0-9$&+,:;=?@#|'<>.^*()%!-]{8,}").expect("Invalid regex for password")
@@ -30,17 +31,20 @@ pub struct LoginForm {
     #[validate(regex(path = *PASSWORD_REGEX, message = "Invalid password"))]
     pub password: String,
 
+    /// Company to log into, when not set the oldest membership of the user is picked.
     #[validate(regex(path = *DOMAIN_REGEX, message = "Invalid company domain"))]
-    pub domain: String,
+    pub domain: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Validate)]
 pub struct UserLoginData {
     pub id: Uuid,
+    pub identity_id: Uuid,
     pub email: String,
     pub password: String,
     pub company_id: Uuid,
     pub role: RoleModel,
+    pub active: bool,
 }
 
 #[derive(Serialize, Deserialize, Validate, ToSchema)]
@@ -76,7 +80,11 @@ pub struct ProfileResponseForm {
     pub avatar: Option<String>,
     pub role: RoleModel,
     pub pending_verification: Option<bool>,
+
+    /// Status of the guest verification case, not set until documents are uploaded.
+    pub verification_status: Option<VerificationStatus>,
     pub company_id: Uuid,
+    pub permissions: Vec<Permission>,
 }
 
 #[derive(Serialize, Deserialize, Validate, ToSchema)]
@@ -114,11 +122,57 @@ pub struct UploadedDocument {
     pub document: String,
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DeleteMode {
+    /// Removes the account with all its bookings.
+    #[default]
+    Delete,
+
+    /// Scrubs personal data from the account, its bookings are kept without anything pointing to the user.
+    Anonymize,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct DeleteAccountQuery {
+    /// Defaults to `delete`.
+    #[serde(default)]
+    pub mode: DeleteMode,
+}
+
 #[derive(Serialize, Deserialize, Validate, ToSchema)]
 pub struct Token {
     pub jwt: String,
 }
 
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LoginResponse {
+    pub jwt: String,
+
+    /// Every company the user can switch to, including the one logged into.
+    pub companies: Vec<CompanyMembership>,
+}
+
+/// Company the user is a member of, with their role there.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CompanyMembership {
+    pub company_id: Uuid,
+    pub company_name: String,
+    pub company_domain: String,
+    pub role: RoleModel,
+    pub role_name: String,
+}
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+pub struct LinkCompanyForm {
+    #[validate(regex(path = *DOMAIN_REGEX, message = "Invalid company domain"))]
+    pub domain: String,
+
+    /// Password of the account with the same e-mail in that company.
+    #[validate(regex(path = *PASSWORD_REGEX, message = "Invalid password"))]
+    pub password: String,
+}
+
 #[derive(Serialize, Deserialize, Validate, ToSchema)]
 pub struct PublicUserData {
     pub id: Uuid,
@@ -128,3 +182,57 @@ pub struct PublicUserData {
     pub avatar: Option<String>,
     pub role: RoleModel,
 }
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SsoAuthorizeQuery {
+    /// Page of the app (same origin as `APP_URL`) to redirect to after login, the JWT is appended
+    /// as `#jwt=<token>`.
+    pub return_to: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SsoCallbackQuery {
+    pub code: String,
+    pub state: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct InviteInfo {
+    pub company_name: String,
+    pub company_domain: String,
+    pub role_name: String,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+pub struct InviteRegisterForm {
+    #[validate(length(
+        min = 1,
+        max = 120,
+        message = "User name length must be between 1 and 120"
+    ))]
+    pub name: String,
+
+    #[validate(length(
+        min = 1,
+        max = 120,
+        message = "User name length must be between 1 and 120"
+    ))]
+    pub surname: String,
+
+    #[validate(email(message = "Email is invalid"))]
+    pub email: String,
+
+    #[validate(regex(path = *PASSWORD_REGEX, message = "Invalid password"))]
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+pub struct ActivateForm {
+    /// Token from the activation e-mail.
+    #[validate(length(min = 1, message = "Token must not be empty"))]
+    pub token: String,
+
+    #[validate(regex(path = *PASSWORD_REGEX, message = "Invalid password"))]
+    pub password: String,
+}
@@ -17,11 +17,13 @@ pub fn create_token(
     company_id: &Uuid,
     role: &RoleModel,
 ) -> Result<String, ProdError> {
-    let claims = Claims::new(user_id, company_id, role);
+    encode_claims(&Claims::new(user_id, company_id, role))
+}
 
+pub fn encode_claims(claims: &Claims) -> Result<String, ProdError> {
     encode(
         &Header::new(Algorithm::HS256),
-        &claims,
+        claims,
         &EncodingKey::from_secret(&SECRET),
     )
     .map_err(ProdError::InvalidToken)
@@ -58,6 +60,7 @@ pub fn validate_qr_token(token: &str) -> Result<QrClaims, ProdError> {
     .map_err(ProdError::InvalidToken)
 }
 
+/// Reads the Bearer JWT, API keys are swapped for one by [`crate::middlewares::resolve_api_keys`].
 pub fn claims_from_headers(headers: &impl Map) -> Result<Claims, ProdError> {
     if !headers.contains_key("authorization") {
         return Err(ProdError::Forbidden(
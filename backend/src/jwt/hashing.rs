@@ -1,5 +1,6 @@
 use crate::errors::ProdError;
 use crate::jwt::generate::generate_bytes;
+use sha2::{Digest, Sha256};
 
 pub struct Argon;
 
@@ -15,3 +16,8 @@ impl Argon {
         argon2::verify_encoded(hash, password).map_err(ProdError::HashingError)
     }
 }
+
+/// Hash for random high-entropy tokens, those don't need the work factor of a password hash.
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
@@ -1,6 +1,6 @@
 use crate::errors::ProdError;
 use crate::jwt::generate::{validate_qr_token, validate_token};
-use crate::models::{BookingModel, RoleModel};
+use crate::models::{BookingModel, Permission, RoleModel};
 use chrono::{Duration, Utc};
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
@@ -8,6 +8,12 @@ use uuid::Uuid;
 
 const JWT_EXPIRY_HOURS: i64 = 24;
 
+/// Start of every API key, so leaked keys are easy to grep for.
+pub const API_KEY_PREFIX: &str = "bqk_";
+
+/// Tokens minted for API keys only live through the request that presented the key.
+const API_KEY_JWT_EXPIRY_MINUTES: i64 = 5;
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Claims {
     pub role: RoleModel,
@@ -15,6 +21,10 @@ pub struct Claims {
     pub company_id: Uuid,
     pub iat: i64,
     pub exp: i64,
+
+    /// Set when authenticated with an API key, permissions are limited to these.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<Permission>>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -48,6 +58,26 @@ impl Claims {
             company_id: *company_id,
             iat: iat.timestamp(),
             exp: exp.timestamp(),
+            scopes: None,
+        }
+    }
+
+    pub fn for_api_key(
+        user_id: &Uuid,
+        company_id: &Uuid,
+        role: &RoleModel,
+        scopes: Vec<Permission>,
+    ) -> Self {
+        let iat = Utc::now();
+        let exp = iat + Duration::minutes(API_KEY_JWT_EXPIRY_MINUTES);
+
+        Self {
+            role: role.clone(),
+            user_id: *user_id,
+            company_id: *company_id,
+            iat: iat.timestamp(),
+            exp: exp.timestamp(),
+            scopes: Some(scopes),
         }
     }
 }
@@ -72,6 +102,11 @@ impl FromStr for Claims {
         let token = claims.get(1).ok_or(ProdError::ShitHappened(
             "Wrong authorization Bearer format".to_string(),
         ))?;
+        if token.starts_with(API_KEY_PREFIX) {
+            return Err(ProdError::Forbidden(
+                "API keys aren't accepted here".to_string(),
+            ));
+        }
         validate_token(token)
     }
 }
@@ -8,10 +8,13 @@
 pub mod controllers;
 pub mod db;
 pub mod errors;
+pub mod extractors;
 pub mod forms;
 pub mod jwt;
+pub mod mail;
 pub mod middlewares;
 pub mod models;
+pub mod oidc;
 pub mod openapi;
 pub mod routes;
 pub mod s3;
@@ -20,16 +23,17 @@ pub mod util;
 use crate::routes::{companies, items};
 use axum::{
     http::{StatusCode, Uri},
-    middleware::from_fn,
+    middleware::{from_fn, from_fn_with_state},
     response::IntoResponse,
     routing::get,
     Router,
 };
-use middlewares::log_request;
+use middlewares::{log_request, request_meta, resolve_api_keys, tenant_scope};
 use openapi::ApiDoc;
-use routes::{admin, booking, places, users};
+use routes::{admin, booking, places, scim, teams, users};
 use s3::setup_s3;
 use sqlx::PgPool;
+use std::net::SocketAddr;
 use tokio::net::TcpListener;
 use tower_http::cors::CorsLayer;
 use tracing::Level;
@@ -69,12 +73,21 @@ async fn main() -> anyhow::Result<()> {
 
     let (s3, bucket_name) = setup_s3().await?;
 
+    tokio::spawn(controllers::trash::purge_job(pool.clone()));
+
     let app_state = AppState {
         pool,
         s3,
         bucket_name,
     };
 
+    tokio::spawn(controllers::companies::offboarding::offboarding_job(
+        app_state.clone(),
+    ));
+    tokio::spawn(controllers::layout::versions::layout_publish_job(
+        app_state.clone(),
+    ));
+
     let router = Router::new()
         .route("/", get("<h1>Hello World</h1>"))
         .route("/healthz", get(StatusCode::OK))
@@ -84,7 +97,12 @@ async fn main() -> anyhow::Result<()> {
         .nest("/company", companies::get_routes(app_state.clone()))
         .nest("/place", places::get_routes(app_state.clone()))
         .nest("/items", items::get_routes(app_state.clone()))
+        .nest("/teams", teams::get_routes(app_state.clone()))
+        .nest("/scim/v2", scim::get_routes(app_state.clone()))
+        .layer(from_fn(tenant_scope))
+        .layer(from_fn_with_state(app_state.clone(), resolve_api_keys))
         .layer(from_fn(log_request))
+        .layer(from_fn(request_meta))
         .layer(CorsLayer::permissive());
 
     let app = Router::new()
@@ -96,6 +114,10 @@ async fn main() -> anyhow::Result<()> {
         .fallback(not_found_handler);
 
     let listener = TcpListener::bind(&format!("0.0.0.0:{port}")).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
     Ok(())
 }
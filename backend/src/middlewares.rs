@@ -1,9 +1,25 @@
-use std::time::Instant;
+use std::{net::SocketAddr, time::Instant};
 
-use axum::{extract::Request, http::StatusCode, response::Response};
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::Response,
+};
+use sqlx::PgPool;
 use tracing::info;
+use uuid::Uuid;
 
-use crate::{errors::ProdError, jwt::generate::claims_from_headers, models::RoleModel};
+use crate::{
+    controllers::{api_keys::resolve_api_key, roles::claims_permissions},
+    db::{Db, TENANT},
+    errors::ProdError,
+    jwt::{
+        generate::{claims_from_headers, encode_claims},
+        models::API_KEY_PREFIX,
+    },
+    models::Permission,
+    AppState,
+};
 
 pub async fn log_request(
     req: Request,
@@ -30,13 +46,127 @@ pub async fn log_request(
     Ok(response)
 }
 
-pub async fn auth_admin(req: Request, next: axum::middleware::Next) -> Result<Response, ProdError> {
-    let headers = req.headers();
-    let jwt = claims_from_headers(headers)?;
+/// State for [`require_permissions`], built per router with the permissions its routes need.
+#[derive(Clone)]
+pub struct RequiredPermissions {
+    pool: PgPool,
+    permissions: &'static [Permission],
+}
 
-    if jwt.role != RoleModel::Admin {
-        return Err(ProdError::Forbidden("You are not an admin".to_string()));
+impl RequiredPermissions {
+    pub fn new(state: &AppState, permissions: &'static [Permission]) -> Self {
+        Self {
+            pool: state.pool.clone(),
+            permissions,
+        }
+    }
+}
+
+pub async fn require_permissions(
+    State(required): State<RequiredPermissions>,
+    req: Request,
+    next: axum::middleware::Next,
+) -> Result<Response, ProdError> {
+    let claims = claims_from_headers(req.headers())?;
+
+    let mut conn = required.pool.conn().await?;
+    let granted = claims_permissions(&mut conn, &claims).await?;
+    drop(conn);
+
+    if let Some(missing) = required.permissions.iter().find(|p| !granted.contains(p)) {
+        return Err(ProdError::Forbidden(format!(
+            "Missing permission `{}`",
+            missing.as_str()
+        )));
     }
 
     Ok(next.run(req).await)
 }
+
+/// Swaps `Bearer bqk_...` API keys for a short-lived JWT, so handlers see them as regular [`Claims`].
+///
+/// [`Claims`]: crate::jwt::models::Claims
+pub async fn resolve_api_keys(
+    State(state): State<AppState>,
+    mut req: Request,
+    next: axum::middleware::Next,
+) -> Result<Response, ProdError> {
+    let key = req
+        .headers()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .filter(|token| token.starts_with(API_KEY_PREFIX))
+        .map(ToString::to_string);
+
+    if let Some(key) = key {
+        let mut conn = state.pool.unscoped_conn().await?;
+        let claims = resolve_api_key(&mut conn, &key).await?;
+        drop(conn);
+
+        let header = HeaderValue::from_str(&format!("Bearer {}", encode_claims(&claims)?))
+            .map_err(|err| ProdError::ShitHappened(err.to_string()))?;
+        req.headers_mut().insert("authorization", header);
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// Limits the connections taken while handling the request to the company it's authenticated for.
+///
+/// Requests without valid claims get connections that see every company, handlers check them on their own.
+pub async fn tenant_scope(req: Request, next: axum::middleware::Next) -> Response {
+    let company_id = claims_from_headers(req.headers())
+        .ok()
+        .map(|claims| claims.company_id);
+
+    TENANT.scope(company_id, next.run(req)).await
+}
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Where a request came from, recorded in the audit log.
+#[derive(Clone, Debug)]
+pub struct RequestMeta {
+    pub request_id: String,
+    pub ip: Option<String>,
+}
+
+impl RequestMeta {
+    fn from_request(req: &Request) -> Self {
+        let request_id = header(req.headers(), REQUEST_ID_HEADER)
+            .filter(|id| id.len() <= 64 && id.bytes().all(|b| b.is_ascii_graphic()))
+            .map_or_else(|| Uuid::now_v7().to_string(), ToString::to_string);
+
+        // The first hop is the client when running behind the proxy
+        let ip = header(req.headers(), "x-forwarded-for")
+            .and_then(|value| value.split(',').next())
+            .or_else(|| header(req.headers(), "x-real-ip"))
+            .map(|ip| ip.trim().to_string())
+            .or_else(|| {
+                req.extensions()
+                    .get::<ConnectInfo<SocketAddr>>()
+                    .map(|ConnectInfo(addr)| addr.ip().to_string())
+            });
+
+        Self { request_id, ip }
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Attaches [`RequestMeta`] to the request and echoes its id back in `x-request-id`.
+pub async fn request_meta(mut req: Request, next: axum::middleware::Next) -> Response {
+    let meta = RequestMeta::from_request(&req);
+    let request_id = HeaderValue::from_str(&meta.request_id).ok();
+    req.extensions_mut().insert(meta);
+
+    let mut response = next.run(req).await;
+    if let Some(request_id) = request_id {
+        response.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+    }
+
+    response
+}
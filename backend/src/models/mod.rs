@@ -2,21 +2,30 @@ use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use sqlx::error::BoxDynError;
 use sqlx::postgres::{PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueRef};
+use sqlx::types::Json;
 use sqlx::{prelude::FromRow, Decode, Encode, Postgres, Type};
+use std::collections::{BTreeMap, HashMap};
 use std::ops::Add;
 use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Serialize, Deserialize, FromRow, Validate)]
+#[derive(Serialize, Deserialize, FromRow, Validate, ToSchema)]
 pub struct CompaniesModel {
     pub id: Uuid,
     pub name: String,
     pub domain: String,
+
+    /// URL of the company logo.
     pub avatar: Option<String>,
+    pub primary_color: Option<String>,
+    pub accent_color: Option<String>,
+
+    /// Admin who can offboard the company, the one that registered it.
+    pub owner_id: Option<Uuid>,
 }
 
-#[derive(Serialize, Deserialize, Type, Clone, PartialEq, Eq, ToSchema)]
+#[derive(Serialize, Deserialize, Type, Clone, PartialEq, Eq, Debug, ToSchema)]
 #[sqlx(type_name = "ROLE", rename_all = "lowercase")]
 #[serde(rename_all = "UPPERCASE")]
 pub enum RoleModel {
@@ -28,6 +37,165 @@ pub enum RoleModel {
     VerifiedGuest,
 }
 
+#[derive(Serialize, Deserialize, Type, Clone, Copy, PartialEq, Eq, Debug, ToSchema)]
+#[sqlx(type_name = "PERMISSION", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    Book,
+    ManageBookings,
+    ManageLayout,
+    ManageUsers,
+    VerifyGuests,
+    ViewAnalytics,
+    ManageRoles,
+    ManageCompany,
+}
+
+impl Permission {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Book => "book",
+            Self::ManageBookings => "manage_bookings",
+            Self::ManageLayout => "manage_layout",
+            Self::ManageUsers => "manage_users",
+            Self::VerifyGuests => "verify_guests",
+            Self::ViewAnalytics => "view_analytics",
+            Self::ManageRoles => "manage_roles",
+            Self::ManageCompany => "manage_company",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Type, Clone, Copy, PartialEq, Eq, Debug, ToSchema)]
+#[sqlx(type_name = "VERIFICATION_STATUS", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationStatus {
+    Pending,
+    Approved,
+    Rejected,
+    NeedsMoreInfo,
+}
+
+impl VerificationStatus {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+            Self::NeedsMoreInfo => "needs_more_info",
+        }
+    }
+}
+
+/// What blocks the cells of an obstacle, for rendering; none of them can hold items.
+#[derive(Serialize, Deserialize, Type, Clone, Copy, PartialEq, Eq, Debug, ToSchema)]
+#[sqlx(type_name = "OBSTACLE_KIND", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ObstacleKind {
+    Wall,
+    Pillar,
+    Door,
+    Window,
+}
+
+/// How values of an item attribute look: `true`/`false`, one of the options or a number.
+#[derive(Serialize, Deserialize, Type, Clone, Copy, PartialEq, Eq, Debug, ToSchema)]
+#[sqlx(type_name = "ITEM_ATTRIBUTE_KIND", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum AttributeKind {
+    Boolean,
+    Enum,
+    Number,
+}
+
+/// Whether bookings of an item exclude each other or share its capacity.
+#[derive(Serialize, Deserialize, Type, Clone, Copy, PartialEq, Eq, Debug, Default, ToSchema)]
+#[sqlx(type_name = "BOOKING_MODE", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum BookingMode {
+    /// One booking at a time, like a desk or a meeting room.
+    #[default]
+    Exclusive,
+    /// Overlapping bookings until their attendees fill the capacity, like a lounge.
+    Shared,
+}
+
+/// Lifecycle of a layout version, only one per coworking is published at a time.
+#[derive(Serialize, Deserialize, Type, Clone, Copy, PartialEq, Eq, Debug, ToSchema)]
+#[sqlx(type_name = "LAYOUT_VERSION_STATUS", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutVersionStatus {
+    Draft,
+    Scheduled,
+    Published,
+    Archived,
+}
+
+/// Kinds of entities that go to the trash instead of being deleted right away.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TrashEntity {
+    User,
+    Building,
+    Coworking,
+    ItemType,
+}
+
+impl TrashEntity {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Building => "building",
+            Self::Coworking => "coworking",
+            Self::ItemType => "item_type",
+        }
+    }
+
+    /// Permission needed to see the entity in the trash and restore it.
+    pub const fn permission(self) -> Permission {
+        match self {
+            Self::User => Permission::ManageUsers,
+            Self::Building | Self::Coworking | Self::ItemType => Permission::ManageLayout,
+        }
+    }
+
+    pub const ALL: [Self; 4] = [Self::User, Self::Building, Self::Coworking, Self::ItemType];
+}
+
+/// Named bundle of permissions. Built-in roles have `base_role` set and can't be removed.
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CompanyRoleModel {
+    pub id: Uuid,
+    pub name: String,
+    pub permissions: Vec<Permission>,
+    pub base_role: Option<RoleModel>,
+
+    #[serde(skip)]
+    pub company_id: Uuid,
+}
+
+/// Team or department, departments have the team they belong to as `parent_id`.
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TeamModel {
+    pub id: Uuid,
+    pub name: String,
+    pub parent_id: Option<Uuid>,
+
+    #[serde(skip)]
+    pub company_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TeamMemberModel {
+    pub user_id: Uuid,
+    pub name: String,
+    pub surname: String,
+    pub email: String,
+
+    /// Books on behalf of the members of the team and its departments.
+    pub manager: bool,
+}
+
 #[derive(Serialize, Deserialize, FromRow, Validate, ToSchema)]
 pub struct UserModel {
     #[serde(skip)]
@@ -45,6 +213,12 @@ pub struct UserModel {
     pub company_id: Uuid,
     pub company_domain: String,
     pub role: RoleModel,
+
+    /// Deactivated users can't log in, set through SCIM.
+    pub active: bool,
+
+    #[serde(skip)]
+    pub external_id: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, FromRow, ToSchema)]
@@ -54,6 +228,7 @@ pub struct CoworkingSpacesModel {
     pub height: i64,
     pub width: i64,
     pub building_id: Uuid,
+    pub floor_id: Uuid,
     pub company_id: Uuid,
 }
 
@@ -112,6 +287,122 @@ impl PgHasArrayType for Point {
     }
 }
 
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct InviteModel {
+    pub id: Uuid,
+    pub code: String,
+
+    /// Registration link for the frontend, `{APP_URL}/invite#code=<code>`.
+    pub link: String,
+    pub role_id: Uuid,
+    pub max_uses: Option<i32>,
+    pub uses: i32,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+
+    #[serde(skip)]
+    pub company_id: Uuid,
+}
+
+/// API key without its secret, `prefix` tells keys apart in listings.
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ApiKeyModel {
+    pub id: Uuid,
+    pub name: String,
+    pub prefix: String,
+    pub permissions: Vec<Permission>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub user_id: Uuid,
+
+    #[serde(skip)]
+    pub company_id: Uuid,
+}
+
+/// Comment on a verification case, `status` is set when it came with a decision.
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct VerificationCommentModel {
+    pub id: Uuid,
+    pub author_id: Option<Uuid>,
+    pub status: Option<VerificationStatus>,
+    pub body: String,
+    pub created_at: NaiveDateTime,
+
+    #[serde(skip)]
+    pub case_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ServiceAccountModel {
+    pub id: Uuid,
+    pub name: String,
+    pub role: RoleModel,
+
+    /// Custom role, `role` applies when not set.
+    pub role_id: Option<Uuid>,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AuditLogModel {
+    pub id: Uuid,
+    /// `None` for actions done by a since deleted user.
+    pub actor_id: Option<Uuid>,
+    pub action: String,
+    pub entity: String,
+    pub entity_id: Option<Uuid>,
+
+    /// Fields of the entity that changed, as they were before the action.
+    pub before: Option<serde_json::Value>,
+    /// Fields of the entity that changed, as they are after the action.
+    pub after: Option<serde_json::Value>,
+    pub ip: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+/// Deletion of a company, it's torn down once `purge_after` passes unless cancelled before.
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CompanyOffboardingModel {
+    pub id: Uuid,
+    #[serde(skip)]
+    pub company_id: Uuid,
+    pub company_name: String,
+    pub company_domain: String,
+    pub requested_by: Uuid,
+    pub created_at: NaiveDateTime,
+    pub purge_after: NaiveDateTime,
+    pub cancelled_at: Option<NaiveDateTime>,
+    pub completed_at: Option<NaiveDateTime>,
+
+    /// Set once the company is torn down.
+    #[schema(value_type = Option<OffboardingReport>)]
+    pub report: Option<Json<OffboardingReport>>,
+}
+
+/// What the teardown of a company removed.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct OffboardingReport {
+    /// Exported and removed rows by table.
+    pub rows: BTreeMap<String, usize>,
+    /// Removed files: avatars, verification documents, item icons and the logo.
+    pub files: usize,
+    /// Size of the export archive in bytes.
+    pub archive_bytes: usize,
+}
+
+/// Soft deleted entity, `purge_at` is when it's gone for good.
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TrashItemModel {
+    /// `user`, `building`, `coworking` or `item_type`.
+    pub entity: String,
+    pub id: Uuid,
+    /// Address of buildings and coworkings, full name of users.
+    pub name: String,
+    pub deleted_at: NaiveDateTime,
+    pub purge_at: NaiveDateTime,
+}
+
 #[derive(Serialize, Deserialize, FromRow, Validate, ToSchema)]
 pub struct ItemsModel {
     pub id: Uuid,
@@ -121,7 +412,66 @@ pub struct ItemsModel {
     pub icon: Option<String>,
     pub offsets: Vec<Point>,
     pub bookable: bool,
+    pub booking_mode: BookingMode,
+
+    /// Most attendees at once: per booking for exclusive items, for all overlapping bookings
+    /// together for shared ones.
+    pub capacity: i32,
     pub company_id: Uuid,
+
+    /// Attribute values of the type by attribute key.
+    pub attributes: serde_json::Value,
+}
+
+/// Amenity defined by the company, like monitors or a standing desk.
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ItemAttributeModel {
+    pub id: Uuid,
+
+    /// Name used in search filters, e.g. `monitors`.
+    pub key: String,
+    pub name: String,
+    pub kind: AttributeKind,
+
+    /// Values an `enum` attribute can take, empty for the other kinds.
+    pub options: Vec<String>,
+    pub company_id: Uuid,
+}
+
+/// What a placed item would run into with the new offsets of its type.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionReason {
+    Borders,
+    Obstacle,
+    Item,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ItemCollisionModel {
+    pub coworking_id: Uuid,
+    pub coworking_item_id: Uuid,
+    pub reason: CollisionReason,
+
+    /// Item it would overlap with, when `reason` is `item`.
+    pub other_item_id: Option<Uuid>,
+}
+
+/// What changing an item type would do to the items of that type placed in coworkings.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ItemTypeImpactModel {
+    pub placed_items: i64,
+    pub coworkings: i64,
+
+    /// Bookings of those items that haven't ended yet.
+    pub future_bookings: i64,
+
+    /// Placed items that wouldn't fit with the new offsets, the change is refused while there are any.
+    pub collisions: Vec<ItemCollisionModel>,
+
+    /// Placed items with upcoming bookings that the new booking mode or capacity can't take,
+    /// the change is refused while there are any.
+    pub overbooked: Vec<Uuid>,
 }
 
 #[derive(Serialize, Deserialize, FromRow, Validate, ToSchema)]
@@ -131,6 +481,98 @@ pub struct CoworkingItemsModel {
     pub name: String,
     pub description: Option<String>,
     pub base_point: Point,
+
+    /// Clockwise turn in degrees, one of 0, 90, 180 and 270.
+    pub rotation: i32,
+
+    /// Whether the item is flipped left to right before it's turned.
+    pub mirrored: bool,
+
+    /// Cells the item covers in the coworking, with rotation and mirroring applied.
+    pub footprint: Vec<Point>,
+
+    /// Teams the item is reserved for, everybody can book it when empty.
+    pub teams: Vec<Uuid>,
+
+    /// Whether the authenticated user can book it, as far as team restrictions of the item
+    /// and its coworking go.
+    pub can_book: bool,
+
+    /// Attribute values by attribute key, the item's own ones over those of its type.
+    pub attributes: serde_json::Value,
+}
+
+/// Placed item that is free for the whole asked time.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct AvailableItemModel {
+    pub building_id: Uuid,
+    pub coworking_id: Uuid,
+    #[serde(flatten)]
+    pub item: CoworkingItemsModel,
+}
+
+/// Cells of a coworking taken by walls, pillars, doors or windows.
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ObstacleModel {
+    pub id: Uuid,
+    pub kind: ObstacleKind,
+    pub cells: Vec<Point>,
+}
+
+/// Everything placed in a coworking, as needed to draw it.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CoworkingLayoutModel {
+    pub items: Vec<CoworkingItemsModel>,
+    pub obstacles: Vec<ObstacleModel>,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct LayoutVersionModel {
+    pub id: Uuid,
+    pub coworking_id: Uuid,
+    pub status: LayoutVersionStatus,
+    pub created_by: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+
+    /// When a scheduled version goes live.
+    pub publish_at: Option<NaiveDateTime>,
+    pub published_by: Option<Uuid>,
+    pub published_at: Option<NaiveDateTime>,
+
+    #[serde(skip)]
+    pub company_id: Uuid,
+}
+
+/// Item of a layout version, `key` is the id it has in the coworking once published.
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct LayoutItemModel {
+    pub key: Uuid,
+    pub item_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub base_point: Point,
+    pub rotation: i32,
+    pub mirrored: bool,
+    pub footprint: Vec<Point>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LayoutVersionItemsModel {
+    pub version: LayoutVersionModel,
+    pub items: Vec<LayoutItemModel>,
+}
+
+/// What publishing a version would change in the live layout of its coworking.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LayoutDiffModel {
+    pub added: Vec<LayoutItemModel>,
+    pub removed: Vec<LayoutItemModel>,
+
+    /// Items that stay, as they'd be after publishing. Their bookings are kept.
+    pub changed: Vec<LayoutItemModel>,
+
+    /// Bookings that haven't ended yet on removed items, they're cancelled by publishing.
+    pub affected_bookings: Vec<BookingModel>,
 }
 
 #[derive(Serialize, Deserialize, FromRow, Validate, ToSchema)]
@@ -142,6 +584,7 @@ pub struct BookingModel {
     pub company_id: Uuid,
     pub time_start: NaiveDateTime,
     pub time_end: NaiveDateTime,
+    pub attendees: i32,
 }
 
 #[derive(Serialize, Deserialize, FromRow, Validate, ToSchema)]
@@ -154,6 +597,7 @@ pub struct PublicBookingModel {
 
     pub time_start: NaiveDateTime,
     pub time_end: NaiveDateTime,
+    pub attendees: i32,
 
     pub building_address: String,
 
@@ -182,13 +626,35 @@ pub struct BuildingModel {
     pub company_id: Uuid,
 }
 
-#[derive(Serialize, Deserialize, FromRow, Validate, ToSchema, Clone)]
-pub struct Coordinates {
-    pub base_point: Point,
-    pub offsets: Vec<Point>,
+/// Level of a building, coworkings are placed on floors.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct FloorModel {
+    pub id: Uuid,
+    pub building_id: Uuid,
+    pub number: i32,
+    pub name: String,
+    pub display_order: i32,
+    pub company_id: Uuid,
 }
 
-#[derive(Serialize, Deserialize, FromRow, Validate, ToSchema, Clone)]
-pub struct Offsets {
-    pub offsets: Vec<Point>,
+#[derive(Serialize, Deserialize, FromRow, ToSchema)]
+pub struct OidcProviderModel {
+    #[serde(skip)]
+    pub company_id: Uuid,
+    pub issuer: String,
+    pub client_id: String,
+
+    #[serde(skip)]
+    pub client_secret: String,
+    pub redirect_uri: String,
+    pub scopes: String,
+    pub name_claim: String,
+    pub surname_claim: String,
+    pub email_claim: String,
+    pub role_claim: Option<String>,
+
+    #[schema(value_type = HashMap<String, RoleModel>)]
+    pub role_mapping: Json<HashMap<String, RoleModel>>,
+    pub default_role: RoleModel,
+    pub password_login_enabled: bool,
 }
@@ -0,0 +1,225 @@
+use std::sync::LazyLock;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    errors::ProdError,
+    jwt::generate::generate_bytes,
+    models::{OidcProviderModel, RoleModel},
+};
+
+static HTTP: LazyLock<reqwest::Client> = LazyLock::new(reqwest::Client::new);
+
+/// Subset of the provider metadata we need for the authorization code flow.
+#[derive(Deserialize)]
+pub struct Discovery {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// User data extracted from a verified ID token.
+pub struct OidcIdentity {
+    pub subject: String,
+    pub name: String,
+    pub surname: String,
+    pub email: String,
+    /// Whether the provider vouches for the e-mail, accounts are only linked by verified ones.
+    pub email_verified: bool,
+    pub role: RoleModel,
+}
+
+pub struct Pkce {
+    pub verifier: String,
+    pub challenge: String,
+}
+
+pub fn random_token() -> String {
+    URL_SAFE_NO_PAD.encode(generate_bytes(32))
+}
+
+impl Pkce {
+    pub fn new() -> Self {
+        let verifier = random_token();
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        Self {
+            verifier,
+            challenge,
+        }
+    }
+}
+
+impl Default for Pkce {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub async fn discover(issuer: &str) -> Result<Discovery, ProdError> {
+    let url = format!(
+        "{}/.well-known/openid-configuration",
+        issuer.trim_end_matches('/')
+    );
+
+    HTTP.get(&url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|err| ProdError::OidcError(format!("Discovery request failed - {err}")))?
+        .json()
+        .await
+        .map_err(|err| ProdError::OidcError(format!("Invalid discovery document - {err}")))
+}
+
+pub fn authorization_url(
+    provider: &OidcProviderModel,
+    discovery: &Discovery,
+    state: &str,
+    nonce: &str,
+    pkce: &Pkce,
+) -> Result<String, ProdError> {
+    reqwest::Url::parse_with_params(
+        &discovery.authorization_endpoint,
+        &[
+            ("response_type", "code"),
+            ("client_id", provider.client_id.as_str()),
+            ("redirect_uri", provider.redirect_uri.as_str()),
+            ("scope", provider.scopes.as_str()),
+            ("state", state),
+            ("nonce", nonce),
+            ("code_challenge", pkce.challenge.as_str()),
+            ("code_challenge_method", "S256"),
+        ],
+    )
+    .map(String::from)
+    .map_err(|err| ProdError::OidcError(format!("Invalid authorization endpoint - {err}")))
+}
+
+/// Exchanges an authorization code for tokens and returns the raw ID token.
+pub async fn exchange_code(
+    provider: &OidcProviderModel,
+    discovery: &Discovery,
+    code: &str,
+    code_verifier: &str,
+) -> Result<String, ProdError> {
+    let params = [
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", provider.redirect_uri.as_str()),
+        ("client_id", provider.client_id.as_str()),
+        ("client_secret", provider.client_secret.as_str()),
+        ("code_verifier", code_verifier),
+    ];
+
+    let response: TokenResponse = HTTP
+        .post(&discovery.token_endpoint)
+        .form(&params)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|err| ProdError::OidcError(format!("Token request failed - {err}")))?
+        .json()
+        .await
+        .map_err(|err| ProdError::OidcError(format!("Invalid token response - {err}")))?;
+
+    Ok(response.id_token)
+}
+
+/// Verifies signature, issuer, audience and nonce of an ID token.
+///
+/// HMAC-signed tokens are checked with the client secret as the spec allows,
+/// everything else is checked against the provider's JWKS.
+pub async fn verify_id_token(
+    provider: &OidcProviderModel,
+    discovery: &Discovery,
+    id_token: &str,
+    nonce: &str,
+) -> Result<Map<String, Value>, ProdError> {
+    let header = decode_header(id_token)?;
+
+    let key = match header.alg {
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+            DecodingKey::from_secret(provider.client_secret.as_bytes())
+        }
+        _ => {
+            let jwks: JwkSet = HTTP
+                .get(&discovery.jwks_uri)
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status)
+                .map_err(|err| ProdError::OidcError(format!("JWKS request failed - {err}")))?
+                .json()
+                .await
+                .map_err(|err| ProdError::OidcError(format!("Invalid JWKS - {err}")))?;
+
+            let jwk = match header.kid.as_deref() {
+                Some(kid) => jwks.find(kid),
+                None => jwks.keys.first(),
+            }
+            .ok_or_else(|| ProdError::OidcError("No matching key in JWKS".to_string()))?;
+
+            DecodingKey::from_jwk(jwk)?
+        }
+    };
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_audience(&[&provider.client_id]);
+    validation.set_issuer(&[&discovery.issuer]);
+
+    let claims = decode::<Map<String, Value>>(id_token, &key, &validation)?.claims;
+
+    if claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+        return Err(ProdError::Forbidden("ID token nonce mismatch".to_string()));
+    }
+
+    Ok(claims)
+}
+
+/// Applies the company's claim mapping to verified ID token claims.
+pub fn map_identity(
+    provider: &OidcProviderModel,
+    claims: &Map<String, Value>,
+) -> Result<OidcIdentity, ProdError> {
+    let claim = |name: &str| claims.get(name).and_then(Value::as_str).map(str::to_string);
+
+    let subject =
+        claim("sub").ok_or_else(|| ProdError::OidcError("ID token has no subject".to_string()))?;
+    let email = claim(&provider.email_claim).ok_or_else(|| {
+        ProdError::OidcError(format!("ID token has no `{}` claim", provider.email_claim))
+    })?;
+
+    let role = provider
+        .role_claim
+        .as_ref()
+        .and_then(|name| claims.get(name))
+        .and_then(|value| match value {
+            Value::String(value) => provider.role_mapping.get(value).cloned(),
+            Value::Array(values) => values
+                .iter()
+                .filter_map(Value::as_str)
+                .find_map(|value| provider.role_mapping.get(value).cloned()),
+            _ => None,
+        })
+        .unwrap_or_else(|| provider.default_role.clone());
+
+    Ok(OidcIdentity {
+        subject,
+        name: claim(&provider.name_claim).unwrap_or_else(|| email.clone()),
+        surname: claim(&provider.surname_claim).unwrap_or_default(),
+        email,
+        // Some providers send it as a string.
+        email_verified: matches!(claims.get("email_verified"), Some(Value::Bool(true)))
+            || claim("email_verified").as_deref() == Some("true"),
+        role,
+    })
+}
@@ -1,15 +1,51 @@
 use crate::routes::{
-    admin::routes::{
-        __path_admin_delete_user, __path_get_user, __path_get_user_document,
-        __path_get_verify_requests, __path_list_users, __path_patch_user, __path_verify_guest,
+    admin::{
+        audit::{__path_export_audit_log, __path_list_audit_log},
+        onboarding::{
+            __path_create_invite, __path_delete_invite, __path_import_users, __path_list_invites,
+        },
+        roles::{
+            __path_assign_role, __path_create_role, __path_delete_role, __path_list_roles,
+            __path_patch_role,
+        },
+        routes::{
+            __path_admin_delete_user, __path_get_user, __path_get_user_document,
+            __path_get_verify_requests, __path_list_users, __path_patch_user, __path_verify_guest,
+        },
+        scim::{__path_create_scim_token, __path_delete_scim_token},
+        service_accounts::{
+            __path_create_service_account, __path_create_service_account_key,
+            __path_delete_service_account, __path_list_company_api_keys,
+            __path_list_service_accounts, __path_revoke_company_api_key,
+        },
+        sso::{__path_delete_sso_provider, __path_get_sso_provider, __path_put_sso_provider},
+        trash::{__path_list_trash, __path_restore_from_trash},
+        verification::{
+            __path_comment_verification, __path_decide_verification, __path_get_verification_case,
+            __path_get_verification_document,
+        },
     },
     booking::routes::{
-        __path_create_booking, __path_delete_booking, __path_get_booking_qr, __path_list_bookings,
-        __path_patch_booking, __path_verify_booking_qr,
+        __path_available_items, __path_create_booking, __path_delete_booking,
+        __path_get_booking_qr, __path_list_bookings, __path_patch_booking,
+        __path_verify_booking_qr,
+    },
+    companies::{
+        offboarding::{
+            __path_cancel_offboarding, __path_get_offboarding, __path_get_offboarding_export,
+            __path_get_offboarding_report, __path_offboard_company,
+        },
+        routes::{
+            __path_company_register, __path_get_branding, __path_get_company, __path_get_logo,
+            __path_patch_company,
+        },
     },
-    companies::routes::__path_company_register,
     items::{
-        routes::{__path_create_items_type, __path_delete_item_type},
+        attributes::{__path_create_attribute, __path_delete_attribute, __path_list_attributes},
+        routes::{
+            __path_check_item_type_impact, __path_create_items_type, __path_delete_item_type,
+            __path_patch_item_type, __path_put_item_type_attributes,
+        },
         selecting::__path_list_items_by_company,
     },
     places::{
@@ -20,19 +56,50 @@ use crate::routes::{
         coworking::{
             __path_create_coworking, __path_delete_coworking, __path_get_coworking_bookings,
             __path_get_coworking_by_id, __path_list_coworkings, __path_list_coworkings_by_building,
-            __path_patch_coworking,
+            __path_list_coworkings_by_floor, __path_patch_coworking, __path_put_coworking_teams,
+        },
+        floor::{
+            __path_create_floor, __path_delete_floor, __path_get_floor, __path_list_floors,
+            __path_patch_floor,
         },
         items::{
             __path_add_item_to_coworking, __path_delete_item_from_coworking,
-            __path_get_items_by_coworking, __path_put_items_in_coworking,
+            __path_get_items_by_coworking, __path_patch_item_in_coworking,
+            __path_put_item_attributes, __path_put_item_teams, __path_put_items_in_coworking,
+        },
+        layout::{
+            __path_get_coworking_layout, __path_put_coworking_obstacles,
+            __path_render_coworking_layout,
         },
+        portable::{
+            __path_clone_coworking, __path_export_coworking_layout, __path_import_coworking_layout,
+        },
+        versions::{
+            __path_create_layout_draft, __path_delete_layout_version, __path_get_layout_diff,
+            __path_get_layout_version, __path_list_layout_versions, __path_publish_layout_version,
+            __path_put_layout_draft_items, __path_rollback_layout_version,
+        },
+    },
+    scim::routes::{
+        __path_create_scim_user, __path_delete_scim_user, __path_get_scim_user,
+        __path_list_scim_users, __path_patch_scim_user, __path_replace_scim_user,
+        __path_service_provider_config,
+    },
+    teams::routes::{
+        __path_create_team, __path_delete_team, __path_delete_team_member,
+        __path_list_team_members, __path_list_teams, __path_patch_team, __path_put_team_member,
     },
     users::{
+        api_keys::{__path_create_api_key, __path_delete_api_key, __path_list_api_keys},
+        companies::{__path_link_company_account, __path_list_companies, __path_switch_company},
+        invites::{__path_activate, __path_get_invite, __path_register_with_invite},
+        privacy::__path_export_data,
         routes::{
             __path_delete_user, __path_get_avatar, __path_login, __path_patch_profile,
             __path_profile, __path_register,
         },
-        validate::__path_upload_document,
+        sso::{__path_sso_authorize, __path_sso_callback},
+        validate::{__path_get_verification, __path_upload_document},
     },
 };
 use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
@@ -55,13 +122,29 @@ impl Modify for SecurityAddon {
 #[derive(OpenApi)]
 #[openapi(
     paths(
-        login, register, profile, patch_profile, upload_document, delete_user, get_avatar,
+        login, register, profile, patch_profile, upload_document, get_verification, delete_user, export_data, get_avatar,
+        sso_authorize, sso_callback, get_invite, register_with_invite, activate,
+        list_api_keys, create_api_key, delete_api_key,
+        list_companies, switch_company, link_company_account,
         verify_guest, admin_delete_user, patch_user, list_users, get_user, get_verify_requests, get_user_document,
-        create_booking, delete_booking, patch_booking, list_bookings, get_booking_qr, verify_booking_qr,
+        get_verification_case, get_verification_document, decide_verification, comment_verification,
+        get_sso_provider, put_sso_provider, delete_sso_provider, create_scim_token, delete_scim_token,
+        list_audit_log, export_audit_log, list_trash, restore_from_trash,
+        list_roles, create_role, patch_role, delete_role, assign_role,
+        create_invite, list_invites, delete_invite, import_users,
+        create_service_account, list_service_accounts, delete_service_account, create_service_account_key,
+        list_company_api_keys, revoke_company_api_key,
+        create_booking, delete_booking, patch_booking, list_bookings, get_booking_qr, verify_booking_qr, available_items,
         create_coworking, create_building, list_buildings, get_building, patch_building, patch_coworking, get_coworking_by_id, delete_building,
-        delete_coworking, get_coworking_bookings, list_coworkings_by_building, list_coworkings,
-        create_items_type, delete_item_type, get_items_by_coworking, list_items_by_company, add_item_to_coworking, delete_item_from_coworking, put_items_in_coworking,
-        company_register,
+        delete_coworking, get_coworking_bookings, list_coworkings_by_building, list_coworkings, put_coworking_teams,
+        create_floor, list_floors, get_floor, patch_floor, delete_floor, list_coworkings_by_floor,
+        create_items_type, patch_item_type, check_item_type_impact, delete_item_type, get_items_by_coworking, list_items_by_company, add_item_to_coworking, patch_item_in_coworking, delete_item_from_coworking, put_items_in_coworking, put_item_teams, get_coworking_layout, render_coworking_layout, put_coworking_obstacles, export_coworking_layout, import_coworking_layout, clone_coworking,
+        list_attributes, create_attribute, delete_attribute, put_item_type_attributes, put_item_attributes,
+        list_layout_versions, create_layout_draft, get_layout_version, put_layout_draft_items, get_layout_diff, publish_layout_version, rollback_layout_version, delete_layout_version,
+        list_teams, create_team, patch_team, delete_team, list_team_members, put_team_member, delete_team_member,
+        company_register, get_company, patch_company, get_branding, get_logo,
+        get_offboarding, offboard_company, cancel_offboarding, get_offboarding_report, get_offboarding_export,
+        service_provider_config, list_scim_users, get_scim_user, create_scim_user, replace_scim_user, patch_scim_user, delete_scim_user,
     ),
     tags(
         (name = "Users", description = "User management"),
@@ -69,7 +152,9 @@ impl Modify for SecurityAddon {
         (name = "Coworkings", description = "Coworking related functionality"),
         (name = "Items", description = "Items related functionality"),
         (name = "Bookings", description = "Booking related functionality"),
+        (name = "Teams", description = "Teams, departments and their members"),
         (name = "Companies", description = "Company related functionality"),
+        (name = "SCIM", description = "User provisioning for identity providers, authenticated with the company SCIM token"),
     ),
     info(
         title = "BooQ",
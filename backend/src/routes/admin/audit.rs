@@ -0,0 +1,179 @@
+use axum::body::Body;
+use axum::extract::{Query, State};
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::http::HeaderMap;
+use axum::response::Response;
+use axum::Json;
+use validator::Validate;
+
+use crate::controllers::audit::{audit_log, AuditFilter};
+use crate::forms::admin::{AuditExportQuery, AuditLogQuery, ExportFormat};
+use crate::jwt::generate::claims_from_headers;
+use crate::models::AuditLogModel;
+use crate::{db::Db, errors::ProdError, AppState};
+
+/// Query audit log
+///
+/// Administrative actions done in the company, newest first.
+#[utoipa::path(
+    get,
+    tag = "Admin",
+    path = "/backend_api/admin/audit",
+    params(
+        ("actor_id" = Option<Uuid>, Query, description = "User who did the action"),
+        ("action" = Option<String>, Query, description = "Exact action, e.g. `role.update`"),
+        ("entity" = Option<String>, Query, description = "Entity type, e.g. `role`"),
+        ("entity_id" = Option<Uuid>, Query),
+        ("from" = Option<NaiveDateTime>, Query, description = "Entries made at or after this moment"),
+        ("to" = Option<NaiveDateTime>, Query, description = "Entries made before this moment"),
+        ("limit" = Option<i64>, Query, description = "1-500, defaults to 50"),
+        ("offset" = Option<i64>, Query)
+    ),
+    responses(
+        (status = 200, body = Vec<AuditLogModel>),
+        (status = 400, description = "wrong query"),
+        (status = 403, description = "no auth / no `manage_company` permission"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_audit_log(
+    headers: HeaderMap,
+    Query(query): Query<AuditLogQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<AuditLogModel>>, ProdError> {
+    query.validate()?;
+
+    let mut conn = state.pool.conn().await?;
+    let company_id = claims_from_headers(&headers)?.company_id;
+
+    let entries = audit_log(
+        conn.as_mut(),
+        company_id,
+        AuditFilter {
+            actor_id: query.actor_id,
+            action: query.action,
+            entity: query.entity,
+            entity_id: query.entity_id,
+            from: query.from,
+            to: query.to,
+        },
+        query.limit,
+        query.offset,
+    )
+    .await?;
+
+    Ok(Json(entries))
+}
+
+/// Export audit log
+///
+/// Every entry matching the filter as a CSV or JSON download, `before` and `after` are JSON encoded in CSV.
+#[utoipa::path(
+    get,
+    tag = "Admin",
+    path = "/backend_api/admin/audit/export",
+    params(
+        ("format" = Option<ExportFormat>, Query, description = "`csv` (default) or `json`"),
+        ("actor_id" = Option<Uuid>, Query),
+        ("action" = Option<String>, Query),
+        ("entity" = Option<String>, Query),
+        ("entity_id" = Option<Uuid>, Query),
+        ("from" = Option<NaiveDateTime>, Query),
+        ("to" = Option<NaiveDateTime>, Query)
+    ),
+    responses(
+        (status = 200, description = "audit log file", content_type = "text/csv"),
+        (status = 403, description = "no auth / no `manage_company` permission"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn export_audit_log(
+    headers: HeaderMap,
+    Query(query): Query<AuditExportQuery>,
+    State(state): State<AppState>,
+) -> Result<Response, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let company_id = claims_from_headers(&headers)?.company_id;
+
+    let entries = audit_log(
+        conn.as_mut(),
+        company_id,
+        AuditFilter {
+            actor_id: query.actor_id,
+            action: query.action,
+            entity: query.entity,
+            entity_id: query.entity_id,
+            from: query.from,
+            to: query.to,
+        },
+        Some(i64::MAX),
+        None,
+    )
+    .await?;
+
+    let (content_type, file_name, body) = match query.format {
+        ExportFormat::Csv => ("text/csv", "audit_log.csv", entries_to_csv(&entries)?),
+        ExportFormat::Json => (
+            "application/json",
+            "audit_log.json",
+            serde_json::to_vec(&entries).map_err(|err| ProdError::Unknown(err.into()))?,
+        ),
+    };
+
+    Response::builder()
+        .header(CONTENT_TYPE, content_type)
+        .header(
+            CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{file_name}\""),
+        )
+        .body(Body::from(body))
+        .map_err(|e| ProdError::Unknown(e.into()))
+}
+
+fn entries_to_csv(entries: &[AuditLogModel]) -> Result<Vec<u8>, ProdError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let to_string = |value: Option<String>| value.unwrap_or_default();
+    let json = |value: &Option<serde_json::Value>| {
+        value.as_ref().map(ToString::to_string).unwrap_or_default()
+    };
+
+    writer
+        .write_record([
+            "id",
+            "created_at",
+            "actor_id",
+            "action",
+            "entity",
+            "entity_id",
+            "before",
+            "after",
+            "ip",
+            "request_id",
+        ])
+        .map_err(|err| ProdError::Unknown(err.into()))?;
+
+    for entry in entries {
+        writer
+            .write_record([
+                entry.id.to_string(),
+                entry.created_at.to_string(),
+                to_string(entry.actor_id.map(|id| id.to_string())),
+                entry.action.clone(),
+                entry.entity.clone(),
+                to_string(entry.entity_id.map(|id| id.to_string())),
+                json(&entry.before),
+                json(&entry.after),
+                to_string(entry.ip.clone()),
+                to_string(entry.request_id.clone()),
+            ])
+            .map_err(|err| ProdError::Unknown(err.into()))?;
+    }
+
+    writer
+        .into_inner()
+        .map_err(|err| ProdError::Unknown(err.to_string().into()))
+}
@@ -1,20 +1,119 @@
-use crate::{middlewares::auth_admin, AppState};
-use axum::routing::{get, patch};
+use crate::middlewares::{require_permissions, RequiredPermissions};
+use crate::models::Permission;
+use crate::AppState;
+use axum::middleware::from_fn_with_state;
+use axum::routing::{get, patch, put};
 use axum::{
     routing::{delete, post},
     Router,
 };
 
+pub mod audit;
+pub mod onboarding;
+pub mod roles;
 pub mod routes;
+pub mod scim;
+pub mod service_accounts;
+pub mod sso;
+pub mod trash;
+pub mod verification;
+
 pub fn get_routes(state: AppState) -> Router {
-    Router::new()
-        .route("/documents/{user_id}", get(routes::get_user_document))
-        .route("/list_requests", get(routes::get_verify_requests))
-        .route("/user/{user_id}/verify", post(routes::verify_guest))
+    let users_routes = Router::new()
         .route("/user/{user_id}", get(routes::get_user))
         .route("/user/{user_id}", delete(routes::admin_delete_user))
         .route("/user/{user_id}", patch(routes::patch_user))
         .route("/user/list", get(routes::list_users))
+        .route("/user/import", post(onboarding::import_users))
+        .route("/invites", get(onboarding::list_invites))
+        .route("/invites", post(onboarding::create_invite))
+        .route("/invites/{invite_id}", delete(onboarding::delete_invite))
+        .route(
+            "/service_accounts",
+            get(service_accounts::list_service_accounts),
+        )
+        .route(
+            "/service_accounts",
+            post(service_accounts::create_service_account),
+        )
+        .route(
+            "/service_accounts/{account_id}",
+            delete(service_accounts::delete_service_account),
+        )
+        .route(
+            "/service_accounts/{account_id}/api_keys",
+            post(service_accounts::create_service_account_key),
+        )
+        .route("/api_keys", get(service_accounts::list_company_api_keys))
+        .route(
+            "/api_keys/{key_id}",
+            delete(service_accounts::revoke_company_api_key),
+        )
+        .layer(from_fn_with_state(
+            RequiredPermissions::new(&state, &[Permission::ManageUsers]),
+            require_permissions,
+        ));
+
+    let verification_routes = Router::new()
+        .route("/documents/{user_id}", get(routes::get_user_document))
+        .route("/list_requests", get(routes::get_verify_requests))
+        .route("/user/{user_id}/verify", post(routes::verify_guest))
+        .route(
+            "/verifications/{case_id}",
+            get(verification::get_verification_case),
+        )
+        .route(
+            "/verifications/{case_id}/documents/{version}",
+            get(verification::get_verification_document),
+        )
+        .route(
+            "/verifications/{case_id}/decision",
+            post(verification::decide_verification),
+        )
+        .route(
+            "/verifications/{case_id}/comments",
+            post(verification::comment_verification),
+        )
+        .layer(from_fn_with_state(
+            RequiredPermissions::new(&state, &[Permission::VerifyGuests]),
+            require_permissions,
+        ));
+
+    let roles_routes = Router::new()
+        .route("/roles", get(roles::list_roles))
+        .route("/roles", post(roles::create_role))
+        .route("/roles/{role_id}", patch(roles::patch_role))
+        .route("/roles/{role_id}", delete(roles::delete_role))
+        .route("/user/{user_id}/role", put(roles::assign_role))
+        .layer(from_fn_with_state(
+            RequiredPermissions::new(&state, &[Permission::ManageRoles]),
+            require_permissions,
+        ));
+
+    let company_routes = Router::new()
+        .route("/sso", get(sso::get_sso_provider))
+        .route("/sso", put(sso::put_sso_provider))
+        .route("/sso", delete(sso::delete_sso_provider))
+        .route("/scim/token", post(scim::create_scim_token))
+        .route("/scim/token", delete(scim::delete_scim_token))
+        .route("/audit", get(audit::list_audit_log))
+        .route("/audit/export", get(audit::export_audit_log))
+        .layer(from_fn_with_state(
+            RequiredPermissions::new(&state, &[Permission::ManageCompany]),
+            require_permissions,
+        ));
+
+    // Permission depends on the entity, checked in the handlers.
+    let trash_routes = Router::new().route("/trash", get(trash::list_trash)).route(
+        "/trash/{entity}/{id}/restore",
+        post(trash::restore_from_trash),
+    );
+
+    Router::new()
+        .merge(users_routes)
+        .merge(verification_routes)
+        .merge(roles_routes)
+        .merge(company_routes)
+        .merge(trash_routes)
         .with_state(state)
-        .layer(axum::middleware::from_fn(auth_admin))
 }
@@ -0,0 +1,432 @@
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use sqlx::{Acquire, PgConnection};
+use tracing::warn;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    controllers::{
+        audit::{record, snapshot, AuditEntity, AuditEntry},
+        roles::{claims_permissions, require_grantable_role},
+        users::{register_user_with_role, send_activation},
+    },
+    db::Db,
+    errors::ProdError,
+    forms::{
+        admin::{
+            CreateInviteForm, ImportIssue, ImportReport, ImportUserRow, ImportUsersFormData,
+            ImportUsersQuery, ImportedUser,
+        },
+        users::RegisterForm,
+    },
+    jwt::{generate::claims_from_headers, models::Claims},
+    mail::APP_URL,
+    middlewares::RequestMeta,
+    models::{InviteModel, Permission, RoleModel},
+    oidc::random_token,
+    util::ValidatedJson,
+    AppState,
+};
+
+/// Hours an invite stays valid when no expiry is given.
+const DEFAULT_INVITE_TTL_HOURS: i32 = 24 * 7;
+
+/// Create invite link
+#[utoipa::path(
+    post,
+    tag = "Admin",
+    path = "/backend_api/admin/invites",
+    request_body = CreateInviteForm,
+    responses(
+        (status = 201, body = InviteModel, description = "Created invite"),
+        (status = 400, description = "wrong data format"),
+        (status = 403, description = "no auth / no `manage_users` permission / role with permissions you don't have"),
+        (status = 404, description = "no such role"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_invite(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<CreateInviteForm>,
+) -> Result<(StatusCode, Json<InviteModel>), ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    let company_id = claims.company_id;
+
+    let mut tx = conn.begin().await?;
+
+    // Invited users can't get permissions the inviter doesn't have.
+    require_grantable_role(tx.as_mut(), &claims, form.role_id).await?;
+
+    let invite = sqlx::query_as!(
+        InviteModel,
+        r#"
+        INSERT INTO invites (code, role_id, max_uses, expires_at, company_id)
+        VALUES ($1, $2, $3, NOW() + make_interval(hours => $4), $5)
+        RETURNING id, code, $6 || code as "link!", role_id, max_uses, uses,
+                  expires_at, created_at, company_id
+        "#,
+        random_token(),
+        form.role_id,
+        form.max_uses,
+        form.expires_in_hours.unwrap_or(DEFAULT_INVITE_TTL_HOURS),
+        company_id,
+        format!("{}/invite#code=", *APP_URL),
+    )
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::Invite, invite.id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("invite.create", AuditEntity::Invite, invite.id).after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(invite)))
+}
+
+/// List active invite links
+#[utoipa::path(
+    get,
+    tag = "Admin",
+    path = "/backend_api/admin/invites",
+    responses(
+        (status = 200, body = Vec<InviteModel>, description = "Invites that aren't expired or used up"),
+        (status = 403, description = "no auth / no `manage_users` permission"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_invites(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<InviteModel>>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let company_id = claims_from_headers(&headers)?.company_id;
+
+    let invites = sqlx::query_as!(
+        InviteModel,
+        r#"
+        SELECT id, code, $2 || code as "link!", role_id, max_uses, uses,
+               expires_at, created_at, company_id
+        FROM invites
+        WHERE company_id = $1
+            AND expires_at > NOW()
+            AND (max_uses IS NULL OR uses < max_uses)
+        ORDER BY created_at DESC
+        "#,
+        company_id,
+        format!("{}/invite#code=", *APP_URL),
+    )
+    .fetch_all(conn.as_mut())
+    .await?;
+
+    Ok(Json(invites))
+}
+
+/// Revoke invite link
+#[utoipa::path(
+    delete,
+    tag = "Admin",
+    path = "/backend_api/admin/invites/{invite_id}",
+    params(
+        ("invite_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 204, description = "Invite was revoked"),
+        (status = 403, description = "no auth / no `manage_users` permission"),
+        (status = 404, description = "no such invite"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_invite(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path(invite_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    let company_id = claims.company_id;
+
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::Invite, invite_id).await?;
+
+    let _ = sqlx::query!(
+        r#"
+        DELETE FROM invites
+        WHERE id = $1 AND company_id = $2
+        RETURNING id
+        "#,
+        invite_id,
+        company_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such invite".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("invite.delete", AuditEntity::Invite, invite_id).before(before),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Bulk create users from CSV
+///
+/// Every created user gets an e-mail with a link to set their password.
+/// Rows with e-mails that are already taken, in the company or earlier in the file, are reported as duplicates.
+#[utoipa::path(
+    post,
+    tag = "Admin",
+    path = "/backend_api/admin/user/import",
+    request_body(content = ImportUsersFormData, content_type = "multipart/form-data"),
+    params(
+        ("dry_run" = Option<bool>, Query, description = "Only validate the file")
+    ),
+    responses(
+        (status = 200, body = ImportReport, description = "Import report"),
+        (status = 400, description = "no file / not a CSV"),
+        (status = 403, description = "no auth / no `manage_users` permission"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn import_users(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Query(query): Query<ImportUsersQuery>,
+    State(state): State<AppState>,
+    mut multipart: Multipart,
+) -> Result<Json<ImportReport>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    let company_id = claims.company_id;
+
+    let mut file = None;
+    while let Some(field) = multipart.next_field().await.unwrap_or(None) {
+        if field.name() == Some("file") {
+            file = Some(
+                field
+                    .bytes()
+                    .await
+                    .map_err(|err| ProdError::ShitHappened(err.to_string()))?,
+            );
+        }
+    }
+    let Some(file) = file else {
+        return Err(ProdError::ShitHappened("No `file` field".to_string()));
+    };
+
+    let company = sqlx::query!(
+        r#"SELECT name, domain FROM companies WHERE id = $1"#,
+        company_id
+    )
+    .fetch_one(conn.as_mut())
+    .await?;
+
+    let mut report = ImportReport {
+        dry_run: query.dry_run,
+        created: vec![],
+        duplicates: vec![],
+        invalid: vec![],
+    };
+    let valid =
+        check_import_rows(conn.as_mut(), &claims, &company.domain, &file, &mut report).await?;
+
+    // Either all rows are created or none, a duplicate only rolls back its own savepoint.
+    let mut tx = conn.begin().await?;
+    let mut activations = vec![];
+    for (row, form, role_id) in valid {
+        let email = form.email.clone();
+
+        let user_id = if query.dry_run {
+            None
+        } else {
+            let mut savepoint = tx.begin().await?;
+            match register_user_with_role(savepoint.as_mut(), form, company_id, role_id).await {
+                Ok(user) => {
+                    savepoint.commit().await?;
+                    Some(user.id)
+                }
+                Err(ProdError::Conflict(error)) => {
+                    report.duplicates.push(ImportIssue {
+                        row,
+                        email: Some(email),
+                        error,
+                    });
+                    continue;
+                }
+                Err(err) => return Err(err),
+            }
+        };
+
+        if let Some(user_id) = user_id {
+            let after = snapshot(tx.as_mut(), AuditEntity::User, user_id).await?;
+            record(
+                tx.as_mut(),
+                &meta,
+                &claims,
+                AuditEntry::new("user.import", AuditEntity::User, user_id).after(after),
+            )
+            .await?;
+            activations.push((user_id, email.clone()));
+        }
+
+        report.created.push(ImportedUser {
+            row,
+            email,
+            role_id,
+            user_id,
+        });
+    }
+    tx.commit().await?;
+
+    for (user_id, email) in activations {
+        // A failed mail shouldn't fail the whole import, the accounts are already created.
+        if let Err(err) = send_activation(conn.as_mut(), user_id, &email, &company.name).await {
+            warn!("Failed to send activation to {email}: {err}");
+        }
+    }
+
+    Ok(Json(report))
+}
+
+/// Sorts CSV rows into the report's invalid and duplicate ones, returning the rows to create.
+async fn check_import_rows(
+    conn: &mut PgConnection,
+    claims: &Claims,
+    company_domain: &str,
+    file: &[u8],
+    report: &mut ImportReport,
+) -> Result<Vec<(usize, RegisterForm, Uuid)>, ProdError> {
+    let company_id = claims.company_id;
+    let granted = claims_permissions(&mut *conn, claims).await?;
+
+    let roles = sqlx::query!(
+        r#"
+        SELECT id, name, base_role as "base_role: RoleModel",
+               permissions as "permissions: Vec<Permission>"
+        FROM roles
+        WHERE company_id = $1
+        "#,
+        company_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let guest_role_id = roles
+        .iter()
+        .find(|role| role.base_role == Some(RoleModel::Guest))
+        .map(|role| role.id)
+        .ok_or_else(|| ProdError::NotFound("No guest role".to_string()))?;
+    let roles_by_name: HashMap<String, (Uuid, Vec<Permission>)> = roles
+        .into_iter()
+        .map(|role| (role.name.to_lowercase(), (role.id, role.permissions)))
+        .collect();
+
+    let mut taken: HashSet<String> = sqlx::query!(
+        r#"SELECT email FROM users WHERE company_id = $1"#,
+        company_id
+    )
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|record| record.email.to_lowercase())
+    .collect();
+
+    let mut valid = vec![];
+
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(file);
+
+    for (index, row) in reader.deserialize::<ImportUserRow>().enumerate() {
+        let row_number = index + 2;
+
+        let row = match row {
+            Ok(row) => row,
+            Err(err) => {
+                report.invalid.push(ImportIssue {
+                    row: row_number,
+                    email: None,
+                    error: err.to_string(),
+                });
+                continue;
+            }
+        };
+
+        let form = RegisterForm {
+            name: row.name,
+            surname: row.surname,
+            email: row.email,
+            password: random_token(),
+            company_domain: company_domain.to_string(),
+        };
+
+        let role_id = row
+            .role
+            .filter(|role| !role.is_empty())
+            .map_or(Ok(guest_role_id), |role| {
+                match roles_by_name.get(&role.to_lowercase()) {
+                    None => Err(format!("Unknown role `{role}`")),
+                    Some((_, permissions)) if !permissions.iter().all(|p| granted.contains(p)) => {
+                        Err(format!("Role `{role}` has permissions you don't have"))
+                    }
+                    Some((id, _)) => Ok(*id),
+                }
+            });
+
+        let error = match (form.validate(), &role_id) {
+            (Err(err), _) => Some(err.to_string()),
+            (Ok(()), Err(err)) => Some(err.clone()),
+            (Ok(()), Ok(_)) => None,
+        };
+
+        if let Some(error) = error {
+            report.invalid.push(ImportIssue {
+                row: row_number,
+                email: Some(form.email),
+                error,
+            });
+        } else if !taken.insert(form.email.to_lowercase()) {
+            report.duplicates.push(ImportIssue {
+                row: row_number,
+                email: Some(form.email),
+                error: "User with that e-mail already exists".to_string(),
+            });
+        } else if let Ok(role_id) = role_id {
+            valid.push((row_number, form, role_id));
+        }
+    }
+
+    Ok(valid)
+}
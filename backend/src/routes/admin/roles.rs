@@ -0,0 +1,403 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use sqlx::Acquire;
+use uuid::Uuid;
+
+use crate::{
+    controllers::{
+        audit::{record, snapshot, AuditEntity, AuditEntry},
+        roles::{require_grantable, require_grantable_role, resolve_role},
+    },
+    db::Db,
+    errors::ProdError,
+    forms::admin::{AssignRoleForm, CreateRoleForm, PatchRoleForm},
+    forms::users::PublicUserData,
+    jwt::{generate::claims_from_headers, models::Claims},
+    middlewares::RequestMeta,
+    models::{CompanyRoleModel, Permission, RoleModel},
+    util::ValidatedJson,
+    AppState,
+};
+
+/// List company roles
+#[utoipa::path(
+    get,
+    tag = "Admin",
+    path = "/backend_api/admin/roles",
+    responses(
+        (status = 200, body = Vec<CompanyRoleModel>, description = "Built-in and custom roles"),
+        (status = 403, description = "no auth / no `manage_roles` permission"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_roles(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<CompanyRoleModel>>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let company_id = claims_from_headers(&headers)?.company_id;
+
+    let roles = sqlx::query_as!(
+        CompanyRoleModel,
+        r#"
+        SELECT id, name, permissions as "permissions: Vec<Permission>",
+               base_role as "base_role: RoleModel", company_id
+        FROM roles
+        WHERE company_id = $1
+        ORDER BY base_role IS NULL, name
+        "#,
+        company_id
+    )
+    .fetch_all(conn.as_mut())
+    .await?;
+
+    Ok(Json(roles))
+}
+
+/// Create custom role
+#[utoipa::path(
+    post,
+    tag = "Admin",
+    path = "/backend_api/admin/roles",
+    request_body = CreateRoleForm,
+    responses(
+        (status = 201, body = CompanyRoleModel, description = "Created role"),
+        (status = 400, description = "wrong data format"),
+        (status = 403, description = "no auth / no `manage_roles` permission / permissions you don't have"),
+        (status = 409, description = "role with that name already exists"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_role(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<CreateRoleForm>,
+) -> Result<(StatusCode, Json<CompanyRoleModel>), ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    let company_id = claims.company_id;
+
+    require_grantable(conn.as_mut(), &claims, &form.permissions).await?;
+
+    let mut tx = conn.begin().await?;
+
+    let role = sqlx::query_as!(
+        CompanyRoleModel,
+        r#"
+        INSERT INTO roles (name, permissions, company_id)
+        VALUES ($1, $2, $3)
+        RETURNING id, name, permissions as "permissions: Vec<Permission>",
+                  base_role as "base_role: RoleModel", company_id
+        "#,
+        form.name,
+        form.permissions as Vec<Permission>,
+        company_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::Database(e) if e.is_unique_violation() => {
+            ProdError::Conflict("Role with that name already exists".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::Role, role.id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("role.create", AuditEntity::Role, role.id).after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(role)))
+}
+
+/// Update role name or permissions
+#[utoipa::path(
+    patch,
+    tag = "Admin",
+    path = "/backend_api/admin/roles/{role_id}",
+    request_body = PatchRoleForm,
+    params(
+        ("role_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = CompanyRoleModel, description = "Updated role"),
+        (status = 400, description = "wrong data format"),
+        (status = 403, description = "no auth / no `manage_roles` permission / built-in admin role / permissions you don't have"),
+        (status = 404, description = "no such role"),
+        (status = 409, description = "role with that name already exists"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn patch_role(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path(role_id): Path<Uuid>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<PatchRoleForm>,
+) -> Result<Json<CompanyRoleModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    let company_id = claims.company_id;
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::Role, role_id).await?;
+
+    let base_role = sqlx::query!(
+        r#"
+        SELECT base_role as "base_role: RoleModel"
+        FROM roles
+        WHERE id = $1 AND company_id = $2
+        "#,
+        role_id,
+        company_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map(|record| record.base_role)
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such role".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    // Keeps at least one role able to manage everything, so a company can't lock itself out.
+    if base_role == Some(RoleModel::Admin) && form.permissions.is_some() {
+        return Err(ProdError::Forbidden(
+            "Permissions of the built-in admin role can't be changed".to_string(),
+        ));
+    }
+
+    // Neither a role above the actor nor the permissions it's given can exceed their own.
+    require_grantable_role(tx.as_mut(), &claims, role_id).await?;
+    if let Some(permissions) = &form.permissions {
+        require_grantable(tx.as_mut(), &claims, permissions).await?;
+    }
+
+    let role = sqlx::query_as!(
+        CompanyRoleModel,
+        r#"
+        UPDATE roles
+        SET
+            name = COALESCE($3, name),
+            permissions = COALESCE($4, permissions)
+        WHERE id = $1 AND company_id = $2
+        RETURNING id, name, permissions as "permissions: Vec<Permission>",
+                  base_role as "base_role: RoleModel", company_id
+        "#,
+        role_id,
+        company_id,
+        form.name,
+        form.permissions as Option<Vec<Permission>>,
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::Database(e) if e.is_unique_violation() => {
+            ProdError::Conflict("Role with that name already exists".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::Role, role_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("role.update", AuditEntity::Role, role_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(role))
+}
+
+/// Delete custom role, its users fall back to their built-in role
+#[utoipa::path(
+    delete,
+    tag = "Admin",
+    path = "/backend_api/admin/roles/{role_id}",
+    params(
+        ("role_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 204, description = "Role was deleted"),
+        (status = 403, description = "no auth / no `manage_roles` permission / built-in role"),
+        (status = 404, description = "no such role"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_role(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path(role_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    let company_id = claims.company_id;
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::Role, role_id).await?;
+
+    let base_role = sqlx::query!(
+        r#"
+        SELECT base_role as "base_role: RoleModel"
+        FROM roles
+        WHERE id = $1 AND company_id = $2
+        FOR UPDATE
+        "#,
+        role_id,
+        company_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map(|record| record.base_role)
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such role".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    if base_role.is_some() {
+        return Err(ProdError::Forbidden(
+            "Built-in roles can't be deleted".to_string(),
+        ));
+    }
+
+    let _ = sqlx::query!(
+        r#"DELETE FROM roles WHERE id = $1 AND company_id = $2"#,
+        role_id,
+        company_id
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("role.delete", AuditEntity::Role, role_id).before(before),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Assign role to user
+#[utoipa::path(
+    put,
+    tag = "Admin",
+    path = "/backend_api/admin/user/{user_id}/role",
+    request_body = AssignRoleForm,
+    params(
+        ("user_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = PublicUserData, description = "User with the new role"),
+        (status = 403, description = "no auth / no `manage_roles` permission / own role / role or user with permissions you don't have"),
+        (status = 404, description = "no such user or role"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn assign_role(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path(user_id): Path<Uuid>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<AssignRoleForm>,
+) -> Result<Json<PublicUserData>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    let Claims {
+        company_id,
+        user_id: actor_id,
+        ..
+    } = claims;
+
+    if actor_id == user_id {
+        return Err(ProdError::Forbidden(
+            "You can't change your own role".to_string(),
+        ));
+    }
+
+    let mut tx = conn.begin().await?;
+
+    // Nobody can promote anybody above themselves or demote somebody who is.
+    require_grantable_role(tx.as_mut(), &claims, form.role_id).await?;
+    let current = sqlx::query_scalar!(
+        r#"
+        SELECT r.permissions as "permissions: Vec<Permission>"
+        FROM users u
+        JOIN roles r ON r.company_id = u.company_id
+            AND (r.id = u.role_id OR (u.role_id IS NULL AND r.base_role = u.role))
+        WHERE u.id = $1 AND u.company_id = $2
+        "#,
+        user_id,
+        company_id
+    )
+    .fetch_optional(tx.as_mut())
+    .await?
+    .unwrap_or_default();
+    require_grantable(tx.as_mut(), &claims, &current).await?;
+
+    let (role, role_id) = resolve_role(tx.as_mut(), company_id, form.role_id).await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::User, user_id).await?;
+
+    let user = sqlx::query_as!(
+        PublicUserData,
+        r#"
+        UPDATE users
+        SET
+            role = COALESCE($3, role),
+            role_id = $4
+        WHERE id = $1 AND company_id = $2 AND deleted_at IS NULL
+        RETURNING id, name, surname, email, avatar, role as "role: RoleModel"
+        "#,
+        user_id,
+        company_id,
+        role as Option<RoleModel>,
+        role_id,
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such user".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::User, user_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("user.assign_role", AuditEntity::User, user_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(user))
+}
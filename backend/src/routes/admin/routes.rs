@@ -4,17 +4,24 @@ use axum::extract::Multipart;
 use axum::http::header::CONTENT_TYPE;
 use axum::http::HeaderMap;
 use axum::response::Response;
-use axum::{extract::Path, extract::State, Json};
+use axum::{extract::Path, extract::Query, extract::State, Extension, Json};
 use sqlx::Acquire;
 use uuid::Uuid;
+use validator::Validate;
 
+use crate::controllers::audit::{record, snapshot, AuditEntity, AuditEntry};
 use crate::controllers::users::update_user;
-use crate::forms::admin::VerificationRequest;
+use crate::controllers::verification::{
+    decide_case, notify_decision, verification_cases, CaseFilter,
+};
+use crate::extractors::CompanyUser;
+use crate::forms::admin::{VerificationDecisionForm, VerificationQueueQuery, VerificationRequest};
 use crate::forms::users::{PatchProfileFormData, PublicUserData};
 use crate::jwt::generate::claims_from_headers;
-use crate::models::UserModel;
+use crate::middlewares::RequestMeta;
+use crate::models::{UserModel, VerificationStatus};
 use crate::s3::utils::get_file;
-use crate::{db::Db, errors::ProdError, AppState, BASE_URL};
+use crate::{db::Db, errors::ProdError, AppState};
 
 /// Get user by id
 #[utoipa::path(
@@ -23,7 +30,7 @@ use crate::{db::Db, errors::ProdError, AppState, BASE_URL};
     path = "/backend_api/admin/user/{user_id}",
     responses(
         (status = 200, body = PublicUserData),
-        (status = 403, description = "not admin / no auth"),
+        (status = 403, description = "no auth / no `manage_users` permission"),
     ),
     security(
         ("bearerAuth" = [])
@@ -42,7 +49,7 @@ pub async fn get_user(
         SELECT
         id, name, surname, email, avatar, role as "role: RoleModel"
         FROM users
-        WHERE company_id = $1 AND id = $2
+        WHERE company_id = $1 AND id = $2 AND deleted_at IS NULL
         "#,
         company_id,
         user_id
@@ -58,14 +65,17 @@ pub async fn get_user(
 }
 
 /// Verify guest user
+///
+/// Approves the guest's verification case, same as deciding it `approved`.
 #[utoipa::path(
     post,
     tag = "Admin",
     path = "/backend_api/admin/user/{user_id}/verify",
     responses(
         (status = 200),
-        (status = 403, description = "not admin / no auth"),
-        (status = 404, description = "guest not found"),
+        (status = 403, description = "no auth / no `verify_guests` permission"),
+        (status = 404, description = "guest has no verification case"),
+        (status = 409, description = "case is already approved"),
     ),
     security(
         ("bearerAuth" = [])
@@ -73,70 +83,78 @@ pub async fn get_user(
 )]
 pub async fn verify_guest(
     headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
     Path(user_id): Path<Uuid>,
     State(state): State<AppState>,
 ) -> Result<(), ProdError> {
     let mut conn = state.pool.conn().await?;
     let mut tx = conn.begin().await?;
-    let company_id = claims_from_headers(&headers)?.company_id;
+    let claims = claims_from_headers(&headers)?;
 
-    let _ = sqlx::query!(
+    let case_id = sqlx::query!(
         r#"
-        SELECT user_id FROM pending_verifications WHERE
-        user_id = $1 AND company_id = $2
+        SELECT id FROM verification_cases
+        WHERE user_id = $1 AND company_id = $2
         "#,
         user_id,
-        company_id
+        claims.company_id
     )
     .fetch_one(tx.as_mut())
     .await
+    .map(|record| record.id)
     .map_err(|err| match err {
         sqlx::Error::RowNotFound => {
             ProdError::NotFound("No active request from that user".to_string())
         }
         _ => ProdError::DatabaseError(err),
     })?;
+    let before = snapshot(tx.as_mut(), AuditEntity::VerificationCase, case_id).await?;
 
-    let _ = sqlx::query!(
-        r#"
-        UPDATE users
-        SET role = 'verified_guest'
-        WHERE id = $1 AND role = 'guest' AND company_id = $2
-        "#,
-        user_id,
-        company_id
+    let form = VerificationDecisionForm {
+        status: VerificationStatus::Approved,
+        comment: None,
+    };
+    let email = decide_case(
+        tx.as_mut(),
+        claims.company_id,
+        case_id,
+        claims.user_id,
+        &form,
     )
-    .execute(tx.as_mut())
-    .await
-    .map_err(|err| match err {
-        sqlx::Error::RowNotFound => ProdError::NotFound(err.to_string()),
-        _ => ProdError::DatabaseError(err),
-    })?;
+    .await?;
 
-    let _ = sqlx::query!(
-        r#"
-        DELETE FROM pending_verifications
-        WHERE user_id = $1 AND company_id = $2
-        "#,
-        user_id,
-        company_id,
+    let after = snapshot(tx.as_mut(), AuditEntity::VerificationCase, case_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new(
+            "verification_case.decide",
+            AuditEntity::VerificationCase,
+            case_id,
+        )
+        .before(before)
+        .after(after),
     )
-    .execute(tx.as_mut())
     .await?;
 
     tx.commit().await?;
 
+    notify_decision(&email, &form).await;
+
     Ok(())
 }
 
 /// Delete user
+///
+/// Moves the user to the trash, they can't log in and can be restored until the retention period ends.
 #[utoipa::path(
     delete,
     tag = "Admin",
     path = "/backend_api/admin/user/{user_id}",
     responses(
         (status = 200),
-        (status = 403, description = "not admin / no auth"),
+        (status = 403, description = "no auth / no `manage_users` permission"),
         (status = 404, description = "user not found"),
     ),
     security(
@@ -144,24 +162,37 @@ pub async fn verify_guest(
     )
 )]
 pub async fn admin_delete_user(
-    Path(user_id): Path<Uuid>,
+    Extension(meta): Extension<RequestMeta>,
     State(state): State<AppState>,
+    CompanyUser { user_id, claims }: CompanyUser,
 ) -> Result<(), ProdError> {
     let mut conn = state.pool.conn().await?;
 
-    let _ = sqlx::query!(
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::User, user_id).await?;
+
+    sqlx::query!(
         r#"
-        DELETE FROM users
+        UPDATE users SET deleted_at = NOW()
         WHERE id = $1
         "#,
         user_id
     )
-    .execute(conn.as_mut())
-    .await
-    .map_err(|err| match err {
-        sqlx::Error::RowNotFound => ProdError::NotFound(err.to_string()),
-        _ => ProdError::DatabaseError(err),
-    })?;
+    .execute(tx.as_mut())
+    .await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::User, user_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("user.delete", AuditEntity::User, user_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
 
     Ok(())
 }
@@ -175,13 +206,13 @@ pub async fn admin_delete_user(
     responses(
         (status = 200, body = UserModel),
         (status = 400, description = "wrong data format"),
-        (status = 403, description = "not admin / no auth"),
+        (status = 403, description = "no auth / no `manage_users` permission"),
     ),
     request_body(content = PatchProfileFormData, content_type = "multipart/form-data"),
     responses(
         (status = 200, description = "Profile updated", body = UserModel),
         (status = 400, description = "Wrong body", body = String),
-        (status = 403, description = "not admin / no auth"),
+        (status = 403, description = "no auth / no `manage_users` permission"),
         (status = 404, description = "user not found"),
     ),
     security(
@@ -189,11 +220,28 @@ pub async fn admin_delete_user(
     )
 )]
 pub async fn patch_user(
-    Path(user_id): Path<Uuid>,
+    Extension(meta): Extension<RequestMeta>,
     State(state): State<AppState>,
+    CompanyUser { user_id, claims }: CompanyUser,
     multipart: Multipart,
 ) -> Result<Json<UserModel>, ProdError> {
-    let updated_user = update_user(user_id, multipart, state).await?;
+    let mut conn = state.pool.conn().await?;
+
+    let before = snapshot(conn.as_mut(), AuditEntity::User, user_id).await?;
+
+    let updated_user = update_user(user_id, multipart, state, false).await?;
+
+    let after = snapshot(conn.as_mut(), AuditEntity::User, user_id).await?;
+    record(
+        conn.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("user.update", AuditEntity::User, user_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
     Ok(Json(updated_user))
 }
 
@@ -204,7 +252,7 @@ pub async fn patch_user(
     path = "/backend_api/admin/user/list",
     responses(
         (status = 200, body = Vec<PublicUserData>),
-        (status = 403, description = "not admin / no auth"),
+        (status = 403, description = "no auth / no `manage_users` permission"),
     ),
     security(
         ("bearerAuth" = [])
@@ -222,7 +270,7 @@ pub async fn list_users(
         SELECT
         id, name, surname, email, avatar, role as "role: RoleModel"
         FROM users
-        WHERE company_id = $1
+        WHERE company_id = $1 AND NOT service_account AND deleted_at IS NULL
         "#,
         company_id
     )
@@ -232,14 +280,23 @@ pub async fn list_users(
     Ok(Json(users))
 }
 
-/// List active verification requests
+/// List verification requests
+///
+/// Queue of verification cases, least recently updated first.
 #[utoipa::path(
     get,
     tag = "Admin",
     path = "/backend_api/admin/list_requests",
+    params(
+        ("status" = Option<VerificationStatus>, Query, description = "All statuses when not set"),
+        ("search" = Option<String>, Query, description = "Part of the guest's name, surname or e-mail"),
+        ("limit" = Option<i64>, Query, description = "1-100, defaults to 50"),
+        ("offset" = Option<i64>, Query)
+    ),
     responses(
         (status = 200, body = Vec<VerificationRequest>),
-        (status = 403, description = "not admin / no auth"),
+        (status = 400, description = "wrong query"),
+        (status = 403, description = "no auth / no `verify_guests` permission"),
     ),
     security(
         ("bearerAuth" = [])
@@ -247,44 +304,41 @@ pub async fn list_users(
 )]
 pub async fn get_verify_requests(
     headers: HeaderMap,
+    Query(query): Query<VerificationQueueQuery>,
     State(state): State<AppState>,
 ) -> Result<Json<Vec<VerificationRequest>>, ProdError> {
+    query.validate()?;
+
     let mut conn = state.pool.conn().await?;
     let company_id = claims_from_headers(&headers)?.company_id;
-    let users = sqlx::query_as!(
-        PublicUserData,
-        r#"
-        SELECT
-        id, name, surname, email, avatar, role as "role: RoleModel"
-        FROM users u
-        JOIN pending_verifications pv ON pv.user_id = u.id
-        WHERE u.company_id = $1
-        "#,
-        company_id
+
+    let requests = verification_cases(
+        conn.as_mut(),
+        company_id,
+        CaseFilter {
+            status: query.status,
+            search: query.search,
+            limit: query.limit,
+            offset: query.offset,
+            ..CaseFilter::default()
+        },
     )
-    .fetch_all(conn.as_mut())
     .await?;
 
-    let mut requests: Vec<VerificationRequest> = Vec::new();
-    for user in users {
-        requests.push(VerificationRequest {
-            document: format!("{BASE_URL}/admin/documents/{}", user.id),
-            user,
-        });
-    }
-
     Ok(Json(requests))
 }
 
 /// Get user document
+///
+/// Latest document of the user's verification case.
 #[utoipa::path(
     get,
     tag = "Admin",
     path = "/backend_api/admin/documents/{user_id}",
     responses(
         (status = 200, description = "user document", content_type = "application/pdf"),
-        (status = 403, description = "not admin / no auth"),
-        (status = 404, description = "no such user")
+        (status = 403, description = "no auth / no `verify_guests` permission"),
+        (status = 404, description = "no such user / no documents")
     ),
     security(
         ("bearerAuth" = [])
@@ -297,26 +351,26 @@ pub async fn get_user_document(
 ) -> Result<Response, ProdError> {
     let mut conn = state.pool.conn().await?;
     let company_id = claims_from_headers(&headers)?.company_id;
-    let _ = sqlx::query_as!(
-        PublicUserData,
+    let file_name = sqlx::query!(
         r#"
-        SELECT
-        id, name, surname, email, avatar, role as "role: RoleModel"
-        FROM users
-        WHERE company_id = $1 AND id = $2
+        SELECT d.s3_key
+        FROM verification_documents d
+        JOIN verification_cases c ON c.id = d.case_id
+        WHERE c.company_id = $1 AND c.user_id = $2
+        ORDER BY d.version DESC
+        LIMIT 1
         "#,
         company_id,
         user_id
     )
     .fetch_one(conn.as_mut())
     .await
+    .map(|record| record.s3_key)
     .map_err(|err| match err {
-        sqlx::Error::RowNotFound => ProdError::NotFound("No such user exists".to_string()),
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such user document".to_string()),
         _ => ProdError::DatabaseError(err),
     })?;
 
-    let file_name = format!("users/{user_id}/document.pdf");
-
     let (stream, content_type) = get_file(&state, &file_name).await?;
     let response = Response::builder()
         .header(CONTENT_TYPE, content_type)
@@ -0,0 +1,145 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use sqlx::Acquire;
+
+use crate::{
+    controllers::audit::{record, snapshot, AuditEntity, AuditEntry},
+    db::Db,
+    errors::ProdError,
+    forms::scim::{ScimTokenForm, ScimTokenResponse},
+    jwt::{generate::claims_from_headers, hashing::hash_token},
+    middlewares::RequestMeta,
+    models::RoleModel,
+    oidc::random_token,
+    util::ValidatedJson,
+    AppState, BASE_URL,
+};
+
+/// Generate company SCIM token
+///
+/// Replaces the previous token, identity providers using it stop working.
+#[utoipa::path(
+    post,
+    tag = "Admin",
+    path = "/backend_api/admin/scim/token",
+    request_body = ScimTokenForm,
+    responses(
+        (status = 201, body = ScimTokenResponse, description = "New SCIM token"),
+        (status = 403, description = "no auth / no `manage_company` permission"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_scim_token(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<ScimTokenForm>,
+) -> Result<(StatusCode, Json<ScimTokenResponse>), ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    let company_id = claims.company_id;
+
+    let token = random_token();
+
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::ScimToken, company_id).await?;
+
+    let default_role = sqlx::query!(
+        r#"
+        INSERT INTO scim_tokens (company_id, token_hash, default_role)
+        VALUES ($1, $2, COALESCE($3, 'student'::ROLE))
+        ON CONFLICT (company_id) DO UPDATE SET
+            token_hash = EXCLUDED.token_hash,
+            default_role = EXCLUDED.default_role,
+            created_at = NOW()
+        RETURNING default_role as "default_role: RoleModel"
+        "#,
+        company_id,
+        hash_token(&token),
+        form.default_role as Option<RoleModel>,
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map(|record| record.default_role)?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::ScimToken, company_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("scim_token.create", AuditEntity::ScimToken, company_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ScimTokenResponse {
+            token,
+            base_url: format!("{BASE_URL}/scim/v2"),
+            default_role,
+        }),
+    ))
+}
+
+/// Revoke company SCIM token
+#[utoipa::path(
+    delete,
+    tag = "Admin",
+    path = "/backend_api/admin/scim/token",
+    responses(
+        (status = 204, description = "SCIM is disabled for the company"),
+        (status = 403, description = "no auth / no `manage_company` permission"),
+        (status = 404, description = "SCIM is not configured"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_scim_token(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    let company_id = claims.company_id;
+
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::ScimToken, company_id).await?;
+
+    let _ = sqlx::query!(
+        r#"
+        DELETE FROM scim_tokens
+        WHERE company_id = $1
+        RETURNING company_id
+        "#,
+        company_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("SCIM is not configured".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("scim_token.delete", AuditEntity::ScimToken, company_id).before(before),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,367 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use sqlx::Acquire;
+use uuid::Uuid;
+
+use crate::{
+    controllers::{
+        api_keys::{issue_api_key, reject_api_key},
+        audit::{record, snapshot, AuditEntity, AuditEntry},
+        roles::{require_grantable, require_grantable_role, resolve_role},
+    },
+    db::Db,
+    errors::ProdError,
+    forms::{
+        admin::CreateServiceAccountForm,
+        api_keys::{CreateApiKeyForm, CreatedApiKey},
+    },
+    jwt::{generate::claims_from_headers, hashing::Argon},
+    middlewares::RequestMeta,
+    models::{ApiKeyModel, Permission, RoleModel, ServiceAccountModel},
+    oidc::random_token,
+    util::ValidatedJson,
+    AppState,
+};
+
+/// Create service account
+///
+/// Service accounts can't log in, they act through API keys created for them.
+#[utoipa::path(
+    post,
+    tag = "Admin",
+    path = "/backend_api/admin/service_accounts",
+    request_body = CreateServiceAccountForm,
+    responses(
+        (status = 201, body = ServiceAccountModel, description = "Created service account"),
+        (status = 400, description = "wrong data format"),
+        (status = 403, description = "no auth / no `manage_users` permission / authenticated with an API key / role with permissions you don't have"),
+        (status = 404, description = "no such role"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_service_account(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<CreateServiceAccountForm>,
+) -> Result<(StatusCode, Json<ServiceAccountModel>), ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    reject_api_key(&claims)?;
+
+    let mut tx = conn.begin().await?;
+
+    require_grantable_role(tx.as_mut(), &claims, form.role_id).await?;
+    let (role, role_id) = resolve_role(tx.as_mut(), claims.company_id, form.role_id).await?;
+
+    // The e-mail only has to be unique, nothing is ever sent to it.
+    let account = sqlx::query_as!(
+        ServiceAccountModel,
+        r#"
+        INSERT INTO users (
+            name, surname, email, role, role_id,
+            password, company_id, service_account
+        )
+        SELECT $1, '', 'service-' || uuidv7() || '@' || c.domain, $2, $3, $4, c.id, TRUE
+        FROM companies c
+        WHERE c.id = $5
+        RETURNING id, name, role as "role: RoleModel", role_id
+        "#,
+        form.name,
+        role.unwrap_or(RoleModel::Guest) as RoleModel,
+        role_id,
+        Argon::hash_password(random_token().as_bytes())?,
+        claims.company_id
+    )
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::ServiceAccount, account.id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new(
+            "service_account.create",
+            AuditEntity::ServiceAccount,
+            account.id,
+        )
+        .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(account)))
+}
+
+/// List service accounts
+#[utoipa::path(
+    get,
+    tag = "Admin",
+    path = "/backend_api/admin/service_accounts",
+    responses(
+        (status = 200, body = Vec<ServiceAccountModel>),
+        (status = 403, description = "no auth / no `manage_users` permission"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_service_accounts(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ServiceAccountModel>>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let company_id = claims_from_headers(&headers)?.company_id;
+
+    let accounts = sqlx::query_as!(
+        ServiceAccountModel,
+        r#"
+        SELECT id, name, role as "role: RoleModel", role_id
+        FROM users
+        WHERE company_id = $1 AND service_account
+        ORDER BY id
+        "#,
+        company_id
+    )
+    .fetch_all(conn.as_mut())
+    .await?;
+
+    Ok(Json(accounts))
+}
+
+/// Delete service account
+///
+/// Revokes all of its API keys.
+#[utoipa::path(
+    delete,
+    tag = "Admin",
+    path = "/backend_api/admin/service_accounts/{account_id}",
+    params(
+        ("account_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 204, description = "Service account was deleted"),
+        (status = 403, description = "no auth / no `manage_users` permission / authenticated with an API key"),
+        (status = 404, description = "no such service account"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_service_account(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path(account_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    reject_api_key(&claims)?;
+
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::ServiceAccount, account_id).await?;
+
+    let _ = sqlx::query!(
+        r#"
+        DELETE FROM users
+        WHERE id = $1 AND company_id = $2 AND service_account
+        RETURNING id
+        "#,
+        account_id,
+        claims.company_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such service account".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new(
+            "service_account.delete",
+            AuditEntity::ServiceAccount,
+            account_id,
+        )
+        .before(before),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Create service account API key
+#[utoipa::path(
+    post,
+    tag = "Admin",
+    path = "/backend_api/admin/service_accounts/{account_id}/api_keys",
+    request_body = CreateApiKeyForm,
+    params(
+        ("account_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 201, body = CreatedApiKey, description = "Created key with its secret"),
+        (status = 400, description = "wrong data format"),
+        (status = 403, description = "no auth / no `manage_users` permission / authenticated with an API key / permission the account's role or you don't have"),
+        (status = 404, description = "no such service account"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_service_account_key(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path(account_id): Path<Uuid>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<CreateApiKeyForm>,
+) -> Result<(StatusCode, Json<CreatedApiKey>), ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    reject_api_key(&claims)?;
+
+    let mut tx = conn.begin().await?;
+
+    let _ = sqlx::query!(
+        r#"
+        SELECT id FROM users
+        WHERE id = $1 AND company_id = $2 AND service_account
+        FOR UPDATE
+        "#,
+        account_id,
+        claims.company_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such service account".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    // The account's role may have more than the admin creating keys for it.
+    require_grantable(tx.as_mut(), &claims, &form.permissions).await?;
+    let api_key = issue_api_key(tx.as_mut(), account_id, claims.company_id, form).await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::ApiKey, api_key.api_key.id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("api_key.create", AuditEntity::ApiKey, api_key.api_key.id).after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(api_key)))
+}
+
+/// List company API keys
+///
+/// Personal keys of all users and keys of service accounts.
+#[utoipa::path(
+    get,
+    tag = "Admin",
+    path = "/backend_api/admin/api_keys",
+    responses(
+        (status = 200, body = Vec<ApiKeyModel>),
+        (status = 403, description = "no auth / no `manage_users` permission"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_company_api_keys(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ApiKeyModel>>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let company_id = claims_from_headers(&headers)?.company_id;
+
+    let api_keys = sqlx::query_as!(
+        ApiKeyModel,
+        r#"
+        SELECT id, name, prefix, permissions as "permissions: Vec<Permission>",
+               expires_at, last_used_at, created_at, user_id, company_id
+        FROM api_keys
+        WHERE company_id = $1
+        ORDER BY created_at DESC
+        "#,
+        company_id
+    )
+    .fetch_all(conn.as_mut())
+    .await?;
+
+    Ok(Json(api_keys))
+}
+
+/// Revoke any company API key
+#[utoipa::path(
+    delete,
+    tag = "Admin",
+    path = "/backend_api/admin/api_keys/{key_id}",
+    params(
+        ("key_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 204, description = "Key was revoked"),
+        (status = 403, description = "no auth / no `manage_users` permission / authenticated with an API key"),
+        (status = 404, description = "no such key"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn revoke_company_api_key(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path(key_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    reject_api_key(&claims)?;
+
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::ApiKey, key_id).await?;
+
+    let _ = sqlx::query!(
+        r#"
+        DELETE FROM api_keys
+        WHERE id = $1 AND company_id = $2
+        RETURNING id
+        "#,
+        key_id,
+        claims.company_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such API key".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("api_key.revoke", AuditEntity::ApiKey, key_id).before(before),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,209 @@
+use axum::{extract::State, http::HeaderMap, http::StatusCode, Extension, Json};
+use sqlx::types::Json as SqlJson;
+use sqlx::Acquire;
+use std::collections::HashMap;
+
+use crate::{
+    controllers::audit::{record, snapshot, AuditEntity, AuditEntry},
+    db::Db,
+    errors::ProdError,
+    forms::admin::OidcProviderForm,
+    jwt::generate::claims_from_headers,
+    middlewares::RequestMeta,
+    models::{OidcProviderModel, RoleModel},
+    util::ValidatedJson,
+    AppState, BASE_URL,
+};
+
+/// Get company SSO configuration
+#[utoipa::path(
+    get,
+    tag = "Admin",
+    path = "/backend_api/admin/sso",
+    responses(
+        (status = 200, body = OidcProviderModel),
+        (status = 403, description = "no auth / no `manage_company` permission"),
+        (status = 404, description = "SSO is not configured"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_sso_provider(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Json<OidcProviderModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let company_id = claims_from_headers(&headers)?.company_id;
+
+    let provider = sqlx::query_as!(
+        OidcProviderModel,
+        r#"
+        SELECT company_id, issuer, client_id, client_secret, redirect_uri, scopes,
+               name_claim, surname_claim, email_claim, role_claim,
+               role_mapping as "role_mapping: SqlJson<HashMap<String, RoleModel>>",
+               default_role as "default_role: RoleModel", password_login_enabled
+        FROM oidc_providers
+        WHERE company_id = $1
+        "#,
+        company_id
+    )
+    .fetch_one(conn.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("SSO is not configured".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    Ok(Json(provider))
+}
+
+/// Create or replace company SSO configuration
+#[utoipa::path(
+    put,
+    tag = "Admin",
+    path = "/backend_api/admin/sso",
+    request_body = OidcProviderForm,
+    responses(
+        (status = 200, body = OidcProviderModel),
+        (status = 400, description = "wrong data format"),
+        (status = 403, description = "no auth / no `manage_company` permission"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn put_sso_provider(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<OidcProviderForm>,
+) -> Result<Json<OidcProviderModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    let company_id = claims.company_id;
+
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::SsoProvider, company_id).await?;
+
+    let redirect_uri = form
+        .redirect_uri
+        .unwrap_or_else(|| format!("{BASE_URL}/user/sso/callback"));
+
+    let provider = sqlx::query_as!(
+        OidcProviderModel,
+        r#"
+        INSERT INTO oidc_providers (
+            company_id, issuer, client_id, client_secret, redirect_uri, scopes,
+            name_claim, surname_claim, email_claim, role_claim,
+            role_mapping, default_role, password_login_enabled
+        )
+        VALUES (
+            $1, $2, $3, $4, $5, COALESCE($6, 'openid email profile'),
+            COALESCE($7, 'given_name'), COALESCE($8, 'family_name'), COALESCE($9, 'email'), $10,
+            $11, COALESCE($12, 'guest'::ROLE), COALESCE($13, TRUE)
+        )
+        ON CONFLICT (company_id) DO UPDATE SET
+            issuer = EXCLUDED.issuer,
+            client_id = EXCLUDED.client_id,
+            client_secret = EXCLUDED.client_secret,
+            redirect_uri = EXCLUDED.redirect_uri,
+            scopes = EXCLUDED.scopes,
+            name_claim = EXCLUDED.name_claim,
+            surname_claim = EXCLUDED.surname_claim,
+            email_claim = EXCLUDED.email_claim,
+            role_claim = EXCLUDED.role_claim,
+            role_mapping = EXCLUDED.role_mapping,
+            default_role = EXCLUDED.default_role,
+            password_login_enabled = EXCLUDED.password_login_enabled
+        RETURNING company_id, issuer, client_id, client_secret, redirect_uri, scopes,
+                  name_claim, surname_claim, email_claim, role_claim,
+                  role_mapping as "role_mapping: SqlJson<HashMap<String, RoleModel>>",
+                  default_role as "default_role: RoleModel", password_login_enabled
+        "#,
+        company_id,
+        form.issuer,
+        form.client_id,
+        form.client_secret,
+        redirect_uri,
+        form.scopes,
+        form.name_claim,
+        form.surname_claim,
+        form.email_claim,
+        form.role_claim,
+        SqlJson(form.role_mapping) as _,
+        form.default_role as Option<RoleModel>,
+        form.password_login_enabled,
+    )
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::SsoProvider, company_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("sso_provider.put", AuditEntity::SsoProvider, company_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(provider))
+}
+
+/// Remove company SSO configuration
+#[utoipa::path(
+    delete,
+    tag = "Admin",
+    path = "/backend_api/admin/sso",
+    responses(
+        (status = 204, description = "SSO configuration removed, password login is enabled again"),
+        (status = 403, description = "no auth / no `manage_company` permission"),
+        (status = 404, description = "SSO is not configured"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_sso_provider(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    let company_id = claims.company_id;
+
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::SsoProvider, company_id).await?;
+
+    let _ = sqlx::query!(
+        r#"
+        DELETE FROM oidc_providers
+        WHERE company_id = $1
+        RETURNING company_id
+        "#,
+        company_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("SSO is not configured".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("sso_provider.delete", AuditEntity::SsoProvider, company_id).before(before),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
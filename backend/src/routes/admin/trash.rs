@@ -0,0 +1,119 @@
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::{Extension, Json};
+use sqlx::Acquire;
+use uuid::Uuid;
+
+use crate::controllers::audit::{record, snapshot, AuditEntity, AuditEntry};
+use crate::controllers::roles::{claims_permissions, require_permission};
+use crate::controllers::trash::{restore, trash};
+use crate::forms::admin::TrashQuery;
+use crate::jwt::generate::claims_from_headers;
+use crate::middlewares::RequestMeta;
+use crate::models::{TrashEntity, TrashItemModel};
+use crate::{db::Db, errors::ProdError, AppState};
+
+/// List trash
+///
+/// Deleted entities that can still be restored, most recently deleted first.
+/// Users are listed with the `manage_users` permission, buildings, coworkings and item types with `manage_layout`.
+#[utoipa::path(
+    get,
+    tag = "Admin",
+    path = "/backend_api/admin/trash",
+    params(
+        ("entity" = Option<TrashEntity>, Query, description = "Everything the caller may restore when not set")
+    ),
+    responses(
+        (status = 200, body = Vec<TrashItemModel>),
+        (status = 403, description = "no auth / no permission to manage the entity"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_trash(
+    headers: HeaderMap,
+    Query(query): Query<TrashQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<TrashItemModel>>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let kinds: Vec<TrashEntity> = if let Some(entity) = query.entity {
+        require_permission(conn.as_mut(), &claims, entity.permission()).await?;
+        vec![entity]
+    } else {
+        let granted = claims_permissions(conn.as_mut(), &claims).await?;
+        TrashEntity::ALL
+            .into_iter()
+            .filter(|entity| granted.contains(&entity.permission()))
+            .collect()
+    };
+    if kinds.is_empty() {
+        return Err(ProdError::Forbidden(
+            "Missing permission `manage_users` or `manage_layout`".to_string(),
+        ));
+    }
+
+    Ok(Json(trash(conn.as_mut(), claims.company_id, &kinds).await?))
+}
+
+/// Restore from trash
+///
+/// Brings back a deleted entity within the retention period, a building comes back with its coworkings.
+#[utoipa::path(
+    post,
+    tag = "Admin",
+    path = "/backend_api/admin/trash/{entity}/{id}/restore",
+    params(
+        ("entity" = TrashEntity, Path),
+        ("id" = Uuid, Path)
+    ),
+    responses(
+        (status = 204, description = "Restored"),
+        (status = 403, description = "no auth / no permission to manage the entity"),
+        (status = 404, description = "Not in the trash or the retention period is over"),
+        (status = 409, description = "Building of the coworking is deleted"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn restore_from_trash(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path((entity, id)): Path<(TrashEntity, Uuid)>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+    require_permission(tx.as_mut(), &claims, entity.permission()).await?;
+
+    let (audit_entity, action) = match entity {
+        TrashEntity::User => (AuditEntity::User, "user.restore"),
+        TrashEntity::Building => (AuditEntity::Building, "building.restore"),
+        TrashEntity::Coworking => (AuditEntity::Coworking, "coworking.restore"),
+        TrashEntity::ItemType => (AuditEntity::ItemType, "item_type.restore"),
+    };
+    let before = snapshot(tx.as_mut(), audit_entity, id).await?;
+
+    restore(tx.as_mut(), claims.company_id, entity, id).await?;
+
+    let after = snapshot(tx.as_mut(), audit_entity, id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new(action, audit_entity, id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,263 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header::CONTENT_TYPE, HeaderMap, StatusCode},
+    response::Response,
+    Extension, Json,
+};
+use sqlx::Acquire;
+use uuid::Uuid;
+
+use crate::{
+    controllers::{
+        audit::{record, snapshot, AuditEntity, AuditEntry},
+        verification::{decide_case, notify_decision, verification_case, CaseFilter},
+    },
+    db::Db,
+    errors::ProdError,
+    forms::admin::{VerificationCommentForm, VerificationDecisionForm, VerificationRequest},
+    jwt::generate::claims_from_headers,
+    middlewares::RequestMeta,
+    models::{VerificationCommentModel, VerificationStatus},
+    s3::utils::get_file,
+    util::ValidatedJson,
+    AppState,
+};
+
+/// Get verification case
+#[utoipa::path(
+    get,
+    tag = "Admin",
+    path = "/backend_api/admin/verifications/{case_id}",
+    params(
+        ("case_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = VerificationRequest),
+        (status = 403, description = "no auth / no `verify_guests` permission"),
+        (status = 404, description = "no such case"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_verification_case(
+    headers: HeaderMap,
+    Path(case_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<Json<VerificationRequest>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let company_id = claims_from_headers(&headers)?.company_id;
+
+    let case = verification_case(
+        conn.as_mut(),
+        company_id,
+        CaseFilter {
+            case_id: Some(case_id),
+            ..CaseFilter::default()
+        },
+    )
+    .await?;
+
+    Ok(Json(case))
+}
+
+/// Get verification document version
+#[utoipa::path(
+    get,
+    tag = "Admin",
+    path = "/backend_api/admin/verifications/{case_id}/documents/{version}",
+    params(
+        ("case_id" = Uuid, Path),
+        ("version" = i32, Path)
+    ),
+    responses(
+        (status = 200, description = "user document", content_type = "application/pdf"),
+        (status = 403, description = "no auth / no `verify_guests` permission"),
+        (status = 404, description = "no such case / version"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_verification_document(
+    headers: HeaderMap,
+    Path((case_id, version)): Path<(Uuid, i32)>,
+    State(state): State<AppState>,
+) -> Result<Response, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let company_id = claims_from_headers(&headers)?.company_id;
+
+    let file_name = sqlx::query!(
+        r#"
+        SELECT d.s3_key
+        FROM verification_documents d
+        JOIN verification_cases c ON c.id = d.case_id
+        WHERE c.company_id = $1 AND d.case_id = $2 AND d.version = $3
+        "#,
+        company_id,
+        case_id,
+        version
+    )
+    .fetch_one(conn.as_mut())
+    .await
+    .map(|record| record.s3_key)
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such document".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    let (stream, content_type) = get_file(&state, &file_name).await?;
+    let response = Response::builder()
+        .header(CONTENT_TYPE, content_type)
+        .body(Body::from_stream(stream))
+        .map_err(|e| ProdError::Unknown(e.into()))?;
+
+    Ok(response)
+}
+
+/// Decide verification case
+///
+/// Approving makes the guest a verified guest, rejecting or asking for more information needs a comment.
+/// The guest is notified by e-mail.
+#[utoipa::path(
+    post,
+    tag = "Admin",
+    path = "/backend_api/admin/verifications/{case_id}/decision",
+    request_body = VerificationDecisionForm,
+    params(
+        ("case_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = VerificationRequest, description = "Updated case"),
+        (status = 400, description = "wrong data format / `pending` decision / no comment"),
+        (status = 403, description = "no auth / no `verify_guests` permission"),
+        (status = 404, description = "no such case"),
+        (status = 409, description = "case is already approved"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn decide_verification(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path(case_id): Path<Uuid>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<VerificationDecisionForm>,
+) -> Result<Json<VerificationRequest>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::VerificationCase, case_id).await?;
+
+    let email = decide_case(
+        tx.as_mut(),
+        claims.company_id,
+        case_id,
+        claims.user_id,
+        &form,
+    )
+    .await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::VerificationCase, case_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new(
+            "verification_case.decide",
+            AuditEntity::VerificationCase,
+            case_id,
+        )
+        .before(before)
+        .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    notify_decision(&email, &form).await;
+
+    let case = verification_case(
+        conn.as_mut(),
+        claims.company_id,
+        CaseFilter {
+            case_id: Some(case_id),
+            ..CaseFilter::default()
+        },
+    )
+    .await?;
+
+    Ok(Json(case))
+}
+
+/// Comment on verification case
+#[utoipa::path(
+    post,
+    tag = "Admin",
+    path = "/backend_api/admin/verifications/{case_id}/comments",
+    request_body = VerificationCommentForm,
+    params(
+        ("case_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 201, body = VerificationCommentModel, description = "Created comment"),
+        (status = 400, description = "wrong data format"),
+        (status = 403, description = "no auth / no `verify_guests` permission"),
+        (status = 404, description = "no such case"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn comment_verification(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path(case_id): Path<Uuid>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<VerificationCommentForm>,
+) -> Result<(StatusCode, Json<VerificationCommentModel>), ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+
+    let comment = sqlx::query_as!(
+        VerificationCommentModel,
+        r#"
+        INSERT INTO verification_comments (case_id, author_id, body)
+        SELECT id, $3, $4
+        FROM verification_cases
+        WHERE id = $1 AND company_id = $2
+        RETURNING id, author_id, status as "status: VerificationStatus", body, created_at, case_id
+        "#,
+        case_id,
+        claims.company_id,
+        claims.user_id,
+        form.body
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such verification case".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new(
+            "verification_case.comment",
+            AuditEntity::VerificationCase,
+            case_id,
+        )
+        .after(serde_json::to_value(&comment).ok()),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(comment)))
+}
@@ -1,4 +1,7 @@
+use crate::middlewares::{require_permissions, RequiredPermissions};
+use crate::models::Permission;
 use crate::AppState;
+use axum::middleware::from_fn_with_state;
 use axum::routing::get;
 use axum::{
     routing::{delete, patch, post},
@@ -8,12 +11,20 @@ use axum::{
 pub mod routes;
 
 pub fn get_routes(state: AppState) -> Router {
+    let booking_routes = Router::new()
+        .route("/create", post(routes::create_booking))
+        .layer(from_fn_with_state(
+            RequiredPermissions::new(&state, &[Permission::Book]),
+            require_permissions,
+        ));
+
     Router::new()
         .route("/list", get(routes::list_bookings))
-        .route("/create", post(routes::create_booking))
+        .route("/available", get(routes::available_items))
         .route("/{booking_id}", delete(routes::delete_booking))
         .route("/{booking_id}", patch(routes::patch_booking))
         .route("/{booking_id}/qr", get(routes::get_booking_qr))
         .route("/verify", post(routes::verify_booking_qr))
+        .merge(booking_routes)
         .with_state(state)
 }
@@ -1,26 +1,32 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::{HeaderMap, StatusCode},
-    Json,
+    Extension, Json,
 };
+use chrono::NaiveDateTime;
 use sqlx::Acquire;
 use tracing::info;
 use uuid::Uuid;
 
-use crate::forms::bookings::{BookingDisplayData, QrToken, Verdict};
+use crate::controllers::attributes::{company_attributes, query_filters};
+use crate::controllers::audit::{record, snapshot, AuditEntity, AuditEntry};
+use crate::controllers::teams::{require_booking_on_behalf, require_can_book};
+use crate::extractors::OwnedBooking;
+use crate::forms::bookings::{AvailabilityQuery, BookingDisplayData, QrToken, Verdict};
 use crate::jwt::generate::{create_qr_token, validate_qr_token};
-use crate::models::PublicBookingModel;
+use crate::middlewares::RequestMeta;
+use crate::models::{AvailableItemModel, CoworkingItemsModel, Point, PublicBookingModel};
 use crate::{
     db::Db,
     errors::ProdError,
     forms::bookings::{CreateBookingForm, PatchBookingForm},
     jwt::{generate::claims_from_headers, models::Claims},
-    models::{BookingModel, RoleModel},
+    models::BookingModel,
     util::ValidatedJson,
     AppState,
 };
 
-/// Create booking (only for verified users)
+/// Create booking (requires `book` permission)
 #[utoipa::path(
     post,
     path = "/backend_api/booking/create",
@@ -29,9 +35,9 @@ use crate::{
     responses(
         (status = 201, body = BookingModel, description = "Successully create booking"),
         (status = 400, description = "Wrong request"),
-        (status = 403, description = "Unverified guest can not create booking"),
+        (status = 403, description = "User has no `book` permission / place is reserved for other teams / can't book for that user"),
         (status = 404, description = "Coworking or coworking_item not found"),
-        (status = 409, description = "Your booking conflicts with existing bookings")
+        (status = 409, description = "Your booking conflicts with existing bookings / item is full at that time")
     ),
     security(
         ("bearerAuth" = [])
@@ -39,31 +45,36 @@ use crate::{
 )]
 pub async fn create_booking(
     headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
     State(state): State<AppState>,
     ValidatedJson(form): ValidatedJson<CreateBookingForm>,
 ) -> Result<(StatusCode, Json<BookingModel>), ProdError> {
     let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
     let Claims {
         user_id,
         company_id,
-        role,
         ..
-    } = claims_from_headers(&headers)?;
-
-    if role == RoleModel::Guest {
-        return Err(ProdError::Forbidden(
-            "Unverified guest can not create booking".to_string(),
-        ));
-    }
+    } = claims;
 
     let mut tx = conn.begin().await?;
 
+    let user_id = match form.user_id {
+        Some(member_id) if member_id != user_id => {
+            require_booking_on_behalf(tx.as_mut(), &claims, member_id).await?;
+            member_id
+        }
+        _ => user_id,
+    };
+
     let bookable = sqlx::query!(
         r#"
         SELECT i.bookable
         FROM coworking_items ci
         JOIN item_types i ON i.id = ci.item_id
-        WHERE ci.id = $1 AND ci.coworking_id = $2
+        JOIN coworking_spaces s ON s.id = ci.coworking_id
+        WHERE ci.id = $1 AND ci.coworking_id = $2 AND ci.retired_at IS NULL
+            AND i.deleted_at IS NULL AND s.deleted_at IS NULL
     "#,
         form.coworking_item_id,
         form.coworking_id
@@ -88,20 +99,22 @@ pub async fn create_booking(
             form.coworking_item_id,
         )));
     }
+    require_can_book(tx.as_mut(), user_id, form.coworking_item_id).await?;
 
     let booking = sqlx::query_as!(
         BookingModel,
         r#"
-        INSERT INTO bookings(user_id, coworking_space_id, coworking_item_id, company_id, time_start, time_end)
-        VALUES ($1, $2, $3, $4, $5, $6)
-        RETURNING id, user_id, coworking_space_id, coworking_item_id, company_id, time_start, time_end
+        INSERT INTO bookings(user_id, coworking_space_id, coworking_item_id, company_id, time_start, time_end, attendees)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, user_id, coworking_space_id, coworking_item_id, company_id, time_start, time_end, attendees
         "#,
         user_id,
         form.coworking_id,
         form.coworking_item_id,
         company_id,
         form.time_start,
-        form.time_end
+        form.time_end,
+        form.attendees.unwrap_or(1)
     )
     .fetch_one(tx.as_mut())
     .await
@@ -109,6 +122,10 @@ pub async fn create_booking(
         sqlx::Error::Database(ref e) => {
             if e.is_foreign_key_violation() {
                 return ProdError::ShitHappened("Coworking or coworking_item not found".to_string())
+            } else if e.constraint() == Some("bookings_attendees_capacity") {
+                return ProdError::ShitHappened(e.message().to_string())
+            } else if e.constraint() == Some("bookings_capacity_excl") {
+                return ProdError::Conflict("Item is full at that time".to_string())
             } else if e.is_check_violation() {
                 return ProdError::ShitHappened("Booking time should be divided by 15 minutes. You can book only in future. Start time should be less than end time.".to_string())
             } else if e.constraint() == Some("bookings_coworking_item_id_tsrange_excl") {
@@ -119,6 +136,15 @@ pub async fn create_booking(
         _ => ProdError::DatabaseError(err)
     })?;
 
+    let after = snapshot(tx.as_mut(), AuditEntity::Booking, booking.id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("booking.create", AuditEntity::Booking, booking.id).after(after),
+    )
+    .await?;
+
     tx.commit().await?;
 
     Ok((StatusCode::CREATED, Json(booking)))
@@ -134,7 +160,7 @@ pub async fn create_booking(
     ),
     responses(
         (status = 204, description = "Booking was successully deleted"),
-        (status = 403, description = "User doesn't own that booking"),
+        (status = 403, description = "User doesn't own that booking and can't manage bookings"),
         (status = 404, description = "No booking was found with booking_id"),
     ),
     security(
@@ -142,36 +168,32 @@ pub async fn create_booking(
     )
 )]
 pub async fn delete_booking(
-    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
     State(state): State<AppState>,
-    Path(booking_id): Path<Uuid>,
+    OwnedBooking { booking, claims }: OwnedBooking,
 ) -> Result<StatusCode, ProdError> {
     let mut conn = state.pool.conn().await?;
-    let claim = claims_from_headers(&headers)?;
 
     let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::Booking, booking.id).await?;
 
-    let (user_id, company_id) = sqlx::query!(
+    sqlx::query!(
         r#"
         DELETE FROM bookings
         WHERE id = $1
-        RETURNING user_id, company_id
         "#,
-        booking_id
+        booking.id
     )
-    .fetch_one(tx.as_mut())
-    .await
-    .map(|record| (record.user_id, record.company_id))
-    .map_err(|err| match err {
-        sqlx::Error::RowNotFound => ProdError::NotFound("No booking was found".to_string()),
-        _ => ProdError::DatabaseError(err),
-    })?;
+    .execute(tx.as_mut())
+    .await?;
 
-    if claim.user_id != user_id || claim.company_id != company_id {
-        return Err(ProdError::Forbidden(
-            "You don't have permission to that booking".to_string(),
-        ));
-    }
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("booking.delete", AuditEntity::Booking, booking.id).before(before),
+    )
+    .await?;
 
     tx.commit().await?;
 
@@ -190,22 +212,27 @@ pub async fn delete_booking(
     responses(
         (status = 200, body = BookingModel, description = "Successully update booking"),
         (status = 400, description = "Wrong request"),
-        (status = 403, description = "User doesn't own that booking"),
-        (status = 404, description = "Coworking or coworking_item not found")
+        (status = 403, description = "User doesn't own that booking and can't manage bookings / place is reserved for other teams"),
+        (status = 404, description = "No booking was found with booking_id"),
+        (status = 409, description = "Your booking conflicts with existing bookings / item is full at that time")
     ),
     security(
         ("bearerAuth" = [])
     )
 )]
 pub async fn patch_booking(
-    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
     State(state): State<AppState>,
-    Path(booking_id): Path<Uuid>,
+    OwnedBooking { booking, claims }: OwnedBooking,
     ValidatedJson(form): ValidatedJson<PatchBookingForm>,
 ) -> Result<Json<BookingModel>, ProdError> {
     let mut conn = state.pool.conn().await?;
-    let claim = claims_from_headers(&headers)?;
+
     let mut tx = conn.begin().await?;
+    if let Some(coworking_item_id) = form.coworking_item_id {
+        require_can_book(tx.as_mut(), booking.user_id, coworking_item_id).await?;
+    }
+    let before = snapshot(tx.as_mut(), AuditEntity::Booking, booking.id).await?;
 
     let booking = sqlx::query_as!(
         BookingModel,
@@ -215,32 +242,45 @@ pub async fn patch_booking(
             coworking_space_id = COALESCE($2, coworking_space_id),
             coworking_item_id = COALESCE($3, coworking_item_id),
             time_start = COALESCE($4, time_start),
-            time_end = COALESCE($5, time_end)
+            time_end = COALESCE($5, time_end),
+            attendees = COALESCE($6, attendees)
         WHERE id = $1
         RETURNING id, user_id, coworking_space_id, coworking_item_id,
-                  company_id, time_start, time_end
+                  company_id, time_start, time_end, attendees
         "#,
-        booking_id,
+        booking.id,
         form.coworking_id,
         form.coworking_item_id,
         form.time_start,
-        form.time_end
+        form.time_end,
+        form.attendees
     )
     .fetch_one(tx.as_mut())
     .await
     .map_err(|err| match err {
         sqlx::Error::RowNotFound => ProdError::NotFound("No booking was found".to_string()),
+        sqlx::Error::Database(e)
+            if e.constraint() == Some("bookings_coworking_item_id_tsrange_excl")
+                || e.constraint() == Some("bookings_capacity_excl") =>
+        {
+            ProdError::Conflict("Your booking conflicts with existing bookings".to_string())
+        }
         sqlx::Error::Database(e) if e.is_check_violation() => {
             ProdError::ShitHappened(e.to_string())
         }
         _ => ProdError::DatabaseError(err),
     })?;
 
-    if claim.user_id != booking.user_id || claim.company_id != booking.company_id {
-        return Err(ProdError::Forbidden(
-            "You don't have permission to that booking".to_string(),
-        ));
-    }
+    let after = snapshot(tx.as_mut(), AuditEntity::Booking, booking.id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("booking.update", AuditEntity::Booking, booking.id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
 
     tx.commit().await?;
 
@@ -277,6 +317,7 @@ pub async fn list_bookings(
             b.coworking_item_id,
             b.time_start,
             b.time_end,
+            b.attendees,
             bu.address as building_address,
             i.name as coworking_item_name,
             i.description as coworking_item_description,
@@ -286,7 +327,7 @@ pub async fn list_bookings(
         JOIN coworking_spaces s ON s.id = b.coworking_space_id
         JOIN buildings bu ON bu.id = s.building_id
         JOIN coworking_items i ON i.id = b.coworking_item_id
-        WHERE b.user_id = $1 AND b.time_end > NOW()
+        WHERE b.user_id = $1 AND b.time_end > NOW() AND s.deleted_at IS NULL
         "#,
         claim.user_id,
     )
@@ -326,7 +367,8 @@ pub async fn get_booking_qr(
         BookingModel,
         r#"
         SELECT
-        id, user_id, coworking_space_id, coworking_item_id, company_id, time_start, time_end
+        id, user_id, coworking_space_id, coworking_item_id, company_id, time_start, time_end,
+        attendees
         FROM bookings
         WHERE user_id = $1 AND id = $2
         "#,
@@ -363,7 +405,7 @@ pub async fn verify_booking_qr(
     State(state): State<AppState>,
     Json(form): Json<QrToken>,
 ) -> Result<Json<Verdict>, ProdError> {
-    let mut conn = state.pool.conn().await?;
+    let mut conn = state.pool.unscoped_conn().await?;
     let pre_claims = validate_qr_token(&form.token);
     info!("here {:?}", pre_claims);
     if pre_claims.is_err() {
@@ -383,13 +425,14 @@ pub async fn verify_booking_qr(
         s.address as "space_name",
         i.name as "item_name",
         bo.time_start,
-        bo.time_end
+        bo.time_end,
+        bo.attendees
         FROM bookings bo
         JOIN users u ON u.id = bo.user_id
         JOIN coworking_spaces s ON s.id = bo.coworking_space_id
         JOIN buildings b ON b.id = s.building_id
         JOIN coworking_items i ON i.id = bo.coworking_item_id
-        WHERE bo.id = $1
+        WHERE bo.id = $1 AND s.deleted_at IS NULL
         "#,
         claims.booking_id,
     )
@@ -416,3 +459,129 @@ pub async fn verify_booking_qr(
         },
     )
 }
+
+/// Search items free for the whole time
+///
+/// Bookable items the user can book that have no bookings in the interval, or shared items with
+/// seats left for the attendees the whole time. Every query parameter besides the listed ones
+/// filters on item attributes: `monitors>=2&standing=true&desk=corner`. Numbers can be compared
+/// with `=`, `!=`, `<`, `<=`, `>` and `>=`, booleans and enums only with `=` and `!=`.
+#[utoipa::path(
+    get,
+    tag = "Bookings",
+    path = "/backend_api/booking/available",
+    params(
+        ("time_start" = NaiveDateTime, Query),
+        ("time_end" = NaiveDateTime, Query),
+        ("building_id" = Option<Uuid>, Query, description = "Only items of that building"),
+        ("coworking_id" = Option<Uuid>, Query, description = "Only items of that coworking"),
+        ("attendees" = Option<i32>, Query, description = "People coming, 1 when not set")
+    ),
+    responses(
+        (status = 200, body = Vec<AvailableItemModel>, description = "Free items"),
+        (status = 400, description = "Wrong interval / unknown attribute / wrong filter"),
+        (status = 403, description = "No auth"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn available_items(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Query(query): Query<AvailabilityQuery>,
+    Query(params): Query<Vec<(String, String)>>,
+) -> Result<Json<Vec<AvailableItemModel>>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    if query.time_start >= query.time_end {
+        return Err(ProdError::ShitHappened(
+            "Start time should be less than end time".to_string(),
+        ));
+    }
+
+    let attributes = company_attributes(conn.as_mut(), claims.company_id).await?;
+    let filters = query_filters(
+        &params,
+        &[
+            "time_start",
+            "time_end",
+            "building_id",
+            "coworking_id",
+            "attendees",
+        ],
+        &attributes,
+    )?;
+
+    let candidates = sqlx::query!(
+        r#"
+        SELECT ci.id, ci.item_id, ci.name, ci.description,
+               ci.base_point as "base_point: Point", ci.rotation, ci.mirrored,
+               item_footprint(ci.base_point, i.offsets, ci.rotation, ci.mirrored) as "footprint!: Vec<Point>",
+               ARRAY(
+                   SELECT team_id FROM coworking_item_teams
+                   WHERE coworking_item_id = ci.id
+                   ORDER BY team_id
+               ) as "teams!",
+               placed_item_attribute_values(ci.id, ci.item_id) as "attributes!",
+               c.id as coworking_id, b.id as building_id
+        FROM coworking_items ci
+        JOIN coworking_spaces c ON c.id = ci.coworking_id
+        JOIN buildings b ON b.id = c.building_id
+        JOIN item_types i ON i.id = ci.item_id
+        WHERE c.company_id = $1 AND i.bookable AND ci.retired_at IS NULL
+            AND c.deleted_at IS NULL AND b.deleted_at IS NULL AND i.deleted_at IS NULL
+            AND ($3::uuid IS NULL OR b.id = $3)
+            AND ($4::uuid IS NULL OR c.id = $4)
+            AND can_book_item($2, ci.id)
+            AND i.capacity >= $7
+            AND CASE i.booking_mode
+                WHEN 'shared' THEN booked_attendees(ci.id, $5, $6, NULL) + $7 <= i.capacity
+                ELSE NOT EXISTS (
+                    SELECT 1 FROM bookings bk
+                    WHERE bk.coworking_item_id = ci.id
+                        AND tsrange(bk.time_start, bk.time_end) && tsrange($5, $6)
+                )
+            END
+        ORDER BY b.id, c.id, ci.name
+        "#,
+        claims.company_id,
+        claims.user_id,
+        query.building_id,
+        query.coworking_id,
+        query.time_start,
+        query.time_end,
+        query.attendees.unwrap_or(1)
+    )
+    .fetch_all(conn.as_mut())
+    .await?;
+
+    let items = candidates
+        .into_iter()
+        .filter(|item| {
+            filters
+                .iter()
+                .all(|filter| filter.matches(&item.attributes))
+        })
+        .map(|item| AvailableItemModel {
+            building_id: item.building_id,
+            coworking_id: item.coworking_id,
+            item: CoworkingItemsModel {
+                id: item.id,
+                item_id: item.item_id,
+                name: item.name,
+                description: item.description,
+                base_point: item.base_point,
+                rotation: item.rotation,
+                mirrored: item.mirrored,
+                footprint: item.footprint,
+                teams: item.teams,
+                can_book: true,
+                attributes: item.attributes,
+            },
+        })
+        .collect();
+
+    Ok(Json(items))
+}
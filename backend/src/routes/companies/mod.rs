@@ -1,11 +1,41 @@
+use crate::middlewares::{require_permissions, RequiredPermissions};
+use crate::models::Permission;
 use crate::AppState;
-use axum::routing::post;
+use axum::middleware::from_fn_with_state;
+use axum::routing::{delete, get, patch, post};
 use axum::Router;
 
+pub mod offboarding;
 pub mod routes;
 
 pub fn get_routes(state: AppState) -> Router {
+    let admin_routes = Router::new()
+        .route("/", get(routes::get_company))
+        .route("/", patch(routes::patch_company))
+        .layer(from_fn_with_state(
+            RequiredPermissions::new(&state, &[Permission::ManageCompany]),
+            require_permissions,
+        ));
+
+    // Only the owner can offboard, checked in the handlers. The rest works with the token.
+    let offboarding_routes = Router::new()
+        .route("/offboarding", get(offboarding::get_offboarding))
+        .route("/offboarding", post(offboarding::offboard_company))
+        .route("/offboarding", delete(offboarding::cancel_offboarding))
+        .route(
+            "/offboarding/{offboarding_id}",
+            get(offboarding::get_offboarding_report),
+        )
+        .route(
+            "/offboarding/{offboarding_id}/export",
+            get(offboarding::get_offboarding_export),
+        );
+
     Router::new()
         .route("/register", post(routes::company_register))
+        .route("/branding/{domain}", get(routes::get_branding))
+        .route("/{company_id}/logo", get(routes::get_logo))
+        .merge(admin_routes)
+        .merge(offboarding_routes)
         .with_state(state)
 }
@@ -0,0 +1,269 @@
+use axum::body::Body;
+use axum::extract::{Path, Query, State};
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::Response;
+use axum::{Extension, Json};
+use sqlx::Acquire;
+use uuid::Uuid;
+
+use crate::controllers::audit::{record, AuditEntity, AuditEntry};
+use crate::controllers::companies::offboarding::{
+    archive_name, export_company, offboarding_by_token, pending_offboarding, require_owner,
+    GRACE_DAYS,
+};
+use crate::forms::companies::{OffboardCompanyForm, OffboardingTokenQuery, ScheduledOffboarding};
+use crate::jwt::generate::claims_from_headers;
+use crate::jwt::hashing::hash_token;
+use crate::middlewares::RequestMeta;
+use crate::models::{CompanyOffboardingModel, OffboardingReport};
+use crate::oidc::random_token;
+use crate::s3::utils::download_file;
+use crate::util::ValidatedJson;
+use crate::{db::Db, errors::ProdError, AppState};
+
+/// Get company offboarding
+///
+/// The pending offboarding of the company, if there is one.
+#[utoipa::path(
+    get,
+    tag = "Companies",
+    path = "/backend_api/company/offboarding",
+    responses(
+        (status = 200, body = CompanyOffboardingModel),
+        (status = 403, description = "no auth / not the owner of the company"),
+        (status = 404, description = "company isn't being offboarded"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_offboarding(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Json<CompanyOffboardingModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    require_owner(conn.as_mut(), &claims).await?;
+    let offboarding = pending_offboarding(conn.as_mut(), claims.company_id).await?;
+
+    Ok(Json(offboarding))
+}
+
+/// Offboard company
+///
+/// Schedules the deletion of the company with all its data after a grace period, during which
+/// it can be cancelled. The company is exported right before it's torn down, the export and the report
+/// can be fetched with the returned token afterwards.
+#[utoipa::path(
+    post,
+    tag = "Companies",
+    path = "/backend_api/company/offboarding",
+    request_body = OffboardCompanyForm,
+    responses(
+        (status = 201, body = ScheduledOffboarding),
+        (status = 400, description = "wrong data format / domain doesn't match"),
+        (status = 403, description = "no auth / not the owner of the company"),
+        (status = 409, description = "company is already being offboarded"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn offboard_company(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<OffboardCompanyForm>,
+) -> Result<(StatusCode, Json<ScheduledOffboarding>), ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    require_owner(conn.as_mut(), &claims).await?;
+
+    let mut tx = conn.begin().await?;
+    let token = random_token();
+
+    let offboarding = sqlx::query_as!(
+        CompanyOffboardingModel,
+        r#"
+        INSERT INTO company_offboardings (
+            company_id, company_name, company_domain, requested_by, token_hash, purge_after
+        )
+        SELECT id, name, domain, $2, $3, NOW() + make_interval(days => $4)
+        FROM companies
+        WHERE id = $1 AND domain = $5
+        RETURNING id, company_id, company_name, company_domain, requested_by, created_at,
+                  purge_after, cancelled_at, completed_at,
+                  report as "report: sqlx::types::Json<OffboardingReport>"
+        "#,
+        claims.company_id,
+        claims.user_id,
+        hash_token(&token),
+        GRACE_DAYS,
+        form.domain
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => {
+            ProdError::ShitHappened("The domain doesn't match the company".to_string())
+        }
+        sqlx::Error::Database(e) if e.is_unique_violation() => {
+            ProdError::Conflict("The company is already being offboarded".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("company.offboard", AuditEntity::Company, claims.company_id),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ScheduledOffboarding { token, offboarding }),
+    ))
+}
+
+/// Cancel company offboarding
+#[utoipa::path(
+    delete,
+    tag = "Companies",
+    path = "/backend_api/company/offboarding",
+    responses(
+        (status = 204, description = "Offboarding was cancelled"),
+        (status = 403, description = "no auth / not the owner of the company"),
+        (status = 404, description = "company isn't being offboarded"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn cancel_offboarding(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    require_owner(conn.as_mut(), &claims).await?;
+
+    let mut tx = conn.begin().await?;
+    let offboarding = pending_offboarding(tx.as_mut(), claims.company_id).await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE company_offboardings
+        SET cancelled_at = NOW()
+        WHERE id = $1
+        "#,
+        offboarding.id
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new(
+            "company.cancel_offboarding",
+            AuditEntity::Company,
+            claims.company_id,
+        ),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Get offboarding report
+///
+/// Works with the token from the offboarding, also once the company is gone. The report is there after the teardown.
+#[utoipa::path(
+    get,
+    tag = "Companies",
+    path = "/backend_api/company/offboarding/{offboarding_id}",
+    params(
+        ("token" = String, Query, description = "Token returned when the offboarding was scheduled")
+    ),
+    responses(
+        (status = 200, body = CompanyOffboardingModel),
+        (status = 404, description = "no such offboarding / wrong token"),
+    )
+)]
+pub async fn get_offboarding_report(
+    Path(offboarding_id): Path<Uuid>,
+    Query(query): Query<OffboardingTokenQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<CompanyOffboardingModel>, ProdError> {
+    let mut conn = state.pool.unscoped_conn().await?;
+
+    let offboarding =
+        offboarding_by_token(conn.as_mut(), offboarding_id, &hash_token(&query.token)).await?;
+
+    Ok(Json(offboarding))
+}
+
+/// Export offboarded company
+///
+/// ZIP archive with every row and file of the company. Until the teardown it's made on request,
+/// afterwards it's the one made right before the teardown.
+#[utoipa::path(
+    get,
+    tag = "Companies",
+    path = "/backend_api/company/offboarding/{offboarding_id}/export",
+    params(
+        ("token" = String, Query, description = "Token returned when the offboarding was scheduled")
+    ),
+    responses(
+        (status = 200, description = "company data archive", content_type = "application/zip"),
+        (status = 404, description = "no such offboarding / wrong token / offboarding was cancelled"),
+    )
+)]
+pub async fn get_offboarding_export(
+    Path(offboarding_id): Path<Uuid>,
+    Query(query): Query<OffboardingTokenQuery>,
+    State(state): State<AppState>,
+) -> Result<Response, ProdError> {
+    let mut conn = state.pool.unscoped_conn().await?;
+
+    let offboarding =
+        offboarding_by_token(conn.as_mut(), offboarding_id, &hash_token(&query.token)).await?;
+
+    let archive = if offboarding.completed_at.is_some() {
+        download_file(&state, &archive_name(offboarding.id))
+            .await?
+            .to_vec()
+    } else if offboarding.cancelled_at.is_some() {
+        return Err(ProdError::NotFound(
+            "The offboarding was cancelled".to_string(),
+        ));
+    } else {
+        export_company(&state, conn.as_mut(), offboarding.company_id)
+            .await?
+            .0
+    };
+
+    Response::builder()
+        .header(CONTENT_TYPE, "application/zip")
+        .header(
+            CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"{}.zip\"",
+                offboarding.company_domain
+            ),
+        )
+        .body(Body::from(archive))
+        .map_err(|e| ProdError::Unknown(e.into()))
+}
@@ -1,15 +1,24 @@
+use crate::controllers::audit::{record, record_as, snapshot, AuditEntity, AuditEntry};
+use crate::controllers::companies::update_company;
 use crate::controllers::users::register_user;
 use crate::db::Db;
 use crate::errors::ProdError;
-use crate::forms::companies::CompanyRegisterData;
+use crate::forms::companies::{CompanyBranding, CompanyRegisterData, PatchCompanyFormData};
 use crate::forms::users::{RegisterForm, Token};
-use crate::jwt::generate::create_token;
-use crate::models::RoleModel;
+use crate::jwt::generate::{claims_from_headers, create_token};
+use crate::middlewares::RequestMeta;
+use crate::models::{CompaniesModel, RoleModel};
+use crate::s3::utils::get_file;
 use crate::util::ValidatedJson;
 use crate::AppState;
-use axum::extract::State;
-use axum::http::StatusCode;
-use axum::Json;
+use axum::body::Body;
+use axum::extract::{Multipart, Path, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::Response;
+use axum::{Extension, Json};
+use sqlx::Acquire;
+use uuid::Uuid;
 
 /// Add new company
 #[utoipa::path(
@@ -24,21 +33,24 @@ use axum::Json;
     )
 )]
 pub async fn company_register(
+    Extension(meta): Extension<RequestMeta>,
     State(state): State<AppState>,
     ValidatedJson(form): ValidatedJson<CompanyRegisterData>,
 ) -> Result<(StatusCode, Json<Token>), ProdError> {
-    let mut conn = state.pool.conn().await?;
+    let mut conn = state.pool.unscoped_conn().await?;
 
-    let _ = sqlx::query!(
+    let company_id = sqlx::query!(
         r#"
         INSERT INTO companies (name, domain)
         VALUES ($1, $2)
+        RETURNING id
         "#,
         form.name,
         form.domain,
     )
-    .execute(conn.as_mut())
+    .fetch_one(conn.as_mut())
     .await
+    .map(|record| record.id)
     .map_err(|err| match err {
         sqlx::Error::Database(e) if e.is_unique_violation() => ProdError::Conflict(e.to_string()),
         _ => ProdError::DatabaseError(err),
@@ -56,7 +68,196 @@ pub async fn company_register(
         RoleModel::Admin,
     )
     .await?;
+
+    sqlx::query!(
+        r#"UPDATE companies SET owner_id = $2 WHERE id = $1"#,
+        company_id,
+        user.id
+    )
+    .execute(conn.as_mut())
+    .await?;
+
+    let after = snapshot(conn.as_mut(), AuditEntity::Company, company_id).await?;
+    record_as(
+        conn.as_mut(),
+        &meta,
+        company_id,
+        Some(user.id),
+        AuditEntry::new("company.create", AuditEntity::Company, company_id).after(after),
+    )
+    .await?;
+
     let token = create_token(&user.id, &user.company_id, &user.role)?;
 
     Ok((StatusCode::CREATED, Json(Token { jwt: token })))
 }
+
+/// Get company profile
+#[utoipa::path(
+    get,
+    tag = "Companies",
+    path = "/backend_api/company",
+    responses(
+        (status = 200, body = CompaniesModel),
+        (status = 403, description = "no auth / missing permission"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_company(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Json<CompaniesModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let company = sqlx::query_as!(
+        CompaniesModel,
+        r#"
+        SELECT id, name, domain, avatar, primary_color, accent_color, owner_id
+        FROM companies
+        WHERE id = $1
+        "#,
+        claims.company_id
+    )
+    .fetch_one(conn.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such company".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    Ok(Json(company))
+}
+
+/// Update company profile with multipart body
+///
+/// Changing the domain changes it for every user of the company, they log in with the new one.
+#[utoipa::path(
+    patch,
+    tag = "Companies",
+    path = "/backend_api/company",
+    request_body(content = PatchCompanyFormData, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, body = CompaniesModel),
+        (status = 400, description = "wrong data format"),
+        (status = 403, description = "no auth / missing permission"),
+        (status = 409, description = "domain is already taken"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn patch_company(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    multipart: Multipart,
+) -> Result<Json<CompaniesModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::Company, claims.company_id).await?;
+
+    let company = update_company(&state, tx.as_mut(), claims.company_id, multipart).await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::Company, claims.company_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("company.update", AuditEntity::Company, claims.company_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(company))
+}
+
+/// Get company branding
+///
+/// Name, logo and colors for the login page, no auth needed.
+#[utoipa::path(
+    get,
+    tag = "Companies",
+    path = "/backend_api/company/branding/{domain}",
+    responses(
+        (status = 200, body = CompanyBranding),
+        (status = 404, description = "company not found"),
+    )
+)]
+pub async fn get_branding(
+    Path(domain): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<CompanyBranding>, ProdError> {
+    let mut conn = state.pool.unscoped_conn().await?;
+
+    let branding = sqlx::query_as!(
+        CompanyBranding,
+        r#"
+        SELECT name, avatar as logo, primary_color, accent_color
+        FROM companies
+        WHERE domain = $1
+        "#,
+        domain
+    )
+    .fetch_one(conn.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NoCompany,
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    Ok(Json(branding))
+}
+
+/// Get company logo
+#[utoipa::path(
+    get,
+    tag = "Companies",
+    path = "/backend_api/company/{company_id}/logo",
+    responses(
+        (status = 200, description = "company logo"),
+        (status = 404, description = "company or logo not found")
+    )
+)]
+pub async fn get_logo(
+    Path(company_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<Response, ProdError> {
+    let mut conn = state.pool.unscoped_conn().await?;
+    let logo = sqlx::query!(
+        r#"
+        SELECT avatar
+        FROM companies
+        WHERE id = $1
+        "#,
+        company_id
+    )
+    .fetch_one(conn.as_mut())
+    .await
+    .map(|record| record.avatar)
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NoCompany,
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    if logo.is_none() {
+        return Err(ProdError::NotFound("Logo not found".to_string()));
+    }
+
+    let file_name = format!("companies/{company_id}/logo");
+
+    let (stream, content_type) = get_file(&state, &file_name).await?;
+    let response = Response::builder()
+        .header(CONTENT_TYPE, content_type)
+        .body(Body::from_stream(stream))
+        .map_err(|e| ProdError::Unknown(e.into()))?;
+
+    Ok(response)
+}
@@ -0,0 +1,184 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use sqlx::Acquire;
+use uuid::Uuid;
+
+use crate::{
+    controllers::{
+        attributes::company_attributes,
+        audit::{record, snapshot, AuditEntity, AuditEntry},
+    },
+    db::Db,
+    errors::ProdError,
+    forms::items::CreateAttributeForm,
+    jwt::generate::claims_from_headers,
+    middlewares::RequestMeta,
+    models::{AttributeKind, ItemAttributeModel},
+    util::ValidatedJson,
+    AppState,
+};
+
+/// List item attributes of company (everybody can use)
+#[utoipa::path(
+    get,
+    tag = "Items",
+    path = "/backend_api/items/attributes",
+    responses(
+        (status = 200, body = Vec<ItemAttributeModel>, description = "Attributes items can have"),
+        (status = 403, description = "no auth")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_attributes(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ItemAttributeModel>>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    Ok(Json(
+        company_attributes(conn.as_mut(), claims.company_id).await?,
+    ))
+}
+
+/// Create item attribute
+///
+/// Values of the attribute are set on item types and can be overridden on single placed items.
+#[utoipa::path(
+    post,
+    tag = "Items",
+    path = "/backend_api/items/attributes",
+    request_body = CreateAttributeForm,
+    responses(
+        (status = 201, body = ItemAttributeModel, description = "Created attribute"),
+        (status = 400, description = "wrong data format"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 409, description = "attribute with that key already exists")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_attribute(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<CreateAttributeForm>,
+) -> Result<(StatusCode, Json<ItemAttributeModel>), ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    if (form.kind == AttributeKind::Enum) == form.options.is_empty() {
+        return Err(ProdError::ShitHappened(
+            "Options are required for enum attributes and only for them".to_string(),
+        ));
+    }
+
+    let mut tx = conn.begin().await?;
+
+    let attribute = sqlx::query_as!(
+        ItemAttributeModel,
+        r#"
+        INSERT INTO item_attributes (key, name, kind, options, company_id)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, key, name, kind as "kind: AttributeKind", options, company_id
+        "#,
+        form.key,
+        form.name,
+        form.kind as AttributeKind,
+        &form.options,
+        claims.company_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::Database(e) if e.is_unique_violation() => {
+            ProdError::Conflict("Attribute with that key already exists".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::ItemAttribute, attribute.id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new(
+            "item_attribute.create",
+            AuditEntity::ItemAttribute,
+            attribute.id,
+        )
+        .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(attribute)))
+}
+
+/// Delete item attribute
+///
+/// Values of the attribute are dropped from all item types and placed items.
+#[utoipa::path(
+    delete,
+    tag = "Items",
+    path = "/backend_api/items/attributes/{attribute_id}",
+    params(
+        ("attribute_id" = Uuid, Path),
+    ),
+    responses(
+        (status = 204, description = "Attribute was deleted"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 404, description = "No such attribute")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_attribute(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    Path(attribute_id): Path<Uuid>,
+) -> Result<StatusCode, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::ItemAttribute, attribute_id).await?;
+
+    let deleted = sqlx::query!(
+        r#"DELETE FROM item_attributes WHERE id = $1 AND company_id = $2"#,
+        attribute_id,
+        claims.company_id
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    if deleted.rows_affected() == 0 {
+        return Err(ProdError::NotFound("No such attribute".to_string()));
+    }
+
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new(
+            "item_attribute.delete",
+            AuditEntity::ItemAttribute,
+            attribute_id,
+        )
+        .before(before),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,21 +1,42 @@
+use attributes::{create_attribute, delete_attribute, list_attributes};
 use axum::{
-    middleware::from_fn,
-    routing::{delete, get, post},
+    middleware::from_fn_with_state,
+    routing::{delete, get, patch, post, put},
     Router,
 };
-use routes::{create_items_type, delete_item_type};
+use routes::{
+    check_item_type_impact, create_items_type, delete_item_type, patch_item_type,
+    put_item_type_attributes,
+};
 use selecting::list_items_by_company;
 
-use crate::{middlewares::auth_admin, AppState};
+use crate::{
+    middlewares::{require_permissions, RequiredPermissions},
+    models::Permission,
+    AppState,
+};
 
+pub mod attributes;
 pub mod routes;
 pub mod selecting;
 
 pub fn get_routes(state: AppState) -> Router {
-    Router::new()
+    let admin_routes = Router::new()
         .route("/", get(list_items_by_company))
         .route("/new", post(create_items_type))
+        .route("/attributes", post(create_attribute))
+        .route("/attributes/{attribute_id}", delete(delete_attribute))
+        .route("/{item_id}", patch(patch_item_type))
         .route("/{item_id}", delete(delete_item_type))
-        .layer(from_fn(auth_admin))
+        .route("/{item_id}/impact", post(check_item_type_impact))
+        .route("/{item_id}/attributes", put(put_item_type_attributes))
+        .layer(from_fn_with_state(
+            RequiredPermissions::new(&state, &[Permission::ManageLayout]),
+            require_permissions,
+        ));
+
+    Router::new()
+        .route("/attributes", get(list_attributes))
+        .merge(admin_routes)
         .with_state(state)
 }
@@ -1,19 +1,31 @@
+use std::collections::HashMap;
+
 use axum::{
     extract::{Multipart, Path, State},
     http::{HeaderMap, StatusCode},
-    Json,
+    Extension, Json,
 };
-use sqlx::{Acquire, Error};
+use sqlx::{Acquire, Error, PgConnection};
 use tracing::warn;
 use uuid::{NoContext, Timestamp, Uuid};
+use validator::Validate;
 
 use crate::{
+    controllers::{
+        attributes::{attribute_values, set_type_attribute_values},
+        audit::{record, snapshot, AuditEntity, AuditEntry},
+        layout::item_type_impact,
+    },
     db::Db,
     errors::ProdError,
-    forms::items::{CreateItemTypeForm, CreateItemTypeFormData},
+    forms::items::{
+        CreateItemTypeForm, CreateItemTypeFormData, UpdateItemTypeForm, UpdateItemTypeFormData,
+    },
     jwt::generate::claims_from_headers,
-    models::ItemsModel,
+    middlewares::RequestMeta,
+    models::{BookingMode, ItemTypeImpactModel, ItemsModel, Point},
     s3::utils::upload_file,
+    util::ValidatedJson,
     AppState,
 };
 
@@ -25,7 +37,7 @@ use crate::{
     request_body(content = CreateItemTypeFormData, content_type = "multipart/form-data"),
     responses(
         (status = 201, description = "Item type was successfully created", body = ItemsModel),
-        (status = 403, description = "You are not an admin")
+        (status = 403, description = "You have no `manage_layout` permission")
     ),
     security(
         ("bearerAuth" = [])
@@ -33,11 +45,13 @@ use crate::{
 )]
 pub async fn create_items_type(
     headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
     State(state): State<AppState>,
     mut multipart: Multipart,
 ) -> Result<(StatusCode, Json<ItemsModel>), ProdError> {
     let mut conn = state.pool.conn().await?;
-    let company_id = claims_from_headers(&headers)?.company_id;
+    let claims = claims_from_headers(&headers)?;
+    let company_id = claims.company_id;
 
     let mut form: Option<CreateItemTypeForm> = None;
     let mut icon_name: Option<String> = None;
@@ -95,13 +109,17 @@ pub async fn create_items_type(
         ));
     }
 
+    let mut tx = conn.begin().await?;
+
     let item = sqlx::query_as::<_, ItemsModel>(&format!(
         r"
-        INSERT INTO item_types(id, name, description, color, icon, offsets, bookable, company_id)
-        VALUES ($1, $2, $3, $4, $5, ARRAY[{}]::point[], $6, $7)
+        INSERT INTO item_types(id, name, description, color, icon, offsets, bookable, company_id,
+                               booking_mode, capacity)
+        VALUES ($1, $2, $3, $4, $5, ARRAY[{}]::point[], $6, $7, $8, $9)
         RETURNING id, name, description, color, icon,
                   offsets,
-                  bookable, company_id
+                  bookable, booking_mode, capacity, company_id,
+                  type_attribute_values(id) as attributes
         ",
         form.offsets
             .iter()
@@ -116,13 +134,28 @@ pub async fn create_items_type(
     .bind(&icon_name)
     .bind(form.bookable)
     .bind(company_id)
-    .fetch_one(conn.as_mut())
+    .bind(form.booking_mode)
+    .bind(form.capacity.unwrap_or(1))
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::ItemType, item.id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("item_type.create", AuditEntity::ItemType, item.id).after(after),
+    )
     .await?;
 
+    tx.commit().await?;
+
     Ok((StatusCode::CREATED, Json(item)))
 }
 
 /// Delete item type by id
+///
+/// Moves the item type to the trash, items of this type are hidden from layouts until it's restored.
 #[utoipa::path(
     delete,
     tag = "Items",
@@ -132,7 +165,8 @@ pub async fn create_items_type(
     ),
     responses(
         (status = 204, description = "Item type was successfully deleted"),
-        (status = 403, description = "Forbidden")
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Item does not exist")
     ),
     security(
         ("bearerAuth" = [])
@@ -140,6 +174,7 @@ pub async fn create_items_type(
 )]
 pub async fn delete_item_type(
     headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
     Path(item_id): Path<Uuid>,
     State(state): State<AppState>,
 ) -> Result<StatusCode, ProdError> {
@@ -147,11 +182,12 @@ pub async fn delete_item_type(
     let claim = claims_from_headers(&headers)?;
 
     let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::ItemType, item_id).await?;
 
     let company_id = sqlx::query!(
         r#"
-        DELETE FROM item_types
-        WHERE id = $1
+        UPDATE item_types SET deleted_at = NOW()
+        WHERE id = $1 AND deleted_at IS NULL
         RETURNING company_id
         "#,
         item_id
@@ -170,7 +206,309 @@ pub async fn delete_item_type(
         ));
     }
 
+    let after = snapshot(tx.as_mut(), AuditEntity::ItemType, item_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claim,
+        AuditEntry::new("item_type.delete", AuditEntity::ItemType, item_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
     tx.commit().await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Fails unless the item type belongs to the company and isn't in the trash.
+async fn company_item_type(
+    conn: &mut PgConnection,
+    item_id: Uuid,
+    company_id: Uuid,
+) -> Result<(), ProdError> {
+    sqlx::query!(
+        r#"
+        SELECT id FROM item_types
+        WHERE id = $1 AND company_id = $2 AND deleted_at IS NULL
+        FOR UPDATE
+        "#,
+        item_id,
+        company_id
+    )
+    .fetch_one(conn)
+    .await
+    .map(|_| ())
+    .map_err(|err| match err {
+        Error::RowNotFound => ProdError::NotFound("Item does not exist".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })
+}
+
+/// Check impact of item type change
+///
+/// Reports the placed items of the type, their upcoming bookings, the items the new offsets
+/// would push over the borders, into obstacles or into other items, and the items whose bookings
+/// a new booking mode or capacity can't take. Nothing is changed.
+#[utoipa::path(
+    post,
+    tag = "Items",
+    path = "/backend_api/items/{item_id}/impact",
+    request_body = UpdateItemTypeForm,
+    params(
+        ("item_id" = Uuid, Path),
+    ),
+    responses(
+        (status = 200, body = ItemTypeImpactModel, description = "Impact of the change"),
+        (status = 400, description = "Wrong request"),
+        (status = 403, description = "You have no `manage_layout` permission"),
+        (status = 404, description = "Item does not exist")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn check_item_type_impact(
+    headers: HeaderMap,
+    Path(item_id): Path<Uuid>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<UpdateItemTypeForm>,
+) -> Result<Json<ItemTypeImpactModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    company_item_type(conn.as_mut(), item_id, claims.company_id).await?;
+    let impact = item_type_impact(conn.as_mut(), item_id, &form).await?;
+
+    Ok(Json(impact))
+}
+
+/// Update item type with multipart body
+///
+/// Placed items of the type keep their ids and bookings. New offsets are refused while they'd
+/// make any placed item collide, and a new booking mode or capacity while upcoming bookings
+/// don't fit it. Check the impact of the change first.
+#[utoipa::path(
+    patch,
+    tag = "Items",
+    path = "/backend_api/items/{item_id}",
+    request_body(content = UpdateItemTypeFormData, content_type = "multipart/form-data"),
+    params(
+        ("item_id" = Uuid, Path),
+    ),
+    responses(
+        (status = 200, body = ItemsModel, description = "Updated item type"),
+        (status = 400, description = "Wrong request"),
+        (status = 403, description = "You have no `manage_layout` permission"),
+        (status = 404, description = "Item does not exist"),
+        (status = 409, description = "New offsets make placed items collide / upcoming bookings don't fit the new booking mode or capacity")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn patch_item_type(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path(item_id): Path<Uuid>,
+    State(state): State<AppState>,
+    mut multipart: Multipart,
+) -> Result<Json<ItemsModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut form: Option<UpdateItemTypeForm> = None;
+    let mut icon = None;
+
+    while let Ok(Some(field)) = multipart.next_field().await {
+        if let Some(field_name) = field.name() {
+            match field_name {
+                "json" => {
+                    let text = field
+                        .text()
+                        .await
+                        .map_err(|_| ProdError::ShitHappened("Bad request".to_string()))?;
+
+                    form = Some(serde_json::from_str(&text).map_err(|err| {
+                        ProdError::ShitHappened(format!("Failed to parse json - {err}"))
+                    })?);
+                }
+                "icon" => {
+                    if let Some(content_type) = field.content_type() {
+                        if content_type != "image/svg" && content_type != "image/svg+xml" {
+                            return Err(ProdError::ShitHappened(
+                                "Icon must be of type image/svg".to_string(),
+                            ));
+                        }
+
+                        icon = Some(
+                            field
+                                .bytes()
+                                .await
+                                .map_err(|err| ProdError::ShitHappened(err.to_string()))?,
+                        );
+                    }
+                }
+                _ => warn!("Unknwon field: {}", field_name),
+            }
+        }
+    }
+
+    let form = form.unwrap_or_default();
+    form.validate()?;
+
+    let mut tx = conn.begin().await?;
+
+    company_item_type(tx.as_mut(), item_id, claims.company_id).await?;
+
+    // New bookings of the placed items wait for this lock in their trigger, so none slip past
+    // the check and they're checked against the updated type.
+    let _ = sqlx::query!(
+        r#"SELECT id FROM coworking_items WHERE item_id = $1 FOR UPDATE"#,
+        item_id
+    )
+    .fetch_all(tx.as_mut())
+    .await?;
+
+    if form.offsets.is_some() || form.booking_mode.is_some() || form.capacity.is_some() {
+        let impact = item_type_impact(tx.as_mut(), item_id, &form).await?;
+        if !impact.collisions.is_empty() {
+            return Err(ProdError::Conflict(format!(
+                "New offsets make {} placed items collide",
+                impact.collisions.len()
+            )));
+        }
+        if !impact.overbooked.is_empty() {
+            return Err(ProdError::Conflict(format!(
+                "{} placed items have upcoming bookings that don't fit the new booking mode or capacity",
+                impact.overbooked.len()
+            )));
+        }
+    }
+
+    let icon_name = match icon {
+        Some(image) => {
+            let name = format!("items/{item_id}/icon.svg");
+            upload_file(&state, &name, "image/svg".to_string(), image).await?;
+            Some(name)
+        }
+        None => None,
+    };
+
+    let before = snapshot(tx.as_mut(), AuditEntity::ItemType, item_id).await?;
+
+    let (xs, ys): (Vec<i64>, Vec<i64>) = form.offsets.iter().flatten().map(|p| (p.x, p.y)).unzip();
+    let item = sqlx::query_as!(
+        ItemsModel,
+        r#"
+        UPDATE item_types SET
+            name = COALESCE($2, name),
+            description = COALESCE($3, description),
+            color = COALESCE($4, color),
+            icon = COALESCE($5, icon),
+            bookable = COALESCE($6, bookable),
+            offsets = CASE WHEN $7 THEN (
+                SELECT array_agg(point(x, y) ORDER BY n)
+                FROM unnest($8::bigint[], $9::bigint[]) WITH ORDINALITY AS o(x, y, n)
+            ) ELSE offsets END,
+            booking_mode = COALESCE($10, booking_mode),
+            capacity = COALESCE($11, capacity)
+        WHERE id = $1
+        RETURNING id, name, description, color, bookable, icon,
+                  offsets as "offsets: Vec<Point>",
+                  booking_mode as "booking_mode: BookingMode", capacity, company_id,
+                  type_attribute_values(id) as "attributes!"
+        "#,
+        item_id,
+        form.name,
+        form.description,
+        form.color,
+        icon_name,
+        form.bookable,
+        form.offsets.is_some(),
+        &xs,
+        &ys,
+        form.booking_mode as Option<BookingMode>,
+        form.capacity
+    )
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::ItemType, item_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("item_type.update", AuditEntity::ItemType, item_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(item))
+}
+
+/// Set attributes of item type
+///
+/// Replaces all attribute values of the type, placed items keep their own overrides.
+#[utoipa::path(
+    put,
+    tag = "Items",
+    path = "/backend_api/items/{item_id}/attributes",
+    request_body = HashMap<String, serde_json::Value>,
+    params(
+        ("item_id" = Uuid, Path),
+    ),
+    responses(
+        (status = 200, body = HashMap<String, serde_json::Value>, description = "Attribute values of the type"),
+        (status = 400, description = "Unknown attribute / wrong value"),
+        (status = 403, description = "You have no `manage_layout` permission"),
+        (status = 404, description = "Item does not exist")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn put_item_type_attributes(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path(item_id): Path<Uuid>,
+    State(state): State<AppState>,
+    Json(form): Json<HashMap<String, serde_json::Value>>,
+) -> Result<Json<serde_json::Value>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+
+    company_item_type(tx.as_mut(), item_id, claims.company_id).await?;
+    let (ids, values) = attribute_values(tx.as_mut(), claims.company_id, form).await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::ItemType, item_id).await?;
+
+    set_type_attribute_values(tx.as_mut(), item_id, &ids, &values).await?;
+
+    let attributes = sqlx::query_scalar!(
+        r#"SELECT type_attribute_values($1) as "attributes!""#,
+        item_id
+    )
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::ItemType, item_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("item_type.update", AuditEntity::ItemType, item_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(attributes))
+}
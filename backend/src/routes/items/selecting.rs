@@ -4,7 +4,7 @@ use crate::{
     db::Db,
     errors::ProdError,
     jwt::generate::claims_from_headers,
-    models::{ItemsModel, Point},
+    models::{BookingMode, ItemsModel, Point},
     AppState,
 };
 
@@ -31,9 +31,11 @@ pub async fn list_items_by_company(
         ItemsModel,
         r#"
         SELECT id, name, description, color, bookable, icon,
-               offsets as "offsets: Vec<Point>", company_id
+               offsets as "offsets: Vec<Point>",
+               booking_mode as "booking_mode: BookingMode", capacity, company_id,
+               type_attribute_values(id) as "attributes!"
         FROM item_types
-        WHERE company_id = $1
+        WHERE company_id = $1 AND deleted_at IS NULL
         "#,
         company_id
     )
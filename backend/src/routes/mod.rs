@@ -3,4 +3,6 @@ pub mod booking;
 pub mod companies;
 pub mod items;
 pub mod places;
+pub mod scim;
+pub mod teams;
 pub mod users;
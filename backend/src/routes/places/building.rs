@@ -1,18 +1,24 @@
+use crate::controllers::audit::{record, snapshot, AuditEntity, AuditEntry};
 use crate::jwt::generate::claims_from_headers;
 use crate::jwt::models::Claims;
+use crate::middlewares::RequestMeta;
 use crate::{
     db::Db, errors::ProdError, forms::places::building::CreateBuildingForm, models::BuildingModel,
     AppState,
 };
 use axum::extract::Path;
+use axum::Extension;
 use axum::{
     extract::State,
     http::{HeaderMap, StatusCode},
     Json,
 };
+use sqlx::Acquire;
 use uuid::Uuid;
 
 /// Create building (place)
+///
+/// The building starts with a ground floor.
 #[utoipa::path(
     post,
     tag = "Coworkings",
@@ -20,7 +26,7 @@ use uuid::Uuid;
     request_body = CreateBuildingForm,
     responses(
         (status = 201, body = BuildingModel, description = "Created building"),
-        (status = 403, description = "No auth / not admin"),
+        (status = 403, description = "No auth / no `manage_layout` permission"),
     ),
     security(
         ("bearerAuth" = [])
@@ -28,11 +34,15 @@ use uuid::Uuid;
 )]
 pub async fn create_building(
     headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
     State(state): State<AppState>,
     Json(form): Json<CreateBuildingForm>,
 ) -> Result<(StatusCode, Json<BuildingModel>), ProdError> {
     let mut conn = state.pool.conn().await?;
-    let Claims { company_id, .. } = claims_from_headers(&headers)?;
+    let claims = claims_from_headers(&headers)?;
+    let company_id = claims.company_id;
+
+    let mut tx = conn.begin().await?;
 
     let building = sqlx::query_as!(
         BuildingModel,
@@ -45,9 +55,31 @@ pub async fn create_building(
         form.address,
         company_id
     )
-    .fetch_one(conn.as_mut())
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO floors (building_id, company_id, number, name, display_order)
+        VALUES ($1, $2, 0, 'Ground floor', 0)
+        "#,
+        building.id,
+        company_id
+    )
+    .execute(tx.as_mut())
     .await?;
 
+    let after = snapshot(tx.as_mut(), AuditEntity::Building, building.id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("building.create", AuditEntity::Building, building.id).after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
     Ok((StatusCode::CREATED, Json(building)))
 }
 
@@ -73,7 +105,7 @@ pub async fn list_buildings(
 
     let buildings = sqlx::query_as!(
         BuildingModel,
-        r#"SELECT id, address, company_id FROM buildings WHERE company_id = $1"#,
+        r#"SELECT id, address, company_id FROM buildings WHERE company_id = $1 AND deleted_at IS NULL"#,
         company_id
     )
     .fetch_all(conn.as_mut())
@@ -109,7 +141,7 @@ pub async fn get_building(
 
     let building = sqlx::query_as!(
         BuildingModel,
-        r#"SELECT id, address, company_id FROM buildings WHERE company_id = $1 AND id = $2"#,
+        r#"SELECT id, address, company_id FROM buildings WHERE company_id = $1 AND id = $2 AND deleted_at IS NULL"#,
         company_id,
         building_id
     )
@@ -143,31 +175,51 @@ pub async fn get_building(
 )]
 pub async fn patch_building(
     headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
     Path(building_id): Path<Uuid>,
     State(state): State<AppState>,
     Json(form): Json<CreateBuildingForm>,
 ) -> Result<Json<BuildingModel>, ProdError> {
     let mut conn = state.pool.conn().await?;
-    let Claims { company_id, .. } = claims_from_headers(&headers)?;
+    let claims = claims_from_headers(&headers)?;
+    let company_id = claims.company_id;
+
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::Building, building_id).await?;
 
     let building = sqlx::query_as!(
         BuildingModel,
-        r#"UPDATE buildings SET address = $1 WHERE company_id = $2 AND id = $3 RETURNING id, address, company_id"#,
+        r#"UPDATE buildings SET address = $1 WHERE company_id = $2 AND id = $3 AND deleted_at IS NULL RETURNING id, address, company_id"#,
         form.address,
         company_id,
         building_id
     )
-    .fetch_one(conn.as_mut())
+    .fetch_one(tx.as_mut())
     .await
     .map_err(|err| match err {
         sqlx::Error::RowNotFound => ProdError::NotFound("No such building".to_string()),
         _ => ProdError::DatabaseError(err),
     })?;
 
+    let after = snapshot(tx.as_mut(), AuditEntity::Building, building_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("building.update", AuditEntity::Building, building_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
     Ok(Json(building))
 }
 
 /// Delete building
+///
+/// Moves the building and its coworkings to the trash, they can be restored until the retention period ends.
 #[utoipa::path(
     delete,
     tag = "Coworkings",
@@ -176,8 +228,8 @@ pub async fn patch_building(
         ("building_id" = Uuid, Path)
     ),
     responses(
-        (status = 204, description = "Coworking successfully deleted"),
-        (status = 403, description = "Not admin"),
+        (status = 204, description = "Building successfully deleted"),
+        (status = 403, description = "No `manage_layout` permission"),
         (status = 404, description = "No such building"),
     ),
     security(
@@ -186,27 +238,57 @@ pub async fn patch_building(
 )]
 pub async fn delete_building(
     headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
     Path(building_id): Path<Uuid>,
     State(state): State<AppState>,
 ) -> Result<StatusCode, ProdError> {
     let mut conn = state.pool.conn().await?;
-    let Claims { company_id, .. } = claims_from_headers(&headers)?;
+    let claims = claims_from_headers(&headers)?;
+    let company_id = claims.company_id;
 
-    let _ = sqlx::query_as!(
-        BuildingModel,
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::Building, building_id).await?;
+
+    let deleted_at = sqlx::query_scalar!(
         r#"
-        DELETE FROM buildings
-        WHERE company_id = $1 AND id = $2
-        RETURNING id, address, company_id"#,
+        UPDATE buildings SET deleted_at = NOW()
+        WHERE company_id = $1 AND id = $2 AND deleted_at IS NULL
+        RETURNING deleted_at as "deleted_at!"
+        "#,
         company_id,
         building_id
     )
-    .fetch_one(conn.as_mut())
+    .fetch_one(tx.as_mut())
     .await
     .map_err(|err| match err {
         sqlx::Error::RowNotFound => ProdError::NotFound("No such building".to_string()),
         _ => ProdError::DatabaseError(err),
     })?;
 
+    // Same timestamp as the building, so restoring it brings back only these coworkings.
+    sqlx::query!(
+        r#"
+        UPDATE coworking_spaces SET deleted_at = $2
+        WHERE building_id = $1 AND deleted_at IS NULL
+        "#,
+        building_id,
+        deleted_at
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::Building, building_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("building.delete", AuditEntity::Building, building_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
     Ok(StatusCode::NO_CONTENT)
 }
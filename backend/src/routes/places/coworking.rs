@@ -1,14 +1,18 @@
-use crate::models::Point;
+use crate::controllers::audit::{record, snapshot, AuditEntity, AuditEntry};
+use crate::controllers::layout::{coworking_footprint, insert_coworking, obstacle_cells};
+use crate::controllers::teams::{company_teams, set_coworking_teams};
+use crate::forms::teams::RestrictToTeamsForm;
+use crate::middlewares::RequestMeta;
 use axum::{
     extract::{Path, State},
     http::{HeaderMap, StatusCode},
-    Json,
+    Extension, Json,
 };
 use sqlx::Acquire;
 use uuid::Uuid;
 
 use crate::forms::places::coworking::UpdateCoworkingForm;
-use crate::models::{BookingModel, Coordinates};
+use crate::models::{BookingModel, TeamModel};
 use crate::util::ValidatedJson;
 use crate::{
     db::Db,
@@ -31,8 +35,8 @@ use crate::{
     responses(
         (status = 201, body = CoworkingSpacesModel, description = "Successully create coworking"),
         (status = 400, description = "Wrong request"),
-        (status = 403, description = "You are not an admin"),
-        (status = 404, description = "No such building")
+        (status = 403, description = "You have no `manage_layout` permission"),
+        (status = 404, description = "No such building / floor")
     ),
     security(
         ("bearerAuth" = [])
@@ -40,34 +44,28 @@ use crate::{
 )]
 pub async fn create_coworking(
     headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
     Path(building_id): Path<Uuid>,
     State(state): State<AppState>,
     ValidatedJson(form): ValidatedJson<CreateCoworkingForm>,
 ) -> Result<(StatusCode, Json<CoworkingSpacesModel>), ProdError> {
     let mut conn = state.pool.conn().await?;
-    let Claims { company_id, .. } = claims_from_headers(&headers)?;
+    let claims = claims_from_headers(&headers)?;
 
-    let coworking = sqlx::query_as!(
-        CoworkingSpacesModel,
-        r#"
-        INSERT INTO coworking_spaces (address,height, width, building_id, company_id)
-        VALUES ($1, $2, $3, $4, $5)
-        RETURNING id, address, height, width, building_id, company_id
-        "#,
-        form.address,
-        form.height,
-        form.width,
-        building_id,
-        company_id
+    let mut tx = conn.begin().await?;
+
+    let coworking = insert_coworking(tx.as_mut(), building_id, &claims, &form).await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::Coworking, coworking.id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("coworking.create", AuditEntity::Coworking, coworking.id).after(after),
     )
-    .fetch_one(conn.as_mut())
-    .await
-    .map_err(|err| match err {
-        sqlx::Error::Database(e) if e.is_foreign_key_violation() => {
-            ProdError::NotFound("No such building".to_string())
-        }
-        _ => ProdError::DatabaseError(err),
-    })?;
+    .await?;
+
+    tx.commit().await?;
 
     Ok((StatusCode::CREATED, Json(coworking)))
 }
@@ -100,10 +98,11 @@ pub async fn list_coworkings_by_building(
     let coworkings = sqlx::query_as!(
         CoworkingSpacesModel,
         r#"
-        SELECT id, address, height, width, building_id, company_id
+        SELECT id, address, height, width, building_id, floor_id, company_id
         FROM coworking_spaces
         WHERE building_id = $1
         AND company_id = $2
+        AND deleted_at IS NULL
         "#,
         building_id,
         company_id
@@ -118,6 +117,51 @@ pub async fn list_coworkings_by_building(
     Ok(Json(coworkings))
 }
 
+/// List coworkings by floor (everybody can use)
+#[utoipa::path(
+    get,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/floor/{floor_id}/coworking/list",
+    params(
+        ("building_id" = Uuid, Path),
+        ("floor_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = Vec<CoworkingSpacesModel>, description = "List of coworkings on the floor"),
+        (status = 403, description = "No auth"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_coworkings_by_floor(
+    headers: HeaderMap,
+    Path((building_id, floor_id)): Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<CoworkingSpacesModel>>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let Claims { company_id, .. } = claims_from_headers(&headers)?;
+
+    let coworkings = sqlx::query_as!(
+        CoworkingSpacesModel,
+        r#"
+        SELECT id, address, height, width, building_id, floor_id, company_id
+        FROM coworking_spaces
+        WHERE building_id = $1
+        AND floor_id = $2
+        AND company_id = $3
+        AND deleted_at IS NULL
+        "#,
+        building_id,
+        floor_id,
+        company_id
+    )
+    .fetch_all(conn.as_mut())
+    .await?;
+
+    Ok(Json(coworkings))
+}
+
 /// List coworkings (everybody can use)
 #[utoipa::path(
     get,
@@ -142,9 +186,9 @@ pub async fn list_coworkings(
     let coworkings = sqlx::query_as!(
         CoworkingSpacesModel,
         r#"
-        SELECT id, address, height, width, building_id, company_id
+        SELECT id, address, height, width, building_id, floor_id, company_id
         FROM coworking_spaces
-        WHERE company_id = $1
+        WHERE company_id = $1 AND deleted_at IS NULL
         "#,
         company_id,
     )
@@ -187,11 +231,12 @@ pub async fn get_coworking_by_id(
     let coworkings = sqlx::query_as!(
         CoworkingSpacesModel,
         r#"
-        SELECT id, address, height, width, building_id, company_id
+        SELECT id, address, height, width, building_id, floor_id, company_id
         FROM coworking_spaces
         WHERE building_id = $1
         AND company_id = $2
         AND id = $3
+        AND deleted_at IS NULL
         "#,
         building_id,
         company_id,
@@ -219,8 +264,8 @@ pub async fn get_coworking_by_id(
     request_body=UpdateCoworkingForm,
     responses(
         (status = 200, body = CoworkingSpacesModel, description = "Updated coworking model"),
-        (status = 403, description = "You are not an admin"),
-        (status = 404, description = "No such coworking or building found"),
+        (status = 403, description = "You have no `manage_layout` permission"),
+        (status = 404, description = "No such coworking or building found / no such floor in the building"),
         (status = 409, description = "New height or width makes some objects inaccessible")
     ),
     security(
@@ -229,20 +274,22 @@ pub async fn get_coworking_by_id(
 )]
 pub async fn patch_coworking(
     headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
     Path((building_id, coworking_id)): Path<(Uuid, Uuid)>,
     State(state): State<AppState>,
     ValidatedJson(form): ValidatedJson<UpdateCoworkingForm>,
 ) -> Result<Json<CoworkingSpacesModel>, ProdError> {
     let mut conn = state.pool.conn().await?;
-    let Claims { company_id, .. } = claims_from_headers(&headers)?;
+    let claims = claims_from_headers(&headers)?;
+    let company_id = claims.company_id;
     let mut tx = conn.begin().await?;
 
     let _ = sqlx::query_as!(
         CoworkingSpacesModel,
         r#"
-        SELECT id, address, height, width, building_id, company_id
+        SELECT id, address, height, width, building_id, floor_id, company_id
         FROM coworking_spaces
-        WHERE building_id = $1 AND id = $2 AND company_id = $3
+        WHERE building_id = $1 AND id = $2 AND company_id = $3 AND deleted_at IS NULL
         "#,
         building_id,
         coworking_id,
@@ -256,37 +303,22 @@ pub async fn patch_coworking(
         }
         _ => ProdError::DatabaseError(err),
     })?;
+    let before = snapshot(tx.as_mut(), AuditEntity::Coworking, coworking_id).await?;
 
-    let coordinates = sqlx::query_as!(
-        Coordinates,
-        r#"
-        SELECT c.base_point as "base_point: Point", i.offsets as "offsets: Vec<Point>"
-        FROM coworking_items c
-        JOIN item_types i ON i.id = c.item_id
-        WHERE c.coworking_id = $1
-        "#,
-        coworking_id
-    )
-    .fetch_all(tx.as_mut())
-    .await?;
-
-    let mut abs_coords: Vec<Point> = Vec::new();
-    for coord in coordinates {
-        for offset in coord.offsets {
-            abs_coords.push(coord.base_point.clone() + offset);
-        }
-    }
+    let mut abs_coords = coworking_footprint(tx.as_mut(), coworking_id).await?;
+    abs_coords.extend(obstacle_cells(tx.as_mut(), coworking_id).await?);
     if let Some(height) = form.height {
         if abs_coords.iter().any(|p| p.y >= height as i64) {
             return Err(ProdError::Conflict(
-                "New height makes some items inaccessible. Delete those objects first.".to_string(),
+                "New height makes some items or obstacles inaccessible. Delete those objects first.".to_string(),
             ));
         }
     }
     if let Some(width) = form.width {
         if abs_coords.iter().any(|p| p.x >= width as i64) {
             return Err(ProdError::Conflict(
-                "New width makes some items inaccessible. Delete those objects first.".to_string(),
+                "New width makes some items or obstacles inaccessible. Delete those objects first."
+                    .to_string(),
             ));
         }
     }
@@ -296,29 +328,48 @@ pub async fn patch_coworking(
         r#"UPDATE coworking_spaces SET
         address = COALESCE($1, address),
         height = COALESCE($2, height),
-        width = COALESCE($3, width)
+        width = COALESCE($3, width),
+        floor_id = COALESCE($7, floor_id)
         WHERE
-        company_id = $4 AND building_id = $5 AND id = $6
-        RETURNING id, address, company_id, building_id, height, width"#,
+        company_id = $4 AND building_id = $5 AND id = $6 AND deleted_at IS NULL
+        RETURNING id, address, company_id, building_id, floor_id, height, width"#,
         form.address,
         form.height,
         form.width,
         company_id,
         building_id,
-        coworking_id
+        coworking_id,
+        form.floor_id
     )
     .fetch_one(tx.as_mut())
     .await
     .map_err(|err| match err {
         sqlx::Error::RowNotFound => ProdError::NotFound("No such coworking".to_string()),
+        // The floor has to be in the same building.
+        sqlx::Error::Database(e) if e.is_foreign_key_violation() => {
+            ProdError::NotFound("No such floor".to_string())
+        }
         _ => ProdError::DatabaseError(err),
     })?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::Coworking, coworking_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("coworking.update", AuditEntity::Coworking, coworking_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
     tx.commit().await?;
 
     Ok(Json(coworking))
 }
 
 /// Delete coworking
+///
+/// Moves the coworking to the trash, it can be restored with its items and bookings until the retention period ends.
 #[utoipa::path(
     delete,
     tag = "Coworkings",
@@ -329,7 +380,7 @@ pub async fn patch_coworking(
     ),
     responses(
         (status = 204, description = "Coworking successfully deleted"),
-        (status = 403, description = "You are not an admin"),
+        (status = 403, description = "You have no `manage_layout` permission"),
         (status = 404, description = "No such coworking or building found")
     ),
     security(
@@ -338,31 +389,122 @@ pub async fn patch_coworking(
 )]
 pub async fn delete_coworking(
     headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
     Path((building_id, coworking_id)): Path<(Uuid, Uuid)>,
     State(state): State<AppState>,
 ) -> Result<StatusCode, ProdError> {
     let mut conn = state.pool.conn().await?;
-    let Claims { company_id, .. } = claims_from_headers(&headers)?;
+    let claims = claims_from_headers(&headers)?;
+    let company_id = claims.company_id;
+
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::Coworking, coworking_id).await?;
 
     let _ = sqlx::query_as!(
         CoworkingSpacesModel,
-        r#"DELETE FROM coworking_spaces
-        WHERE company_id = $1 AND building_id = $2 AND id = $3
-        RETURNING id, address, company_id, building_id, height, width"#,
+        r#"UPDATE coworking_spaces SET deleted_at = NOW()
+        WHERE company_id = $1 AND building_id = $2 AND id = $3 AND deleted_at IS NULL
+        RETURNING id, address, company_id, building_id, floor_id, height, width"#,
         company_id,
         building_id,
         coworking_id
     )
-    .fetch_one(conn.as_mut())
+    .fetch_one(tx.as_mut())
     .await
     .map_err(|err| match err {
         sqlx::Error::RowNotFound => ProdError::NotFound("No such coworking".to_string()),
         _ => ProdError::DatabaseError(err),
     })?;
 
+    let after = snapshot(tx.as_mut(), AuditEntity::Coworking, coworking_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("coworking.delete", AuditEntity::Coworking, coworking_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Reserve coworking for teams
+///
+/// Only members of the teams and their departments can book in the coworking, an empty list opens it to everybody.
+#[utoipa::path(
+    put,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/coworking/{coworking_id}/teams",
+    request_body = RestrictToTeamsForm,
+    params(
+        ("building_id" = Uuid, Path),
+        ("coworking_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = Vec<TeamModel>, description = "Teams the coworking is reserved for"),
+        (status = 403, description = "You have no `manage_layout` permission"),
+        (status = 404, description = "No such coworking / building / team")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn put_coworking_teams(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path((building_id, coworking_id)): Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+    Json(form): Json<RestrictToTeamsForm>,
+) -> Result<Json<Vec<TeamModel>>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut team_ids = form.team_ids;
+    team_ids.sort_unstable();
+    team_ids.dedup();
+
+    let mut tx = conn.begin().await?;
+
+    let _ = sqlx::query!(
+        r#"
+        SELECT id FROM coworking_spaces
+        WHERE company_id = $1 AND building_id = $2 AND id = $3 AND deleted_at IS NULL
+        "#,
+        claims.company_id,
+        building_id,
+        coworking_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such coworking".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+    let teams = company_teams(tx.as_mut(), claims.company_id, &team_ids).await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::Coworking, coworking_id).await?;
+
+    set_coworking_teams(tx.as_mut(), coworking_id, &team_ids).await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::Coworking, coworking_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("coworking.reserve", AuditEntity::Coworking, coworking_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(teams))
+}
+
 /// Get coworking bookings by id (everybody can use)
 #[utoipa::path(
     get,
@@ -390,7 +532,7 @@ pub async fn get_coworking_bookings(
     let Claims { company_id, .. } = claims_from_headers(&headers)?;
 
     let _ = sqlx::query!(
-        r#"SELECT id FROM coworking_spaces WHERE building_id = $1 AND id = $2 AND company_id = $3"#,
+        r#"SELECT id FROM coworking_spaces WHERE building_id = $1 AND id = $2 AND company_id = $3 AND deleted_at IS NULL"#,
         building_id,
         coworking_id,
         company_id,
@@ -407,7 +549,8 @@ pub async fn get_coworking_bookings(
     let bookings = sqlx::query_as!(
         BookingModel,
         r#"
-        SELECT id, user_id, coworking_space_id, coworking_item_id, company_id, time_start, time_end
+        SELECT id, user_id, coworking_space_id, coworking_item_id, company_id, time_start, time_end,
+               attendees
         FROM bookings
         WHERE company_id = $1
         AND coworking_space_id = $2
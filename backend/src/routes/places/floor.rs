@@ -0,0 +1,337 @@
+use crate::controllers::audit::{record, snapshot, AuditEntity, AuditEntry};
+use crate::forms::places::floor::{CreateFloorForm, UpdateFloorForm};
+use crate::jwt::generate::claims_from_headers;
+use crate::jwt::models::Claims;
+use crate::middlewares::RequestMeta;
+use crate::util::ValidatedJson;
+use crate::{db::Db, errors::ProdError, models::FloorModel, AppState};
+use axum::extract::Path;
+use axum::Extension;
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use sqlx::Acquire;
+use uuid::Uuid;
+
+fn floor_error(err: sqlx::Error) -> ProdError {
+    match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such floor".to_string()),
+        sqlx::Error::Database(e) if e.is_unique_violation() => {
+            ProdError::Conflict("The building already has a floor with this number".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    }
+}
+
+/// Create floor
+#[utoipa::path(
+    post,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/floor/new",
+    params(
+        ("building_id" = Uuid, Path)
+    ),
+    request_body = CreateFloorForm,
+    responses(
+        (status = 201, body = FloorModel, description = "Created floor"),
+        (status = 400, description = "Wrong request"),
+        (status = 403, description = "No auth / no `manage_layout` permission"),
+        (status = 404, description = "No such building"),
+        (status = 409, description = "The building already has a floor with this number"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_floor(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path(building_id): Path<Uuid>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<CreateFloorForm>,
+) -> Result<(StatusCode, Json<FloorModel>), ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+
+    let floor = sqlx::query_as!(
+        FloorModel,
+        r#"
+        INSERT INTO floors (building_id, company_id, number, name, display_order)
+        SELECT id, company_id, $3, $4, $5
+        FROM buildings
+        WHERE id = $1 AND company_id = $2 AND deleted_at IS NULL
+        RETURNING id, building_id, number, name, display_order, company_id
+        "#,
+        building_id,
+        claims.company_id,
+        form.number,
+        form.name,
+        form.display_order.unwrap_or(form.number)
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such building".to_string()),
+        _ => floor_error(err),
+    })?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::Floor, floor.id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("floor.create", AuditEntity::Floor, floor.id).after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(floor)))
+}
+
+/// List floors of building (everybody can use)
+///
+/// Ordered for display, the coworkings of each floor are listed separately.
+#[utoipa::path(
+    get,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/floor/list",
+    params(
+        ("building_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = Vec<FloorModel>, description = "List of floors"),
+        (status = 403, description = "No auth"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_floors(
+    headers: HeaderMap,
+    Path(building_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<FloorModel>>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let Claims { company_id, .. } = claims_from_headers(&headers)?;
+
+    let floors = sqlx::query_as!(
+        FloorModel,
+        r#"
+        SELECT f.id, f.building_id, f.number, f.name, f.display_order, f.company_id
+        FROM floors f
+        JOIN buildings b ON b.id = f.building_id
+        WHERE f.building_id = $1 AND f.company_id = $2 AND b.deleted_at IS NULL
+        ORDER BY f.display_order, f.number
+        "#,
+        building_id,
+        company_id
+    )
+    .fetch_all(conn.as_mut())
+    .await?;
+
+    Ok(Json(floors))
+}
+
+/// Get floor by id (everybody can use)
+#[utoipa::path(
+    get,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/floor/{floor_id}",
+    params(
+        ("building_id" = Uuid, Path),
+        ("floor_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = FloorModel, description = "Floor"),
+        (status = 403, description = "No auth"),
+        (status = 404, description = "No such floor"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_floor(
+    headers: HeaderMap,
+    Path((building_id, floor_id)): Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+) -> Result<Json<FloorModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let Claims { company_id, .. } = claims_from_headers(&headers)?;
+
+    let floor = sqlx::query_as!(
+        FloorModel,
+        r#"
+        SELECT f.id, f.building_id, f.number, f.name, f.display_order, f.company_id
+        FROM floors f
+        JOIN buildings b ON b.id = f.building_id
+        WHERE f.building_id = $1 AND f.id = $2 AND f.company_id = $3 AND b.deleted_at IS NULL
+        "#,
+        building_id,
+        floor_id,
+        company_id
+    )
+    .fetch_one(conn.as_mut())
+    .await
+    .map_err(floor_error)?;
+
+    Ok(Json(floor))
+}
+
+/// Update floor
+#[utoipa::path(
+    patch,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/floor/{floor_id}",
+    params(
+        ("building_id" = Uuid, Path),
+        ("floor_id" = Uuid, Path)
+    ),
+    request_body = UpdateFloorForm,
+    responses(
+        (status = 200, body = FloorModel, description = "Updated floor"),
+        (status = 400, description = "Wrong request"),
+        (status = 403, description = "No auth / no `manage_layout` permission"),
+        (status = 404, description = "No such floor"),
+        (status = 409, description = "The building already has a floor with this number"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn patch_floor(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path((building_id, floor_id)): Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<UpdateFloorForm>,
+) -> Result<Json<FloorModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::Floor, floor_id).await?;
+
+    let floor = sqlx::query_as!(
+        FloorModel,
+        r#"
+        UPDATE floors SET
+            number = COALESCE($4, number),
+            name = COALESCE($5, name),
+            display_order = COALESCE($6, display_order)
+        WHERE building_id = $1 AND id = $2 AND company_id = $3
+            AND building_id IN (SELECT id FROM buildings WHERE deleted_at IS NULL)
+        RETURNING id, building_id, number, name, display_order, company_id
+        "#,
+        building_id,
+        floor_id,
+        claims.company_id,
+        form.number,
+        form.name,
+        form.display_order
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(floor_error)?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::Floor, floor_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("floor.update", AuditEntity::Floor, floor_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(floor))
+}
+
+/// Delete floor
+///
+/// Only empty floors can be deleted, coworkings of the floor that are in the trash have to be restored and
+/// moved or purged first.
+#[utoipa::path(
+    delete,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/floor/{floor_id}",
+    params(
+        ("building_id" = Uuid, Path),
+        ("floor_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 204, description = "Floor successfully deleted"),
+        (status = 403, description = "No auth / no `manage_layout` permission"),
+        (status = 404, description = "No such floor"),
+        (status = 409, description = "The floor still has coworkings, in the trash or not"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_floor(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path((building_id, floor_id)): Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+
+    let has_coworkings = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM coworking_spaces WHERE floor_id = f.id
+        ) as "has_coworkings!"
+        FROM floors f
+        WHERE f.building_id = $1 AND f.id = $2 AND f.company_id = $3
+        FOR UPDATE
+        "#,
+        building_id,
+        floor_id,
+        claims.company_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(floor_error)?;
+
+    if has_coworkings {
+        return Err(ProdError::Conflict(
+            "The floor still has coworkings, maybe in the trash. Move or purge them first."
+                .to_string(),
+        ));
+    }
+
+    let before = snapshot(tx.as_mut(), AuditEntity::Floor, floor_id).await?;
+
+    sqlx::query!(r#"DELETE FROM floors WHERE id = $1"#, floor_id)
+        .execute(tx.as_mut())
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::Database(e) if e.is_foreign_key_violation() => {
+                ProdError::Conflict("The floor still has coworkings".to_string())
+            }
+            _ => ProdError::DatabaseError(err),
+        })?;
+
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("floor.delete", AuditEntity::Floor, floor_id).before(before),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
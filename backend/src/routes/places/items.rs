@@ -1,20 +1,34 @@
+use crate::controllers::attributes::{attribute_values, set_item_attribute_values};
+use crate::controllers::audit::{record, snapshot, AuditEntity, AuditEntry};
+use crate::controllers::layout::versions::{
+    create_draft, layout_version, publish, put_version_items,
+};
+use crate::controllers::layout::{
+    coworking_footprint, coworking_space, obstacle_cells, other_items_footprint, out_of_bounds,
+    placed_footprint, placed_items, require_bookings_fit, retire_items,
+};
+use crate::controllers::teams::{company_teams, set_item_teams};
 use crate::db::Db;
 use crate::errors::ProdError;
-use crate::forms::items::CreateItemForm;
+use crate::forms::items::{CreateItemForm, DraftItemForm, PutItemForm, UpdateItemForm};
+use crate::forms::teams::RestrictToTeamsForm;
 use crate::jwt::generate::claims_from_headers;
-use crate::models::{Coordinates, CoworkingItemsModel, CoworkingSpacesModel};
-use crate::models::{Offsets, Point};
+use crate::middlewares::RequestMeta;
+use crate::models::{CoworkingItemsModel, CoworkingSpacesModel, Point, TeamModel};
 use crate::util::ValidatedJson;
 use crate::AppState;
 use axum::extract::{Path, State};
 use axum::http::{HeaderMap, StatusCode};
-use axum::Json;
+use axum::{Extension, Json};
 use sqlx::Acquire;
-use std::collections::HashSet;
+use std::collections::HashMap;
 use tracing::info;
 use uuid::Uuid;
 
 /// List all items from coworking by id (everybody can use)
+///
+/// Items reserved for teams the user isn't in have `can_book` unset. `footprint` lists the cells
+/// each item covers, so clients don't have to turn the offsets of its type themselves.
 #[utoipa::path(
     get,
     tag = "Coworkings",
@@ -38,43 +52,32 @@ pub async fn get_items_by_coworking(
     Path((building_id, coworking_id)): Path<(Uuid, Uuid)>,
 ) -> Result<Json<Vec<CoworkingItemsModel>>, ProdError> {
     let mut conn = state.pool.conn().await?;
-    let company_id = claims_from_headers(&headers)?.company_id;
+    let claims = claims_from_headers(&headers)?;
 
-    let items = sqlx::query_as!(
-        CoworkingItemsModel,
-        r#"
-        SELECT ci.id, ci.item_id, ci.name, ci.description,
-               ci.base_point as "base_point: Point"
-        FROM coworking_items ci
-        JOIN buildings b ON b.id = $1
-        JOIN coworking_spaces c ON c.id = $2 AND c.building_id = b.id
-        WHERE c.company_id = $3 AND ci.coworking_id = $2
-        "#,
-        building_id,
-        coworking_id,
-        company_id
-    )
-    .fetch_all(conn.as_mut())
-    .await?;
+    let items = placed_items(conn.as_mut(), building_id, coworking_id, &claims).await?;
 
     Ok(Json(items))
 }
 
 /// Put new items' positions in coworking
+///
+/// Publishes the items as a new layout version. Pass the `id` of a placed item to move it with
+/// its bookings, items left out are taken off the layout and their upcoming bookings cancelled.
 #[utoipa::path(
     put,
     tag = "Coworkings",
     path = "/backend_api/place/{building_id}/coworking/{coworking_id}/items/put",
-    request_body = Vec<CreateItemForm>,
+    request_body = Vec<PutItemForm>,
     params(
         ("building_id" = Uuid, Path),
         ("coworking_id" = Uuid, Path)
     ),
     responses(
-        (status = 201, body = Vec<CoworkingItemsModel>, description = "New items"),
-        (status = 403, description = "no auth / not admin"),
-        (status = 404, description = "No such coworking / building"),
-        (status = 409, description = "Items overlaps with borders / other items")
+        (status = 201, body = Vec<CoworkingItemsModel>, description = "Items of the coworking"),
+        (status = 400, description = "Wrong request"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 404, description = "No such coworking / building / item type / item"),
+        (status = 409, description = "Items overlaps with borders / other items / obstacles / upcoming bookings don't fit new item types")
     ),
     security(
         ("bearerAuth" = [])
@@ -82,109 +85,43 @@ pub async fn get_items_by_coworking(
 )]
 pub async fn put_items_in_coworking(
     headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
     State(state): State<AppState>,
     Path((building_id, coworking_id)): Path<(Uuid, Uuid)>,
-    ValidatedJson(form): ValidatedJson<Vec<CreateItemForm>>,
+    ValidatedJson(form): ValidatedJson<Vec<PutItemForm>>,
 ) -> Result<(StatusCode, Json<Vec<CoworkingItemsModel>>), ProdError> {
     let mut conn = state.pool.conn().await?;
-    let company_id = claims_from_headers(&headers)?.company_id;
+    let claims = claims_from_headers(&headers)?;
 
     let mut tx = conn.begin().await?;
 
-    let space = sqlx::query_as!(
-        CoworkingSpacesModel,
-        r#"
-        SELECT id, address, height, width, building_id, company_id
-        FROM coworking_spaces
-        WHERE building_id = $1 AND id = $2 AND company_id = $3
-        "#,
-        building_id,
-        coworking_id,
-        company_id
-    )
-    .fetch_one(tx.as_mut())
-    .await
-    .map_err(|err| match err {
-        sqlx::Error::RowNotFound => {
-            ProdError::NotFound("No such coworking or building exists".to_string())
-        }
-        _ => ProdError::DatabaseError(err),
-    })?;
+    let space = coworking_space(tx.as_mut(), building_id, coworking_id, claims.company_id).await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::Layout, coworking_id).await?;
 
-    let _ = sqlx::query!(
-        r#"
-        DELETE
-        FROM coworking_items c
-        WHERE c.coworking_id = $1
-        "#,
-        coworking_id
-    )
-    .execute(tx.as_mut())
-    .await?;
-
-    let mut created_items: Vec<CoworkingItemsModel> = Vec::new();
-    let mut abs_coords: Vec<Point> = Vec::new();
-    for item in form {
-        let item_offsets = sqlx::query_as!(
-            Offsets,
-            r#"
-        SELECT i.offsets as "offsets: Vec<Point>"
-        FROM item_types i
-        WHERE i.id = $1
-        "#,
-            item.item_id
-        )
-        .fetch_one(tx.as_mut())
-        .await?;
+    let version_id =
+        create_draft(tx.as_mut(), coworking_id, claims.company_id, claims.user_id).await?;
+    let items = form
+        .into_iter()
+        .map(|item| DraftItemForm {
+            key: item.id,
+            item: item.item,
+        })
+        .collect();
+    put_version_items(tx.as_mut(), &space, version_id, items).await?;
+    let version = layout_version(tx.as_mut(), coworking_id, version_id, claims.company_id).await?;
+    publish(tx.as_mut(), &version, Some(claims.user_id)).await?;
 
-        let mut item_coords: Vec<Point> = Vec::new();
-        for offset in item_offsets.offsets {
-            item_coords.push(item.base_point.clone() + offset);
-        }
-        abs_coords.extend(item_coords.clone());
-
-        if item_coords
-            .iter()
-            .any(|p| p.x >= space.width || p.y >= space.height)
-        {
-            return Err(ProdError::Conflict(
-                "Item overlaps with borders".to_string(),
-            ));
-        }
+    let items = placed_items(tx.as_mut(), building_id, coworking_id, &claims).await?;
 
-        let item = sqlx::query_as::<_, CoworkingItemsModel>(&format!(
-            r"
-        INSERT INTO coworking_items
-        (name, description, item_id, base_point, coworking_id)
-        VALUES  ($1, $2, $3, {}::point, $4)
-        RETURNING id, item_id, name, description,
-                  base_point
-        ",
-            format!("point({}, {})", item.base_point.x, item.base_point.y)
-        ))
-        .bind(item.name)
-        .bind(item.description)
-        .bind(item.item_id)
-        .bind(coworking_id)
-        .fetch_one(tx.as_mut())
-        .await
-        .map_err(|err| {
-            info!("returned {:?}", err);
-            ProdError::DatabaseError(err)
-        })?;
-        created_items.push(item);
-    }
-
-    let unique_coords: HashSet<Point> = abs_coords.clone().into_iter().collect();
-    if abs_coords.len() != unique_coords.len() {
-        return Err(ProdError::Conflict(
-            "Item overlaps with other item".to_string(),
-        ));
-    }
+    let after = snapshot(tx.as_mut(), AuditEntity::Layout, coworking_id).await?;
+    let entry = AuditEntry::new("layout.replace", AuditEntity::Layout, coworking_id)
+        .before(before)
+        .after(after);
+    record(tx.as_mut(), &meta, &claims, entry).await?;
 
     tx.commit().await?;
 
-    Ok((StatusCode::CREATED, Json(created_items)))
+    Ok((StatusCode::CREATED, Json(items)))
 }
 
 /// Create new item in coworking
@@ -199,9 +136,9 @@ pub async fn put_items_in_coworking(
     ),
     responses(
         (status = 201, body = CoworkingItemsModel, description = "Created item"),
-        (status = 403, description = "no auth / not admin"),
-        (status = 404, description = "No such coworking / building"),
-        (status = 409, description = "Item overlaps with borders / other items")
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 404, description = "No such coworking / building / item type"),
+        (status = 409, description = "Item overlaps with borders / other items / obstacles")
     ),
     security(
         ("bearerAuth" = [])
@@ -209,21 +146,23 @@ pub async fn put_items_in_coworking(
 )]
 pub async fn add_item_to_coworking(
     headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
     State(state): State<AppState>,
     Path((building_id, coworking_id)): Path<(Uuid, Uuid)>,
     ValidatedJson(form): ValidatedJson<CreateItemForm>,
 ) -> Result<(StatusCode, Json<CoworkingItemsModel>), ProdError> {
     let mut conn = state.pool.conn().await?;
-    let company_id = claims_from_headers(&headers)?.company_id;
+    let claims = claims_from_headers(&headers)?;
+    let company_id = claims.company_id;
 
     let mut tx = conn.begin().await?;
 
     let space = sqlx::query_as!(
         CoworkingSpacesModel,
         r#"
-        SELECT id, address, height, width, building_id, company_id
+        SELECT id, address, height, width, building_id, floor_id, company_id
         FROM coworking_spaces
-        WHERE building_id = $1 AND id = $2 AND company_id = $3
+        WHERE building_id = $1 AND id = $2 AND company_id = $3 AND deleted_at IS NULL
         "#,
         building_id,
         coworking_id,
@@ -238,62 +177,37 @@ pub async fn add_item_to_coworking(
         _ => ProdError::DatabaseError(err),
     })?;
 
-    let coordinates = sqlx::query_as!(
-        Coordinates,
-        r#"
-        SELECT c.base_point as "base_point: Point", i.offsets as "offsets: Vec<Point>"
-        FROM coworking_items c
-        JOIN item_types i ON i.id = c.item_id
-        WHERE c.coworking_id = $1
-        "#,
-        coworking_id
-    )
-    .fetch_all(tx.as_mut())
-    .await?;
+    let taken = coworking_footprint(tx.as_mut(), coworking_id).await?;
+    let item_coords = placed_footprint(tx.as_mut(), &form).await?;
 
-    let item_offsets = sqlx::query_as!(
-        Offsets,
-        r#"
-        SELECT i.offsets as "offsets: Vec<Point>"
-        FROM item_types i
-        WHERE i.id = $1
-        "#,
-        form.item_id
-    )
-    .fetch_one(tx.as_mut())
-    .await?;
-
-    let mut item_coords: Vec<Point> = Vec::new();
-    for offset in item_offsets.offsets {
-        item_coords.push(form.base_point.clone() + offset);
+    if out_of_bounds(&item_coords, space.width, space.height) {
+        return Err(ProdError::Conflict(
+            "Item overlaps with borders".to_string(),
+        ));
     }
 
-    if item_coords
-        .iter()
-        .any(|p| p.x >= space.width || p.y >= space.height)
-    {
+    if taken.iter().any(|p| item_coords.contains(p)) {
         return Err(ProdError::Conflict(
-            "Item overlaps with borders".to_string(),
+            "Item overlaps with other item".to_string(),
         ));
     }
 
-    for coord in coordinates {
-        for offset in coord.offsets {
-            if item_coords.contains(&(coord.base_point.clone() + offset)) {
-                return Err(ProdError::Conflict(
-                    "Item overlaps with other item".to_string(),
-                ));
-            }
-        }
+    let blocked = obstacle_cells(tx.as_mut(), coworking_id).await?;
+    if blocked.iter().any(|p| item_coords.contains(p)) {
+        return Err(ProdError::Conflict(
+            "Item overlaps with obstacle".to_string(),
+        ));
     }
 
     let item = sqlx::query_as::<_, CoworkingItemsModel>(&format!(
         r"
         INSERT INTO coworking_items
-        (name, description, item_id, base_point, coworking_id)
-        VALUES  ($1, $2, $3, {}::point, $4)
-        RETURNING id, item_id, name, description,
-                  base_point
+        (name, description, item_id, base_point, coworking_id, rotation, mirrored)
+        VALUES  ($1, $2, $3, {}::point, $4, $6, $7)
+        RETURNING id, item_id, name, description, base_point, rotation, mirrored,
+                  item_footprint(base_point, (SELECT offsets FROM item_types WHERE id = $3), rotation, mirrored) as footprint,
+                  ARRAY[]::uuid[] as teams, can_book_item($5, id) as can_book,
+                  placed_item_attribute_values(id, item_id) as attributes
         ",
         format!("point({}, {})", form.base_point.x, form.base_point.y)
     ))
@@ -301,6 +215,9 @@ pub async fn add_item_to_coworking(
     .bind(form.description)
     .bind(form.item_id)
     .bind(coworking_id)
+    .bind(claims.user_id)
+    .bind(form.rotation)
+    .bind(form.mirrored)
     .fetch_one(tx.as_mut())
     .await
     .map_err(|err| {
@@ -308,12 +225,166 @@ pub async fn add_item_to_coworking(
         ProdError::DatabaseError(err)
     })?;
 
+    let after = snapshot(tx.as_mut(), AuditEntity::CoworkingItem, item.id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("coworking_item.create", AuditEntity::CoworkingItem, item.id).after(after),
+    )
+    .await?;
+
     tx.commit().await?;
 
     Ok((StatusCode::CREATED, Json(item)))
 }
 
+/// Update item in coworking
+///
+/// Moves, turns or renames a placed item, or swaps its type. The item keeps its id and bookings.
+#[utoipa::path(
+    patch,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/coworking/{coworking_id}/items/{item_id}",
+    request_body = UpdateItemForm,
+    params(
+        ("building_id" = Uuid, Path),
+        ("coworking_id" = Uuid, Path),
+        ("item_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = CoworkingItemsModel, description = "Updated item"),
+        (status = 400, description = "Wrong request"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 404, description = "No such coworking / building / item / item type"),
+        (status = 409, description = "Item overlaps with borders / other items / obstacles / upcoming bookings don't fit the new item type")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn patch_item_in_coworking(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    Path((building_id, coworking_id, item_id)): Path<(Uuid, Uuid, Uuid)>,
+    ValidatedJson(form): ValidatedJson<UpdateItemForm>,
+) -> Result<Json<CoworkingItemsModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+
+    let space = coworking_space(tx.as_mut(), building_id, coworking_id, claims.company_id).await?;
+
+    let current = sqlx::query!(
+        r#"
+        SELECT item_id, name, description, base_point as "base_point: Point", rotation, mirrored
+        FROM coworking_items
+        WHERE id = $1 AND coworking_id = $2 AND retired_at IS NULL
+        FOR UPDATE
+        "#,
+        item_id,
+        coworking_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such item exists".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    let item = CreateItemForm {
+        item_id: form.item_id.unwrap_or(current.item_id),
+        name: form.name.unwrap_or(current.name),
+        description: form.description.or(current.description),
+        base_point: form.base_point.unwrap_or(current.base_point),
+        rotation: form.rotation.unwrap_or(current.rotation),
+        mirrored: form.mirrored.unwrap_or(current.mirrored),
+    };
+
+    let item_coords = placed_footprint(tx.as_mut(), &item).await?;
+    if out_of_bounds(&item_coords, space.width, space.height) {
+        return Err(ProdError::Conflict(
+            "Item overlaps with borders".to_string(),
+        ));
+    }
+
+    let taken = other_items_footprint(tx.as_mut(), coworking_id, item_id).await?;
+    if taken.iter().any(|p| item_coords.contains(p)) {
+        return Err(ProdError::Conflict(
+            "Item overlaps with other item".to_string(),
+        ));
+    }
+
+    let blocked = obstacle_cells(tx.as_mut(), coworking_id).await?;
+    if blocked.iter().any(|p| item_coords.contains(p)) {
+        return Err(ProdError::Conflict(
+            "Item overlaps with obstacle".to_string(),
+        ));
+    }
+
+    if item.item_id != current.item_id {
+        require_bookings_fit(tx.as_mut(), item_id, item.item_id).await?;
+    }
+
+    let before = snapshot(tx.as_mut(), AuditEntity::CoworkingItem, item_id).await?;
+
+    let updated = sqlx::query_as!(
+        CoworkingItemsModel,
+        r#"
+        UPDATE coworking_items SET
+            item_id = $3,
+            name = $4,
+            description = $5,
+            base_point = point($6::bigint, $7::bigint),
+            rotation = $8,
+            mirrored = $9
+        WHERE id = $1 AND coworking_id = $2
+        RETURNING id, item_id, name, description,
+                  base_point as "base_point: Point", rotation, mirrored,
+                  item_footprint(base_point, (SELECT offsets FROM item_types WHERE id = $3), rotation, mirrored) as "footprint!: Vec<Point>",
+                  ARRAY(
+                      SELECT team_id FROM coworking_item_teams
+                      WHERE coworking_item_id = $1
+                      ORDER BY team_id
+                  ) as "teams!",
+                  can_book_item($10, id) as "can_book!",
+                  placed_item_attribute_values(id, item_id) as "attributes!"
+        "#,
+        item_id,
+        coworking_id,
+        item.item_id,
+        item.name,
+        item.description,
+        item.base_point.x,
+        item.base_point.y,
+        item.rotation,
+        item.mirrored,
+        claims.user_id
+    )
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::CoworkingItem, item_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("coworking_item.update", AuditEntity::CoworkingItem, item_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(updated))
+}
+
 /// Delete item from coworking
+///
+/// Bookings that haven't ended yet are cancelled, past ones stay in the history.
 #[utoipa::path(
     delete,
     tag = "Coworkings",
@@ -325,7 +396,7 @@ pub async fn add_item_to_coworking(
     ),
     responses(
         (status = 204, description = "Item successfully deleted"),
-        (status = 403, description = "no auth / not admin"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
         (status = 404, description = "No such coworking / building / item")
     ),
     security(
@@ -334,22 +405,26 @@ pub async fn add_item_to_coworking(
 )]
 pub async fn delete_item_from_coworking(
     headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
     State(state): State<AppState>,
     Path((building_id, coworking_id, item_id)): Path<(Uuid, Uuid, Uuid)>,
 ) -> Result<StatusCode, ProdError> {
     let mut conn = state.pool.conn().await?;
-    let company_id = claims_from_headers(&headers)?.company_id;
+    let claims = claims_from_headers(&headers)?;
+    let company_id = claims.company_id;
+
+    let mut tx = conn.begin().await?;
 
     let _ = sqlx::query!(
         r#"
         SELECT id FROM coworking_spaces
-        WHERE company_id = $1 AND building_id = $2 AND id = $3
+        WHERE company_id = $1 AND building_id = $2 AND id = $3 AND deleted_at IS NULL
         "#,
         company_id,
         building_id,
         coworking_id
     )
-    .fetch_one(conn.as_mut())
+    .fetch_one(tx.as_mut())
     .await
     .map_err(|err| match err {
         sqlx::Error::RowNotFound => {
@@ -357,22 +432,208 @@ pub async fn delete_item_from_coworking(
         }
         _ => ProdError::DatabaseError(err),
     })?;
+    let before = snapshot(tx.as_mut(), AuditEntity::CoworkingItem, item_id).await?;
 
     let _ = sqlx::query!(
         r#"
-        DELETE FROM coworking_items
-        WHERE coworking_id = $1 and id = $2
-        RETURNING id
+        SELECT id FROM coworking_items
+        WHERE coworking_id = $1 AND id = $2 AND retired_at IS NULL
+        FOR UPDATE
         "#,
         coworking_id,
         item_id
     )
-    .fetch_one(conn.as_mut())
+    .fetch_one(tx.as_mut())
     .await
     .map_err(|err| match err {
         sqlx::Error::RowNotFound => ProdError::NotFound("No such item exists".to_string()),
         _ => ProdError::DatabaseError(err),
     })?;
+    retire_items(tx.as_mut(), &[item_id]).await?;
+
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("coworking_item.delete", AuditEntity::CoworkingItem, item_id)
+            .before(before),
+    )
+    .await?;
+
+    tx.commit().await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Reserve item for teams
+///
+/// Only members of the teams and their departments can book the item, an empty list opens it to everybody.
+#[utoipa::path(
+    put,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/coworking/{coworking_id}/items/{item_id}/teams",
+    request_body = RestrictToTeamsForm,
+    params(
+        ("building_id" = Uuid, Path),
+        ("coworking_id" = Uuid, Path),
+        ("item_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = Vec<TeamModel>, description = "Teams the item is reserved for"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 404, description = "No such coworking / building / item / team")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn put_item_teams(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    Path((building_id, coworking_id, item_id)): Path<(Uuid, Uuid, Uuid)>,
+    Json(form): Json<RestrictToTeamsForm>,
+) -> Result<Json<Vec<TeamModel>>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut team_ids = form.team_ids;
+    team_ids.sort_unstable();
+    team_ids.dedup();
+
+    let mut tx = conn.begin().await?;
+
+    let _ = sqlx::query!(
+        r#"
+        SELECT ci.id
+        FROM coworking_items ci
+        JOIN coworking_spaces c ON c.id = ci.coworking_id
+        WHERE ci.id = $1 AND c.id = $2 AND c.building_id = $3 AND c.company_id = $4
+            AND ci.retired_at IS NULL AND c.deleted_at IS NULL
+        "#,
+        item_id,
+        coworking_id,
+        building_id,
+        claims.company_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => {
+            ProdError::NotFound("No such item in that coworking".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    })?;
+    let teams = company_teams(tx.as_mut(), claims.company_id, &team_ids).await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::CoworkingItem, item_id).await?;
+
+    set_item_teams(tx.as_mut(), item_id, &team_ids).await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::CoworkingItem, item_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new(
+            "coworking_item.reserve",
+            AuditEntity::CoworkingItem,
+            item_id,
+        )
+        .before(before)
+        .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(teams))
+}
+
+/// Set attributes of placed item
+///
+/// Values given here win over those of the item type, the rest still come from the type. An empty
+/// body drops all overrides.
+#[utoipa::path(
+    put,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/coworking/{coworking_id}/items/{item_id}/attributes",
+    request_body = HashMap<String, serde_json::Value>,
+    params(
+        ("building_id" = Uuid, Path),
+        ("coworking_id" = Uuid, Path),
+        ("item_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = HashMap<String, serde_json::Value>, description = "Attribute values of the item"),
+        (status = 400, description = "Unknown attribute / wrong value"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 404, description = "No such coworking / building / item")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn put_item_attributes(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    Path((building_id, coworking_id, item_id)): Path<(Uuid, Uuid, Uuid)>,
+    Json(form): Json<HashMap<String, serde_json::Value>>,
+) -> Result<Json<serde_json::Value>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+
+    let _ = sqlx::query!(
+        r#"
+        SELECT ci.id
+        FROM coworking_items ci
+        JOIN coworking_spaces c ON c.id = ci.coworking_id
+        WHERE ci.id = $1 AND c.id = $2 AND c.building_id = $3 AND c.company_id = $4
+            AND ci.retired_at IS NULL AND c.deleted_at IS NULL
+        "#,
+        item_id,
+        coworking_id,
+        building_id,
+        claims.company_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => {
+            ProdError::NotFound("No such item in that coworking".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    })?;
+    let (ids, values) = attribute_values(tx.as_mut(), claims.company_id, form).await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::CoworkingItem, item_id).await?;
+
+    set_item_attribute_values(tx.as_mut(), item_id, &ids, &values).await?;
+
+    let attributes = sqlx::query_scalar!(
+        r#"
+        SELECT placed_item_attribute_values(id, item_id) as "attributes!"
+        FROM coworking_items
+        WHERE id = $1
+        "#,
+        item_id
+    )
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::CoworkingItem, item_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("coworking_item.update", AuditEntity::CoworkingItem, item_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(attributes))
+}
@@ -0,0 +1,217 @@
+use crate::controllers::audit::{record, snapshot, AuditEntity, AuditEntry};
+use crate::controllers::layout::render::{
+    busy_items, fit_cell_size, item_looks, layout_svg, svg_to_png,
+};
+use crate::controllers::layout::{
+    coworking_footprint, coworking_obstacles, coworking_space, insert_obstacle, out_of_bounds,
+    placed_items,
+};
+use crate::db::Db;
+use crate::errors::ProdError;
+use crate::forms::items::CreateObstacleForm;
+use crate::forms::places::coworking::{RenderFormat, RenderLayoutQuery};
+use crate::jwt::generate::claims_from_headers;
+use crate::middlewares::RequestMeta;
+use crate::models::{CoworkingLayoutModel, ObstacleModel};
+use crate::util::ValidatedJson;
+use crate::AppState;
+use axum::body::Body;
+use axum::extract::{Path, Query, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::HeaderMap;
+use axum::response::Response;
+use axum::{Extension, Json};
+use sqlx::Acquire;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Get layout of coworking (everybody can use)
+///
+/// Items together with the walls, pillars, doors and windows of the coworking, enough to draw it.
+#[utoipa::path(
+    get,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/coworking/{coworking_id}/layout",
+    params(
+        ("building_id" = Uuid, Path),
+        ("coworking_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = CoworkingLayoutModel, description = "Items and obstacles of coworking"),
+        (status = 403, description = "no auth"),
+        (status = 404, description = "No such coworking / building")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_coworking_layout(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Path((building_id, coworking_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<CoworkingLayoutModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    coworking_space(conn.as_mut(), building_id, coworking_id, claims.company_id).await?;
+
+    let items = placed_items(conn.as_mut(), building_id, coworking_id, &claims).await?;
+    let obstacles = coworking_obstacles(conn.as_mut(), coworking_id).await?;
+
+    Ok(Json(CoworkingLayoutModel { items, obstacles }))
+}
+
+/// Render layout of coworking (everybody can use)
+///
+/// The coworking drawn as SVG or PNG with item icons, for e-mails, kiosks and printing.
+/// With `at` set, bookable items are tinted green when free and red when booked at that moment.
+#[utoipa::path(
+    get,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/coworking/{coworking_id}/layout/render",
+    params(
+        ("building_id" = Uuid, Path),
+        ("coworking_id" = Uuid, Path),
+        ("format" = Option<RenderFormat>, Query, description = "`svg` (default) or `png`"),
+        ("at" = Option<NaiveDateTime>, Query),
+        ("cell_size" = Option<i64>, Query, description = "Pixels per cell, 40 by default, shrunk to keep the picture within 4096 pixels")
+    ),
+    responses(
+        (status = 200, description = "Picture of coworking", content_type = "image/svg+xml"),
+        (status = 400, description = "Wrong query"),
+        (status = 403, description = "no auth"),
+        (status = 404, description = "No such coworking / building")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn render_coworking_layout(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Path((building_id, coworking_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<RenderLayoutQuery>,
+) -> Result<Response, ProdError> {
+    query.validate()?;
+
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let space =
+        coworking_space(conn.as_mut(), building_id, coworking_id, claims.company_id).await?;
+
+    let items = placed_items(conn.as_mut(), building_id, coworking_id, &claims).await?;
+    let obstacles = coworking_obstacles(conn.as_mut(), coworking_id).await?;
+    let looks = item_looks(&state, conn.as_mut(), &items).await?;
+    let busy = match query.at {
+        Some(at) => Some(busy_items(conn.as_mut(), coworking_id, at).await?),
+        None => None,
+    };
+
+    let svg = layout_svg(
+        &space,
+        &items,
+        &looks,
+        &obstacles,
+        busy.as_ref(),
+        fit_cell_size(&space, query.cell_size.unwrap_or(40)),
+    );
+
+    let (content_type, body) = match query.format {
+        RenderFormat::Svg => ("image/svg+xml", svg.into_bytes()),
+        // Rasterizing takes a while, it mustn't hold up the other requests of the worker.
+        RenderFormat::Png => (
+            "image/png",
+            tokio::task::spawn_blocking(move || svg_to_png(&svg))
+                .await
+                .map_err(|err| ProdError::Unknown(err.into()))??,
+        ),
+    };
+
+    Response::builder()
+        .header(CONTENT_TYPE, content_type)
+        .body(Body::from(body))
+        .map_err(|e| ProdError::Unknown(e.into()))
+}
+
+/// Put obstacles in coworking
+///
+/// Replaces all walls, pillars, doors and windows of the coworking. Their cells can't hold items.
+#[utoipa::path(
+    put,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/coworking/{coworking_id}/obstacles",
+    request_body = Vec<CreateObstacleForm>,
+    params(
+        ("building_id" = Uuid, Path),
+        ("coworking_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = Vec<ObstacleModel>, description = "New obstacles"),
+        (status = 400, description = "Wrong request"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 404, description = "No such coworking / building"),
+        (status = 409, description = "Obstacle overlaps with borders / items")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn put_coworking_obstacles(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    Path((building_id, coworking_id)): Path<(Uuid, Uuid)>,
+    ValidatedJson(form): ValidatedJson<Vec<CreateObstacleForm>>,
+) -> Result<Json<Vec<ObstacleModel>>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+
+    let space = coworking_space(tx.as_mut(), building_id, coworking_id, claims.company_id).await?;
+    let taken = coworking_footprint(tx.as_mut(), coworking_id).await?;
+
+    for obstacle in &form {
+        if out_of_bounds(&obstacle.cells, space.width, space.height) {
+            return Err(ProdError::Conflict(
+                "Obstacle overlaps with borders".to_string(),
+            ));
+        }
+        if obstacle.cells.iter().any(|p| taken.contains(p)) {
+            return Err(ProdError::Conflict(
+                "Obstacle overlaps with item".to_string(),
+            ));
+        }
+    }
+
+    let before = snapshot(tx.as_mut(), AuditEntity::Obstacles, coworking_id).await?;
+
+    sqlx::query!(
+        r#"DELETE FROM coworking_obstacles WHERE coworking_id = $1"#,
+        coworking_id
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    for obstacle in &form {
+        insert_obstacle(tx.as_mut(), coworking_id, obstacle).await?;
+    }
+
+    let obstacles = coworking_obstacles(tx.as_mut(), coworking_id).await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::Obstacles, coworking_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("obstacles.replace", AuditEntity::Obstacles, coworking_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(obstacles))
+}
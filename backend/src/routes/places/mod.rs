@@ -2,28 +2,94 @@ use crate::routes::places::building::{
     delete_building, get_building, list_buildings, patch_building,
 };
 use crate::routes::places::coworking::{
-    delete_coworking, get_coworking_bookings, get_coworking_by_id, list_coworkings, patch_coworking,
+    delete_coworking, get_coworking_bookings, get_coworking_by_id, list_coworkings,
+    list_coworkings_by_floor, patch_coworking, put_coworking_teams,
+};
+use crate::routes::places::floor::{
+    create_floor, delete_floor, get_floor, list_floors, patch_floor,
 };
 use crate::routes::places::items::{
     add_item_to_coworking, delete_item_from_coworking, get_items_by_coworking,
-    put_items_in_coworking,
+    patch_item_in_coworking, put_item_attributes, put_item_teams, put_items_in_coworking,
+};
+use crate::routes::places::layout::{
+    get_coworking_layout, put_coworking_obstacles, render_coworking_layout,
+};
+use crate::routes::places::portable::{
+    clone_coworking, export_coworking_layout, import_coworking_layout,
+};
+use crate::routes::places::versions::{
+    create_layout_draft, delete_layout_version, get_layout_diff, get_layout_version,
+    list_layout_versions, publish_layout_version, put_layout_draft_items, rollback_layout_version,
+};
+use crate::{
+    middlewares::{require_permissions, RequiredPermissions},
+    models::Permission,
+    AppState,
 };
-use crate::{middlewares::auth_admin, AppState};
 use axum::routing::{delete, get, patch, post, put};
-use axum::{middleware::from_fn, Router};
+use axum::{middleware::from_fn_with_state, Router};
 use building::create_building;
 use coworking::{create_coworking, list_coworkings_by_building};
 
 pub mod building;
 pub mod coworking;
+pub mod floor;
 pub mod items;
+pub mod layout;
+pub mod portable;
+pub mod versions;
+
+/// Drafts and versions of coworking layouts, for those who can manage the layout.
+fn layout_version_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/{building_id}/coworking/{coworking_id}/layout/versions",
+            get(list_layout_versions).post(create_layout_draft),
+        )
+        .route(
+            "/{building_id}/coworking/{coworking_id}/layout/versions/{version_id}",
+            get(get_layout_version).delete(delete_layout_version),
+        )
+        .route(
+            "/{building_id}/coworking/{coworking_id}/layout/versions/{version_id}/items",
+            put(put_layout_draft_items),
+        )
+        .route(
+            "/{building_id}/coworking/{coworking_id}/layout/versions/{version_id}/diff",
+            get(get_layout_diff),
+        )
+        .route(
+            "/{building_id}/coworking/{coworking_id}/layout/versions/{version_id}/publish",
+            post(publish_layout_version),
+        )
+        .route(
+            "/{building_id}/coworking/{coworking_id}/layout/versions/{version_id}/rollback",
+            post(rollback_layout_version),
+        )
+}
 
 pub fn get_routes(state: AppState) -> Router {
     let admin_routes = Router::new()
         .route("/new", post(create_building))
         .route("/{building_id}", patch(patch_building))
         .route("/{building_id}", delete(delete_building))
+        .route("/{building_id}/floor/new", post(create_floor))
+        .route("/{building_id}/floor/{floor_id}", patch(patch_floor))
+        .route("/{building_id}/floor/{floor_id}", delete(delete_floor))
         .route("/{building_id}/coworking/new", post(create_coworking))
+        .route(
+            "/{building_id}/coworking/import",
+            post(import_coworking_layout),
+        )
+        .route(
+            "/{building_id}/coworking/{coworking_id}/clone",
+            post(clone_coworking),
+        )
+        .route(
+            "/{building_id}/coworking/{coworking_id}/layout/export",
+            get(export_coworking_layout),
+        )
         .route(
             "/{building_id}/coworking/{coworking_id}",
             patch(patch_coworking),
@@ -32,6 +98,10 @@ pub fn get_routes(state: AppState) -> Router {
             "/{building_id}/coworking/{coworking_id}",
             delete(delete_coworking),
         )
+        .route(
+            "/{building_id}/coworking/{coworking_id}/teams",
+            put(put_coworking_teams),
+        )
         .route(
             "/{building_id}/coworking/{coworking_id}/items/new",
             post(add_item_to_coworking),
@@ -40,11 +110,31 @@ pub fn get_routes(state: AppState) -> Router {
             "/{building_id}/coworking/{coworking_id}/items/put",
             put(put_items_in_coworking),
         )
+        .route(
+            "/{building_id}/coworking/{coworking_id}/items/{item_id}",
+            patch(patch_item_in_coworking),
+        )
         .route(
             "/{building_id}/coworking/{coworking_id}/items/{item_id}",
             delete(delete_item_from_coworking),
         )
-        .layer(from_fn(auth_admin))
+        .route(
+            "/{building_id}/coworking/{coworking_id}/items/{item_id}/teams",
+            put(put_item_teams),
+        )
+        .route(
+            "/{building_id}/coworking/{coworking_id}/items/{item_id}/attributes",
+            put(put_item_attributes),
+        )
+        .route(
+            "/{building_id}/coworking/{coworking_id}/obstacles",
+            put(put_coworking_obstacles),
+        )
+        .merge(layout_version_routes())
+        .layer(from_fn_with_state(
+            RequiredPermissions::new(&state, &[Permission::ManageLayout]),
+            require_permissions,
+        ))
         .with_state(state.clone());
 
     Router::new()
@@ -55,6 +145,12 @@ pub fn get_routes(state: AppState) -> Router {
             "/{building_id}/coworking/list",
             get(list_coworkings_by_building),
         )
+        .route("/{building_id}/floor/list", get(list_floors))
+        .route("/{building_id}/floor/{floor_id}", get(get_floor))
+        .route(
+            "/{building_id}/floor/{floor_id}/coworking/list",
+            get(list_coworkings_by_floor),
+        )
         .route(
             "/{building_id}/coworking/{coworking_id}",
             get(get_coworking_by_id),
@@ -63,6 +159,14 @@ pub fn get_routes(state: AppState) -> Router {
             "/{building_id}/coworking/{coworking_id}/items",
             get(get_items_by_coworking),
         )
+        .route(
+            "/{building_id}/coworking/{coworking_id}/layout",
+            get(get_coworking_layout),
+        )
+        .route(
+            "/{building_id}/coworking/{coworking_id}/layout/render",
+            get(render_coworking_layout),
+        )
         .route(
             "/{building_id}/coworking/{coworking_id}/bookings",
             get(get_coworking_bookings),
@@ -0,0 +1,170 @@
+use crate::controllers::layout::coworking_space;
+use crate::controllers::layout::portable::{export_layout, import_layout};
+use crate::db::Db;
+use crate::errors::ProdError;
+use crate::forms::places::coworking::{
+    CloneCoworkingForm, ImportLayoutForm, ImportLayoutQuery, LayoutDocument, LayoutImportReport,
+};
+use crate::jwt::generate::claims_from_headers;
+use crate::middlewares::RequestMeta;
+use crate::util::ValidatedJson;
+use crate::AppState;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::{Extension, Json};
+use sqlx::Acquire;
+use uuid::Uuid;
+
+/// Export layout of coworking
+///
+/// Dimensions, item types in use with their icons, placed items and obstacles as a portable
+/// document that can be imported into another building or company.
+#[utoipa::path(
+    get,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/coworking/{coworking_id}/layout/export",
+    params(
+        ("building_id" = Uuid, Path),
+        ("coworking_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = LayoutDocument, description = "Layout document"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 404, description = "No such coworking / building")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn export_coworking_layout(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Path((building_id, coworking_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<LayoutDocument>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let space =
+        coworking_space(conn.as_mut(), building_id, coworking_id, claims.company_id).await?;
+
+    Ok(Json(export_layout(&state, conn.as_mut(), &space).await?))
+}
+
+/// Import coworking from layout document
+///
+/// Creates a coworking in the building from an exported document. Item types are matched by name,
+/// the missing ones are created. With `dry_run` everything is checked, but nothing is saved.
+#[utoipa::path(
+    post,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/coworking/import",
+    request_body = ImportLayoutForm,
+    params(
+        ("building_id" = Uuid, Path),
+        ("dry_run" = Option<bool>, Query, description = "Only validate the document")
+    ),
+    responses(
+        (status = 200, body = LayoutImportReport, description = "Dry run report"),
+        (status = 201, body = LayoutImportReport, description = "Coworking was imported"),
+        (status = 400, description = "Wrong document"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 404, description = "No such building / floor"),
+        (status = 409, description = "Items overlap with borders / obstacles / each other")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn import_coworking_layout(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    Path(building_id): Path<Uuid>,
+    Query(query): Query<ImportLayoutQuery>,
+    ValidatedJson(form): ValidatedJson<ImportLayoutForm>,
+) -> Result<(StatusCode, Json<LayoutImportReport>), ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+
+    let report = import_layout(
+        &state,
+        tx.as_mut(),
+        &meta,
+        &claims,
+        building_id,
+        form,
+        query.dry_run,
+    )
+    .await?;
+
+    if query.dry_run {
+        return Ok((StatusCode::OK, Json(report)));
+    }
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(report)))
+}
+
+/// Clone coworking
+///
+/// Copies the coworking with its items and obstacles, the same way an exported layout is imported.
+#[utoipa::path(
+    post,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/coworking/{coworking_id}/clone",
+    request_body = CloneCoworkingForm,
+    params(
+        ("building_id" = Uuid, Path),
+        ("coworking_id" = Uuid, Path),
+        ("dry_run" = Option<bool>, Query, description = "Only check the copy")
+    ),
+    responses(
+        (status = 200, body = LayoutImportReport, description = "Dry run report"),
+        (status = 201, body = LayoutImportReport, description = "Coworking was cloned"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 404, description = "No such coworking / building / floor")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn clone_coworking(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    Path((building_id, coworking_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<ImportLayoutQuery>,
+    ValidatedJson(form): ValidatedJson<CloneCoworkingForm>,
+) -> Result<(StatusCode, Json<LayoutImportReport>), ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+
+    let space = coworking_space(tx.as_mut(), building_id, coworking_id, claims.company_id).await?;
+    let document = export_layout(&state, tx.as_mut(), &space).await?;
+
+    let report = import_layout(
+        &state,
+        tx.as_mut(),
+        &meta,
+        &claims,
+        form.building_id.unwrap_or(building_id),
+        ImportLayoutForm {
+            document,
+            address: form.address,
+            floor_id: form.floor_id,
+        },
+        query.dry_run,
+    )
+    .await?;
+
+    if query.dry_run {
+        return Ok((StatusCode::OK, Json(report)));
+    }
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(report)))
+}
@@ -0,0 +1,493 @@
+use crate::controllers::audit::{record, snapshot, AuditEntity, AuditEntry};
+use crate::controllers::layout::coworking_space;
+use crate::controllers::layout::versions::{
+    copy_version, create_draft, diff, layout_version, publish, put_version_items, version_items,
+};
+use crate::db::Db;
+use crate::errors::ProdError;
+use crate::forms::items::{DraftItemForm, PublishLayoutForm};
+use crate::jwt::generate::claims_from_headers;
+use crate::middlewares::RequestMeta;
+use crate::models::{
+    LayoutDiffModel, LayoutVersionItemsModel, LayoutVersionModel, LayoutVersionStatus,
+};
+use crate::util::ValidatedJson;
+use crate::AppState;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::{Extension, Json};
+use chrono::Utc;
+use sqlx::Acquire;
+use uuid::Uuid;
+
+/// List layout versions of coworking
+///
+/// Newest first, the published one is the live layout.
+#[utoipa::path(
+    get,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/coworking/{coworking_id}/layout/versions",
+    params(
+        ("building_id" = Uuid, Path),
+        ("coworking_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = Vec<LayoutVersionModel>, description = "Layout versions"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 404, description = "No such coworking / building")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_layout_versions(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Path((building_id, coworking_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<Vec<LayoutVersionModel>>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    coworking_space(conn.as_mut(), building_id, coworking_id, claims.company_id).await?;
+
+    let versions = sqlx::query_as!(
+        LayoutVersionModel,
+        r#"
+        SELECT id, coworking_id, status as "status: LayoutVersionStatus", created_by, created_at,
+               publish_at, published_by, published_at, company_id
+        FROM layout_versions
+        WHERE coworking_id = $1
+        ORDER BY id DESC
+        "#,
+        coworking_id
+    )
+    .fetch_all(conn.as_mut())
+    .await?;
+
+    Ok(Json(versions))
+}
+
+/// Create layout draft
+///
+/// The draft starts as a copy of the live layout, its items keep the ids of the live ones.
+#[utoipa::path(
+    post,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/coworking/{coworking_id}/layout/versions",
+    params(
+        ("building_id" = Uuid, Path),
+        ("coworking_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 201, body = LayoutVersionItemsModel, description = "Created draft"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 404, description = "No such coworking / building")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_layout_draft(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    Path((building_id, coworking_id)): Path<(Uuid, Uuid)>,
+) -> Result<(StatusCode, Json<LayoutVersionItemsModel>), ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+
+    coworking_space(tx.as_mut(), building_id, coworking_id, claims.company_id).await?;
+
+    let version_id =
+        create_draft(tx.as_mut(), coworking_id, claims.company_id, claims.user_id).await?;
+    let version = layout_version(tx.as_mut(), coworking_id, version_id, claims.company_id).await?;
+    let items = version_items(tx.as_mut(), version_id).await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::LayoutVersion, version_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new(
+            "layout_version.create",
+            AuditEntity::LayoutVersion,
+            version_id,
+        )
+        .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(LayoutVersionItemsModel { version, items }),
+    ))
+}
+
+/// Get layout version with its items
+#[utoipa::path(
+    get,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/coworking/{coworking_id}/layout/versions/{version_id}",
+    params(
+        ("building_id" = Uuid, Path),
+        ("coworking_id" = Uuid, Path),
+        ("version_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = LayoutVersionItemsModel, description = "Layout version"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 404, description = "No such coworking / building / layout version")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_layout_version(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Path((building_id, coworking_id, version_id)): Path<(Uuid, Uuid, Uuid)>,
+) -> Result<Json<LayoutVersionItemsModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    coworking_space(conn.as_mut(), building_id, coworking_id, claims.company_id).await?;
+    let version =
+        layout_version(conn.as_mut(), coworking_id, version_id, claims.company_id).await?;
+    let items = version_items(conn.as_mut(), version_id).await?;
+
+    Ok(Json(LayoutVersionItemsModel { version, items }))
+}
+
+/// Put items in layout draft
+///
+/// Replaces the items of the draft, which are checked against the borders and obstacles of
+/// the coworking like placed items. Pass the `key` of an item to keep its bookings.
+#[utoipa::path(
+    put,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/coworking/{coworking_id}/layout/versions/{version_id}/items",
+    request_body = Vec<DraftItemForm>,
+    params(
+        ("building_id" = Uuid, Path),
+        ("coworking_id" = Uuid, Path),
+        ("version_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = LayoutVersionItemsModel, description = "Updated draft"),
+        (status = 400, description = "Wrong request"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 404, description = "No such coworking / building / layout version / item type / item"),
+        (status = 409, description = "Not a draft / items overlap with borders, obstacles or each other")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn put_layout_draft_items(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    Path((building_id, coworking_id, version_id)): Path<(Uuid, Uuid, Uuid)>,
+    ValidatedJson(form): ValidatedJson<Vec<DraftItemForm>>,
+) -> Result<Json<LayoutVersionItemsModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+
+    let space = coworking_space(tx.as_mut(), building_id, coworking_id, claims.company_id).await?;
+    let version = layout_version(tx.as_mut(), coworking_id, version_id, claims.company_id).await?;
+    if version.status != LayoutVersionStatus::Draft {
+        return Err(ProdError::Conflict(
+            "Only drafts can be changed".to_string(),
+        ));
+    }
+
+    let before = snapshot(tx.as_mut(), AuditEntity::LayoutVersion, version_id).await?;
+    put_version_items(tx.as_mut(), &space, version_id, form).await?;
+    let items = version_items(tx.as_mut(), version_id).await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::LayoutVersion, version_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new(
+            "layout_version.update",
+            AuditEntity::LayoutVersion,
+            version_id,
+        )
+        .before(before)
+        .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(LayoutVersionItemsModel { version, items }))
+}
+
+/// Compare layout version with live layout
+///
+/// Lists the items publishing the version would add, remove and change, and the bookings that
+/// haven't ended yet on the removed ones.
+#[utoipa::path(
+    get,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/coworking/{coworking_id}/layout/versions/{version_id}/diff",
+    params(
+        ("building_id" = Uuid, Path),
+        ("coworking_id" = Uuid, Path),
+        ("version_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = LayoutDiffModel, description = "Changes to the live layout"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 404, description = "No such coworking / building / layout version")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_layout_diff(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Path((building_id, coworking_id, version_id)): Path<(Uuid, Uuid, Uuid)>,
+) -> Result<Json<LayoutDiffModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    coworking_space(conn.as_mut(), building_id, coworking_id, claims.company_id).await?;
+    let version =
+        layout_version(conn.as_mut(), coworking_id, version_id, claims.company_id).await?;
+
+    Ok(Json(diff(conn.as_mut(), &version).await?))
+}
+
+/// Publish layout version
+///
+/// Publishes the draft right away, or schedules it when `publish_at` is still ahead.
+/// A scheduled version can be rescheduled or published early the same way.
+#[utoipa::path(
+    post,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/coworking/{coworking_id}/layout/versions/{version_id}/publish",
+    request_body = PublishLayoutForm,
+    params(
+        ("building_id" = Uuid, Path),
+        ("coworking_id" = Uuid, Path),
+        ("version_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = LayoutVersionModel, description = "Published or scheduled version"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 404, description = "No such coworking / building / layout version"),
+        (status = 409, description = "Already published / items overlap with borders, obstacles or each other / upcoming bookings don't fit new item types")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn publish_layout_version(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    Path((building_id, coworking_id, version_id)): Path<(Uuid, Uuid, Uuid)>,
+    ValidatedJson(form): ValidatedJson<PublishLayoutForm>,
+) -> Result<Json<LayoutVersionModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+
+    coworking_space(tx.as_mut(), building_id, coworking_id, claims.company_id).await?;
+    let version = layout_version(tx.as_mut(), coworking_id, version_id, claims.company_id).await?;
+    if !matches!(
+        version.status,
+        LayoutVersionStatus::Draft | LayoutVersionStatus::Scheduled
+    ) {
+        return Err(ProdError::Conflict(
+            "The version was already published".to_string(),
+        ));
+    }
+
+    let version = match form.publish_at {
+        Some(publish_at) if publish_at > Utc::now().naive_utc() => {
+            let before = snapshot(tx.as_mut(), AuditEntity::LayoutVersion, version_id).await?;
+            let version = sqlx::query_as!(
+                LayoutVersionModel,
+                r#"
+                UPDATE layout_versions
+                SET status = 'scheduled', publish_at = $2, published_by = $3
+                WHERE id = $1
+                RETURNING id, coworking_id, status as "status: LayoutVersionStatus", created_by,
+                          created_at, publish_at, published_by, published_at, company_id
+                "#,
+                version_id,
+                publish_at,
+                claims.user_id
+            )
+            .fetch_one(tx.as_mut())
+            .await?;
+            let after = snapshot(tx.as_mut(), AuditEntity::LayoutVersion, version_id).await?;
+            let entry = AuditEntry::new(
+                "layout_version.schedule",
+                AuditEntity::LayoutVersion,
+                version_id,
+            )
+            .before(before)
+            .after(after);
+            record(tx.as_mut(), &meta, &claims, entry).await?;
+            version
+        }
+        _ => {
+            let before = snapshot(tx.as_mut(), AuditEntity::Layout, coworking_id).await?;
+            let version = publish(tx.as_mut(), &version, Some(claims.user_id)).await?;
+            let after = snapshot(tx.as_mut(), AuditEntity::Layout, coworking_id).await?;
+            let entry = AuditEntry::new("layout.publish", AuditEntity::Layout, coworking_id)
+                .before(before)
+                .after(after);
+            record(tx.as_mut(), &meta, &claims, entry).await?;
+            version
+        }
+    };
+
+    tx.commit().await?;
+
+    Ok(Json(version))
+}
+
+/// Roll layout back to version
+///
+/// Publishes a copy of an earlier version right away. Items that were removed since come back
+/// with their old ids, bookings of items the version doesn't have are cancelled.
+#[utoipa::path(
+    post,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/coworking/{coworking_id}/layout/versions/{version_id}/rollback",
+    params(
+        ("building_id" = Uuid, Path),
+        ("coworking_id" = Uuid, Path),
+        ("version_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = LayoutVersionModel, description = "Published copy of the version"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 404, description = "No such coworking / building / layout version"),
+        (status = 409, description = "Not a previously published version / items overlap with borders, obstacles or each other / upcoming bookings don't fit new item types")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn rollback_layout_version(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    Path((building_id, coworking_id, version_id)): Path<(Uuid, Uuid, Uuid)>,
+) -> Result<Json<LayoutVersionModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+
+    coworking_space(tx.as_mut(), building_id, coworking_id, claims.company_id).await?;
+    let version = layout_version(tx.as_mut(), coworking_id, version_id, claims.company_id).await?;
+    if version.status != LayoutVersionStatus::Archived {
+        return Err(ProdError::Conflict(
+            "Only previously published versions can be rolled back to".to_string(),
+        ));
+    }
+
+    let copy_id = copy_version(tx.as_mut(), &version, claims.user_id).await?;
+    let copy = layout_version(tx.as_mut(), coworking_id, copy_id, claims.company_id).await?;
+
+    let before = snapshot(tx.as_mut(), AuditEntity::Layout, coworking_id).await?;
+    let published = publish(tx.as_mut(), &copy, Some(claims.user_id)).await?;
+    let after = snapshot(tx.as_mut(), AuditEntity::Layout, coworking_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("layout.rollback", AuditEntity::Layout, coworking_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(published))
+}
+
+/// Delete layout version
+///
+/// Discards a draft or cancels a scheduled version, published ones are kept for rollback.
+#[utoipa::path(
+    delete,
+    tag = "Coworkings",
+    path = "/backend_api/place/{building_id}/coworking/{coworking_id}/layout/versions/{version_id}",
+    params(
+        ("building_id" = Uuid, Path),
+        ("coworking_id" = Uuid, Path),
+        ("version_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 204, description = "Version successfully deleted"),
+        (status = 403, description = "no auth / no `manage_layout` permission"),
+        (status = 404, description = "No such coworking / building / layout version"),
+        (status = 409, description = "The version was published")
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_layout_version(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    Path((building_id, coworking_id, version_id)): Path<(Uuid, Uuid, Uuid)>,
+) -> Result<StatusCode, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+
+    coworking_space(tx.as_mut(), building_id, coworking_id, claims.company_id).await?;
+    let version = layout_version(tx.as_mut(), coworking_id, version_id, claims.company_id).await?;
+    if !matches!(
+        version.status,
+        LayoutVersionStatus::Draft | LayoutVersionStatus::Scheduled
+    ) {
+        return Err(ProdError::Conflict(
+            "Published versions are kept for rollback".to_string(),
+        ));
+    }
+
+    let before = snapshot(tx.as_mut(), AuditEntity::LayoutVersion, version_id).await?;
+
+    sqlx::query!(r#"DELETE FROM layout_versions WHERE id = $1"#, version_id)
+        .execute(tx.as_mut())
+        .await?;
+
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new(
+            "layout_version.delete",
+            AuditEntity::LayoutVersion,
+            version_id,
+        )
+        .before(before),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,23 @@
+use crate::AppState;
+use axum::routing::{delete, get, patch, post, put};
+use axum::Router;
+
+pub mod routes;
+
+use routes::{
+    create_scim_user, delete_scim_user, get_scim_user, list_scim_users, patch_scim_user,
+    replace_scim_user, service_provider_config,
+};
+
+/// SCIM 2.0 server for identity providers, authenticated with the company SCIM token instead of a JWT.
+pub fn get_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/ServiceProviderConfig", get(service_provider_config))
+        .route("/Users", get(list_scim_users))
+        .route("/Users", post(create_scim_user))
+        .route("/Users/{user_id}", get(get_scim_user))
+        .route("/Users/{user_id}", put(replace_scim_user))
+        .route("/Users/{user_id}", patch(patch_scim_user))
+        .route("/Users/{user_id}", delete(delete_scim_user))
+        .with_state(state)
+}
@@ -0,0 +1,462 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+};
+use serde_json::{json, Value};
+use sqlx::{Acquire, PgConnection};
+use tracing::warn;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    controllers::{
+        scim::{apply_patch, cancel_future_bookings, parse_filter, scim_company, ScimFilter},
+        users::{register_user, send_activation},
+    },
+    db::Db,
+    errors::{ProdError, ScimError},
+    forms::{
+        scim::{
+            ScimListQuery, ScimListResponse, ScimPatchForm, ScimUser, ScimUserForm,
+            LIST_RESPONSE_SCHEMA,
+        },
+        users::RegisterForm,
+    },
+    models::{RoleModel, UserModel},
+    oidc::random_token,
+    util::{ScimJson, ValidatedJson},
+    AppState,
+};
+
+const MAX_PAGE_SIZE: i64 = 100;
+
+async fn fetch_user(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+    user_id: Uuid,
+) -> Result<UserModel, ProdError> {
+    sqlx::query_as!(
+        UserModel,
+        r#"
+        SELECT id, name, surname, email, password, avatar,
+               company_id,
+               (SELECT domain FROM companies WHERE companies.id = users.company_id) as "company_domain!",
+               role as "role: RoleModel", active, external_id
+        FROM users
+        WHERE id = $1 AND company_id = $2 AND deleted_at IS NULL
+        FOR UPDATE
+        "#,
+        user_id,
+        company_id
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such user".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })
+}
+
+/// Writes the SCIM attributes, cancelling future bookings when the user gets deactivated.
+async fn save_user(
+    conn: &mut PgConnection,
+    company_id: Uuid,
+    user_id: Uuid,
+    form: ScimUserForm,
+) -> Result<UserModel, ProdError> {
+    form.validate()?;
+
+    let was_active = fetch_user(&mut *conn, company_id, user_id).await?.active;
+
+    let user = sqlx::query_as!(
+        UserModel,
+        r#"
+        UPDATE users
+        SET
+            email = $3,
+            name = COALESCE($4, name),
+            surname = COALESCE($5, surname),
+            active = $6,
+            external_id = $7
+        WHERE id = $1 AND company_id = $2
+        RETURNING id, name, surname, email, password, avatar,
+                  company_id,
+                  (SELECT domain FROM companies WHERE companies.id = users.company_id) as "company_domain!",
+                  role as "role: RoleModel", active, external_id
+        "#,
+        user_id,
+        company_id,
+        form.user_name,
+        form.name.given_name,
+        form.name.family_name,
+        form.active,
+        form.external_id,
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::Database(e) if e.is_unique_violation() => {
+            ProdError::Conflict("User with that userName already exists".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    if was_active && !user.active {
+        cancel_future_bookings(conn, user_id).await?;
+    }
+
+    Ok(user)
+}
+
+/// SCIM service provider configuration
+#[utoipa::path(
+    get,
+    tag = "SCIM",
+    path = "/backend_api/scim/v2/ServiceProviderConfig",
+    responses(
+        (status = 200, description = "Supported SCIM features"),
+    )
+)]
+pub async fn service_provider_config() -> ScimJson<Value> {
+    ScimJson(json!({
+        "schemas": ["urn:ietf:params:scim:schemas:core:2.0:ServiceProviderConfig"],
+        "patch": { "supported": true },
+        "bulk": { "supported": false, "maxOperations": 0, "maxPayloadSize": 0 },
+        "filter": { "supported": true, "maxResults": MAX_PAGE_SIZE },
+        "changePassword": { "supported": false },
+        "sort": { "supported": false },
+        "etag": { "supported": false },
+        "authenticationSchemes": [{
+            "type": "oauthbearertoken",
+            "name": "Bearer token",
+            "description": "Company SCIM secret from /admin/scim/token",
+        }],
+    }))
+}
+
+/// List SCIM users
+#[utoipa::path(
+    get,
+    tag = "SCIM",
+    path = "/backend_api/scim/v2/Users",
+    params(
+        ("filter" = Option<String>, Query, description = "`userName eq \"...\"` or `externalId eq \"...\"`"),
+        ("startIndex" = Option<i64>, Query),
+        ("count" = Option<i64>, Query)
+    ),
+    responses(
+        (status = 200, body = ScimListResponse),
+        (status = 400, description = "Unsupported filter"),
+        (status = 403, description = "Invalid SCIM token"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_scim_users(
+    headers: HeaderMap,
+    Query(query): Query<ScimListQuery>,
+    State(state): State<AppState>,
+) -> Result<ScimJson<ScimListResponse>, ScimError> {
+    let mut conn = state.pool.conn().await?;
+    let company_id = scim_company(conn.as_mut(), &headers).await?.company_id;
+
+    let (user_name, external_id) = match query.filter.as_deref().map(parse_filter) {
+        None => (None, None),
+        Some(Ok(ScimFilter::UserName(user_name))) => (Some(user_name), None),
+        Some(Ok(ScimFilter::ExternalId(external_id))) => (None, Some(external_id)),
+        Some(Err(err)) => return Err(err.into()),
+    };
+    let start_index = query.start_index.unwrap_or(1).max(1);
+    let count = query.count.unwrap_or(MAX_PAGE_SIZE).clamp(0, MAX_PAGE_SIZE);
+
+    let total_results = sqlx::query!(
+        r#"
+        SELECT COUNT(*) as "count!"
+        FROM users
+        WHERE company_id = $1
+            AND deleted_at IS NULL
+            AND ($2::TEXT IS NULL OR LOWER(email) = LOWER($2))
+            AND ($3::TEXT IS NULL OR external_id = $3)
+        "#,
+        company_id,
+        user_name,
+        external_id,
+    )
+    .fetch_one(conn.as_mut())
+    .await?
+    .count;
+
+    let users = sqlx::query_as!(
+        UserModel,
+        r#"
+        SELECT id, name, surname, email, password, avatar,
+               company_id,
+               (SELECT domain FROM companies WHERE companies.id = users.company_id) as "company_domain!",
+               role as "role: RoleModel", active, external_id
+        FROM users
+        WHERE company_id = $1
+            AND deleted_at IS NULL
+            AND ($2::TEXT IS NULL OR LOWER(email) = LOWER($2))
+            AND ($3::TEXT IS NULL OR external_id = $3)
+        ORDER BY id
+        OFFSET $4
+        LIMIT $5
+        "#,
+        company_id,
+        user_name,
+        external_id,
+        start_index - 1,
+        count,
+    )
+    .fetch_all(conn.as_mut())
+    .await?;
+
+    Ok(ScimJson(ScimListResponse {
+        schemas: vec![LIST_RESPONSE_SCHEMA.to_string()],
+        total_results,
+        start_index,
+        items_per_page: i64::try_from(users.len()).unwrap_or(count),
+        resources: users.into_iter().map(ScimUser::from).collect(),
+    }))
+}
+
+/// Get SCIM user
+#[utoipa::path(
+    get,
+    tag = "SCIM",
+    path = "/backend_api/scim/v2/Users/{user_id}",
+    params(
+        ("user_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = ScimUser),
+        (status = 403, description = "Invalid SCIM token"),
+        (status = 404, description = "No such user"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_scim_user(
+    headers: HeaderMap,
+    Path(user_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<ScimJson<ScimUser>, ScimError> {
+    let mut conn = state.pool.conn().await?;
+    let company_id = scim_company(conn.as_mut(), &headers).await?.company_id;
+
+    let user = fetch_user(conn.as_mut(), company_id, user_id).await?;
+
+    Ok(ScimJson(user.into()))
+}
+
+/// Create SCIM user
+///
+/// Users get the role configured for the SCIM token. Unless the company uses SSO,
+/// they receive an e-mail to set their password.
+#[utoipa::path(
+    post,
+    tag = "SCIM",
+    path = "/backend_api/scim/v2/Users",
+    request_body = ScimUserForm,
+    responses(
+        (status = 201, body = ScimUser),
+        (status = 400, description = "Invalid user"),
+        (status = 403, description = "Invalid SCIM token"),
+        (status = 409, description = "User with that userName already exists"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_scim_user(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<ScimUserForm>,
+) -> Result<(StatusCode, ScimJson<ScimUser>), ScimError> {
+    let mut conn = state.pool.conn().await?;
+    let scim = scim_company(conn.as_mut(), &headers).await?;
+
+    let company = sqlx::query!(
+        r#"
+        SELECT c.name, c.domain, p.company_id IS NOT NULL as "sso!"
+        FROM companies c
+        LEFT JOIN oidc_providers p ON p.company_id = c.id
+        WHERE c.id = $1
+        "#,
+        scim.company_id
+    )
+    .fetch_one(conn.as_mut())
+    .await?;
+
+    let (Some(given_name), Some(family_name)) = (&form.name.given_name, &form.name.family_name)
+    else {
+        return Err(ProdError::ShitHappened(
+            "name.givenName and name.familyName are required".to_string(),
+        )
+        .into());
+    };
+
+    let register_form = RegisterForm {
+        name: given_name.clone(),
+        surname: family_name.clone(),
+        email: form.user_name.clone(),
+        password: random_token(),
+        company_domain: company.domain,
+    };
+    register_form.validate()?;
+
+    let user_id = register_user(state.clone(), register_form, scim.default_role)
+        .await
+        .map_err(|err| match err {
+            ProdError::Conflict(_) => {
+                ProdError::Conflict("User with that userName already exists".to_string())
+            }
+            _ => err,
+        })?
+        .id;
+
+    let mut tx = conn.begin().await?;
+    let user = save_user(tx.as_mut(), scim.company_id, user_id, form).await?;
+    tx.commit().await?;
+
+    if !company.sso {
+        if let Err(err) = send_activation(conn.as_mut(), user.id, &user.email, &company.name).await
+        {
+            warn!("Failed to send activation to {}: {err}", user.email);
+        }
+    }
+
+    Ok((StatusCode::CREATED, ScimJson(user.into())))
+}
+
+/// Replace SCIM user
+#[utoipa::path(
+    put,
+    tag = "SCIM",
+    path = "/backend_api/scim/v2/Users/{user_id}",
+    request_body = ScimUserForm,
+    params(
+        ("user_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = ScimUser),
+        (status = 400, description = "Invalid user"),
+        (status = 403, description = "Invalid SCIM token"),
+        (status = 404, description = "No such user"),
+        (status = 409, description = "User with that userName already exists"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn replace_scim_user(
+    headers: HeaderMap,
+    Path(user_id): Path<Uuid>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<ScimUserForm>,
+) -> Result<ScimJson<ScimUser>, ScimError> {
+    let mut conn = state.pool.conn().await?;
+    let company_id = scim_company(conn.as_mut(), &headers).await?.company_id;
+
+    let mut tx = conn.begin().await?;
+    let user = save_user(tx.as_mut(), company_id, user_id, form).await?;
+    tx.commit().await?;
+
+    Ok(ScimJson(user.into()))
+}
+
+/// Patch SCIM user
+///
+/// Setting `active` to `false` deactivates the user and cancels their future bookings.
+#[utoipa::path(
+    patch,
+    tag = "SCIM",
+    path = "/backend_api/scim/v2/Users/{user_id}",
+    request_body = ScimPatchForm,
+    params(
+        ("user_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = ScimUser),
+        (status = 400, description = "Invalid patch operation"),
+        (status = 403, description = "Invalid SCIM token"),
+        (status = 404, description = "No such user"),
+        (status = 409, description = "User with that userName already exists"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn patch_scim_user(
+    headers: HeaderMap,
+    Path(user_id): Path<Uuid>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<ScimPatchForm>,
+) -> Result<ScimJson<ScimUser>, ScimError> {
+    let mut conn = state.pool.conn().await?;
+    let company_id = scim_company(conn.as_mut(), &headers).await?.company_id;
+
+    let mut tx = conn.begin().await?;
+
+    let mut user = fetch_user(tx.as_mut(), company_id, user_id).await?;
+    apply_patch(&mut user, &form.operations)?;
+    let user = save_user(tx.as_mut(), company_id, user_id, user.into()).await?;
+
+    tx.commit().await?;
+
+    Ok(ScimJson(user.into()))
+}
+
+/// Delete SCIM user
+#[utoipa::path(
+    delete,
+    tag = "SCIM",
+    path = "/backend_api/scim/v2/Users/{user_id}",
+    params(
+        ("user_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 204, description = "User was moved to the trash, their upcoming bookings were cancelled"),
+        (status = 403, description = "Invalid SCIM token"),
+        (status = 404, description = "No such user"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_scim_user(
+    headers: HeaderMap,
+    Path(user_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, ScimError> {
+    let mut conn = state.pool.conn().await?;
+    let company_id = scim_company(conn.as_mut(), &headers).await?.company_id;
+
+    let mut tx = conn.begin().await?;
+
+    // Goes to the trash like users removed by an admin, past bookings stay for the history.
+    let _ = sqlx::query!(
+        r#"
+        UPDATE users
+        SET deleted_at = NOW(), active = FALSE
+        WHERE id = $1 AND company_id = $2 AND deleted_at IS NULL
+        RETURNING id
+        "#,
+        user_id,
+        company_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such user".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    cancel_future_bookings(tx.as_mut(), user_id).await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,31 @@
+use crate::middlewares::{require_permissions, RequiredPermissions};
+use crate::models::Permission;
+use crate::AppState;
+use axum::middleware::from_fn_with_state;
+use axum::routing::{delete, get, patch, post, put};
+use axum::Router;
+
+pub mod routes;
+
+pub fn get_routes(state: AppState) -> Router {
+    let admin_routes = Router::new()
+        .route("/", post(routes::create_team))
+        .route("/{team_id}", patch(routes::patch_team))
+        .route("/{team_id}", delete(routes::delete_team))
+        .layer(from_fn_with_state(
+            RequiredPermissions::new(&state, &[Permission::ManageUsers]),
+            require_permissions,
+        ));
+
+    // Team managers can change members too, checked in the handlers.
+    Router::new()
+        .route("/", get(routes::list_teams))
+        .route("/{team_id}/members", get(routes::list_team_members))
+        .route("/{team_id}/members/{user_id}", put(routes::put_team_member))
+        .route(
+            "/{team_id}/members/{user_id}",
+            delete(routes::delete_team_member),
+        )
+        .merge(admin_routes)
+        .with_state(state)
+}
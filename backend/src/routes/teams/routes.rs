@@ -0,0 +1,509 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use sqlx::Acquire;
+use uuid::Uuid;
+
+use crate::{
+    controllers::{
+        audit::{record, snapshot, AuditEntity, AuditEntry},
+        teams::require_team_manager,
+    },
+    db::Db,
+    errors::ProdError,
+    forms::teams::{CreateTeamForm, PatchTeamForm, TeamMemberForm},
+    jwt::generate::claims_from_headers,
+    middlewares::RequestMeta,
+    models::{TeamMemberModel, TeamModel},
+    util::ValidatedJson,
+    AppState,
+};
+
+/// List teams
+///
+/// Teams and departments of the company, departments have `parent_id` set.
+#[utoipa::path(
+    get,
+    tag = "Teams",
+    path = "/backend_api/teams",
+    responses(
+        (status = 200, body = Vec<TeamModel>),
+        (status = 403, description = "no auth"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_teams(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<TeamModel>>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let company_id = claims_from_headers(&headers)?.company_id;
+
+    let teams = sqlx::query_as!(
+        TeamModel,
+        r#"
+        SELECT id, name, parent_id, company_id
+        FROM teams
+        WHERE company_id = $1
+        ORDER BY name, id
+        "#,
+        company_id
+    )
+    .fetch_all(conn.as_mut())
+    .await?;
+
+    Ok(Json(teams))
+}
+
+/// Create team
+#[utoipa::path(
+    post,
+    tag = "Teams",
+    path = "/backend_api/teams",
+    request_body = CreateTeamForm,
+    responses(
+        (status = 201, body = TeamModel),
+        (status = 400, description = "wrong data format"),
+        (status = 403, description = "no auth / no `manage_users` permission"),
+        (status = 404, description = "no such parent team"),
+        (status = 409, description = "team with that name already exists there"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_team(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<CreateTeamForm>,
+) -> Result<(StatusCode, Json<TeamModel>), ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+
+    let team = sqlx::query_as!(
+        TeamModel,
+        r#"
+        INSERT INTO teams (name, parent_id, company_id)
+        SELECT $1, $2, $3
+        WHERE $2::uuid IS NULL OR EXISTS (SELECT 1 FROM teams WHERE id = $2 AND company_id = $3)
+        RETURNING id, name, parent_id, company_id
+        "#,
+        form.name,
+        form.parent_id,
+        claims.company_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(team_error)?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::Team, team.id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("team.create", AuditEntity::Team, team.id).after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(team)))
+}
+
+/// Rename or move team
+#[utoipa::path(
+    patch,
+    tag = "Teams",
+    path = "/backend_api/teams/{team_id}",
+    request_body = PatchTeamForm,
+    params(
+        ("team_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = TeamModel),
+        (status = 400, description = "wrong data format"),
+        (status = 403, description = "no auth / no `manage_users` permission"),
+        (status = 404, description = "no such team / parent team"),
+        (status = 409, description = "team with that name already exists there / moved into its own department"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn patch_team(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path(team_id): Path<Uuid>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<PatchTeamForm>,
+) -> Result<Json<TeamModel>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::Team, team_id).await?;
+
+    if form.top_level && form.parent_id.is_some() {
+        return Err(ProdError::ShitHappened(
+            "A top-level team can't have a parent".to_string(),
+        ));
+    }
+
+    if let Some(parent_id) = form.parent_id {
+        let cycle = sqlx::query!(
+            r#"
+            WITH RECURSIVE down AS (
+                SELECT id FROM teams WHERE id = $1
+                UNION
+                SELECT t.id FROM teams t JOIN down ON t.parent_id = down.id
+            )
+            SELECT EXISTS (SELECT 1 FROM down WHERE id = $2) as "cycle!"
+            "#,
+            team_id,
+            parent_id
+        )
+        .fetch_one(tx.as_mut())
+        .await?
+        .cycle;
+
+        if cycle {
+            return Err(ProdError::Conflict(
+                "A team can't be moved into its own department".to_string(),
+            ));
+        }
+    }
+
+    let team = sqlx::query_as!(
+        TeamModel,
+        r#"
+        UPDATE teams
+        SET
+            name = COALESCE($3, name),
+            parent_id = CASE WHEN $4 THEN $5 ELSE parent_id END
+        WHERE id = $1 AND company_id = $2
+            AND ($5::uuid IS NULL OR EXISTS (SELECT 1 FROM teams WHERE id = $5 AND company_id = $2))
+        RETURNING id, name, parent_id, company_id
+        "#,
+        team_id,
+        claims.company_id,
+        form.name,
+        form.top_level || form.parent_id.is_some(),
+        form.parent_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(team_error)?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::Team, team_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("team.update", AuditEntity::Team, team_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(team))
+}
+
+/// Delete team
+///
+/// Places reserved only for this team become open to everybody.
+#[utoipa::path(
+    delete,
+    tag = "Teams",
+    path = "/backend_api/teams/{team_id}",
+    params(
+        ("team_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 204, description = "Team was deleted"),
+        (status = 403, description = "no auth / no `manage_users` permission"),
+        (status = 404, description = "no such team"),
+        (status = 409, description = "team has departments"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_team(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path(team_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::Team, team_id).await?;
+
+    let _ = sqlx::query!(
+        r#"
+        DELETE FROM teams
+        WHERE id = $1 AND company_id = $2
+        RETURNING id
+        "#,
+        team_id,
+        claims.company_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such team".to_string()),
+        sqlx::Error::Database(e) if e.is_foreign_key_violation() => {
+            ProdError::Conflict("The team has departments, delete or move them first".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("team.delete", AuditEntity::Team, team_id).before(before),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// List team members
+///
+/// Direct members of the team, managers first.
+#[utoipa::path(
+    get,
+    tag = "Teams",
+    path = "/backend_api/teams/{team_id}/members",
+    params(
+        ("team_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 200, body = Vec<TeamMemberModel>),
+        (status = 403, description = "no auth"),
+        (status = 404, description = "no such team"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_team_members(
+    headers: HeaderMap,
+    Path(team_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<TeamMemberModel>>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let company_id = claims_from_headers(&headers)?.company_id;
+
+    let _ = sqlx::query!(
+        r#"SELECT id FROM teams WHERE id = $1 AND company_id = $2"#,
+        team_id,
+        company_id
+    )
+    .fetch_one(conn.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such team".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    let members = sqlx::query_as!(
+        TeamMemberModel,
+        r#"
+        SELECT u.id as user_id, u.name, u.surname, u.email, m.manager
+        FROM team_members m
+        JOIN users u ON u.id = m.user_id
+        WHERE m.team_id = $1 AND u.deleted_at IS NULL
+        ORDER BY m.manager DESC, u.surname, u.name
+        "#,
+        team_id
+    )
+    .fetch_all(conn.as_mut())
+    .await?;
+
+    Ok(Json(members))
+}
+
+/// Add or update team member
+///
+/// Admins with `manage_users` and managers of the team can change its members,
+/// only admins can make or demote managers.
+#[utoipa::path(
+    put,
+    tag = "Teams",
+    path = "/backend_api/teams/{team_id}/members/{user_id}",
+    request_body = TeamMemberForm,
+    params(
+        ("team_id" = Uuid, Path),
+        ("user_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 204, description = "Member was added or updated"),
+        (status = 403, description = "no auth / not a manager of the team"),
+        (status = 404, description = "no such team / user"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn put_team_member(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path((team_id, user_id)): Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+    Json(form): Json<TeamMemberForm>,
+) -> Result<StatusCode, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+    let admin = require_team_manager(tx.as_mut(), &claims, team_id).await?;
+
+    let manager = sqlx::query!(
+        r#"
+        SELECT m.manager as "manager?"
+        FROM users u
+        LEFT JOIN team_members m ON m.user_id = u.id AND m.team_id = $3
+        WHERE u.id = $1 AND u.company_id = $2
+            AND u.deleted_at IS NULL AND NOT u.service_account
+        "#,
+        user_id,
+        claims.company_id,
+        team_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map(|record| record.manager.unwrap_or_default())
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such user".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    if !admin && (manager || form.manager) {
+        return Err(ProdError::Forbidden(
+            "Only admins can change team managers".to_string(),
+        ));
+    }
+
+    let before = snapshot(tx.as_mut(), AuditEntity::Team, team_id).await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO team_members (team_id, user_id, manager)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (team_id, user_id) DO UPDATE SET manager = EXCLUDED.manager
+        "#,
+        team_id,
+        user_id,
+        form.manager
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    let after = snapshot(tx.as_mut(), AuditEntity::Team, team_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("team.put_member", AuditEntity::Team, team_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Remove team member
+#[utoipa::path(
+    delete,
+    tag = "Teams",
+    path = "/backend_api/teams/{team_id}/members/{user_id}",
+    params(
+        ("team_id" = Uuid, Path),
+        ("user_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 204, description = "Member was removed"),
+        (status = 403, description = "no auth / not a manager of the team / member is a manager"),
+        (status = 404, description = "no such team / member"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_team_member(
+    headers: HeaderMap,
+    Extension(meta): Extension<RequestMeta>,
+    Path((team_id, user_id)): Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    let mut tx = conn.begin().await?;
+    let admin = require_team_manager(tx.as_mut(), &claims, team_id).await?;
+    let before = snapshot(tx.as_mut(), AuditEntity::Team, team_id).await?;
+
+    let manager = sqlx::query!(
+        r#"
+        DELETE FROM team_members
+        WHERE team_id = $1 AND user_id = $2
+        RETURNING manager
+        "#,
+        team_id,
+        user_id
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map(|record| record.manager)
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such team member".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    if manager && !admin {
+        return Err(ProdError::Forbidden(
+            "Only admins can change team managers".to_string(),
+        ));
+    }
+
+    let after = snapshot(tx.as_mut(), AuditEntity::Team, team_id).await?;
+    record(
+        tx.as_mut(),
+        &meta,
+        &claims,
+        AuditEntry::new("team.delete_member", AuditEntity::Team, team_id)
+            .before(before)
+            .after(after),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn team_error(err: sqlx::Error) -> ProdError {
+    match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such team".to_string()),
+        sqlx::Error::Database(e) if e.is_unique_violation() => {
+            ProdError::Conflict("Team with that name already exists there".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    }
+}
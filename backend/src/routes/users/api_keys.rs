@@ -0,0 +1,128 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    controllers::api_keys::{issue_api_key, reject_api_key},
+    db::Db,
+    errors::ProdError,
+    forms::api_keys::{CreateApiKeyForm, CreatedApiKey},
+    jwt::generate::claims_from_headers,
+    models::{ApiKeyModel, Permission},
+    util::ValidatedJson,
+    AppState,
+};
+
+/// List personal API keys
+#[utoipa::path(
+    get,
+    tag = "Users",
+    path = "/backend_api/user/api_keys",
+    responses(
+        (status = 200, body = Vec<ApiKeyModel>),
+        (status = 403, description = "no auth / invalid auth"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_api_keys(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ApiKeyModel>>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let user_id = claims_from_headers(&headers)?.user_id;
+
+    let api_keys = sqlx::query_as!(
+        ApiKeyModel,
+        r#"
+        SELECT id, name, prefix, permissions as "permissions: Vec<Permission>",
+               expires_at, last_used_at, created_at, user_id, company_id
+        FROM api_keys
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        "#,
+        user_id
+    )
+    .fetch_all(conn.as_mut())
+    .await?;
+
+    Ok(Json(api_keys))
+}
+
+/// Create personal API key
+#[utoipa::path(
+    post,
+    tag = "Users",
+    path = "/backend_api/user/api_keys",
+    request_body = CreateApiKeyForm,
+    responses(
+        (status = 201, body = CreatedApiKey, description = "Created key with its secret"),
+        (status = 400, description = "wrong data format"),
+        (status = 403, description = "no auth / authenticated with an API key / permission the user doesn't have"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn create_api_key(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<CreateApiKeyForm>,
+) -> Result<(StatusCode, Json<CreatedApiKey>), ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    reject_api_key(&claims)?;
+
+    let api_key = issue_api_key(conn.as_mut(), claims.user_id, claims.company_id, form).await?;
+
+    Ok((StatusCode::CREATED, Json(api_key)))
+}
+
+/// Revoke personal API key
+#[utoipa::path(
+    delete,
+    tag = "Users",
+    path = "/backend_api/user/api_keys/{key_id}",
+    params(
+        ("key_id" = Uuid, Path)
+    ),
+    responses(
+        (status = 204, description = "Key was revoked"),
+        (status = 403, description = "no auth / authenticated with an API key"),
+        (status = 404, description = "no such key"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn delete_api_key(
+    headers: HeaderMap,
+    Path(key_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    reject_api_key(&claims)?;
+
+    let _ = sqlx::query!(
+        r#"
+        DELETE FROM api_keys
+        WHERE id = $1 AND user_id = $2
+        RETURNING id
+        "#,
+        key_id,
+        claims.user_id
+    )
+    .fetch_one(conn.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such API key".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,116 @@
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::Json;
+use sqlx::Acquire;
+use uuid::Uuid;
+
+use crate::controllers::api_keys::reject_api_key;
+use crate::controllers::users::companies::{identity_of, link_company, membership, memberships};
+use crate::forms::users::{CompanyMembership, LinkCompanyForm, Token};
+use crate::jwt::generate::{claims_from_headers, create_token};
+use crate::models::TokenData;
+use crate::util::ValidatedJson;
+use crate::{db::Db, errors::ProdError, AppState};
+
+/// List user companies
+///
+/// Companies the user has an account in, oldest first.
+#[utoipa::path(
+    get,
+    tag = "Users",
+    path = "/backend_api/user/companies",
+    responses(
+        (status = 200, body = Vec<CompanyMembership>),
+        (status = 403, description = "no auth / invalid auth"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn list_companies(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<CompanyMembership>>, ProdError> {
+    let mut conn = state.pool.unscoped_conn().await?;
+    let user_id = claims_from_headers(&headers)?.user_id;
+
+    let identity_id = identity_of(conn.as_mut(), user_id).await?;
+    let companies = memberships(conn.as_mut(), identity_id).await?;
+
+    Ok(Json(companies))
+}
+
+/// Switch company
+///
+/// Token for the user's account in another company, with the role they have there.
+#[utoipa::path(
+    post,
+    tag = "Users",
+    path = "/backend_api/user/companies/{company_id}/switch",
+    responses(
+        (status = 200, body = Token),
+        (status = 403, description = "no auth / API key / password login disabled / user is deactivated"),
+        (status = 404, description = "user isn't a member of the company"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn switch_company(
+    headers: HeaderMap,
+    Path(company_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<Json<Token>, ProdError> {
+    let mut conn = state.pool.unscoped_conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    // API keys belong to one company.
+    reject_api_key(&claims)?;
+
+    let TokenData {
+        id,
+        company_id,
+        role,
+    } = membership(conn.as_mut(), claims.user_id, company_id).await?;
+    let token = create_token(&id, &company_id, &role)?;
+
+    Ok(Json(Token { jwt: token }))
+}
+
+/// Link company account
+///
+/// Joins the user's account in another company, proven by its password, so it can be switched to.
+/// The linked account takes over the password of this one.
+#[utoipa::path(
+    post,
+    tag = "Users",
+    path = "/backend_api/user/companies/link",
+    request_body = LinkCompanyForm,
+    responses(
+        (status = 200, body = Vec<CompanyMembership>),
+        (status = 400, description = "wrong data format"),
+        (status = 403, description = "no auth / wrong password"),
+        (status = 404, description = "no account with the user's e-mail in the company"),
+        (status = 409, description = "already linked"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn link_company_account(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<LinkCompanyForm>,
+) -> Result<Json<Vec<CompanyMembership>>, ProdError> {
+    let mut conn = state.pool.unscoped_conn().await?;
+    let claims = claims_from_headers(&headers)?;
+
+    reject_api_key(&claims)?;
+
+    let mut tx = conn.begin().await?;
+    let identity_id = link_company(tx.as_mut(), claims.user_id, &form).await?;
+    let companies = memberships(tx.as_mut(), identity_id).await?;
+    tx.commit().await?;
+
+    Ok(Json(companies))
+}
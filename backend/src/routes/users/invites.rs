@@ -0,0 +1,206 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use sqlx::Acquire;
+
+use crate::{
+    controllers::users::register_user_with_role,
+    db::Db,
+    errors::ProdError,
+    forms::users::{ActivateForm, InviteInfo, InviteRegisterForm, RegisterForm, Token},
+    jwt::{
+        generate::create_token,
+        hashing::{hash_token, Argon},
+    },
+    models::{RoleModel, TokenData},
+    util::ValidatedJson,
+    AppState,
+};
+
+/// Get invite details
+#[utoipa::path(
+    get,
+    tag = "Users",
+    path = "/backend_api/user/invite/{code}",
+    params(
+        ("code" = String, Path)
+    ),
+    responses(
+        (status = 200, body = InviteInfo),
+        (status = 404, description = "No such invite / expired / used up"),
+    )
+)]
+pub async fn get_invite(
+    Path(code): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<InviteInfo>, ProdError> {
+    let mut conn = state.pool.unscoped_conn().await?;
+
+    let invite = sqlx::query_as!(
+        InviteInfo,
+        r#"
+        SELECT c.name as company_name, c.domain as company_domain,
+               r.name as role_name, i.expires_at
+        FROM invites i
+        JOIN companies c ON c.id = i.company_id
+        JOIN roles r ON r.id = i.role_id
+        WHERE i.code = $1
+            AND i.expires_at > NOW()
+            AND (i.max_uses IS NULL OR i.uses < i.max_uses)
+        "#,
+        code
+    )
+    .fetch_one(conn.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such invite".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    Ok(Json(invite))
+}
+
+/// Register user with invite
+#[utoipa::path(
+    post,
+    tag = "Users",
+    path = "/backend_api/user/invite/{code}/register",
+    request_body = InviteRegisterForm,
+    params(
+        ("code" = String, Path)
+    ),
+    responses(
+        (status = 201, body = Token),
+        (status = 400, description = "wrong data format"),
+        (status = 404, description = "No such invite / expired / used up"),
+        (status = 409, description = "User with that e-mail already exists"),
+    )
+)]
+pub async fn register_with_invite(
+    Path(code): Path<String>,
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<InviteRegisterForm>,
+) -> Result<(StatusCode, Json<Token>), ProdError> {
+    let mut conn = state.pool.unscoped_conn().await?;
+    let mut tx = conn.begin().await?;
+
+    // The row stays locked until the user is created, so concurrent registrations can't exceed `max_uses`.
+    let invite = sqlx::query!(
+        r#"
+        UPDATE invites i
+        SET uses = uses + 1
+        FROM companies c
+        WHERE c.id = i.company_id
+            AND i.code = $1
+            AND i.expires_at > NOW()
+            AND (i.max_uses IS NULL OR i.uses < i.max_uses)
+        RETURNING i.role_id, i.company_id, c.domain
+        "#,
+        code
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound("No such invite".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    let TokenData {
+        id,
+        company_id,
+        role,
+    } = register_user_with_role(
+        tx.as_mut(),
+        RegisterForm {
+            name: form.name,
+            surname: form.surname,
+            email: form.email,
+            password: form.password,
+            company_domain: invite.domain,
+        },
+        invite.company_id,
+        invite.role_id,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    let token = create_token(&id, &company_id, &role)?;
+
+    Ok((StatusCode::CREATED, Json(Token { jwt: token })))
+}
+
+/// Activate account created by admin
+#[utoipa::path(
+    post,
+    tag = "Users",
+    path = "/backend_api/user/activate",
+    request_body = ActivateForm,
+    responses(
+        (status = 200, body = Token),
+        (status = 400, description = "wrong data format"),
+        (status = 403, description = "Unknown or expired activation token / user is deactivated"),
+    )
+)]
+pub async fn activate(
+    State(state): State<AppState>,
+    ValidatedJson(form): ValidatedJson<ActivateForm>,
+) -> Result<Json<Token>, ProdError> {
+    let mut conn = state.pool.unscoped_conn().await?;
+    let mut tx = conn.begin().await?;
+
+    let user_id = sqlx::query!(
+        r#"
+        DELETE FROM user_activations
+        WHERE token_hash = $1 AND expires_at > NOW()
+        RETURNING user_id
+        "#,
+        hash_token(&form.token)
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map(|record| record.user_id)
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => {
+            ProdError::Forbidden("Unknown or expired activation token".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    let user = sqlx::query_as!(
+        TokenData,
+        r#"
+        UPDATE users
+        SET password = $2
+        WHERE id = $1 AND active
+        RETURNING id, company_id, role as "role: RoleModel"
+        "#,
+        user_id,
+        Argon::hash_password(form.password.as_bytes())?,
+    )
+    .fetch_one(tx.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::Forbidden("User is deactivated".to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    // Older links mailed to the same user are useless now.
+    let _ = sqlx::query!(
+        r#"
+        DELETE FROM user_activations
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .execute(tx.as_mut())
+    .await?;
+
+    tx.commit().await?;
+
+    let token = create_token(&user.id, &user.company_id, &user.role)?;
+
+    Ok(Json(Token { jwt: token }))
+}
@@ -1,12 +1,22 @@
 use crate::AppState;
+use api_keys::{create_api_key, delete_api_key, list_api_keys};
 use axum::routing::{delete, post};
 use axum::{
     routing::{get, patch},
     Router,
 };
-use validate::upload_document;
+use companies::{link_company_account, list_companies, switch_company};
+use invites::{activate, get_invite, register_with_invite};
+use privacy::export_data;
+use sso::{sso_authorize, sso_callback};
+use validate::{get_verification, upload_document};
 
+pub mod api_keys;
+pub mod companies;
+pub mod invites;
+pub mod privacy;
 pub mod routes;
+pub mod sso;
 pub mod validate;
 
 use crate::routes::users::routes::{get_avatar, register};
@@ -17,9 +27,22 @@ pub fn get_routes(state: AppState) -> Router {
         .route("/{user_id}/avatar", get(get_avatar))
         .route("/login", post(login))
         .route("/register", post(register))
+        .route("/invite/{code}", get(get_invite))
+        .route("/invite/{code}/register", post(register_with_invite))
+        .route("/activate", post(activate))
+        .route("/sso/{domain}/authorize", get(sso_authorize))
+        .route("/sso/callback", get(sso_callback))
         .route("/profile", get(profile))
         .route("/profile", patch(patch_profile))
         .route("/upload_document", post(upload_document))
+        .route("/verification", get(get_verification))
+        .route("/api_keys", get(list_api_keys))
+        .route("/api_keys", post(create_api_key))
+        .route("/api_keys/{key_id}", delete(delete_api_key))
+        .route("/companies", get(list_companies))
+        .route("/companies/link", post(link_company_account))
+        .route("/companies/{company_id}/switch", post(switch_company))
+        .route("/export", get(export_data))
         .route("/", delete(delete_user))
         .with_state(state)
 }
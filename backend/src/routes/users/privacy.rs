@@ -0,0 +1,44 @@
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::http::HeaderMap;
+use axum::response::Response;
+
+use crate::controllers::api_keys::reject_api_key;
+use crate::controllers::users::privacy::export_user_data;
+use crate::jwt::generate::claims_from_headers;
+use crate::{errors::ProdError, AppState};
+
+/// Export personal data
+///
+/// ZIP archive with the profile, all bookings, the verification case and uploaded files of the user.
+#[utoipa::path(
+    get,
+    tag = "Users",
+    path = "/backend_api/user/export",
+    responses(
+        (status = 200, description = "personal data archive", content_type = "application/zip"),
+        (status = 403, description = "no auth / invalid auth / authenticated with an API key"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn export_data(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Response, ProdError> {
+    let claims = claims_from_headers(&headers)?;
+    reject_api_key(&claims)?;
+
+    let archive = export_user_data(&state, claims.user_id).await?;
+
+    Response::builder()
+        .header(CONTENT_TYPE, "application/zip")
+        .header(
+            CONTENT_DISPOSITION,
+            "attachment; filename=\"personal_data.zip\"",
+        )
+        .body(Body::from(archive))
+        .map_err(|e| ProdError::Unknown(e.into()))
+}
@@ -1,5 +1,11 @@
+use crate::controllers::api_keys::reject_api_key;
+use crate::controllers::users::companies::{check_password_login, memberships};
+use crate::controllers::users::privacy::{anonymize_user, delete_user_files};
 use crate::controllers::users::{register_user, update_user};
-use crate::forms::users::{LoginForm, PatchProfileFormData, RegisterForm, Token, UserLoginData};
+use crate::forms::users::{
+    DeleteAccountQuery, DeleteMode, LoginForm, LoginResponse, PatchProfileFormData, RegisterForm,
+    Token, UserLoginData,
+};
 use crate::jwt::generate::create_token;
 use crate::jwt::hashing::Argon;
 
@@ -10,67 +16,93 @@ use crate::{
     errors::ProdError,
     forms::users::ProfileResponseForm,
     jwt::generate::claims_from_headers,
-    models::{RoleModel, UserModel},
+    models::{Permission, RoleModel, UserModel, VerificationStatus},
     AppState,
 };
 use axum::body::Body;
-use axum::extract::{Multipart, Path};
+use axum::extract::{Multipart, Path, Query};
 use axum::http::header::CONTENT_TYPE;
 use axum::http::StatusCode;
 use axum::response::Response;
 use axum::{extract::State, http::HeaderMap, Json};
+use sqlx::Acquire;
 use uuid::Uuid;
 
 /// Login user
+///
+/// Without `domain` the oldest membership is logged into, the others can be switched to
+/// with the returned token.
 #[utoipa::path(
     post,
     tag = "Users",
     path = "/backend_api/user/login",
     request_body = LoginForm,
     responses(
-        (status = 200, body = Token),
-        (status = 403, description = "wrong credentials"),
+        (status = 200, body = LoginResponse),
+        (status = 403, description = "wrong credentials / password login disabled / user is deactivated"),
     )
 )]
 pub async fn login(
     State(state): State<AppState>,
     ValidatedJson(form): ValidatedJson<LoginForm>,
-) -> Result<Json<Token>, ProdError> {
-    let mut conn = state.pool.conn().await?;
-    let UserLoginData {
-        id,
-        password,
-        company_id,
-        role,
-        ..
-    } = sqlx::query_as!(
+) -> Result<Json<LoginResponse>, ProdError> {
+    let mut conn = state.pool.unscoped_conn().await?;
+    let accounts = sqlx::query_as!(
         UserLoginData,
         r#"
         SELECT email,
                id,
+               identity_id,
                password,
                company_id,
-               role as "role: RoleModel"
+               role as "role: RoleModel",
+               active
         FROM users
-        WHERE users.email = $1 AND users.company_domain = $2
+        WHERE users.email = $1
+            AND ($2::text IS NULL OR users.company_id = (SELECT id FROM companies WHERE domain = $2))
+            AND users.deleted_at IS NULL
+        ORDER BY users.id
         "#,
         form.email,
         form.domain
     )
-    .fetch_one(conn.as_mut())
-    .await
-    .map_err(|err| match err {
-        sqlx::Error::RowNotFound => ProdError::NotFound("No such user".to_string()),
-        _ => ProdError::DatabaseError(err),
-    })?;
+    .fetch_all(conn.as_mut())
+    .await?;
 
-    if !Argon::verify(form.password.as_bytes(), &password)? {
-        return Err(ProdError::Forbidden("wrong password".to_string()));
+    if accounts.is_empty() {
+        return Err(ProdError::NotFound("No such user".to_string()));
     }
 
+    // Accounts in different companies that aren't linked can have different passwords.
+    let mut account = None;
+    for candidate in accounts {
+        if Argon::verify(form.password.as_bytes(), &candidate.password)? {
+            account = Some(candidate);
+            break;
+        }
+    }
+    let UserLoginData {
+        id,
+        identity_id,
+        company_id,
+        role,
+        active,
+        ..
+    } = account.ok_or_else(|| ProdError::Forbidden("wrong password".to_string()))?;
+
+    if !active {
+        return Err(ProdError::Forbidden("User is deactivated".to_string()));
+    }
+
+    check_password_login(conn.as_mut(), company_id, &role).await?;
+
     let token = create_token(&id, &company_id, &role)?;
+    let companies = memberships(conn.as_mut(), identity_id).await?;
 
-    Ok(Json(Token { jwt: token }))
+    Ok(Json(LoginResponse {
+        jwt: token,
+        companies,
+    }))
 }
 
 /// Register user
@@ -119,24 +151,29 @@ pub async fn profile(
     let user_profile = sqlx::query_as!(
         ProfileResponseForm,
         r#"
-        SELECT name,
-               surname,
-               email,
-               avatar,
-               company_id,
-               role as "role: RoleModel",
-               (EXISTS (SELECT 1 FROM pending_verifications pv WHERE pv.user_id = u.id))::boolean as pending_verification
+        SELECT u.name,
+               u.surname,
+               u.email,
+               u.avatar,
+               u.company_id,
+               u.role as "role: RoleModel",
+               (vc.status IS NOT DISTINCT FROM 'pending')::boolean as pending_verification,
+               vc.status as "verification_status?: VerificationStatus",
+               r.permissions as "permissions: Vec<Permission>"
         FROM users u
-        WHERE u.id = $1
+        JOIN roles r ON r.company_id = u.company_id
+            AND (r.id = u.role_id OR (u.role_id IS NULL AND r.base_role = u.role))
+        LEFT JOIN verification_cases vc ON vc.user_id = u.id
+        WHERE u.id = $1 AND u.deleted_at IS NULL
         "#,
         user_id
     )
-        .fetch_one(conn.as_mut())
-        .await
-        .map_err(|err| match err {
-            sqlx::Error::RowNotFound => ProdError::NotFound(err.to_string()),
-            _ => ProdError::DatabaseError(err)
-        })?;
+    .fetch_one(conn.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => ProdError::NotFound(err.to_string()),
+        _ => ProdError::DatabaseError(err),
+    })?;
 
     Ok(Json(user_profile))
 }
@@ -154,7 +191,8 @@ pub async fn profile(
     request_body(content = PatchProfileFormData, content_type = "multipart/form-data"),
     responses(
         (status = 200, description = "Profile updated", body = UserModel),
-        (status = 400, description = "Wrong body", body = String)
+        (status = 400, description = "Wrong body", body = String),
+        (status = 403, description = "no auth / authenticated with an API key")
     ),
     security(
         ("bearerAuth" = [])
@@ -165,18 +203,26 @@ pub async fn patch_profile(
     State(state): State<AppState>,
     multipart: Multipart,
 ) -> Result<Json<UserModel>, ProdError> {
-    let user_id = claims_from_headers(&headers)?.user_id;
-    let updated_user = update_user(user_id, multipart, state).await?;
+    let claims = claims_from_headers(&headers)?;
+    reject_api_key(&claims)?;
+    let updated_user = update_user(claims.user_id, multipart, state, true).await?;
     Ok(Json(updated_user))
 }
 
 /// Delete user
+///
+/// Removes the account with its bookings, or with `mode=anonymize` scrubs personal data and keeps anonymous bookings.
+/// Uploaded files are removed either way.
 #[utoipa::path(
     delete,
     tag = "Users",
     path = "/backend_api/user",
+    params(
+        ("mode" = Option<DeleteMode>, Query, description = "`delete` (default) or `anonymize`")
+    ),
     responses(
-        (status = 204, description = "User was successfully deleted")
+        (status = 204, description = "User was successfully deleted"),
+        (status = 403, description = "no auth / authenticated with an API key")
     ),
     security(
         ("bearerAuth" = [])
@@ -184,20 +230,35 @@ pub async fn patch_profile(
 )]
 pub async fn delete_user(
     headers: HeaderMap,
+    Query(query): Query<DeleteAccountQuery>,
     State(state): State<AppState>,
 ) -> Result<StatusCode, ProdError> {
     let mut conn = state.pool.conn().await?;
-    let user_id = claims_from_headers(&headers)?.user_id;
+    let claims = claims_from_headers(&headers)?;
+    reject_api_key(&claims)?;
+    let user_id = claims.user_id;
 
-    let _ = sqlx::query!(
-        r#"
-        DELETE FROM users
-        WHERE id = $1
-        "#,
-        user_id
-    )
-    .execute(conn.as_mut())
-    .await?;
+    let mut tx = conn.begin().await?;
+
+    match query.mode {
+        DeleteMode::Delete => {
+            let _ = sqlx::query!(
+                r#"
+                DELETE FROM users
+                WHERE id = $1
+                "#,
+                user_id
+            )
+            .execute(tx.as_mut())
+            .await?;
+        }
+        DeleteMode::Anonymize => anonymize_user(tx.as_mut(), user_id).await?,
+    }
+
+    // Before the commit, so the account stays as it was if the files can't be removed.
+    delete_user_files(&state, user_id).await?;
+
+    tx.commit().await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -216,12 +277,12 @@ pub async fn get_avatar(
     Path(user_id): Path<Uuid>,
     State(state): State<AppState>,
 ) -> Result<Response, ProdError> {
-    let mut conn = state.pool.conn().await?;
+    let mut conn = state.pool.unscoped_conn().await?;
     let avatar = sqlx::query!(
         r#"
             SELECT avatar
             FROM users
-            WHERE id = $1
+            WHERE id = $1 AND deleted_at IS NULL
             "#,
         user_id
     )
@@ -0,0 +1,218 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Redirect, Response},
+    Json,
+};
+use reqwest::Url;
+use sqlx::types::Json as SqlJson;
+use std::collections::HashMap;
+
+use crate::{
+    controllers::users::provision_oidc_user,
+    db::Db,
+    errors::ProdError,
+    forms::users::{SsoAuthorizeQuery, SsoCallbackQuery, Token},
+    jwt::generate::create_token,
+    mail::APP_URL,
+    models::{OidcProviderModel, RoleModel},
+    oidc::{self, Pkce},
+    AppState,
+};
+
+/// Minutes an authorization request stays valid between redirect and callback.
+const LOGIN_STATE_TTL_MINUTES: i32 = 10;
+
+/// Fails unless `return_to` is a page of the app itself, anywhere else would be handed the token.
+fn check_return_to(return_to: &str) -> Result<(), ProdError> {
+    let app = Url::parse(&APP_URL).map_err(|err| ProdError::Unknown(err.into()))?;
+    let target = Url::parse(return_to)
+        .map_err(|_| ProdError::ShitHappened("Invalid return_to".to_string()))?;
+
+    if target.origin() != app.origin() {
+        return Err(ProdError::ShitHappened(
+            "return_to should be a page of the app".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Start SSO login for company
+#[utoipa::path(
+    get,
+    tag = "Users",
+    path = "/backend_api/user/sso/{domain}/authorize",
+    params(
+        ("domain" = String, Path),
+        ("return_to" = Option<String>, Query, description = "Page of the app to redirect to with `#jwt=<token>` after login")
+    ),
+    responses(
+        (status = 303, description = "Redirect to the company identity provider"),
+        (status = 400, description = "return_to is not a page of the app"),
+        (status = 404, description = "No such company / SSO is not configured"),
+        (status = 502, description = "Identity provider is unavailable"),
+    )
+)]
+pub async fn sso_authorize(
+    Path(domain): Path<String>,
+    Query(query): Query<SsoAuthorizeQuery>,
+    State(state): State<AppState>,
+) -> Result<Redirect, ProdError> {
+    if let Some(return_to) = &query.return_to {
+        check_return_to(return_to)?;
+    }
+
+    let mut conn = state.pool.unscoped_conn().await?;
+
+    let provider = sqlx::query_as!(
+        OidcProviderModel,
+        r#"
+        SELECT p.company_id, issuer, client_id, client_secret, redirect_uri, scopes,
+               name_claim, surname_claim, email_claim, role_claim,
+               role_mapping as "role_mapping: SqlJson<HashMap<String, RoleModel>>",
+               default_role as "default_role: RoleModel", password_login_enabled
+        FROM oidc_providers p
+        JOIN companies c ON c.id = p.company_id
+        WHERE c.domain = $1
+        "#,
+        domain
+    )
+    .fetch_one(conn.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => {
+            ProdError::NotFound("SSO is not configured for this company".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    let discovery = oidc::discover(&provider.issuer).await?;
+
+    let login_state = oidc::random_token();
+    let nonce = oidc::random_token();
+    let pkce = Pkce::new();
+
+    let _ = sqlx::query!(
+        r#"
+        INSERT INTO oidc_login_states (state, company_id, code_verifier, nonce, return_to)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        login_state,
+        provider.company_id,
+        pkce.verifier,
+        nonce,
+        query.return_to,
+    )
+    .execute(conn.as_mut())
+    .await?;
+
+    let url = oidc::authorization_url(&provider, &discovery, &login_state, &nonce, &pkce)?;
+
+    Ok(Redirect::to(&url))
+}
+
+/// Finish SSO login
+#[utoipa::path(
+    get,
+    tag = "Users",
+    path = "/backend_api/user/sso/callback",
+    params(
+        ("code" = String, Query),
+        ("state" = String, Query)
+    ),
+    responses(
+        (status = 200, body = Token, description = "JWT for logged in user"),
+        (status = 303, description = "Redirect to `return_to` with the JWT in the fragment"),
+        (status = 403, description = "Unknown or expired login state / invalid ID token / user is deactivated"),
+        (status = 409, description = "Account with that e-mail exists, but the identity provider hasn't verified the e-mail"),
+        (status = 502, description = "Identity provider is unavailable"),
+    )
+)]
+pub async fn sso_callback(
+    Query(query): Query<SsoCallbackQuery>,
+    State(state): State<AppState>,
+) -> Result<Response, ProdError> {
+    let mut conn = state.pool.unscoped_conn().await?;
+
+    let login_state = sqlx::query!(
+        r#"
+        DELETE FROM oidc_login_states
+        WHERE state = $1 AND created_at > NOW() - make_interval(mins => $2)
+        RETURNING company_id, code_verifier, nonce, return_to
+        "#,
+        query.state,
+        LOGIN_STATE_TTL_MINUTES,
+    )
+    .fetch_one(conn.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => {
+            ProdError::Forbidden("Unknown or expired login state".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    let provider = sqlx::query_as!(
+        OidcProviderModel,
+        r#"
+        SELECT company_id, issuer, client_id, client_secret, redirect_uri, scopes,
+               name_claim, surname_claim, email_claim, role_claim,
+               role_mapping as "role_mapping: SqlJson<HashMap<String, RoleModel>>",
+               default_role as "default_role: RoleModel", password_login_enabled
+        FROM oidc_providers
+        WHERE company_id = $1
+        "#,
+        login_state.company_id
+    )
+    .fetch_one(conn.as_mut())
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::RowNotFound => {
+            ProdError::NotFound("SSO is not configured for this company".to_string())
+        }
+        _ => ProdError::DatabaseError(err),
+    })?;
+
+    let company_domain = sqlx::query!(
+        r#"SELECT domain FROM companies WHERE id = $1"#,
+        provider.company_id
+    )
+    .fetch_one(conn.as_mut())
+    .await
+    .map(|record| record.domain)?;
+
+    let discovery = oidc::discover(&provider.issuer).await?;
+    let id_token = oidc::exchange_code(
+        &provider,
+        &discovery,
+        &query.code,
+        &login_state.code_verifier,
+    )
+    .await?;
+    let claims =
+        oidc::verify_id_token(&provider, &discovery, &id_token, &login_state.nonce).await?;
+    let identity = oidc::map_identity(&provider, &claims)?;
+
+    let user = provision_oidc_user(state, provider.company_id, company_domain, identity).await?;
+
+    let active = sqlx::query!(
+        r#"SELECT active AND deleted_at IS NULL as "active!" FROM users WHERE id = $1"#,
+        user.id
+    )
+    .fetch_one(conn.as_mut())
+    .await?
+    .active;
+
+    if !active {
+        return Err(ProdError::Forbidden("User is deactivated".to_string()));
+    }
+    let token = create_token(&user.id, &user.company_id, &user.role)?;
+
+    Ok(match login_state.return_to {
+        Some(return_to) => {
+            check_return_to(&return_to)?;
+            Redirect::to(&format!("{return_to}#jwt={token}")).into_response()
+        }
+        None => Json(Token { jwt: token }).into_response(),
+    })
+}
@@ -1,8 +1,13 @@
 use axum::{
     extract::{Multipart, State},
     http::HeaderMap,
+    Json,
 };
+use sqlx::Acquire;
 
+use crate::controllers::api_keys::reject_api_key;
+use crate::controllers::verification::{verification_case, CaseFilter};
+use crate::forms::admin::VerificationRequest;
 use crate::forms::users::UploadedDocument;
 use crate::{
     db::Db,
@@ -14,14 +19,18 @@ use crate::{
 };
 
 /// Upload document for user verification
+///
+/// Opens a verification case or adds the documents to it as new versions, moving it back to pending.
 #[utoipa::path(
     post,
     tag = "Users",
     path = "/backend_api/user/upload_document",
     request_body(content = UploadedDocument, content_type = "multipart/form-data"),
     responses(
-        (status = 200, description = "document successfully uploaded"),
-        (status = 403, description = "no auth / already verified"),
+        (status = 200, body = VerificationRequest, description = "document successfully uploaded"),
+        (status = 400, description = "no document / not a PDF"),
+        (status = 403, description = "no auth / already verified / authenticated with an API key"),
+        (status = 409, description = "verification was already approved"),
     ),
     security(
         ("bearerAuth" = [])
@@ -32,14 +41,16 @@ pub async fn upload_document(
     headers: HeaderMap,
     State(state): State<AppState>,
     mut multipart: Multipart,
-) -> Result<(), ProdError> {
+) -> Result<Json<VerificationRequest>, ProdError> {
     let mut conn = state.pool.conn().await?;
+    let claims = claims_from_headers(&headers)?;
+    reject_api_key(&claims)?;
     let Claims {
         role,
         user_id,
         company_id,
         ..
-    } = claims_from_headers(&headers)?;
+    } = claims;
 
     if role != RoleModel::Guest {
         return Err(ProdError::Forbidden(
@@ -47,6 +58,7 @@ pub async fn upload_document(
         ));
     }
 
+    let mut documents = Vec::new();
     while let Some(field) = multipart.next_field().await.unwrap_or(None) {
         let Some(content_type) = field.content_type().map(ToString::to_string) else {
             return Err(ProdError::ShitHappened(
@@ -65,24 +77,118 @@ pub async fn upload_document(
             .await
             .map_err(|err| ProdError::ShitHappened(err.to_string()))?;
 
-        let name = format!("users/{user_id}/document.pdf");
-        upload_file(&state, &name, content_type, content).await?;
+        documents.push((content_type, content));
+    }
+
+    if documents.is_empty() {
+        return Err(ProdError::ShitHappened(
+            "No document was uploaded".to_string(),
+        ));
     }
 
-    let _ = sqlx::query!(
+    let mut tx = conn.begin().await?;
+
+    // Locks the case, so concurrent uploads can't take the same version.
+    // Approved cases stay closed, the token may still carry the role from before the approval.
+    let case_id = sqlx::query!(
         r#"
-        INSERT INTO pending_verifications (user_id, company_id)
+        INSERT INTO verification_cases (user_id, company_id)
         VALUES ($1, $2)
+        ON CONFLICT (user_id) DO UPDATE SET
+            status = 'pending',
+            updated_at = NOW(),
+            decided_at = NULL
+        WHERE verification_cases.status <> 'approved'
+        RETURNING id
         "#,
         user_id,
         company_id,
     )
-    .execute(conn.as_mut())
-    .await
-    .map_err(|err| match err {
-        sqlx::Error::Database(e) if e.is_unique_violation() => ProdError::VerificationError,
-        _ => ProdError::DatabaseError(err),
-    })?;
-
-    Ok(())
+    .fetch_optional(tx.as_mut())
+    .await?
+    .ok_or_else(|| ProdError::Conflict("Your verification was already approved".to_string()))?
+    .id;
+
+    let mut version = sqlx::query!(
+        r#"
+        SELECT COALESCE(MAX(version), 0) as "version!"
+        FROM verification_documents
+        WHERE case_id = $1
+        "#,
+        case_id
+    )
+    .fetch_one(tx.as_mut())
+    .await?
+    .version;
+
+    for (content_type, content) in documents {
+        version += 1;
+
+        let name = format!("users/{user_id}/verification/{case_id}/{version}.pdf");
+        upload_file(&state, &name, content_type, content).await?;
+
+        let _ = sqlx::query!(
+            r#"
+            INSERT INTO verification_documents (case_id, version, s3_key)
+            VALUES ($1, $2, $3)
+            "#,
+            case_id,
+            version,
+            name
+        )
+        .execute(tx.as_mut())
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    let case = verification_case(
+        conn.as_mut(),
+        company_id,
+        CaseFilter {
+            case_id: Some(case_id),
+            ..CaseFilter::default()
+        },
+    )
+    .await?;
+
+    Ok(Json(case))
+}
+
+/// Get own verification case
+#[utoipa::path(
+    get,
+    tag = "Users",
+    path = "/backend_api/user/verification",
+    responses(
+        (status = 200, body = VerificationRequest),
+        (status = 403, description = "no auth / invalid auth"),
+        (status = 404, description = "no documents were uploaded yet"),
+    ),
+    security(
+        ("bearerAuth" = [])
+    )
+)]
+pub async fn get_verification(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Json<VerificationRequest>, ProdError> {
+    let mut conn = state.pool.conn().await?;
+    let Claims {
+        user_id,
+        company_id,
+        ..
+    } = claims_from_headers(&headers)?;
+
+    let case = verification_case(
+        conn.as_mut(),
+        company_id,
+        CaseFilter {
+            user_id: Some(user_id),
+            ..CaseFilter::default()
+        },
+    )
+    .await?;
+
+    Ok(Json(case))
 }
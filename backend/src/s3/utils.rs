@@ -3,7 +3,7 @@ use minio::s3::types::S3Api;
 use minio::s3::{args::PutObjectApiArgs, error, utils::Multimap};
 use std::io::Error;
 use std::pin::Pin;
-use tonic::codegen::tokio_stream::Stream;
+use tonic::codegen::tokio_stream::{Stream, StreamExt};
 use tracing::info;
 
 use crate::{errors::ProdError, AppState};
@@ -71,3 +71,68 @@ pub async fn get_file(
         .map_err(|e| ProdError::Unknown(e.into()))?;
     Ok((content, content_type.to_string()))
 }
+
+pub async fn download_file(state: &AppState, name: &str) -> Result<Bytes, ProdError> {
+    let response = state
+        .s3
+        .get_object(&state.bucket_name, name)
+        .send()
+        .await
+        .map_err(|err| match err {
+            error::Error::S3Error(err) if err.code == "NoSuchKey" => {
+                ProdError::NotFound(format!("File not found: {name}"))
+            }
+            _ => ProdError::Unknown(err.into()),
+        })?;
+
+    let content = response
+        .content
+        .to_segmented_bytes()
+        .await
+        .map_err(|e| ProdError::Unknown(e.into()))?;
+    Ok(content.to_bytes())
+}
+
+/// Names of every object that starts with `prefix`.
+pub async fn list_files(state: &AppState, prefix: &str) -> Result<Vec<String>, ProdError> {
+    let mut pages = state
+        .s3
+        .list_objects(&state.bucket_name)
+        .recursive(true)
+        .prefix(Some(prefix.to_string()))
+        .to_stream()
+        .await;
+
+    let mut names = Vec::new();
+    while let Some(page) = pages.next().await {
+        let page = page.map_err(|err| ProdError::S3Error(err.to_string()))?;
+        names.extend(page.contents.into_iter().map(|object| object.name));
+    }
+
+    Ok(names)
+}
+
+/// Removes every object whose name starts with `prefix`.
+pub async fn delete_files(state: &AppState, prefix: &str) -> Result<(), ProdError> {
+    let mut pages = state
+        .s3
+        .list_objects(&state.bucket_name)
+        .recursive(true)
+        .prefix(Some(prefix.to_string()))
+        .to_stream()
+        .await;
+
+    while let Some(page) = pages.next().await {
+        let page = page.map_err(|err| ProdError::S3Error(err.to_string()))?;
+        for object in page.contents {
+            state
+                .s3
+                .remove_object(&state.bucket_name, object.name.as_str())
+                .send()
+                .await
+                .map_err(|err| ProdError::S3Error(err.to_string()))?;
+        }
+    }
+
+    Ok(())
+}
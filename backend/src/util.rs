@@ -1,8 +1,10 @@
 use axum::{
     extract::{FromRequest, Request},
+    http::header::CONTENT_TYPE,
+    response::{IntoResponse, Response},
     Json,
 };
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Serialize};
 use validator::Validate;
 
 use crate::errors::ProdError;
@@ -30,3 +32,12 @@ where
         Ok(Self(value))
     }
 }
+
+/// JSON response with the `application/scim+json` content type.
+pub struct ScimJson<T>(pub T);
+
+impl<T: Serialize> IntoResponse for ScimJson<T> {
+    fn into_response(self) -> Response {
+        ([(CONTENT_TYPE, "application/scim+json")], Json(self.0)).into_response()
+    }
+}